@@ -0,0 +1,154 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::{borrow::Cow, fmt, ops::Deref, sync::OnceLock};
+use tracing::warn;
+
+#[cfg(not(feature = "simd"))]
+use serde_json::value::RawValue;
+
+/// A request or response body, kept as the still-escaped JSON string in the input and only
+/// unescaped the first time it's read, so commands that never look at bodies don't hold a
+/// decoded copy of each one.
+pub struct Body<'a> {
+    source: Source<'a>,
+    // the unescaped text, `None` if there was nothing to unescape, and whether it unescaped
+    // cleanly
+    decoded: OnceLock<(Option<String>, bool)>,
+}
+
+enum Source<'a> {
+    #[cfg_attr(feature = "simd", allow(dead_code))]
+    Json(&'a str),
+    Text(Cow<'a, str>),
+}
+
+impl Deref for Body<'_> {
+    type Target = str;
+
+    /// The text, with any escapes JSON can't decode read as U+FFFD; see [`Body::checked`].
+    fn deref(&self) -> &str {
+        match &self.source {
+            Source::Text(text) => text,
+            Source::Json(json) => match &self.decode(json).0 {
+                Some(text) => text,
+                None => &json[1..json.len() - 1],
+            },
+        }
+    }
+}
+
+impl Body<'_> {
+    /// The text, `None` if it has escapes JSON can't decode, i.e. half of a UTF-16 surrogate
+    /// pair left where an exporter cut a string short.
+    pub fn checked(&self) -> Option<&str> {
+        match &self.source {
+            Source::Json(json) if !self.decode(json).1 => None,
+            _ => Some(self),
+        }
+    }
+
+    fn decode(&self, json: &str) -> &(Option<String>, bool) {
+        self.decoded.get_or_init(|| {
+            // most bodies, base64 ones especially, have nothing to unescape
+            if !json.contains('\\') {
+                return (None, true);
+            }
+            match serde_json::from_str(json) {
+                Ok(text) => (Some(text), true),
+                Err(e) => {
+                    warn!(
+                        "a body has an escape that can't be decoded, read as U+FFFD: {}",
+                        e
+                    );
+                    let lossy = serde_json::from_str(&replace_lone_surrogates(json));
+                    (Some(lossy.unwrap_or_default()), false)
+                }
+            }
+        })
+    }
+}
+
+/// Replace the `\u` escapes of unpaired UTF-16 surrogates, which JSON can't decode, with U+FFFD.
+fn replace_lone_surrogates(json: &str) -> String {
+    let surrogate = |escape: &str| {
+        let hex = escape.strip_prefix("\\u")?.get(..4)?;
+        u16::from_str_radix(hex, 16)
+            .ok()
+            .filter(|c| (0xd800..0xe000).contains(c))
+    };
+    let mut out = String::with_capacity(json.len());
+    let mut rest = json;
+    while let Some(i) = rest.find('\\') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        let low = rest.get(6..).and_then(surrogate);
+        let len = match surrogate(rest) {
+            Some(high) if high < 0xdc00 && low.is_some_and(|low| low >= 0xdc00) => {
+                out.push_str(&rest[..12]);
+                12
+            }
+            Some(_) => {
+                out.push_str("\\ufffd");
+                6
+            }
+            // any other escape is the backslash and one character
+            None => {
+                let len = rest[1..].chars().next().map_or(1, |c| 1 + c.len_utf8());
+                out.push_str(&rest[..len]);
+                len
+            }
+        };
+        rest = &rest[len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+impl fmt::Debug for Body<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl From<String> for Body<'_> {
+    fn from(text: String) -> Self {
+        Self {
+            source: Source::Text(Cow::Owned(text)),
+            decoded: OnceLock::new(),
+        }
+    }
+}
+
+// simd-json can't hand out raw JSON, so bodies are decoded as they're parsed
+#[cfg(not(feature = "simd"))]
+impl<'de: 'a, 'a> Deserialize<'de> for Body<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = <&RawValue>::deserialize(deserializer)?.get();
+        if !raw.starts_with('"') {
+            return Err(serde::de::Error::custom("expected a string"));
+        }
+        Ok(Self {
+            source: Source::Json(raw),
+            decoded: OnceLock::new(),
+        })
+    }
+}
+
+#[cfg(feature = "simd")]
+impl<'de: 'a, 'a> Deserialize<'de> for Body<'a> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct Borrowed<'a>(#[serde(borrow)] Cow<'a, str>);
+
+        let Borrowed(text) = Borrowed::deserialize(deserializer)?;
+        Ok(Self {
+            source: Source::Text(text),
+            decoded: OnceLock::new(),
+        })
+    }
+}
+
+impl Serialize for Body<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self)
+    }
+}
@@ -0,0 +1,278 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use hickory_resolver::proto::rr::Record;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::{Duration, SystemTime},
+};
+use xxhash_rust::xxh3::{Xxh3, xxh3_128};
+
+use crate::{dirs, offline};
+
+// DNS answers are shared between captures, unlike everything else
+const DNS_CACHE_FILE: &str = "dns.json";
+
+// the caches of captures that haven't been read for this long are deleted
+const MAX_CAPTURE_CACHE_AGE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheData {
+    #[serde(default)]
+    registrable_domains: BTreeMap<String, Option<String>>,
+    // hash of the encoded body -> SHA-256 and size of the decoded body
+    #[serde(default)]
+    body_digests: BTreeMap<String, Option<(String, usize)>>,
+}
+
+struct Cache {
+    path: PathBuf,
+    data: CacheData,
+    dirty: bool,
+}
+
+static CACHE: OnceLock<Mutex<Cache>> = OnceLock::new();
+
+struct DnsCache {
+    path: PathBuf,
+    // query -> (when the answer expires, records)
+    answers: BTreeMap<String, (i64, Vec<Record>)>,
+    dirty: bool,
+}
+
+static DNS_CACHE: OnceLock<Mutex<DnsCache>> = OnceLock::new();
+
+fn read_json<T: for<'de> Deserialize<'de> + Default>(path: &Path) -> T {
+    // an unreadable cache is only a slower run, so start over rather than fail
+    fs::read(path)
+        .ok()
+        .and_then(|contents| serde_json::from_slice(&contents).ok())
+        .unwrap_or_default()
+}
+
+/// Open the cache for an input, kept in the data directory under a hash of its contents so
+/// it's reused for as long as the capture doesn't change, along with the DNS cache.
+pub fn load(input: &[u8]) -> Result<()> {
+    let dir = dirs::get_data_dir("cache")?;
+    let path = dir.join(format!("{:032x}.json", xxh3_128(input)));
+    // mark it as used, so it isn't evicted while the capture is still being looked at
+    let _ = fs::File::options()
+        .write(true)
+        .open(&path)
+        .and_then(|file| file.set_modified(SystemTime::now()));
+    evict(&dir, &path);
+    let data = read_json(&path);
+    let _ = CACHE.set(Mutex::new(Cache {
+        path,
+        data,
+        dirty: false,
+    }));
+
+    let path = dir.join(DNS_CACHE_FILE);
+    let answers = read_json(&path);
+    let _ = DNS_CACHE.set(Mutex::new(DnsCache {
+        path,
+        answers,
+        dirty: false,
+    }));
+    Ok(())
+}
+
+/// Delete the caches of other captures that haven't been read for a while. Failing to is only
+/// a bigger data directory, so errors are ignored.
+fn evict(dir: &Path, current: &Path) {
+    let Ok(files) = fs::read_dir(dir) else {
+        return;
+    };
+    for file in files.flatten() {
+        let path = file.path();
+        let stale = file
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > MAX_CAPTURE_CACHE_AGE);
+        if stale
+            && path != current
+            && path.extension().is_some_and(|ext| ext == "json")
+            && file.file_name() != DNS_CACHE_FILE
+        {
+            let _ = fs::remove_file(&path);
+        }
+    }
+}
+
+/// Write the caches back if anything was added to them.
+pub fn save() -> Result<()> {
+    if let Some(cache) = CACHE.get() {
+        let cache = cache.lock().unwrap();
+        if cache.dirty {
+            write_json(&cache.path, &cache.data)?;
+        }
+    }
+
+    if let Some(cache) = DNS_CACHE.get() {
+        let mut cache = cache.lock().unwrap();
+        if cache.dirty {
+            // other runs may have saved answers since this one loaded, so keep theirs too,
+            // preferring whichever of two answers to the same query lasts longer
+            let mut answers: BTreeMap<String, (i64, Vec<Record>)> = read_json(&cache.path);
+            for (query, answer) in std::mem::take(&mut cache.answers) {
+                match answers.get(&query) {
+                    Some((expires, _)) if *expires >= answer.0 => {}
+                    _ => {
+                        answers.insert(query, answer);
+                    }
+                }
+            }
+            let now = Utc::now().timestamp();
+            answers.retain(|_, (expires, _)| *expires > now);
+            write_json(&cache.path, &answers)?;
+            cache.answers = answers;
+            cache.dirty = false;
+        }
+    }
+    Ok(())
+}
+
+/// Write through a temporary file renamed into place, so a run reading the cache never sees it
+/// half written.
+fn write_json(path: &Path, data: &impl Serialize) -> Result<()> {
+    let json = serde_json::to_vec(data)?;
+    let temp = path.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&temp, json)
+        .and_then(|_| fs::rename(&temp, path))
+        .inspect_err(|_| {
+            let _ = fs::remove_file(&temp);
+        })
+        .with_context(|| format!("Failed to write cache: {:?}", path))
+}
+
+/// Delete the DNS cache, returning how many answers were in it.
+pub fn clear_dns() -> Result<usize> {
+    let path = dirs::get_data_dir("cache")?.join(DNS_CACHE_FILE);
+    if !path.exists() {
+        return Ok(0);
+    }
+
+    let answers: BTreeMap<String, (i64, Vec<Record>)> = read_json(&path);
+    fs::remove_file(&path).with_context(|| format!("Failed to remove {:?}", path))?;
+    Ok(answers.len())
+}
+
+fn with_cache<T>(f: impl FnOnce(&mut Cache) -> T) -> Option<T> {
+    CACHE.get().map(|cache| f(&mut cache.lock().unwrap()))
+}
+
+/// Whether the DNS cache has an answer to a query, however old, i.e. whether an offline lookup
+/// can say anything about it.
+pub fn has_dns(key: &str) -> bool {
+    DNS_CACHE
+        .get()
+        .is_some_and(|cache| cache.lock().unwrap().answers.contains_key(key))
+}
+
+/// Get DNS records from the cache, or look them up and remember any that were found until the
+/// shortest of their TTLs runs out. Offline, cached records are returned even if they've expired.
+pub async fn dns<F>(key: String, lookup: F) -> Vec<Record>
+where
+    F: Future<Output = Vec<Record>>,
+{
+    let now = Utc::now().timestamp();
+    let offline = offline::is_offline();
+    let cached = DNS_CACHE.get().and_then(|cache| {
+        cache
+            .lock()
+            .unwrap()
+            .answers
+            .get(&key)
+            .filter(|(expires, _)| offline || now < *expires)
+            .map(|(_, records)| records.clone())
+    });
+    if let Some(records) = cached {
+        return records;
+    }
+    if offline {
+        return Vec::new();
+    }
+
+    // failed lookups come back empty too, and shouldn't be remembered
+    let records = lookup.await;
+    let ttl = records.iter().map(Record::ttl).min();
+    if let (Some(ttl), Some(cache)) = (ttl, DNS_CACHE.get()) {
+        let mut cache = cache.lock().unwrap();
+        cache
+            .answers
+            .insert(key, (now + i64::from(ttl), records.clone()));
+        cache.dirty = true;
+    }
+    records
+}
+
+/// Get a host's registrable domain from the cache, or extract it and remember it.
+pub fn registrable_domain(host: &str, extract: impl FnOnce() -> Option<String>) -> Option<String> {
+    if let Some(cached) =
+        with_cache(|cache| cache.data.registrable_domains.get(host).cloned()).flatten()
+    {
+        return cached;
+    }
+
+    let domain = extract();
+    with_cache(|cache| {
+        cache
+            .data
+            .registrable_domains
+            .insert(host.to_string(), domain.clone());
+        cache.dirty = true;
+    });
+    domain
+}
+
+/// Get the digest and size of a decoded body from the cache, or decode and hash it and remember
+/// them. Bodies are looked up by a quick hash of their parts as the HAR has them, `kind` keeping
+/// different digests of the same body apart, so a hit skips decoding them.
+pub fn body_hash(
+    kind: &str,
+    encoded: &[&[u8]],
+    hash: impl FnOnce() -> Option<([u8; 32], usize)>,
+) -> Option<([u8; 32], usize)> {
+    let mut hasher = Xxh3::new();
+    for part in encoded {
+        hasher.update(&part.len().to_le_bytes());
+        hasher.update(part);
+    }
+    let key = format!("{} {:032x}", kind, hasher.digest128());
+    let cached = with_cache(|cache| cache.data.body_digests.get(&key).cloned()).flatten();
+    if let Some(cached) = cached {
+        return cached.and_then(|(hex, size)| Some((from_hex(&hex)?, size)));
+    }
+
+    let digest = hash();
+    with_cache(|cache| {
+        cache
+            .data
+            .body_digests
+            .insert(key, digest.map(|(d, size)| (to_hex(&d), size)));
+        cache.dirty = true;
+    });
+    digest
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<[u8; 32]> {
+    let mut bytes = [0; 32];
+    if hex.len() != 64 {
+        return None;
+    }
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(bytes)
+}
@@ -0,0 +1,23 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+// exit codes, so audits can be used in scripts without parsing their output
+pub const EXIT_FINDINGS: u8 = 1;
+pub const EXIT_ERROR: u8 = 2;
+
+static CHECK: AtomicBool = AtomicBool::new(false);
+static FINDINGS: AtomicUsize = AtomicUsize::new(0);
+
+/// Exit with [`EXIT_FINDINGS`] if the audit finds anything, for `--check`.
+pub fn set_check(check: bool) {
+    CHECK.store(check, Ordering::Relaxed);
+}
+
+/// Record problems an audit found.
+pub fn report(findings: usize) {
+    FINDINGS.fetch_add(findings, Ordering::Relaxed);
+}
+
+/// Whether the run should exit with [`EXIT_FINDINGS`].
+pub fn failed() -> bool {
+    CHECK.load(Ordering::Relaxed) && FINDINGS.load(Ordering::Relaxed) > 0
+}
@@ -0,0 +1,170 @@
+use anyhow::{Context, Result, anyhow, bail};
+use clap::{ArgMatches, Command, parser::ValueSource};
+use serde::Deserialize;
+use std::{collections::HashMap, ffi::OsString, fs};
+use toml::{Table, Value};
+
+use crate::dirs;
+
+/// The config file, mapping long flag names to their default values, i.e.
+///
+/// ```toml
+/// [defaults]
+/// color = "auto"
+///
+/// [profiles.errors]
+/// where = "status>=400"
+/// template = "/home/me/templates/errors.hbs"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+struct Config {
+    #[serde(default)]
+    defaults: Table,
+
+    #[serde(default)]
+    profiles: HashMap<String, Table>,
+}
+
+fn load_config() -> Result<Option<Config>> {
+    let path = dirs::get_config_file()?;
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read config: {:?}", path)),
+    };
+
+    toml::from_str(&content)
+        .map(Some)
+        .with_context(|| format!("Failed to parse config: {:?}", path))
+}
+
+/// Find the value of `--profile` before clap has parsed anything.
+fn find_profile(args: &[OsString]) -> Option<String> {
+    let mut iter = args.iter().map(|arg| arg.to_string_lossy());
+    while let Some(arg) = iter.next() {
+        if arg == "--" {
+            break;
+        }
+        if arg == "--profile" {
+            return iter.next().map(|x| x.to_string());
+        }
+        if let Some(name) = arg.strip_prefix("--profile=") {
+            return Some(name.to_string());
+        }
+    }
+    None
+}
+
+fn value_to_flags(key: &str, value: &Value, flags: &mut Vec<OsString>) -> Result<()> {
+    match value {
+        Value::Boolean(true) => flags.push(format!("--{}", key).into()),
+        Value::Boolean(false) => {}
+        Value::String(s) => flags.push(format!("--{}={}", key, s).into()),
+        Value::Integer(i) => flags.push(format!("--{}={}", key, i).into()),
+        Value::Float(f) => flags.push(format!("--{}={}", key, f).into()),
+        Value::Datetime(dt) => flags.push(format!("--{}={}", key, dt).into()),
+        Value::Array(values) => {
+            for value in values {
+                value_to_flags(key, value, flags)?;
+            }
+        }
+        Value::Table(_) => bail!("Config key '{}' can't be a table", key),
+    }
+    Ok(())
+}
+
+/// Whether `cmd` has a flag named `key`, and if so whether the command line gave it.
+fn given_on_command_line(cmd: &Command, matches: &ArgMatches, key: &str) -> Option<bool> {
+    let arg = cmd
+        .get_arguments()
+        .find(|arg| arg.get_long() == Some(key))?;
+    Some(matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine))
+}
+
+/// Add the config defaults and selected profile to the command line arguments, for the flags
+/// the command line doesn't give itself. A command's own flags go after its name, and flags no
+/// command has are an error, while those only other commands have are left out. `--no-<flag>`
+/// turns off a flag the config turns on.
+pub fn apply_config(cmd: Command, args: Vec<OsString>) -> Result<Vec<OsString>> {
+    let profile = find_profile(&args);
+
+    let Some(mut config) = load_config()? else {
+        if let Some(profile) = profile {
+            bail!("Profile '{}' requested but no config file exists", profile);
+        }
+        return Ok(args);
+    };
+
+    let mut settings = config.defaults;
+    if let Some(name) = profile {
+        let profile = config
+            .profiles
+            .remove(&name)
+            .ok_or_else(|| anyhow!("Unknown profile: {}", name))?;
+        settings.extend(profile);
+    }
+
+    let mut args = args;
+    args.retain(|arg| {
+        let arg = arg.to_string_lossy();
+        let Some(key) = arg.strip_prefix("--no-") else {
+            return true;
+        };
+        settings.remove(key).is_none_or(|value| {
+            // only booleans can be turned off, so anything else goes back for clap to reject
+            let kept = value != Value::Boolean(true);
+            if kept {
+                settings.insert(key.to_string(), value);
+            }
+            kept
+        })
+    });
+
+    // `--help` and the like don't need the config
+    let Ok(cli) = cmd.clone().ignore_errors(true).try_get_matches_from(&args) else {
+        return Ok(args);
+    };
+    let subcommand = cli
+        .subcommand()
+        .and_then(|(name, matches)| Some((cmd.find_subcommand(name)?, matches)));
+
+    let mut flags = Vec::new();
+    let mut subcommand_flags = Vec::new();
+    for (key, value) in &settings {
+        if let Some(given) = given_on_command_line(&cmd, &cli, key) {
+            if !given {
+                value_to_flags(key, value, &mut flags)?;
+            }
+            continue;
+        }
+        if let Some((sub, matches)) = subcommand {
+            if let Some(given) = given_on_command_line(sub, matches, key) {
+                if !given {
+                    value_to_flags(key, value, &mut subcommand_flags)?;
+                }
+                continue;
+            }
+        }
+        let any_command = cmd
+            .get_subcommands()
+            .any(|sub| sub.get_arguments().any(|arg| arg.get_long() == Some(key)));
+        if !any_command {
+            bail!(
+                "Config key '{}' isn't a flag of harper or any of its commands",
+                key
+            );
+        }
+    }
+
+    if let Some((sub, _)) = subcommand {
+        let position = args.iter().skip(1).position(|arg| {
+            let arg = arg.to_string_lossy();
+            arg == sub.get_name() || sub.get_all_aliases().any(|alias| arg == alias)
+        });
+        if let Some(position) = position {
+            args.splice(position + 2..position + 2, subcommand_flags);
+        }
+    }
+    args.splice(1..1, flags);
+    Ok(args)
+}
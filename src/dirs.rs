@@ -0,0 +1,25 @@
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use std::{fs, path::PathBuf};
+
+/// Get (and create if needed) a named subdirectory of harper's data directory.
+pub fn get_data_dir(name: &str) -> Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "peterc-s", "harper")
+        .context("Failed to determine platform-specific project directories.")?;
+
+    let dir = proj_dirs.data_dir().join(name);
+
+    if !dir.exists() {
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {} directory", name))?;
+    }
+
+    Ok(dir)
+}
+
+/// Path of harper's config file, i.e. `~/.config/harper/config.toml` on Linux.
+pub fn get_config_file() -> Result<PathBuf> {
+    let proj_dirs = ProjectDirs::from("com", "peterc-s", "harper")
+        .context("Failed to determine platform-specific project directories.")?;
+
+    Ok(proj_dirs.config_dir().join("config.toml"))
+}
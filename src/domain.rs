@@ -0,0 +1,258 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    net::IpAddr,
+    path::Path,
+    sync::OnceLock,
+};
+use tldextract::{TldExtractor, TldOption};
+
+use crate::{cache, har::Har};
+
+// DuckDuckGo's entity map, cut down to the brands that own the most domains
+const BUNDLED_ENTITIES: &str = include_str!("../data/entities.json");
+
+#[derive(Debug, Deserialize)]
+struct Entity {
+    #[serde(default)]
+    properties: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct Ownership {
+    // the sites given with `--first-party`
+    declared: Vec<String>,
+    // domain -> the entity owning it
+    owners: HashMap<String, String>,
+}
+
+static OWNERSHIP: OnceLock<Ownership> = OnceLock::new();
+
+thread_local! {
+    // for `owner`, whose callers don't have an extractor to hand, only built on a cache miss
+    static TLD_EXTRACTOR: TldExtractor = build_tld_extractor();
+}
+
+fn normalise_site(site: &str) -> String {
+    to_ascii(site.trim().trim_start_matches("*.").trim_matches('.'))
+}
+
+/// Set the sites to treat as the first party, from `--first-party`, and load the entity map
+/// from `entities` or the bundled one.
+pub fn set_ownership(first_party: &[String], entities: Option<&Path>) -> Result<()> {
+    let content = match entities {
+        Some(path) => fs::read_to_string(path)
+            .with_context(|| format!("Failed to read entity map {}", path.display()))?,
+        None => BUNDLED_ENTITIES.to_string(),
+    };
+    let entities: BTreeMap<String, Entity> =
+        serde_json::from_str(&content).context("Failed to parse entity map")?;
+
+    let owners = entities
+        .into_iter()
+        .flat_map(|(name, entity)| {
+            entity
+                .properties
+                .into_iter()
+                .map(move |property| (normalise_site(&property), name.clone()))
+        })
+        .collect();
+    let declared = first_party
+        .iter()
+        .map(|site| normalise_site(site))
+        .filter(|site| !site.is_empty())
+        .collect();
+    let _ = OWNERSHIP.set(Ownership { declared, owners });
+    Ok(())
+}
+
+fn ownership() -> &'static Ownership {
+    OWNERSHIP.get_or_init(Ownership::default)
+}
+
+/// The entity owning a domain or any domain above it up to its registrable domain, going by the
+/// entity map. Suffixes above that are shared by unrelated sites, so say nothing of the owner.
+pub fn owner(domain: &str) -> Option<&'static str> {
+    let owners = &ownership().owners;
+    let registrable = cache::registrable_domain(domain, || {
+        TLD_EXTRACTOR.with(|tld_extractor| extract_registrable(domain, tld_extractor))
+    });
+    let mut domain = domain;
+    loop {
+        if let Some(owner) = owners.get(domain) {
+            return Some(owner);
+        }
+        if registrable
+            .as_deref()
+            .is_none_or(|registrable| registrable == domain)
+        {
+            return None;
+        }
+        domain = domain.split_once('.')?.1;
+    }
+}
+
+/// Whether two sites belong to the same owner, i.e. `youtube.com` and `google.com`.
+pub fn same_owner(a: &str, b: &str) -> bool {
+    a == b || owner(a).is_some_and(|entity| Some(entity) == owner(b))
+}
+
+fn within(site: &str, domain: &str) -> bool {
+    site == domain
+        || site
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// The sites a capture treats as its own, and the entities that own them.
+#[derive(Debug, Clone)]
+pub struct FirstParty {
+    // named in reports, the first declared site or the guessed one
+    pub primary: String,
+    sites: BTreeSet<String>,
+    owners: BTreeSet<&'static str>,
+}
+
+impl FirstParty {
+    fn new(primary: String, sites: BTreeSet<String>) -> FirstParty {
+        let owners = sites.iter().filter_map(|site| owner(site)).collect();
+        FirstParty {
+            primary,
+            sites,
+            owners,
+        }
+    }
+
+    /// The first party of a page on `site`, along with any sites declared with `--first-party`.
+    pub fn for_site(site: &str) -> FirstParty {
+        let mut sites: BTreeSet<String> = ownership().declared.iter().cloned().collect();
+        sites.insert(site.to_string());
+        FirstParty::new(site.to_string(), sites)
+    }
+
+    /// The first party made of `sites`, naming the first of them, as a baseline records it.
+    pub fn from_sites(sites: &[String]) -> Option<FirstParty> {
+        let primary = sites.first()?;
+        Some(FirstParty::new(
+            primary.clone(),
+            sites.iter().cloned().collect(),
+        ))
+    }
+
+    /// The sites the first party was declared or guessed as, not counting ones it only owns.
+    pub fn sites(&self) -> &BTreeSet<String> {
+        &self.sites
+    }
+
+    /// Whether a site, or host, is one of the first party's or owned by the same entity.
+    pub fn contains(&self, site: &str) -> bool {
+        self.sites.iter().any(|own| within(site, own))
+            || owner(site).is_some_and(|owner| self.owners.contains(owner))
+    }
+
+    /// The first-party site a host is on, or the primary one for hosts it only owns, if the host
+    /// is first party at all.
+    pub fn site_of(&self, host: &str, tld_extractor: &TldExtractor) -> Option<&str> {
+        let site = registrable_domain(host, tld_extractor).unwrap_or_else(|| host.to_string());
+        if !self.contains(&site) {
+            return None;
+        }
+        let own = self
+            .sites
+            .iter()
+            .find(|own| within(host, own) || within(&site, own));
+        Some(own.unwrap_or(&self.primary))
+    }
+
+    /// Whether a host belongs to the first party, going by its registrable domain.
+    pub fn contains_host(&self, host: &str, tld_extractor: &TldExtractor) -> bool {
+        self.contains(&registrable_domain(host, tld_extractor).unwrap_or_else(|| host.to_string()))
+    }
+}
+
+pub fn build_tld_extractor() -> TldExtractor {
+    TldOption::default()
+        .cache_path(".tld_cache")
+        .private_domains(false)
+        .update_local(false)
+        .naive_mode(false)
+        .build()
+}
+
+/// Parse a host that is an IP literal, including bracketed IPv6 as `Url::host_str` gives it.
+pub fn ip_literal(host: &str) -> Option<IpAddr> {
+    host.strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host)
+        .parse()
+        .ok()
+}
+
+/// Normalise a host to its ASCII (punycode) form, which URLs and DNS use.
+pub fn to_ascii(host: &str) -> String {
+    if host.is_ascii() {
+        return host.to_lowercase();
+    }
+    idna::domain_to_ascii(host).unwrap_or_else(|_| host.to_lowercase())
+}
+
+/// Get the Unicode form of a host with punycode labels.
+pub fn to_unicode(host: &str) -> Option<String> {
+    if !host.split('.').any(|label| label.starts_with("xn--")) {
+        return None;
+    }
+    let (unicode, result) = idna::domain_to_unicode(host);
+    (result.is_ok() && unicode != host).then_some(unicode)
+}
+
+/// Show a host with its Unicode form alongside, i.e. `xn--mnchen-3ya.de (münchen.de)`.
+pub fn display_host(host: &str) -> String {
+    match to_unicode(host) {
+        Some(unicode) => format!("{} ({})", host, unicode),
+        None => host.to_string(),
+    }
+}
+
+/// Get the registrable domain (eTLD+1) of a host, i.e. `www.example.co.uk` -> `example.co.uk`.
+pub fn registrable_domain(host: &str, tld_extractor: &TldExtractor) -> Option<String> {
+    cache::registrable_domain(host, || extract_registrable(host, tld_extractor))
+}
+
+fn extract_registrable(host: &str, tld_extractor: &TldExtractor) -> Option<String> {
+    let extracted = tld_extractor.extract(host).ok()?;
+    match (extracted.domain, extracted.suffix) {
+        (Some(domain), Some(suffix)) => Some(format!("{}.{}", domain, suffix)),
+        (Some(domain), None) => Some(domain),
+        _ => None,
+    }
+}
+
+/// Guess the first-party registrable domain as that of the first http(s) request in the HAR.
+fn guess_first_party(har: &Har, tld_extractor: &TldExtractor) -> Option<String> {
+    har.log
+        .entries
+        .iter()
+        .filter(|entry| entry.request.url.starts_with("http"))
+        .find_map(|entry| entry.request.host())
+        .and_then(|host| registrable_domain(&host, tld_extractor))
+}
+
+/// The first party of the HAR: the sites given with `--first-party`, or failing those the site
+/// of the first http(s) request. Sites owned by the same entity in the entity map count too.
+pub fn first_party(har: &Har, tld_extractor: &TldExtractor) -> Option<FirstParty> {
+    let declared = &ownership().declared;
+    match declared.first() {
+        Some(primary) => Some(FirstParty::new(
+            primary.clone(),
+            declared.iter().cloned().collect(),
+        )),
+        None => guess_first_party(har, tld_extractor).map(|site| FirstParty::for_site(&site)),
+    }
+}
+
+/// The registrable domain reports name as the first party, see [`first_party`].
+pub fn first_party_domain(har: &Har, tld_extractor: &TldExtractor) -> Option<String> {
+    first_party(har, tld_extractor).map(|first_party| first_party.primary)
+}
@@ -0,0 +1,60 @@
+/// Format a byte count with binary units, i.e. `1536` -> `1.5 KiB`.
+pub fn bytes(n: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+
+    let mut value = n as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} {}", n, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Keep just enough of a secret to recognise it, i.e. `alice@example.com` -> `al*************om`.
+pub fn redact(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    if chars.len() <= 4 {
+        return "*".repeat(chars.len());
+    }
+    let mut redacted: String = chars[..2].iter().collect();
+    redacted.push_str(&"*".repeat(chars.len() - 4));
+    redacted.extend(&chars[chars.len() - 2..]);
+    redacted
+}
+
+/// Parse a duration with a unit suffix, i.e. `500ms`, `10s`, `5m` or `1h`.
+pub fn parse_duration(s: &str) -> Result<std::time::Duration, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{}'", s))?;
+
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        "d" => number * 86400.0,
+        _ => {
+            return Err(format!(
+                "unknown duration unit '{}', expected ms, s, m, h or d",
+                unit
+            ));
+        }
+    };
+    if seconds <= 0.0 {
+        return Err(format!("duration '{}' must be positive", s));
+    }
+
+    Ok(std::time::Duration::from_secs_f64(seconds))
+}
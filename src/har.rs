@@ -1,218 +1,873 @@
-use serde::{Deserialize, Deserializer, Serialize, de::IntoDeserializer};
-use serde_json::Value;
+use base64::{
+    Engine, alphabet,
+    engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig},
+};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, SecondsFormat};
+use serde::{
+    Deserialize, Deserializer, Serialize, Serializer,
+    de::{DeserializeSeed, IntoDeserializer, MapAccess, Visitor, value::MapAccessDeserializer},
+};
+use serde_json::{Map, Value};
+use std::{borrow::Cow, fmt, marker::PhantomData, net::IpAddr};
+use url::Url;
+
+use crate::{
+    body::Body,
+    mime::{self, ResourceKind},
+};
+
+// exporters aren't consistent about padding
+pub const BASE64_LENIENT: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Har {
-    pub log: Log,
+pub struct Har<'a> {
+    #[serde(borrow)]
+    pub log: Log<'a>,
+    // every struct keeps the fields it doesn't model, such as Chrome's `_initiator`, so
+    // they survive a round-trip
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Log {
-    pub version: String,
-    pub creator: Creator,
-    pub browser: Option<Browser>,
-    pub pages: Option<Vec<Page>>,
-    pub entries: Vec<Entry>,
-    pub comment: Option<String>,
+pub struct Log<'a> {
+    #[serde(borrow)]
+    pub version: Cow<'a, str>,
+    #[serde(borrow)]
+    pub creator: Creator<'a>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub browser: Option<Browser<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub pages: Option<Vec<Page<'a>>>,
+    #[serde(borrow)]
+    pub entries: Vec<Entry<'a>>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub comment: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Creator {
-    pub name: String,
-    pub version: String,
-    pub comment: Option<String>,
+pub struct Creator<'a> {
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub version: Cow<'a, str>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub comment: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Browser {
-    pub name: String,
-    pub version: String,
-    pub comment: Option<String>,
+pub struct Browser<'a> {
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub version: Cow<'a, str>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub comment: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Page {
-    pub started_date_time: String,
-    pub id: String,
-    pub title: String,
-    pub page_timings: PageTimings,
-    pub comment: Option<String>,
+pub struct Page<'a> {
+    pub started_date_time: Timestamp,
+    #[serde(borrow)]
+    pub id: Cow<'a, str>,
+    #[serde(borrow)]
+    pub title: Cow<'a, str>,
+    #[serde(borrow)]
+    pub page_timings: PageTimings<'a>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub comment: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct PageTimings {
+pub struct PageTimings<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub on_content_load: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub on_load: Option<f64>,
-    pub comment: Option<String>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub comment: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Entry {
-    pub pageref: Option<String>,
-    pub started_date_time: String,
+pub struct Entry<'a> {
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub pageref: Option<Cow<'a, str>>,
+    pub started_date_time: Timestamp,
     pub time: f64,
-    pub request: Request,
-    pub response: Response,
-    pub cache: Cache,
+    #[serde(borrow)]
+    pub request: Request<'a>,
+    #[serde(borrow)]
+    pub response: Response<'a>,
+    #[serde(borrow)]
+    pub cache: Cache<'a>,
     // leniancy given, shouldn't be optional.
     #[serde(deserialize_with = "deserialize_empty_object")]
-    pub timings: Option<Timing>,
-    pub server_ip_address: Option<String>,
-    pub connection: Option<String>,
-    pub comment: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timings: Option<Timing<'a>>,
+    // the spec capitalises IP, which camelCase renaming doesn't
+    #[serde(rename = "serverIPAddress", alias = "serverIpAddress")]
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub server_ip_address: Option<Cow<'a, str>>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub connection: Option<Cow<'a, str>>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub comment: Option<Cow<'a, str>>,
+    // Chrome's `memory` or `disk` for responses from the browser cache
+    #[serde(
+        rename = "_fromCache",
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub from_cache: Option<Cow<'a, str>>,
+    // WebPageTest's marker for HTTP/2 server pushes, which it writes as 1 or 0
+    #[serde(
+        rename = "_was_pushed",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub was_pushed: Option<Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Request {
-    pub method: String,
-    pub url: String,
-    pub http_version: String,
-    pub cookies: Vec<Cookie>,
-    pub headers: Vec<Header>,
-    pub query_string: Vec<QueryString>,
-    pub post_data: Option<PostData>,
+pub struct Request<'a> {
+    #[serde(borrow)]
+    pub method: Cow<'a, str>,
+    #[serde(borrow)]
+    pub url: Cow<'a, str>,
+    #[serde(borrow)]
+    pub http_version: Cow<'a, str>,
+    #[serde(borrow)]
+    pub cookies: Vec<Cookie<'a>>,
+    #[serde(borrow)]
+    pub headers: Vec<Header<'a>>,
+    #[serde(borrow)]
+    pub query_string: Vec<QueryString<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<PostData<'a>>,
     // leniancy given, shouldn't be optional.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub headers_size: Option<i64>,
     pub body_size: i64,
-    pub comment: Option<String>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub comment: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Response {
+pub struct Response<'a> {
     pub status: u16,
-    pub status_text: String,
-    pub http_version: String,
-    pub cookies: Vec<Cookie>,
-    pub headers: Vec<Header>,
+    #[serde(borrow)]
+    pub status_text: Cow<'a, str>,
+    #[serde(borrow)]
+    pub http_version: Cow<'a, str>,
+    #[serde(borrow)]
+    pub cookies: Vec<Cookie<'a>>,
+    #[serde(borrow)]
+    pub headers: Vec<Header<'a>>,
     #[serde(rename = "redirectURL")]
-    pub redirect_url: String,
+    #[serde(borrow)]
+    pub redirect_url: Cow<'a, str>,
     // leniency given, shouldn't be optional.
     #[serde(deserialize_with = "deserialize_empty_object")]
-    pub content: Option<Content>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<Content<'a>>,
     // leniency given, shouldn't be optional.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub headers_size: Option<i64>,
     pub body_size: i64,
-    pub comment: Option<String>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub comment: Option<Cow<'a, str>>,
+    // bytes over the network including headers, as Chrome and Safari record it
+    #[serde(
+        rename = "_transferSize",
+        default,
+        deserialize_with = "lenient_i64",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub transfer_size: Option<i64>,
+    #[serde(
+        rename = "_fetchedViaServiceWorker",
+        default,
+        deserialize_with = "lenient_bool",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub fetched_via_service_worker: Option<bool>,
+    // where the service worker got the response: `network`, `cache-storage`, `http-cache` or
+    // `fallback-code`
+    #[serde(
+        rename = "_serviceWorkerResponseSource",
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub service_worker_response_source: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Cookie {
-    pub name: String,
-    pub value: String,
-    pub path: Option<String>,
-    pub domain: Option<String>,
-    pub expires: Option<String>,
+pub struct Cookie<'a> {
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub value: Cow<'a, str>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub path: Option<Cow<'a, str>>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub domain: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<Expiry>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub http_only: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub secure: Option<bool>,
-    pub comment: Option<String>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub comment: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Header {
-    pub name: String,
-    pub value: String,
-    pub comment: Option<String>,
+pub struct Header<'a> {
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub value: Cow<'a, str>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub comment: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct QueryString {
-    pub name: String,
-    pub value: String,
-    pub comment: Option<String>,
+pub struct QueryString<'a> {
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(borrow)]
+    pub value: Cow<'a, str>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub comment: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct PostData {
-    pub mime_type: String,
+pub struct PostData<'a> {
+    #[serde(borrow)]
+    pub mime_type: Cow<'a, str>,
     // leniency given, shouldn't be optional.
-    pub params: Option<Vec<Param>>,
-    pub text: String,
-    pub comment: Option<String>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Vec<Param<'a>>>,
+    #[serde(borrow)]
+    pub text: Body<'a>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub comment: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Param {
-    pub name: String,
-    pub value: Option<String>,
-    pub file_name: Option<String>,
-    pub content_type: Option<String>,
-    pub comment: Option<String>,
+pub struct Param<'a> {
+    #[serde(borrow)]
+    pub name: Cow<'a, str>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub value: Option<Cow<'a, str>>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub file_name: Option<Cow<'a, str>>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub content_type: Option<Cow<'a, str>>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub comment: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Content {
+pub struct Content<'a> {
     // leniency given, shouldn't be optional.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub size: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub compression: Option<i64>,
     // leniency given, shouldn't be optional.
-    pub mime_type: Option<String>,
-    pub text: Option<String>,
-    pub encoding: Option<String>,
-    pub comment: Option<String>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub mime_type: Option<Cow<'a, str>>,
+    #[serde(borrow, default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<Body<'a>>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub encoding: Option<Cow<'a, str>>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub comment: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Cache {
-    pub before_request: Option<CacheEntry>,
-    pub after_request: Option<CacheEntry>,
-    pub comment: Option<String>,
+pub struct Cache<'a> {
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub before_request: Option<CacheEntry<'a>>,
+    #[serde(borrow, skip_serializing_if = "Option::is_none")]
+    pub after_request: Option<CacheEntry<'a>>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub comment: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct CacheEntry {
-    pub expires: Option<String>,
-    pub last_access: String,
-    pub e_tag: String,
+pub struct CacheEntry<'a> {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires: Option<Expiry>,
+    #[serde(borrow)]
+    pub last_access: Cow<'a, str>,
+    #[serde(borrow)]
+    pub e_tag: Cow<'a, str>,
     pub hit_count: u32,
-    pub comment: Option<String>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub comment: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
-pub struct Timing {
+pub struct Timing<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub blocked: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub dns: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub connect: Option<f64>,
     pub send: f64,
     pub wait: f64,
     pub receive: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub ssl: Option<f64>,
-    pub comment: Option<String>,
+    #[serde(
+        borrow,
+        default,
+        deserialize_with = "borrow_option_str",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub comment: Option<Cow<'a, str>>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Entry<'_> {
+    /// Get the server IP address, stripping the brackets some exporters put around IPv6.
+    pub fn server_ip(&self) -> Option<IpAddr> {
+        self.server_ip_address
+            .as_deref()
+            .map(|ip| ip.trim_start_matches('[').trim_end_matches(']'))
+            .and_then(|ip| ip.parse().ok())
+    }
+
+    /// Whether the server pushed the response rather than the browser requesting it.
+    pub fn was_pushed(&self) -> bool {
+        match &self.was_pushed {
+            Some(Value::Bool(pushed)) => *pushed,
+            Some(Value::Number(n)) => n.as_i64().is_some_and(|n| n != 0),
+            _ => false,
+        }
+    }
+
+    /// Milliseconds from the request starting to the first byte of the response arriving.
+    pub fn first_byte_ms(&self) -> f64 {
+        self.timings.as_ref().map_or(self.time.max(0.0), |t| {
+            [t.blocked, t.dns, t.connect, Some(t.send), Some(t.wait)]
+                .into_iter()
+                .flatten()
+                .filter(|ms| *ms > 0.0)
+                .sum()
+        })
+    }
+}
+
+impl Request<'_> {
+    /// Get the host of the request URL, if it parses and has one.
+    pub fn host(&self) -> Option<String> {
+        Url::parse(&self.url)
+            .ok()
+            .and_then(|url| url.host_str().map(|h| h.to_string()))
+    }
+
+    /// Get the value of the first header matching `name`, case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
 }
 
+impl Response<'_> {
+    /// Get the value of the first header matching `name`, case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+
+    /// Get the MIME type of the response content, falling back to the Content-Type header.
+    pub fn mime_type(&self) -> Option<String> {
+        self.content
+            .as_ref()
+            .and_then(|c| c.mime_type.as_deref())
+            .filter(|m| !m.is_empty())
+            .or_else(|| self.header("content-type"))
+            .map(mime::essence)
+    }
+
+    /// Size of the response body as transferred, falling back to the content size when unknown.
+    pub fn body_bytes(&self) -> u64 {
+        if self.body_size > 0 {
+            return self.body_size as u64;
+        }
+
+        self.content
+            .as_ref()
+            .and_then(|c| c.size)
+            .filter(|size| *size > 0)
+            .unwrap_or(0) as u64
+    }
+
+    pub fn resource_kind(&self) -> ResourceKind {
+        self.mime_type()
+            .map(|m| ResourceKind::from_mime(&m))
+            .unwrap_or(ResourceKind::Other)
+    }
+}
+
+impl Content<'_> {
+    /// Get the body bytes, decoding base64 encoded content. `None` without a body or where its
+    /// escapes can't be decoded, so nothing is worked out from a corrupted copy.
+    pub fn decoded(&self) -> Option<Vec<u8>> {
+        let text = self.text.as_ref()?.checked()?;
+        match self.encoding.as_deref() {
+            Some("base64") => {
+                let cleaned: String = text.split_whitespace().collect();
+                BASE64_LENIENT.decode(cleaned).ok()
+            }
+            _ => Some(text.as_bytes().to_vec()),
+        }
+    }
+
+    /// Get the body as text, replacing invalid UTF-8.
+    pub fn decoded_text(&self) -> Option<String> {
+        self.decoded()
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+fn find_header<'a>(headers: &'a [Header], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|h| h.name.eq_ignore_ascii_case(name))
+        .map(|h| h.value.as_ref())
+}
+
+/// Deserialize an object, taking an empty one as `None`. Fields are read straight from the
+/// input rather than through a `Value`, so strings can still be borrowed.
 fn deserialize_empty_object<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
 where
     D: Deserializer<'de>,
     T: Deserialize<'de>,
 {
-    let value: Value = Deserialize::deserialize(deserializer)?;
+    struct EmptyAsNone<T>(PhantomData<T>);
+
+    impl<'de, T: Deserialize<'de>> Visitor<'de> for EmptyAsNone<T> {
+        type Value = Option<T>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("an object")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let Some(first) = map.next_key::<Cow<'de, str>>()? else {
+                return Ok(None);
+            };
+            T::deserialize(MapAccessDeserializer::new(Prepended {
+                first: Some(first),
+                map,
+            }))
+            .map(Some)
+        }
+    }
+
+    // hands back the key read to check for emptiness before the rest of the map
+    struct Prepended<'de, A> {
+        first: Option<Cow<'de, str>>,
+        map: A,
+    }
+
+    impl<'de, A: MapAccess<'de>> MapAccess<'de> for Prepended<'de, A> {
+        type Error = A::Error;
+
+        fn next_key_seed<K: DeserializeSeed<'de>>(
+            &mut self,
+            seed: K,
+        ) -> Result<Option<K::Value>, Self::Error> {
+            match self.first.take() {
+                Some(key) => seed.deserialize(key.into_deserializer()).map(Some),
+                None => self.map.next_key_seed(seed),
+            }
+        }
+
+        fn next_value_seed<V: DeserializeSeed<'de>>(
+            &mut self,
+            seed: V,
+        ) -> Result<V::Value, Self::Error> {
+            self.map.next_value_seed(seed)
+        }
+    }
+
+    deserializer.deserialize_map(EmptyAsNone(PhantomData))
+}
+
+/// Read a non-standard integer field leniently: any number is taken, floats rounded, and
+/// anything else is `None` rather than an error.
+fn lenient_i64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Number(n) => n.as_i64().or_else(|| {
+            n.as_f64()
+                .filter(|f| f.is_finite())
+                .map(|f| f.round() as i64)
+        }),
+        _ => None,
+    })
+}
+
+/// Read a non-standard boolean field leniently, taking anything but a boolean as `None`.
+fn lenient_bool<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<bool>, D::Error> {
+    Ok(Value::deserialize(deserializer)?.as_bool())
+}
+
+/// Borrow an optional string from the input when possible, which serde only does for a bare
+/// `Cow` field.
+fn borrow_option_str<'de: 'a, 'a, D>(deserializer: D) -> Result<Option<Cow<'a, str>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    struct Borrowed<'a>(#[serde(borrow)] Cow<'a, str>);
+
+    Option::<Borrowed>::deserialize(deserializer).map(|s| s.map(|Borrowed(s)| s))
+}
+
+/// Parse a HAR timestamp, accepting the near-misses exporters produce: a space for the `T`, no
+/// offset (taken as UTC), RFC 2822, RFC 850, asctime and cookie-style dates, and unix epoch
+/// milliseconds.
+pub fn parse_timestamp(s: &str) -> Option<DateTime<FixedOffset>> {
+    let s = s.trim();
+    let t_separated = s.replacen(' ', "T", 1);
+
+    DateTime::parse_from_rfc3339(s)
+        .or_else(|_| DateTime::parse_from_rfc3339(&t_separated))
+        .or_else(|_| DateTime::parse_from_str(&t_separated, "%Y-%m-%dT%H:%M:%S%.f%z"))
+        .or_else(|_| DateTime::parse_from_rfc2822(s))
+        .ok()
+        .or_else(|| {
+            [
+                "%Y-%m-%dT%H:%M:%S%.f",
+                "%a, %d-%b-%Y %H:%M:%S GMT",
+                "%a, %d %b %Y %H:%M:%S UTC",
+                // RFC 850 and asctime dates, which HTTP still allows in `Expires`
+                "%A, %d-%b-%y %H:%M:%S GMT",
+                "%a %b %e %H:%M:%S %Y",
+            ]
+            .iter()
+            .find_map(|format| {
+                let source = if format.contains('T') {
+                    &t_separated
+                } else {
+                    s
+                };
+                NaiveDateTime::parse_from_str(source, format).ok()
+            })
+            .map(|t| t.and_utc().fixed_offset())
+        })
+        .or_else(|| {
+            s.parse::<i64>()
+                .ok()
+                .and_then(DateTime::from_timestamp_millis)
+                .map(|t| t.fixed_offset())
+        })
+}
+
+/// Format a timestamp as HAR exporters do, i.e. `2024-01-01T10:00:00.000Z`.
+pub fn format_timestamp(time: &DateTime<FixedOffset>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Millis, true)
+}
 
-    // check for empty object
-    if let Value::Object(o) = &value {
-        if o.is_empty() {
-            return Ok(None);
+/// Read a timestamp from JSON, as a string [`parse_timestamp`] accepts or epoch milliseconds.
+fn parse_timestamp_value(value: &Value) -> Option<DateTime<FixedOffset>> {
+    match value {
+        Value::String(s) => parse_timestamp(s),
+        Value::Number(n) => n
+            .as_i64()
+            .and_then(DateTime::from_timestamp_millis)
+            .map(|t| t.fixed_offset()),
+        _ => None,
+    }
+}
+
+/// A `startedDateTime`, which derefs to the time it names. Text that was already ISO 8601 is
+/// written back as the exporter wrote it, keeping its precision and offset, and only values that
+/// had to be repaired, or were made here, are formatted with [`format_timestamp`].
+#[derive(Debug, Clone)]
+pub struct Timestamp {
+    time: DateTime<FixedOffset>,
+    // the exporter's text, where it needed no repair
+    raw: Option<String>,
+    repaired: bool,
+}
+
+impl Timestamp {
+    /// Whether the exporter wrote something other than an ISO 8601 string that had to be read
+    /// leniently, i.e. a space for the `T` or epoch milliseconds.
+    pub fn is_repaired(&self) -> bool {
+        self.repaired
+    }
+}
+
+impl From<DateTime<FixedOffset>> for Timestamp {
+    fn from(time: DateTime<FixedOffset>) -> Self {
+        Timestamp {
+            time,
+            raw: None,
+            repaired: false,
+        }
+    }
+}
+
+impl std::ops::Deref for Timestamp {
+    type Target = DateTime<FixedOffset>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.time
+    }
+}
+
+impl Serialize for Timestamp {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match &self.raw {
+            Some(raw) => serializer.serialize_str(raw),
+            None => serializer.serialize_str(&format_timestamp(&self.time)),
         }
     }
+}
 
-    // convert remaining cases to deserializer and parse
-    T::deserialize(value.into_deserializer())
-        .map(Some)
-        .map_err(|e| serde::de::Error::custom(format!("Failed to deserialize: {}", e)))
+impl<'de> Deserialize<'de> for Timestamp {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Value::deserialize(deserializer)?;
+        let time = parse_timestamp_value(&value)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid timestamp {}", value)))?;
+        let raw = value
+            .as_str()
+            .filter(|s| DateTime::parse_from_rfc3339(s).is_ok())
+            .map(String::from);
+        Ok(Timestamp {
+            time,
+            repaired: raw.is_none(),
+            raw,
+        })
+    }
+}
+
+/// An expiry date as the exporter wrote it, parsed where it's a date this can read. Session
+/// cookies, empty strings and unreadable dates keep their text but have no `time`.
+#[derive(Debug, Clone)]
+pub struct Expiry {
+    pub raw: Value,
+    pub time: Option<DateTime<FixedOffset>>,
+}
+
+impl Expiry {
+    /// Whether a date was given that couldn't be read, as opposed to none for a session cookie.
+    pub fn is_unreadable(&self) -> bool {
+        let given = match &self.raw {
+            Value::Null => false,
+            Value::String(s) => !s.trim().is_empty(),
+            _ => true,
+        };
+        given && self.time.is_none()
+    }
+}
+
+impl Serialize for Expiry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.raw.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Expiry {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = Value::deserialize(deserializer)?;
+        Ok(Expiry {
+            time: parse_timestamp_value(&raw),
+            raw,
+        })
+    }
 }
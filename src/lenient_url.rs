@@ -0,0 +1,212 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Mutex, OnceLock},
+};
+use tracing::{debug, warn};
+use url::Url;
+
+// how many unparseable URLs to show in the summary
+const MAX_EXAMPLES: usize = 3;
+
+// longer URLs are cut short in the summary
+const MAX_EXAMPLE_CHARS: usize = 80;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Repair {
+    /// `//host/path`, given the scheme `https:`.
+    SchemeRelative,
+    /// `host/path`, given the scheme `http://`.
+    MissingScheme,
+    /// Surrounding whitespace trimmed and spaces inside percent-encoded.
+    Whitespace,
+    /// Still unparseable, but the scheme and host were picked out by hand.
+    Salvaged,
+    /// Nothing could be recovered.
+    Unparseable,
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Repair::SchemeRelative => "scheme-relative",
+            Repair::MissingScheme => "missing a scheme",
+            Repair::Whitespace => "with whitespace",
+            Repair::Salvaged => "only partly parseable",
+            Repair::Unparseable => "unparseable",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LenientUrl {
+    pub url: Option<Url>,
+    pub scheme: Option<String>,
+    pub host: Option<String>,
+    // how the URL was recovered, `None` if it parsed as it was
+    pub repair: Option<Repair>,
+}
+
+// raw URL -> how it was repaired, for the summary at the end of the run
+static REPAIRS: OnceLock<Mutex<BTreeMap<String, Repair>>> = OnceLock::new();
+
+fn record(raw: &str, repair: Repair) {
+    debug!(url = raw, %repair, "repaired URL");
+    let repairs = REPAIRS.get_or_init(Default::default);
+    if let Ok(mut repairs) = repairs.lock() {
+        repairs.insert(raw.to_string(), repair);
+    }
+}
+
+fn from_url(url: Url, repair: Option<Repair>) -> LenientUrl {
+    LenientUrl {
+        scheme: Some(url.scheme().to_string()),
+        host: url.host_str().map(str::to_string),
+        url: Some(url),
+        repair,
+    }
+}
+
+/// Pick the scheme and host out of a URL `Url::parse` rejects, i.e. for a bad port or
+/// characters the host can't contain.
+fn salvage(raw: &str) -> LenientUrl {
+    let (scheme, rest) = match raw.split_once(':') {
+        Some((scheme, rest))
+            if scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "+.-".contains(c)) =>
+        {
+            (Some(scheme.to_lowercase()), rest)
+        }
+        _ => (None, raw),
+    };
+    let host = rest
+        .strip_prefix("//")
+        .and_then(|authority| authority.split(['/', '?', '#']).next())
+        .map(|authority| authority.rsplit('@').next().unwrap_or(authority))
+        .map(|host_port| match host_port.rsplit_once(':') {
+            // a port, unless it's part of an IPv6 literal
+            Some((host, port)) if !port.contains(']') => host,
+            _ => host_port,
+        })
+        .filter(|host| {
+            !host.is_empty()
+                && host
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-.[]:_".contains(c))
+        })
+        .map(str::to_lowercase);
+    LenientUrl {
+        url: None,
+        scheme,
+        host,
+        repair: None,
+    }
+}
+
+/// Parse a URL as HAR exporters write them, recovering scheme-relative URLs, URLs without a
+/// scheme, whitespace and, failing those, whatever scheme and host can be picked out. Repairs
+/// are kept for [`report`].
+pub fn parse(raw: &str) -> LenientUrl {
+    if let Ok(url) = Url::parse(raw) {
+        return from_url(url, None);
+    }
+
+    let trimmed = raw.trim();
+    let mut candidates = Vec::new();
+    if trimmed.contains(char::is_whitespace) || trimmed.len() != raw.len() {
+        let encoded: String = trimmed
+            .chars()
+            .filter(|c| !matches!(c, '\n' | '\r' | '\t'))
+            .collect::<String>()
+            .replace(' ', "%20");
+        candidates.push((encoded, Repair::Whitespace));
+    }
+    let base = candidates
+        .first()
+        .map_or(trimmed.to_string(), |(url, _)| url.clone());
+    if base.starts_with("//") {
+        candidates.push((format!("https:{}", base), Repair::SchemeRelative));
+    } else if !base.contains("://")
+        && base
+            .split(['/', '?', '#'])
+            .next()
+            .is_some_and(|host| host.contains('.') && !host.contains(':'))
+    {
+        candidates.push((format!("http://{}", base), Repair::MissingScheme));
+    }
+
+    // later candidates build on the earlier ones, so the last that parses fixed the most
+    if let Some((url, repair)) = candidates
+        .into_iter()
+        .rev()
+        .find_map(|(candidate, repair)| Some((Url::parse(&candidate).ok()?, repair)))
+    {
+        record(raw, repair);
+        return from_url(url, Some(repair));
+    }
+
+    let mut salvaged = salvage(trimmed);
+    let repair = if salvaged.host.is_some() || salvaged.scheme.is_some() {
+        Repair::Salvaged
+    } else {
+        Repair::Unparseable
+    };
+    record(raw, repair);
+    salvaged.repair = Some(repair);
+    salvaged
+}
+
+/// The host of a URL, leniently parsed.
+pub fn host(raw: &str) -> Option<String> {
+    parse(raw).host
+}
+
+fn shorten(url: &str) -> String {
+    if url.chars().count() <= MAX_EXAMPLE_CHARS {
+        return url.to_string();
+    }
+    let cut: String = url.chars().take(MAX_EXAMPLE_CHARS).collect();
+    format!("{}...", cut)
+}
+
+/// Warn about the URLs that needed repairing this run, with a few that couldn't be parsed.
+pub fn report() {
+    let Some(repairs) = REPAIRS.get() else {
+        return;
+    };
+    let Ok(repairs) = repairs.lock() else {
+        return;
+    };
+    if repairs.is_empty() {
+        return;
+    }
+
+    let mut counts: BTreeMap<Repair, usize> = BTreeMap::new();
+    for repair in repairs.values() {
+        *counts.entry(*repair).or_default() += 1;
+    }
+    let counts: Vec<String> = counts
+        .iter()
+        .map(|(repair, count)| format!("{} {}", count, repair))
+        .collect();
+    let examples: Vec<String> = repairs
+        .iter()
+        .filter(|(_, repair)| matches!(repair, Repair::Salvaged | Repair::Unparseable))
+        .take(MAX_EXAMPLES)
+        .map(|(url, _)| format!("{:?}", shorten(url)))
+        .collect();
+    let examples = if examples.is_empty() {
+        String::new()
+    } else {
+        format!(", e.g. {}", examples.join(", "))
+    };
+    warn!(
+        "{} malformed URLs: {}{}",
+        repairs.len(),
+        counts.join(", "),
+        examples
+    );
+}
@@ -0,0 +1,40 @@
+use std::io;
+use tracing_subscriber::{EnvFilter, fmt::format::FmtSpan};
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines.
+    Text,
+    /// One JSON object per line, for log collectors.
+    Json,
+}
+
+/// Log warnings to stderr, and with `-v` and up more detail along with how long each phase of
+/// the run took. `RUST_LOG` overrides the levels.
+pub fn init(verbosity: u8, format: LogFormat) {
+    // dependencies are only let past warnings at -vvv, they drown out harper's own logs
+    let directives = match verbosity {
+        0 => "warn",
+        1 => "warn,harper=info",
+        2 => "warn,harper=debug",
+        _ => "debug,harper=trace",
+    };
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(directives));
+    let spans = if verbosity > 0 {
+        FmtSpan::CLOSE
+    } else {
+        FmtSpan::NONE
+    };
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_span_events(spans)
+        .with_writer(io::stderr)
+        .with_ansi(colored::control::SHOULD_COLORIZE.should_colorize());
+
+    match format {
+        LogFormat::Json => builder.json().init(),
+        // plain warnings read better without a timestamp and module path
+        LogFormat::Text if verbosity == 0 => builder.without_time().with_target(false).init(),
+        LogFormat::Text => builder.init(),
+    }
+}
@@ -1,34 +1,299 @@
 use anyhow::{Context, Result, anyhow};
-use base64::{Engine, prelude::BASE64_STANDARD_NO_PAD};
-use chrono::{DateTime, Local};
-use clap::{CommandFactory, Parser, Subcommand, error::ErrorKind};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, error::ErrorKind};
 use colored::Colorize;
-use serde_json::{self, error::Category};
+use indicatif::ProgressBar;
+use logging::LogFormat;
+use serde_json::{self, error::Category, json};
 use std::{
     cmp::Reverse,
     collections::HashMap,
     fs,
     io::{self, IsTerminal, Read},
+    path::{Path, PathBuf},
     process::ExitCode,
 };
-use tldextract::TldOption;
+use tracing::{Instrument, info, info_span};
 
 mod ops;
+use ops::filter::TimeBound;
+use ops::selector::Selector;
 use ops::{
-    blocklist, count_requests, count_schemes, count_urls, dns, filter, list_domains, search_for,
+    accept_mismatch, anonymize, assert, asset_refs, auth_flows, bandwidth, beacons, blocklist,
+    budget, capture, completions, compression, consent, cookie_lineage, count, count_methods,
+    count_requests, count_schemes, count_urls, csp, ct, data_flows, diff, dns, dns_security,
+    duplicates, early_hints, entropy, expect_absent, export, filter, fingerprinting, fonts, forms,
+    grpc, hashes, homographs, hsts, i18n, import, inline_urls, ip_info, js_libs, lcp, list_domains,
+    mime_sniff, mitmproxy, multipart, output, pcap, pii, polling, protocols, referrer_audit,
+    resource_hints, reverse_dns, score, scrub, search_for, servers, service_worker, session_tokens,
+    sort, split, streams, timeline, tls_audit, trend, uploads, whois,
 };
 
+mod body;
+mod cache;
+mod check;
+mod config;
+mod dirs;
+mod domain;
+mod format;
+
 mod har;
 use har::Har;
 
+mod lenient_url;
+mod logging;
+mod mime;
+mod offline;
+mod progress;
+mod quirks;
+mod sizes;
+mod template;
+
 #[derive(Parser, Debug)]
 #[command(version, about = "Command line HAR analyser.", long_about = None)]
 struct Args {
-    #[arg(short, long, help = "Filters out requests after the time.", default_value = None, global = true)]
-    before: Option<DateTime<Local>>,
+    #[arg(
+        short,
+        long,
+        help = "Filters out requests after the time, or relative to the capture, e.g. '+30s' or '-5m'.",
+        long_help = "Filters out requests after the time.\n\n\
+            Takes an RFC 3339 time, or a duration relative to the capture: '+30s' is 30 seconds after\n\
+            the first request started and '-5m' is 5 minutes before the last one started.",
+        value_parser = filter::parse_time_bound,
+        allow_hyphen_values = true,
+        global = true
+    )]
+    before: Option<TimeBound>,
+
+    #[arg(
+        short,
+        long,
+        help = "Filters out requests before the time, or relative to the capture, e.g. '+30s' or '-5m'.",
+        value_parser = filter::parse_time_bound,
+        allow_hyphen_values = true,
+        global = true
+    )]
+    after: Option<TimeBound>,
+
+    #[arg(
+        long,
+        help = "Only keep requests between two times, e.g. '+10s..+1m'. Either side may be left open.",
+        value_parser = filter::parse_time_window,
+        allow_hyphen_values = true,
+        conflicts_with_all = ["before", "after"],
+        global = true
+    )]
+    between: Option<(Option<TimeBound>, Option<TimeBound>)>,
+
+    #[arg(
+        long = "where",
+        help = "Only keep requests matching the expression, e.g. 'status>=400 && domain~\"*.example.com\"'.",
+        long_help = "Only keep requests matching the expression.\n\n\
+            Fields: method, url, domain, path, scheme, status, mime, kind, size, time, http_version, ip, page.\n\
+            Operators: == != > >= < <= ~ (glob) !~ (negated glob), combined with && || ! and parentheses.\n\
+            e.g. 'status>=400 && domain~\"*.google.com\" && mime==\"application/json\"'",
+        value_parser = Selector::parse,
+        global = true
+    )]
+    selector: Option<Selector>,
+
+    #[arg(
+        long,
+        help = "Only keep requests to this domain, its subdomains or a glob, can be given multiple times.",
+        global = true
+    )]
+    include_domain: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Drop requests to this domain, its subdomains or a glob, can be given multiple times.",
+        global = true
+    )]
+    exclude_domain: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Only keep requests with these methods, e.g. 'POST,PUT'.",
+        value_delimiter = ',',
+        global = true
+    )]
+    method: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Only keep responses with these statuses, classes or ranges, e.g. '4xx,500-504,302'.",
+        value_delimiter = ',',
+        value_parser = filter::parse_status_range,
+        global = true
+    )]
+    status: Vec<(u16, u16)>,
+
+    #[arg(
+        long,
+        help = "Only keep responses matching these MIME type globs or kinds, e.g. 'image/*,script'.",
+        value_delimiter = ',',
+        global = true
+    )]
+    mime: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Only keep these entries, numbered from 1 as search-for reports them, e.g. '10..50,73'.",
+        long_help = "Only keep these entries, e.g. '10..50,73' or '100..'.\n\n\
+            Entries are numbered from 1 as search-for reports them. The other filters are applied\n\
+            first, so use the same filters when selecting entries from a search.",
+        value_delimiter = ',',
+        value_parser = filter::parse_entry_range,
+        global = true
+    )]
+    entries: Vec<(usize, usize)>,
+
+    #[arg(
+        long,
+        help = "Render the command's results through a handlebars template file.",
+        long_help = "Render the command's results through a handlebars template file.\n\n\
+            The template is given `command`, `results` and the filtered HAR `entries`, e.g.\n\
+            '{{#each entries}}{{this.response.status}} {{this.request.url}}\n{{/each}}'.\n\
+            Supported by list-domains, count-schemes, count-methods, count-requests, count, search-for,\n\
+            output and duplicates.",
+        global = true
+    )]
+    template: Option<PathBuf>,
+
+    #[arg(long, help = "When to colour output.", default_value = ColorChoice::Auto.as_ref(), global = true)]
+    color: ColorChoice,
+
+    #[arg(short, long, help = "Don't show progress bars.", global = true)]
+    quiet: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "Log more to stderr, -v for progress and phase timings, -vv for debugging.",
+        action = clap::ArgAction::Count,
+        global = true
+    )]
+    verbose: u8,
+
+    #[arg(
+        long,
+        help = "Format of the logs on stderr.",
+        default_value = "text",
+        global = true
+    )]
+    log_format: LogFormat,
+
+    #[arg(
+        long,
+        help = "Don't read or write the analysis cache.",
+        long_help = "Don't read or write the analysis cache.\n\n\
+            Registrable domains and body hashes are cached per capture in harper's data directory,\n\
+            keyed by a hash of the input, so repeated runs on the same file are faster. DNS answers\n\
+            are cached there for all captures until their TTLs run out; see `dns-cache clear`.",
+        global = true
+    )]
+    no_cache: bool,
+
+    #[arg(
+        long,
+        help = "Skip network operations.",
+        long_help = "Skip network operations.\n\n\
+            DNS commands only show cached answers, TLS handshakes and reverse lookups are skipped,\n\
+            block-list falls back to the bundled tracker list if none are downloaded, and commands\n\
+            that only download things fail.",
+        global = true
+    )]
+    offline: bool,
+
+    #[arg(
+        long,
+        help = "Print which tool exported the HAR and the quirks found in it.",
+        long_help = "Print which tool exported the HAR and the quirks found in it.\n\n\
+            The tool is identified from log.creator and log.browser, i.e. Firefox, Chrome, Safari,\n\
+            Charles or Fiddler. Quirks a tool is known for, such as Firefox's empty content objects,\n\
+            are otherwise only logged with -v, while unexpected ones are warned about. Captures from\n\
+            proxies always warn, since their timings and versions are the proxy's. Quirks are read\n\
+            the same way whichever tool exported the HAR; the tool only decides what's warned about.",
+        global = true
+    )]
+    explain_quirks: bool,
+
+    #[arg(
+        long,
+        help = "Nameserver for DNS commands instead of the system's, e.g. '1.1.1.1:53'.",
+        long_help = "Nameserver for DNS commands instead of the system's, e.g. '1.1.1.1:53'.\n\n\
+            Can be given multiple times or as a comma-separated list, and the port defaults to 53.\n\
+            Without it the system's config is used, i.e. /etc/resolv.conf, which some containers\n\
+            don't have.",
+        value_delimiter = ',',
+        value_parser = dns::parse_dns_server,
+        global = true
+    )]
+    dns_server: Vec<std::net::SocketAddr>,
+
+    #[arg(
+        long,
+        help = "How long to wait for each DNS answer, e.g. '2s' or '500ms'.",
+        default_value = dns::default_timeout_arg(),
+        value_parser = format::parse_duration,
+        global = true
+    )]
+    dns_timeout: std::time::Duration,
+
+    #[arg(
+        long,
+        help = "How many times to retry a DNS query that times out.",
+        default_value_t = dns::DEFAULT_RETRIES,
+        global = true
+    )]
+    dns_retries: usize,
+
+    #[arg(
+        long,
+        help = "Treat these sites as the first party, e.g. 'example.com,example-cdn.net'.",
+        long_help = "Treat these sites as the first party, e.g. 'example.com,example-cdn.net'.\n\n\
+            Third-party analyses otherwise guess the first party as the registrable domain of the\n\
+            first request. Sites owned by the same entity as a first-party site, going by the entity\n\
+            map, count as first party too, so youtube.com is first party to google.com. In the config\n\
+            file give the sites as one string, e.g. `first-party = \"example.com,example.net\"`.",
+        value_delimiter = ',',
+        global = true
+    )]
+    first_party: Vec<String>,
+
+    #[arg(
+        long,
+        help = "Entity map of who owns which domains, in DuckDuckGo's entity_map.json format.",
+        long_help = "Entity map of who owns which domains, in DuckDuckGo's entity_map.json format.\n\n\
+            Defaults to a bundled map of the largest brands, e.g. Google, Facebook, Microsoft and\n\
+            Amazon. Each entity's `properties` are the domains it owns.",
+        global = true
+    )]
+    entities: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Use a named profile from the config file.",
+        long_help = "Use a named profile from the config file.\n\n\
+            The config file lives at ~/.config/harper/config.toml on Linux. Its [defaults] table and\n\
+            [profiles.<name>] tables map long flag names to values, e.g. `where = \"status>=400\"`.\n\
+            Flags given on the command line replace the config's values, and `--no-<flag>` turns off\n\
+            a flag the config turns on. Flags of a command only apply to the commands that have them.",
+        global = true
+    )]
+    profile: Option<String>,
 
-    #[arg(short, long, help = "Filters out requests before the time.", default_value = None, global = true)]
-    after: Option<DateTime<Local>>,
+    #[arg(
+        long,
+        help = "Exit with status 1 if the audit finds anything.",
+        long_help = "Exit with status 1 if the audit finds anything.\n\n\
+            harper exits with 0 when a run is clean and 2 on errors. With --check, audits exit with 1\n\
+            when they report findings. Supported by block-list, hsts, tls-audit, csp analyze, js-libs,\n\
+            pii, referrer-audit, homographs, hashes, consent, fingerprinting, trend,\n\
+            asset-refs, resource-hints, i18n, accept-mismatch and uploads. budget, assert and\n\
+            expect-absent always check.",
+        global = true
+    )]
+    check: bool,
 
     #[clap(subcommand)]
     command: Commands,
@@ -45,175 +310,1195 @@ enum Commands {
     CountUrls(CountUrlArgs),
 
     /// Lists domains in the HAR.
-    ListDomains,
+    ListDomains(ListDomainsArgs),
 
     /// Count number of each scheme in the HAR.
     CountSchemes,
 
+    /// Count requests by method, against status classes and domains, i.e. to spot writes to third parties.
+    CountMethods(CountMethodsArgs),
+
     /// Count the number of requests made.
     CountRequests,
 
-    /// Search for a specific string.
-    SearchFor(SearchForArgs),
+    /// Count requests by scheme, method, status, MIME type, domain or HTTP version, then by another.
+    Count(CountArgs),
+
+    /// Search for a specific string.
+    SearchFor(SearchForArgs),
+
+    /// Fail if a string or pattern is found anywhere in the HAR, i.e. an internal hostname.
+    ExpectAbsent(ExpectAbsentArgs),
+
+    /// Return the contents of the HAR, after filtering.
+    Output(OutputArgs),
+
+    /// Return the HAR with its entries sorted, or list them in order.
+    Sort(SortArgs),
+
+    /// Check if URLs contained in the HAR are using DNSSEC.
+    DNSSECAudit,
+
+    /// Lookup common DNS record types of URLs contained in the HAR.
+    DNSLookup(DnsLookupArgs),
+
+    /// Audit CAA, TLSA, SPF, DMARC and DKIM records of the first-party domain.
+    DNSSecurity,
+
+    /// Compare the reverse and forward DNS of each server IP address with the requested host.
+    ReverseDNS,
+
+    /// Look up the registrar, creation date and registrant country of each domain over RDAP.
+    Whois(WhoisArgs),
+
+    /// List certificates issued for the first-party domain from Certificate Transparency logs.
+    CertTransparency(CertTransparencyArgs),
+
+    /// Manage the DNS answers cached between runs, use '-' for FILE.
+    DnsCache(DnsCacheArgs),
+
+    /// Downloads common blocklists, use '-' for FILE.
+    GetBlockLists,
+
+    /// Deletes block lists.
+    RemoveBlockLists,
+
+    /// Checks for URLs in common blocklists.
+    BlockList,
+
+    /// Downloads the Chromium HSTS preload list, use '-' for FILE.
+    GetHstsPreload,
+
+    /// Checks https domains for HSTS preloading and Strict-Transport-Security headers.
+    Hsts,
+
+    /// Report server software, CDNs and hosting providers per domain.
+    Servers,
+
+    /// Report the countries and networks of server IP addresses.
+    IpInfo(IpInfoArgs),
+
+    /// Inspect the TLS certificates and protocol support of https domains.
+    TlsAudit(TlsAuditArgs),
+
+    /// Analyze Content-Security-Policy headers or generate a draft policy per page.
+    Csp(CspArgs),
+
+    /// Detect JavaScript libraries and versions with known vulnerabilities.
+    JsLibs(JsLibsArgs),
+
+    /// Run an HTTP(S) proxy that records traffic into a HAR, use '-' for FILE.
+    Capture(CaptureArgs),
+
+    /// Convert captures from other tools into a HAR, use '-' for FILE.
+    Import(ImportArgs),
+
+    /// Export the requests as load-test scripts or other formats.
+    Export(ExportArgs),
+
+    /// Track where cookie values were set and sent, and detect cookie syncing.
+    CookieLineage,
+
+    /// Find duplicate requests and the bytes wasted re-fetching identical responses.
+    Duplicates,
+
+    /// Detect emails, phone numbers, card numbers, national IDs and coordinates sent to each domain.
+    Pii(PiiArgs),
+
+    /// Detect tracking pixels and beacons, and the fields sent to each analytics provider.
+    Beacons,
+
+    /// Find full URLs leaked to other sites through the Referer header.
+    ReferrerAudit,
+
+    /// Map login, OAuth, OIDC and SAML flows, and flag tokens sent insecurely or to other parties.
+    AuthFlows,
+
+    /// Report session cookies and Authorization values, where they were sent and for how long.
+    SessionTokens,
+
+    /// Check the HAR against performance budgets, failing if any are exceeded.
+    Budget(BudgetArgs),
+
+    /// Grade the capture's performance, privacy and security, explaining each deduction.
+    Score,
+
+    /// Report HTTP version share and connection reuse per domain, suggesting upgrades.
+    Protocols,
+
+    /// Find text responses served without compression and estimate gzip and brotli savings.
+    Compression,
+
+    /// Compare declared MIME types against the sniffed content of response bodies.
+    MimeSniff,
+
+    /// List the forms in captured HTML, their hidden fields and CSRF tokens, and the requests submitting them.
+    Forms,
+
+    /// Find responses in types their request's Accept header didn't ask for, i.e. HTML error pages for JSON.
+    AcceptMismatch,
+
+    /// Report the charsets and languages responses declare, flagging missing or conflicting ones.
+    I18n,
+
+    /// Find high-entropy query parameters, cookies and body segments that may be encoded data.
+    Entropy(EntropyArgs),
+
+    /// Bucket requests over time and draw request counts and bytes per interval.
+    Timeline(TimelineArgs),
+
+    /// Detect endpoints requested at regular intervals, with their period and overhead.
+    Polling(PollingArgs),
+
+    /// Flag internationalised domains that mix scripts or imitate Latin names (homographs).
+    Homographs,
+
+    /// Split the capture into smaller HAR files by page, domain or hour.
+    Split(SplitArgs),
+
+    /// Replace domains, IPs, user identifiers and cookie values with consistent pseudonyms.
+    Anonymize(AnonymizeArgs),
+
+    /// Replace matches of a regex across URLs, headers, cookies and bodies.
+    Scrub(ScrubArgs),
+
+    /// Hash each response body with SHA-256, grouping identical bodies and checking a hash list.
+    Hashes(HashesArgs),
+
+    /// Guess which asset drove each page's LCP and list render-blocking candidates.
+    Lcp,
+
+    /// List web font downloads by family, with their formats, caching and possible savings.
+    Fonts,
+
+    /// Detect consent management platforms and tracking requests sent before consent was given.
+    Consent,
+
+    /// Map which countries and organisations received cookies or PII on each page.
+    DataFlows(DataFlowsArgs),
+
+    /// Score scripts by the fingerprinting APIs they use and flag the domains serving them.
+    Fingerprinting,
+
+    /// Compare the requests in this capture with another, optionally down to response bodies.
+    Diff(DiffArgs),
+
+    /// Report per-endpoint latency and size trends across a directory of HARs, use '-' for FILE.
+    Trend(TrendArgs),
+
+    /// Check the HAR against a baseline recorded from a known-good capture.
+    Assert(AssertArgs),
+
+    /// Cross-check the assets captured HTML references against the requests actually made.
+    AssetRefs,
+
+    /// Show how much of the capture was served by service workers and caches rather than the network.
+    ServiceWorker,
+
+    /// Audit preload and preconnect hints for wasted ones and missing third-party preconnects.
+    ResourceHints,
+
+    /// Report resources loaded early through 103 Early Hints or HTTP/2 push, and the time saved.
+    EarlyHints,
+
+    /// Account for bytes sent and received, headers and bodies, and estimate load times on slower links.
+    Bandwidth(BandwidthArgs),
+
+    /// Summarise data: and blob: requests, their MIME types and largest payloads.
+    InlineUrls(InlineUrlsArgs),
+
+    /// Parse multipart/form-data request bodies, listing each part's name, filename, type and size.
+    Multipart(MultipartArgs),
+
+    /// List the requests that sent a body by destination, with sizes and file types, flagging third-party and plain HTTP uploads.
+    Uploads,
+
+    /// Decode gRPC-web and protobuf bodies, reporting calls, messages and sizes per RPC method.
+    Grpc,
+
+    /// Parse Server-Sent Events and streamed JSON responses into events, with counts, sizes and how long each stream was open.
+    Streams,
+
+    /// Print a shell completion script, use '-' for FILE.
+    Completions(CompletionsArgs),
+
+    /// Write man pages for harper and each of its commands to a directory, use '-' for FILE.
+    Manpages(ManpagesArgs),
+}
+
+impl Commands {
+    /// Whether the command produces structured results that `--template` can render.
+    fn supports_template(&self) -> bool {
+        matches!(
+            self,
+            Commands::ListDomains(_)
+                | Commands::CountSchemes
+                | Commands::CountMethods(_)
+                | Commands::CountRequests
+                | Commands::Count(_)
+                | Commands::SearchFor(_)
+                | Commands::Output(_)
+                | Commands::Duplicates
+        )
+    }
+
+    fn supports_check(&self) -> bool {
+        matches!(
+            self,
+            Commands::BlockList
+                | Commands::Hsts
+                | Commands::TlsAudit(_)
+                | Commands::Csp(CspArgs {
+                    mode: CspMode::Analyze
+                })
+                | Commands::JsLibs(_)
+                | Commands::Pii(_)
+                | Commands::ReferrerAudit
+                | Commands::Homographs
+                | Commands::Hashes(_)
+                | Commands::Consent
+                | Commands::Fingerprinting
+                | Commands::Budget(_)
+                | Commands::Trend(_)
+                | Commands::Assert(_)
+                | Commands::AssetRefs
+                | Commands::ResourceHints
+                | Commands::ExpectAbsent(_)
+                | Commands::I18n
+                | Commands::AcceptMismatch
+                | Commands::Uploads
+        )
+    }
+}
+
+#[derive(Debug, clap::Args)]
+struct CountUrlArgs {
+    #[arg(short, long, help="Method used for sorting, sorting is done at each level of the domain tree.", default_value = SortBy::Frequency.as_ref())]
+    sort: SortBy,
+
+    #[arg(
+        short,
+        long,
+        help = "Merge the tld and the sld, i.e. merge example and .com"
+    )]
+    merge_tld: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "How far down the tree goes, paths continues below the host into URL path segments.",
+        default_value = "domains"
+    )]
+    depth: count_urls::TreeDepth,
+
+    #[arg(
+        long,
+        help = "The maximum number of path segments to show with '--depth paths'."
+    )]
+    max_depth: Option<usize>,
+
+    #[arg(
+        short,
+        long,
+        help = "Only show the N largest entries at each level of the tree."
+    )]
+    top: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Hide entries with fewer than N requests.",
+        default_value_t = 1
+    )]
+    min_count: usize,
+
+    #[arg(
+        short,
+        long,
+        help = "Print one fully-qualified name per line with counts and percentages of the total."
+    )]
+    flat: bool,
+
+    #[arg(
+        long,
+        help = "What the tree shows and sorts by.",
+        default_value = "requests"
+    )]
+    metric: count_urls::Metric,
+}
+
+#[derive(Debug, clap::Args)]
+struct OutputArgs {
+    #[arg(short, long, help = "Print compact JSON on a single line.")]
+    minify: bool,
+
+    #[arg(short, long, help = "Sort object keys alphabetically.")]
+    sort_keys: bool,
+
+    #[arg(short, long, help = "Only print part of the HAR.")]
+    only: Option<output::Projection>,
+}
+
+#[derive(Debug, clap::Args)]
+struct DnsLookupArgs {
+    #[arg(
+        short,
+        long,
+        help = "Look up PTR records for hosts that are IP addresses."
+    )]
+    reverse: bool,
+
+    #[arg(
+        long,
+        help = "Record types to look up, e.g. 'A,AAAA,TXT', rather than the usual 13.",
+        value_delimiter = ',',
+        value_parser = dns::parse_record_type
+    )]
+    types: Vec<hickory_resolver::proto::rr::RecordType>,
+
+    #[arg(long, default_value = "text", help = "How to print the records.")]
+    format: dns::DnsFormat,
+
+    #[arg(
+        long,
+        help = "Only list hosts without AAAA records or without any records at all."
+    )]
+    only_missing: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct WhoisArgs {
+    #[arg(
+        long,
+        help = "Flag domains registered within this many days.",
+        default_value_t = 30
+    )]
+    days: i64,
+}
+
+#[derive(Debug, clap::Args)]
+struct CertTransparencyArgs {
+    #[arg(
+        long,
+        help = "List certificates issued within this many days.",
+        default_value_t = 90
+    )]
+    days: i64,
+}
+
+#[derive(Debug, clap::Args)]
+struct ListDomainsArgs {
+    #[arg(short, long, help = "Show the number of requests to each domain.")]
+    counts: bool,
+
+    #[arg(long, help = "Show when each domain was first requested.")]
+    first_seen: bool,
+
+    #[arg(long, help = "Show when each domain was last requested.")]
+    last_seen: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "Show the server IP addresses each domain resolved to."
+    )]
+    ips: bool,
+
+    #[arg(
+        short,
+        long,
+        help = "Group domains under their registrable domain (eTLD+1)."
+    )]
+    etld1: bool,
+}
+
+#[derive(Debug, Clone, clap::ValueEnum)]
+enum SortBy {
+    /// Sort alphanumerically at each level.
+    Alpha,
+
+    /// Sort by frequency, or the chosen metric, at each level.
+    Frequency,
+}
+
+impl AsRef<str> for SortBy {
+    fn as_ref(&self) -> &str {
+        match self {
+            SortBy::Frequency => "frequency",
+            SortBy::Alpha => "alpha",
+        }
+    }
+}
+
+#[derive(Debug, Clone, clap::ValueEnum)]
+enum ColorChoice {
+    /// Colour output when writing to a terminal.
+    Auto,
+
+    /// Always colour output.
+    Always,
+
+    /// Never colour output.
+    Never,
+}
+
+impl AsRef<str> for ColorChoice {
+    fn as_ref(&self) -> &str {
+        match self {
+            ColorChoice::Auto => "auto",
+            ColorChoice::Always => "always",
+            ColorChoice::Never => "never",
+        }
+    }
+}
+
+#[derive(Debug, clap::Args)]
+struct SearchForArgs {
+    /// The string to search for.
+    #[arg(required_unless_present = "patterns_file")]
+    string: Option<String>,
+
+    #[arg(
+        long,
+        help = "Search for every string and regex in a file, in one pass.",
+        long_help = "Search for every string and regex in a file, in one pass.\n\n\
+            One pattern per line, optionally labelled as `[label] pattern`, with regexes wrapped \
+            in slashes, i.e. `[token] /eyJ[A-Za-z0-9_-]+/`. Blank lines and lines starting with \
+            `#` are skipped. Matches are reported under the pattern's label, or the pattern \
+            itself when it has none."
+    )]
+    patterns_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Encodings to also search for the strings under, defaults to all of them.",
+        long_help = "Encodings to also search for the strings under, defaults to all of them.\n\n\
+            Matches are reported separately for each, to catch known values sent obfuscated. \
+            Regexes from a patterns file are only searched for as they are.",
+        value_delimiter = ','
+    )]
+    transforms: Vec<search_for::Transform>,
+
+    #[arg(
+        long,
+        help = "Print the text around each match with the match highlighted.",
+        conflicts_with_all = ["count_only", "json"]
+    )]
+    show_matches: bool,
+
+    #[arg(long, help = "Only print how many requests and matches were found.")]
+    count_only: bool,
+
+    #[arg(
+        long,
+        help = "Print the results as JSON, with each match's field, byte offset and value.",
+        long_help = "Print the results as JSON, with each match's field, byte offset and value.\n\n\
+            Offsets are into the field serialised as JSON, as it is searched.",
+        conflicts_with = "count_only"
+    )]
+    json: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct SortArgs {
+    #[arg(
+        long,
+        help = "Keys to sort by in order of priority, each optionally ':desc', e.g. 'status,duration:desc'.",
+        long_help = "Keys to sort by in order of priority, each optionally ':desc', e.g. 'status,duration:desc'.\n\n\
+            Keys are time, duration, size, url and status. Entries that tie on every key keep \
+            their order in the HAR, so the output is the same for the same input.",
+        value_delimiter = ',',
+        value_parser = sort::parse_sort_field,
+        required = true
+    )]
+    by: Vec<sort::SortField>,
+
+    #[arg(
+        short,
+        long,
+        help = "List the entries in order instead of printing the HAR.",
+        conflicts_with = "minify"
+    )]
+    list: bool,
+
+    #[arg(short, long, help = "Print compact JSON on a single line.")]
+    minify: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct ExpectAbsentArgs {
+    /// The string that must not be found.
+    #[arg(required_unless_present = "patterns_file")]
+    string: Option<String>,
+
+    #[arg(
+        long,
+        help = "Expect every string and regex in a file to be absent, in the format search-for takes."
+    )]
+    patterns_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Encodings to also search for the strings under, defaults to all of them.",
+        value_delimiter = ','
+    )]
+    transforms: Vec<search_for::Transform>,
+}
+
+#[derive(Debug, clap::Args)]
+struct IpInfoArgs {
+    #[arg(
+        long,
+        help = "GeoLite2 Country or City database, defaults to the one in the data directory."
+    )]
+    country_db: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "GeoLite2 ASN database, defaults to the one in the data directory."
+    )]
+    asn_db: Option<PathBuf>,
+
+    #[arg(short, long, help = "Perform reverse DNS lookups of the IP addresses.")]
+    reverse: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct DataFlowsArgs {
+    #[arg(
+        long,
+        help = "GeoLite2 Country or City database, defaults to the one in the data directory."
+    )]
+    country_db: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "GeoLite2 ASN database, defaults to the one in the data directory."
+    )]
+    asn_db: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Args)]
+struct DiffArgs {
+    #[arg(help = "The HAR file to compare against, taken as the newer capture.")]
+    other: PathBuf,
+
+    #[arg(
+        long,
+        help = "Compare decoded response bodies of requests in both captures.",
+        long_help = "Compare decoded response bodies of requests in both captures. JSON bodies are \
+                     diffed structurally by path, so API contract changes show as changed, added \
+                     and removed fields; other text bodies get a unified diff."
+    )]
+    bodies: bool,
+}
+
+#[derive(Debug, clap::Args)]
+struct TrendArgs {
+    #[arg(help = "Directory of HAR files, one per run, e.g. nightly synthetic tests.")]
+    dir: PathBuf,
+
+    #[arg(long, default_value = "text", help = "How to print the trends.")]
+    format: trend::TrendFormat,
+
+    #[arg(
+        long,
+        default_value_t = 50.0,
+        help = "Percentage increase over earlier runs that counts as a regression.",
+        long_help = "Percentage increase over earlier runs that counts as a regression.\n\n\
+            Runs are ordered by when each capture started. An endpoint regressed when its median\n\
+            time or size in the latest run is this much above the median of the runs before it."
+    )]
+    threshold: f64,
+}
+
+#[derive(Debug, clap::Args)]
+struct AssertArgs {
+    #[arg(
+        long,
+        help = "JSON baseline of expected domains, allowed third parties and maximum sizes.",
+        long_help = "JSON baseline of expected domains, allowed third parties and maximum sizes.\n\n\
+            Record one from a known-good HAR with --record. Later HARs fail when they request\n\
+            first-party domains the baseline doesn't have, stop requesting ones it does, load from\n\
+            new third parties or exceed the total or per-kind sizes."
+    )]
+    baseline: PathBuf,
+
+    #[arg(
+        long,
+        help = "Record the baseline from this HAR instead of checking against it."
+    )]
+    record: bool,
+
+    #[arg(
+        long,
+        default_value_t = 10.0,
+        requires = "record",
+        help = "Percentage sizes may grow by over the recorded HAR."
+    )]
+    headroom: f64,
+}
+
+#[derive(Debug, clap::Args)]
+struct TlsAuditArgs {
+    #[arg(
+        short,
+        long,
+        help = "Flag certificates expiring within this many days.",
+        default_value_t = 30
+    )]
+    warn_days: i64,
+}
+
+#[derive(Debug, clap::Args)]
+struct CspArgs {
+    /// Whether to analyze existing policies or generate new ones.
+    #[arg(default_value = CspMode::Analyze.as_ref())]
+    mode: CspMode,
+}
+
+#[derive(Debug, Clone, clap::ValueEnum)]
+enum CspMode {
+    /// Analyze existing policies for weaknesses.
+    Analyze,
+
+    /// Generate a draft policy from the resource origins loaded by each page.
+    Generate,
+}
+
+impl AsRef<str> for CspMode {
+    fn as_ref(&self) -> &str {
+        match self {
+            CspMode::Analyze => "analyze",
+            CspMode::Generate => "generate",
+        }
+    }
+}
+
+#[derive(Debug, clap::Args)]
+struct JsLibsArgs {
+    #[arg(
+        long,
+        help = "Use a retire.js format repository instead of the bundled one."
+    )]
+    db: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Args)]
+struct CaptureArgs {
+    #[arg(short, long, help = "Port to listen on.", default_value_t = 8080)]
+    port: u16,
+
+    #[arg(short, long, help = "File to write the captured HAR to.")]
+    output: PathBuf,
+}
+
+#[derive(Debug, clap::Args)]
+struct CountArgs {
+    #[arg(long, help = "What to group requests by.")]
+    by: count::CountKey,
+
+    #[arg(long, help = "What to group requests by within each group.")]
+    then_by: Option<count::CountKey>,
+
+    #[arg(long, help = "How many groups to list at each level, all by default.")]
+    top: Option<usize>,
+}
+
+#[derive(Debug, clap::Args)]
+struct CountMethodsArgs {
+    #[arg(
+        long,
+        help = "How many domains to list for each method.",
+        default_value_t = 10
+    )]
+    top: usize,
+}
+
+#[derive(Debug, clap::Args)]
+struct BandwidthArgs {
+    #[arg(
+        long,
+        help = "Links to estimate load times on, e.g. '3g,4g' or '2000/500/100'.",
+        long_help = "Links to estimate load times on, e.g. '3g,4g' or '2000/500/100'.\n\n\
+            Profiles are WebPageTest's: slow-3g, 3g, 4g, lte, cable and fiber. Custom links are\n\
+            given as down and up speeds in Kbps and the round trip in ms.",
+        value_delimiter = ',',
+        value_parser = bandwidth::parse_link,
+        default_value = "3g,4g"
+    )]
+    link: Vec<bandwidth::Link>,
+}
+
+#[derive(Debug, clap::Args)]
+struct InlineUrlsArgs {
+    #[arg(
+        long,
+        help = "How many of the largest data: URIs to list.",
+        default_value_t = 10
+    )]
+    top: usize,
+
+    #[arg(
+        long,
+        help = "Write each distinct decoded payload to a directory, named by request number."
+    )]
+    extract: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Args)]
+struct MultipartArgs {
+    #[arg(
+        long,
+        help = "Write each distinct uploaded file to a directory, named by request number."
+    )]
+    extract: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Args)]
+struct CompletionsArgs {
+    /// The shell to complete for.
+    shell: clap_complete::Shell,
+}
+
+#[derive(Debug, clap::Args)]
+struct ManpagesArgs {
+    /// Directory to write the pages to, created if missing.
+    dir: PathBuf,
+}
+
+#[derive(Debug, clap::Args)]
+struct DnsCacheArgs {
+    #[command(subcommand)]
+    action: DnsCacheAction,
+}
+
+#[derive(Subcommand, Debug)]
+enum DnsCacheAction {
+    /// Delete every cached DNS answer.
+    Clear,
+}
+
+#[derive(Debug, clap::Args)]
+struct ImportArgs {
+    #[command(subcommand)]
+    format: ImportFormat,
+
+    #[arg(
+        short,
+        long,
+        help = "Write the HAR to a file instead of stdout.",
+        global = true
+    )]
+    output: Option<PathBuf>,
+}
+
+#[derive(Subcommand, Debug)]
+enum ImportFormat {
+    /// Import a mitmproxy flow dump, i.e. from `mitmdump -w`.
+    Mitmproxy {
+        /// The flow file to import.
+        input: PathBuf,
+    },
+
+    /// Reassemble plaintext HTTP/1.x traffic from a pcap or pcapng capture.
+    Pcap {
+        /// The capture file to import.
+        input: PathBuf,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+struct ExportArgs {
+    #[command(subcommand)]
+    format: ExportFormat,
+}
+
+#[derive(Subcommand, Debug)]
+enum ExportFormat {
+    /// Generate a k6 script replaying the requests with their captured think-times.
+    K6 {
+        #[arg(short, long, help = "Write the script to a file instead of stdout.")]
+        output: Option<PathBuf>,
+    },
+
+    /// Write each request as a raw HTTP/1.1 message to its own file, i.e. for Burp or ffuf.
+    Raw {
+        #[arg(short, long, help = "Directory to write the request files to.")]
+        output: PathBuf,
+    },
+
+    /// Summarise request counts, bytes, durations and statuses in the Prometheus text format.
+    Metrics {
+        #[arg(short, long, help = "Write the metrics to a file instead of stdout.")]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, clap::Args)]
+struct PiiArgs {
+    #[arg(
+        long,
+        help = "TOML file of `class = 'regex'` patterns to add to or override the built-in set."
+    )]
+    patterns: Option<PathBuf>,
+}
+
+#[derive(Debug, clap::Args)]
+struct BudgetArgs {
+    #[arg(
+        short,
+        long,
+        help = "TOML file of budgets to check.",
+        long_help = "TOML file of budgets to check.\n\n\
+            Keys: total_bytes, requests, third_party_bytes, third_party_requests, slowest_entry_ms,\n\
+            and [mime.<kind or type>] tables with bytes and requests, e.g. [mime.\"image/*\"].\n\
+            Sizes can be numbers of bytes or strings with units, e.g. \"500 KiB\"."
+    )]
+    config: PathBuf,
+}
 
-    /// Return the contents of the HAR.
-    Output,
+#[derive(Debug, clap::Args)]
+struct EntropyArgs {
+    #[arg(
+        short,
+        long,
+        help = "Minimum Shannon entropy in bits per character, scaled down for hex values.",
+        default_value_t = 4.5
+    )]
+    threshold: f64,
 
-    /// Check if URLs contained in the HAR are using DNSSEC.
-    DNSSECAudit,
+    #[arg(long, help = "Ignore values shorter than this.", default_value_t = 20)]
+    min_length: usize,
 
-    /// Lookup common DNS record types of URLs contained in the HAR.
-    DNSLookup,
+    #[arg(
+        long,
+        help = "Parameter or cookie names to ignore, can be given multiple times."
+    )]
+    allow: Vec<String>,
+}
 
-    /// Downloads common blocklists, use '-' for FILE.
-    GetBlockLists,
+#[derive(Debug, clap::Args)]
+struct TimelineArgs {
+    #[arg(
+        short,
+        long,
+        help = "Bucket size, e.g. 500ms, 1s, 10s or 1m.",
+        default_value = "1s",
+        value_parser = format::parse_duration
+    )]
+    interval: std::time::Duration,
+}
 
-    /// Deletes block lists.
-    RemoveBlockLists,
+#[derive(Debug, clap::Args)]
+struct PollingArgs {
+    #[arg(
+        long,
+        help = "Minimum number of requests to count as polling.",
+        default_value_t = 3
+    )]
+    min_count: usize,
 
-    /// Checks for URLs in common blocklists.
-    BlockList,
+    #[arg(
+        long,
+        help = "Maximum jitter as a fraction of the period, e.g. 0.25 for 25%.",
+        default_value_t = 0.25
+    )]
+    tolerance: f64,
 }
 
 #[derive(Debug, clap::Args)]
-struct CountUrlArgs {
-    #[arg(short, long, help="Method used for sorting, sorting is done at each level of the domain tree.", default_value = SortBy::Frequency.as_ref())]
-    sort: SortBy,
+struct SplitArgs {
+    #[arg(long, help = "What to split the entries by.", value_enum)]
+    by: split::SplitBy,
 
     #[arg(
         short,
         long,
-        help = "Merge the tld and the sld, i.e. merge example and .com"
+        help = "Directory to write the HAR files to.",
+        default_value = "."
     )]
-    merge_tld: bool,
+    output: PathBuf,
 }
 
-#[derive(Debug, Clone, clap::ValueEnum)]
-enum SortBy {
-    /// Sort alphanumerically at each level.
-    Alpha,
-
-    /// Sort by frequency at each level.
-    Frequency,
+#[derive(Debug, clap::Args)]
+struct AnonymizeArgs {
+    #[arg(short, long, help = "Write the HAR to a file instead of stdout.")]
+    output: Option<PathBuf>,
 }
 
-impl AsRef<str> for SortBy {
-    fn as_ref(&self) -> &str {
-        match self {
-            SortBy::Frequency => "frequency",
-            SortBy::Alpha => "alpha",
-        }
-    }
+#[derive(Debug, clap::Args)]
+struct ScrubArgs {
+    #[arg(long, help = "Regex to replace.")]
+    pattern: String,
+
+    #[arg(
+        long,
+        help = "Replacement text, which may refer to capture groups as `$1` or `${name}`."
+    )]
+    replace: String,
+
+    #[arg(short, long, help = "Write the HAR to a file instead of stdout.")]
+    output: Option<PathBuf>,
 }
 
 #[derive(Debug, clap::Args)]
-struct SearchForArgs {
-    /// The string to search for.
-    string: String,
+struct HashesArgs {
+    #[arg(
+        long,
+        help = "File of SHA-256 hashes to flag, one per line with an optional file name or URL as `sha256sum` writes them."
+    )]
+    list: Option<PathBuf>,
 }
 
 #[tokio::main]
 async fn main() -> ExitCode {
-    env_logger::init();
-
-    if let Err(e) = run().await {
+    // commands return early in places, so the cache is written once they're done
+    let result = run().await.and_then(|()| cache::save());
+    lenient_url::report();
+    if let Err(e) = result {
         eprintln!("{}: {:#}", "Error".red().bold(), e);
-        return ExitCode::FAILURE;
+        return ExitCode::from(check::EXIT_ERROR);
+    }
+    if check::failed() {
+        return ExitCode::from(check::EXIT_FINDINGS);
     }
     ExitCode::SUCCESS
 }
 
 #[allow(unreachable_code)]
-fn read_input(file_path: &String) -> Result<String> {
-    fs::read_to_string(file_path).with_context(|| format!("Failed to read file: {}", file_path))
-}
-
-fn parse_har(input: &str) -> Result<Har> {
-    // parse the file
-    serde_json::from_str(input)
-        .map_err(|e| {
-            // on error, get 1-based line and column number of error
-            let line = e.line();
-            let column = e.column();
-
-            // get error string and class
-            let err_str = e.to_string();
-            let error_class = e.classify();
-
-            // get surrounding lines of context
-            let lines: Vec<&str> = input.lines().collect();
-            let line_index = line.saturating_sub(1);
-            let start = line_index.saturating_sub(5);
-            let end = (line_index + 5).min(lines.len());
-            let context_lines = lines.get(start..end).unwrap_or_default();
-            let error_line_in_context = line_index.saturating_sub(start);
-
-            let mut context_str = String::new();
-            for (i, line) in context_lines.iter().enumerate() {
-                // add source
-                context_str.push_str(line);
-                context_str.push('\n');
-
-                // add line pointer
-                if i == error_line_in_context {
-                    let pointer = format!(
-                        "{}{}",
-                        " ".repeat(column.saturating_sub(1)),
-                        "^-- Error occurred here".purple().bold()
-                    );
-                    context_str.push_str(&pointer);
-                    context_str.push('\n');
-                }
-            }
+fn read_input(file_path: &String) -> Result<Vec<u8>> {
+    let read = || -> io::Result<Vec<u8>> {
+        let file = fs::File::open(file_path)?;
+        let len = file.metadata()?.len();
+        let pb = if len >= progress::MIN_INPUT_BYTES {
+            progress::bytes(len).with_message("Reading")
+        } else {
+            ProgressBar::hidden()
+        };
 
-            // cleanup trailing newline
-            if context_str.ends_with('\n') {
-                context_str.pop();
-            }
+        let mut contents = Vec::with_capacity(len as usize);
+        pb.wrap_read(file).read_to_end(&mut contents)?;
+        pb.finish_and_clear();
+        Ok(contents)
+    };
+    read().with_context(|| format!("Failed to read file: {}", file_path))
+}
 
-            // create error message based off error class
-            let error_msg = match error_class {
-                Category::Syntax => format!(
-                    "{}: {}",
-                    "JSON syntax error".red().bold(),
-                    err_str.split(" at ").next().unwrap_or(&err_str)
-                ),
-                Category::Eof => "Unexpected end of JSON input".red().bold().to_string(),
-                Category::Data => {
-                    if let Some(field) = err_str
-                        .strip_prefix("missing field `")
-                        .and_then(|s| s.split('`').next())
-                    {
-                        format!("{}: `{}`", "Missing required field".red().bold(), field)
-                    } else {
-                        format!("{}: {}", "Data validation error".red().bold(), err_str)
-                    }
-                }
-                _ => format!("{}: {}", "JSON parsing error".red().bold(), err_str),
-            };
+/// Parse with simd-json, rerunning serde_json over anything it rejects so errors still get a
+/// line and column.
+#[cfg(feature = "simd")]
+fn parse_har(input: &mut [u8]) -> Result<Har<'_>> {
+    // simd-json rewrites strings in place, so the error needs a copy of the original
+    let original = input.to_vec();
+    simd_json::serde::from_slice(input).map_err(|simd_err| match parse_json(&original) {
+        Err(e) => e,
+        Ok(_) => anyhow!(simd_err).context("Failed to parse HAR file"),
+    })
+}
 
-            anyhow!(
-                "HAR validation failed at line {line}:{column}\n\
-             {}\n\
-             {}:\n{}\n",
-                error_msg,
-                "Context".yellow().bold(),
-                context_str
-            )
-        })
+#[cfg(not(feature = "simd"))]
+fn parse_har(input: &mut [u8]) -> Result<Har<'_>> {
+    parse_json(input)
+}
+
+fn parse_json(input: &[u8]) -> Result<Har<'_>> {
+    serde_json::from_slice(input)
+        .map_err(|e| har_error(input, e))
         .context("Failed to parse HAR file")
 }
 
+/// Describe a parse error with the lines around where it happened.
+fn har_error(input: &[u8], e: serde_json::Error) -> anyhow::Error {
+    // on error, get 1-based line and column number of error
+    let line = e.line();
+    let column = e.column();
+
+    // get error string and class
+    let err_str = e.to_string();
+    let error_class = e.classify();
+
+    // get surrounding lines of context
+    let input = String::from_utf8_lossy(input);
+    let lines: Vec<&str> = input.lines().collect();
+    let line_index = line.saturating_sub(1);
+    let start = line_index.saturating_sub(5);
+    let end = (line_index + 5).min(lines.len());
+    let context_lines = lines.get(start..end).unwrap_or_default();
+    let error_line_in_context = line_index.saturating_sub(start);
+
+    let mut context_str = String::new();
+    for (i, line) in context_lines.iter().enumerate() {
+        // add source
+        context_str.push_str(line);
+        context_str.push('\n');
+
+        // add line pointer
+        if i == error_line_in_context {
+            let pointer = format!(
+                "{}{}",
+                " ".repeat(column.saturating_sub(1)),
+                "^-- Error occurred here".purple().bold()
+            );
+            context_str.push_str(&pointer);
+            context_str.push('\n');
+        }
+    }
+
+    // cleanup trailing newline
+    if context_str.ends_with('\n') {
+        context_str.pop();
+    }
+
+    // create error message based off error class
+    let error_msg = match error_class {
+        Category::Syntax => format!(
+            "{}: {}",
+            "JSON syntax error".red().bold(),
+            err_str.split(" at ").next().unwrap_or(&err_str)
+        ),
+        Category::Eof => "Unexpected end of JSON input".red().bold().to_string(),
+        Category::Data => {
+            if let Some(field) = err_str
+                .strip_prefix("missing field `")
+                .and_then(|s| s.split('`').next())
+            {
+                format!("{}: `{}`", "Missing required field".red().bold(), field)
+            } else {
+                format!("{}: {}", "Data validation error".red().bold(), err_str)
+            }
+        }
+        _ => format!("{}: {}", "JSON parsing error".red().bold(), err_str),
+    };
+
+    anyhow!(
+        "HAR validation failed at line {line}:{column}\n\
+     {}\n\
+     {}:\n{}\n",
+        error_msg,
+        "Context".yellow().bold(),
+        context_str
+    )
+}
+
+/// Narrow a HAR down with the global time, selector, domain, method, status, MIME and entry
+/// filters, in that order.
+fn apply_filters(har: &mut Har, args: &Args) {
+    let (after, before) = args.between.unwrap_or((args.after, args.before));
+    filter::filter_by_time(har, after, before);
+
+    if let Some(selector) = &args.selector {
+        filter::filter_by_selector(har, selector);
+    }
+
+    filter::filter_by_domains(har, &args.include_domain, &args.exclude_domain);
+    filter::filter_by_method(har, &args.method);
+    filter::filter_by_status(har, &args.status);
+    filter::filter_by_mime(har, &args.mime);
+    filter::filter_by_entries(har, &args.entries);
+}
+
 async fn run() -> Result<()> {
-    let args = Args::parse();
+    let matches = Args::command().get_matches_from(config::apply_config(
+        Args::command(),
+        std::env::args_os().collect(),
+    )?);
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if let Commands::DNSLookup(lookup_args) = &args.command {
+        if lookup_args.only_missing && lookup_args.format == dns::DnsFormat::Zone {
+            Args::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--only-missing lists hosts, so can't be written with --format zone",
+                )
+                .exit();
+        }
+    }
+    let command_name = matches.subcommand_name().unwrap_or_default().to_string();
+
+    match args.color {
+        ColorChoice::Auto => {}
+        ColorChoice::Always => colored::control::set_override(true),
+        ColorChoice::Never => colored::control::set_override(false),
+    }
+    progress::set_quiet(args.quiet);
+    logging::init(args.verbose, args.log_format);
+    offline::set_offline(args.offline);
+    dns::configure(dns::DnsSettings {
+        servers: args.dns_server.clone(),
+        timeout: args.dns_timeout,
+        retries: args.dns_retries,
+    });
+    domain::set_ownership(&args.first_party, args.entities.as_deref())?;
+    // budgets, baselines and absence checks are only worth setting if breaking them fails
+    check::set_check(
+        args.check
+            || matches!(
+                args.command,
+                Commands::Budget(_) | Commands::Assert(_) | Commands::ExpectAbsent(_)
+            ),
+    );
+
+    // before the commands that don't read a HAR return, so they refuse these flags too
+    if args.template.is_some() && !args.command.supports_template() {
+        return Err(anyhow!("--template is not supported by this command"));
+    }
+
+    if args.check && !args.command.supports_check() {
+        return Err(anyhow!("--check is not supported by this command"));
+    }
 
     match &args.command {
         Commands::GetBlockLists => return blocklist::download_all_blocklists().await,
         Commands::RemoveBlockLists => return blocklist::remove_blocklists(),
+        Commands::DnsCache(cache_args) => {
+            return match cache_args.action {
+                DnsCacheAction::Clear => {
+                    let cleared = cache::clear_dns()?;
+                    println!(
+                        "{} {} cached DNS answers",
+                        "Cleared".green().bold(),
+                        cleared
+                    );
+                    Ok(())
+                }
+            };
+        }
+        Commands::GetHstsPreload => return hsts::download_preload_list().await,
+        Commands::Capture(capture_args) => {
+            return capture::capture(capture_args.port, &capture_args.output).await;
+        }
+        Commands::Completions(completions_args) => {
+            completions::completions(Args::command(), completions_args.shell);
+            return Ok(());
+        }
+        Commands::Manpages(manpages_args) => {
+            return completions::manpages(Args::command(), &manpages_args.dir);
+        }
+        Commands::Trend(trend_args) => {
+            let loader = trend::Loader {
+                parse: parse_har,
+                filter: &|har| apply_filters(har, &args),
+            };
+            return trend::trend(
+                &trend_args.dir,
+                trend_args.format,
+                trend_args.threshold,
+                &loader,
+            );
+        }
+        Commands::Import(import_args) => {
+            let entries = match &import_args.format {
+                ImportFormat::Mitmproxy { input } => mitmproxy::import_flows(input)?,
+                ImportFormat::Pcap { input } => pcap::import_capture(input)?,
+            };
+            return import::write_har(entries, import_args.output.as_deref());
+        }
         _ => {}
     }
 
-    let contents = match args.file {
+    let input_name = args.file.clone();
+    let read_span = info_span!("read", file = %input_name).entered();
+    let mut contents = match &args.file {
         stdin if stdin == "-" => {
             let mut stdin = io::stdin();
             if stdin.is_terminal() {
@@ -228,31 +1513,53 @@ async fn run() -> Result<()> {
                 return Err(anyhow!(format!("{}", clap_err_str)));
             }
 
-            let mut contents = String::new();
-            stdin.read_to_string(&mut contents)?;
+            let mut contents = Vec::new();
+            stdin.read_to_end(&mut contents)?;
             contents
         }
-        file => read_input(&file)?,
+        file => read_input(file)?,
     };
+    drop(read_span);
 
-    let mut parsed = parse_har(&contents)?;
-
-    if let Some(dt) = args.before {
-        filter::filter_by_time(&mut parsed, dt, false);
+    if !args.no_cache {
+        cache::load(&contents)?;
     }
 
-    if let Some(dt) = args.after {
-        filter::filter_by_time(&mut parsed, dt, true);
+    let pb = if contents.len() as u64 >= progress::MIN_INPUT_BYTES {
+        progress::spinner("Parsing")
+    } else {
+        ProgressBar::hidden()
+    };
+    let parse_span = info_span!("parse", bytes = contents.len()).entered();
+    let mut parsed = parse_har(&mut contents)?;
+    pb.finish_and_clear();
+    info!(entries = parsed.log.entries.len(), "parsed HAR");
+    drop(parse_span);
+
+    let quirks = quirks::apply(&mut parsed);
+    if args.explain_quirks {
+        quirks::explain(&quirks);
+    } else {
+        quirks::report(&quirks);
     }
 
+    let filter_span = info_span!("filter").entered();
+    apply_filters(&mut parsed, &args);
+    info!(entries = parsed.log.entries.len(), "filtered HAR");
+    drop(filter_span);
+
+    // instrumented rather than entered, since a span entered across an await stays entered
+    // for whatever else runs on the thread meanwhile
+    analyze(args, parsed)
+        .instrument(info_span!("analyze", command = %command_name))
+        .await
+}
+
+/// Run the command on the parsed and filtered HAR.
+async fn analyze(args: Args, mut parsed: Har<'_>) -> Result<()> {
     match args.command {
         Commands::CountUrls(count_args) => {
-            let tld_extractor = TldOption::default()
-                .cache_path(".tld_cache")
-                .private_domains(false)
-                .update_local(false)
-                .naive_mode(false)
-                .build();
+            let tld_extractor = domain::build_tld_extractor();
 
             let mut domain_tree = count_urls::DomainNode::default();
             count_urls::build_domain_tree(
@@ -260,22 +1567,65 @@ async fn run() -> Result<()> {
                 &mut domain_tree,
                 &tld_extractor,
                 count_args.merge_tld,
+                count_args.depth,
+                count_args.max_depth,
+            );
+            let metric = count_args.metric;
+            count_urls::prune(
+                &mut domain_tree,
+                count_args.top,
+                count_args.min_count,
+                metric,
             );
 
+            if count_args.flat {
+                let mut rows = count_urls::flatten(&domain_tree);
+                match count_args.sort {
+                    SortBy::Alpha => rows.sort_by(|a, b| a.0.cmp(&b.0)),
+                    SortBy::Frequency => rows.sort_by(|a, b| {
+                        metric
+                            .value(b.1)
+                            .cmp(&metric.value(a.1))
+                            .then_with(|| a.0.cmp(&b.0))
+                    }),
+                }
+                count_urls::print_flat(&rows, &domain_tree, metric);
+                return Ok(());
+            }
+
             match count_args.sort {
                 SortBy::Alpha => {
-                    count_urls::print_tree(&domain_tree, &mut |(name, _)| name.to_string());
+                    count_urls::print_tree(&domain_tree, metric, &mut |(name, _)| name.to_string());
                 }
                 SortBy::Frequency => {
-                    count_urls::print_tree(&domain_tree, &mut |(_, node)| Reverse(node.count));
+                    count_urls::print_tree(&domain_tree, metric, &mut |(_, node)| {
+                        Reverse(metric.value(node))
+                    });
                 }
             }
         }
 
-        Commands::ListDomains => {
+        Commands::ListDomains(list_args) => {
             let domains = list_domains::list_domains(&parsed);
-            for domain in domains {
-                println!("{}", domain);
+            if let Some(path) = &args.template {
+                return template::render(path, "list-domains", &domains, &parsed);
+            }
+
+            let options = list_domains::ListOptions {
+                counts: list_args.counts,
+                first_seen: list_args.first_seen,
+                last_seen: list_args.last_seen,
+                ips: list_args.ips,
+                etld1: list_args.etld1,
+            };
+            if options.enriched() {
+                let tld_extractor = domain::build_tld_extractor();
+                list_domains::print_inventory(&parsed, &options, &tld_extractor);
+                return Ok(());
+            }
+
+            for host in domains {
+                println!("{}", domain::display_host(&host));
             }
         }
 
@@ -286,51 +1636,352 @@ async fn run() -> Result<()> {
             let mut counts_vec: Vec<(&String, &usize)> = counts.iter().collect();
             counts_vec.sort_by_key(|a| Reverse(a.1));
 
+            if let Some(path) = &args.template {
+                let results: Vec<_> = counts_vec
+                    .iter()
+                    .map(|(scheme, count)| json!({ "scheme": scheme, "count": count }))
+                    .collect();
+                return template::render(path, "count-schemes", &results, &parsed);
+            }
+
             for (scheme, count) in counts_vec {
                 println!("{}: {}", scheme, count);
             }
         }
 
+        Commands::CountMethods(methods_args) => {
+            if let Some(path) = &args.template {
+                let counts = count_methods::get_counts(&parsed);
+                return template::render(path, "count-methods", &counts, &parsed);
+            }
+
+            count_methods::count_methods(&parsed, methods_args.top);
+        }
+
         Commands::CountRequests => {
             let count = count_requests::get_counts(&parsed);
+            if let Some(path) = &args.template {
+                return template::render(path, "count-requests", &count, &parsed);
+            }
 
             println!("Found {} requests.", count);
         }
 
+        Commands::Count(count_args) => {
+            let groups = count::count(&parsed, count_args.by, count_args.then_by);
+            if let Some(path) = &args.template {
+                return template::render(path, "count", &groups, &parsed);
+            }
+
+            count::print_counts(&groups, count_args.by, count_args.then_by, count_args.top);
+        }
+
         Commands::SearchFor(search_args) => {
-            let matches = search_for::search_for(&parsed, &search_args.string);
-            for result in matches {
-                println!("Found in request {}:", result.request_num);
-                println!(
-                    "Time: {}\nURL: {}\nMethod: {}\nIn fields: {:?}\n",
-                    result.time, result.url, result.method, result.in_fields
-                );
+            let needles = search_for::load_needles(
+                search_args.string.as_deref(),
+                search_args.patterns_file.as_deref(),
+            )?;
+
+            let transforms = if search_args.transforms.is_empty() {
+                search_for::Transform::ALL.to_vec()
+            } else {
+                search_args.transforms.clone()
+            };
+            let results = search_for::search_transformed(&parsed, &needles, &transforms)?;
+            let show_patterns = search_args.patterns_file.is_some();
+
+            let mut json_results = serde_json::Map::new();
+            for (transform, matches) in &results {
+                let key = transform.map_or("matches", |t| t.key());
+                json_results.insert(key.to_string(), serde_json::to_value(matches)?);
             }
+            let encoding = |transform: &Option<search_for::Transform>| {
+                transform.map(|t| format!("{} encoded", t.name()))
+            };
 
-            let b64_search_string = BASE64_STANDARD_NO_PAD.encode(&search_args.string);
-            let matches_b64 = search_for::search_for(&parsed, &b64_search_string);
-            for result in matches_b64 {
-                println!("Found base64 encoded in request {}:", result.request_num);
-                println!(
-                    "Time: {}\nURL: {}\nMethod: {}\nIn fields: {:?}\n",
-                    result.time, result.url, result.method, result.in_fields
-                );
+            if let Some(path) = &args.template {
+                return template::render(path, "search-for", &json_results, &parsed);
+            }
+
+            if search_args.json {
+                println!("{}", serde_json::to_string_pretty(&json_results)?);
+            } else if search_args.count_only {
+                for (transform, matches) in &results {
+                    search_for::print_counts(matches, encoding(transform).as_deref());
+                }
+            } else {
+                for (transform, matches) in &results {
+                    search_for::print_results(
+                        matches,
+                        encoding(transform).as_deref(),
+                        search_args.show_matches,
+                        show_patterns,
+                    );
+                }
+            }
+        }
+
+        Commands::ExpectAbsent(absent_args) => {
+            let needles = search_for::load_needles(
+                absent_args.string.as_deref(),
+                absent_args.patterns_file.as_deref(),
+            )?;
+            let transforms = if absent_args.transforms.is_empty() {
+                search_for::Transform::ALL.to_vec()
+            } else {
+                absent_args.transforms.clone()
+            };
+            let results = search_for::search_transformed(&parsed, &needles, &transforms)?;
+            expect_absent::expect_absent(&needles, &results);
+        }
+
+        Commands::Output(output_args) => {
+            if let Some(path) = &args.template {
+                return template::render(path, "output", &parsed.log.entries, &parsed);
             }
+
+            output::output(
+                &parsed,
+                output_args.only,
+                output_args.minify,
+                output_args.sort_keys,
+            )?;
         }
 
-        Commands::Output => {
-            println!("{}", json::stringify_pretty(json::parse(&contents)?, 4));
+        Commands::Sort(sort_args) => {
+            let numbers = sort::sort_entries(&mut parsed, &sort_args.by);
+            if sort_args.list {
+                sort::print_listing(&parsed, &numbers);
+            } else {
+                output::output(&parsed, None, sort_args.minify, false)?;
+            }
         }
 
         Commands::DNSSECAudit => dns::dnssec_audit(&parsed).await?,
 
-        Commands::DNSLookup => dns::dns_lookup(&parsed).await?,
+        Commands::DNSLookup(lookup_args) => {
+            dns::dns_lookup(
+                &parsed,
+                lookup_args.reverse,
+                &lookup_args.types,
+                lookup_args.format,
+                lookup_args.only_missing,
+            )
+            .await?
+        }
+
+        Commands::DNSSecurity => dns_security::dns_security(&parsed).await?,
+
+        Commands::ReverseDNS => reverse_dns::print_reverse_dns(&parsed).await?,
+
+        Commands::Whois(whois_args) => whois::print_whois(&parsed, whois_args.days).await?,
+
+        Commands::CertTransparency(ct_args) => ct::print_ct(&parsed, ct_args.days).await?,
 
         Commands::GetBlockLists => unreachable!(),
 
         Commands::RemoveBlockLists => unreachable!(),
 
+        Commands::DnsCache(_) => unreachable!(),
+
         Commands::BlockList => blocklist::check_blocklists(&parsed)?,
+
+        Commands::GetHstsPreload => unreachable!(),
+
+        Commands::Hsts => hsts::hsts_audit(&parsed)?,
+
+        Commands::Servers => servers::print_servers(&parsed),
+
+        Commands::IpInfo(ip_args) => {
+            ip_info::print_ip_info(&parsed, ip_args.country_db, ip_args.asn_db, ip_args.reverse)
+                .await?
+        }
+
+        Commands::TlsAudit(tls_args) => tls_audit::tls_audit(&parsed, tls_args.warn_days).await?,
+
+        Commands::Csp(csp_args) => match csp_args.mode {
+            CspMode::Analyze => csp::csp_analyze(&parsed),
+            CspMode::Generate => csp::csp_generate(&parsed),
+        },
+
+        Commands::JsLibs(js_args) => js_libs::js_libs(&parsed, js_args.db)?,
+
+        Commands::Capture(_) => unreachable!(),
+
+        Commands::CookieLineage => cookie_lineage::cookie_lineage(&parsed),
+
+        Commands::Import(_) => unreachable!(),
+
+        Commands::Trend(_) => unreachable!(),
+
+        Commands::Completions(_) => unreachable!(),
+
+        Commands::Manpages(_) => unreachable!(),
+
+        Commands::Export(export_args) => match export_args.format {
+            ExportFormat::K6 { output } => {
+                export::write_output(&export::k6_script(&parsed), output.as_deref())?
+            }
+            ExportFormat::Raw { output } => {
+                let written = export::write_raw_requests(&parsed, &output)?;
+                println!(
+                    "{}: {} requests to {:?}",
+                    "Saved".green().bold(),
+                    written,
+                    output
+                );
+            }
+            ExportFormat::Metrics { output } => {
+                export::write_output(&export::metrics(&parsed), output.as_deref())?
+            }
+        },
+
+        Commands::Duplicates => match &args.template {
+            Some(path) => {
+                let duplicates = duplicates::find_duplicates(&parsed);
+                template::render(path, "duplicates", &duplicates, &parsed)?
+            }
+            None => duplicates::print_duplicates(&parsed),
+        },
+
+        Commands::Pii(pii_args) => pii::pii(&parsed, pii_args.patterns)?,
+
+        Commands::Beacons => beacons::beacons(&parsed),
+
+        Commands::ReferrerAudit => referrer_audit::referrer_audit(&parsed),
+
+        Commands::AuthFlows => auth_flows::auth_flows(&parsed),
+
+        Commands::SessionTokens => session_tokens::session_tokens(&parsed),
+
+        Commands::Budget(budget_args) => budget::budget(&parsed, &budget_args.config)?,
+
+        Commands::Assert(assert_args) => assert::assert(
+            &parsed,
+            &assert_args.baseline,
+            assert_args.record,
+            assert_args.headroom,
+        )?,
+
+        Commands::Score => score::score(&parsed),
+
+        Commands::Protocols => protocols::protocols(&parsed),
+
+        Commands::Compression => compression::compression(&parsed),
+
+        Commands::MimeSniff => mime_sniff::mime_sniff(&parsed),
+
+        Commands::AcceptMismatch => accept_mismatch::accept_mismatch(&parsed),
+
+        Commands::Forms => forms::forms(&parsed),
+
+        Commands::I18n => i18n::i18n(&parsed),
+
+        Commands::Entropy(entropy_args) => entropy::entropy(
+            &parsed,
+            &entropy::EntropyOptions {
+                threshold: entropy_args.threshold,
+                min_length: entropy_args.min_length,
+                allowed: entropy_args.allow,
+            },
+        ),
+
+        Commands::Timeline(timeline_args) => timeline::timeline(&parsed, timeline_args.interval),
+
+        Commands::Polling(polling_args) => {
+            polling::polling(&parsed, polling_args.min_count, polling_args.tolerance)
+        }
+
+        Commands::Homographs => homographs::homographs(&parsed),
+
+        Commands::Split(split_args) => {
+            // name the parts after the input so splits of different captures don't collide
+            let prefix = Path::new(&args.file)
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .filter(|_| args.file != "-")
+                .unwrap_or("split");
+            let written = split::split(&parsed, split_args.by, &split_args.output, prefix)?;
+            split::print_split(&written, &split_args.output);
+        }
+
+        Commands::Anonymize(anonymize_args) => {
+            let pseudonyms = anonymize::anonymize(&mut parsed)?;
+            let json = serde_json::to_string_pretty(&parsed)?;
+            export::write_output(&json, anonymize_args.output.as_deref())?;
+            if let Some(output) = anonymize_args.output {
+                println!(
+                    "{}: {} hosts, {} IPs and {} identifiers replaced, to {:?}",
+                    "Saved".green().bold(),
+                    pseudonyms.hosts(),
+                    pseudonyms.ips(),
+                    pseudonyms.identifiers(),
+                    output
+                );
+            }
+        }
+
+        Commands::Scrub(scrub_args) => {
+            let replaced = scrub::scrub(&mut parsed, &scrub_args.pattern, &scrub_args.replace)?;
+            let json = serde_json::to_string_pretty(&parsed)?;
+            export::write_output(&json, scrub_args.output.as_deref())?;
+            if let Some(output) = scrub_args.output {
+                println!(
+                    "{}: {} replacements, to {:?}",
+                    "Saved".green().bold(),
+                    replaced,
+                    output
+                );
+            }
+        }
+
+        Commands::Hashes(hashes_args) => {
+            hashes::print_hashes(&parsed, hashes_args.list.as_deref())?
+        }
+
+        Commands::Lcp => lcp::lcp(&parsed),
+
+        Commands::Fonts => fonts::fonts(&parsed),
+
+        Commands::Consent => consent::consent(&parsed),
+
+        Commands::Fingerprinting => fingerprinting::fingerprinting(&parsed),
+
+        Commands::AssetRefs => asset_refs::asset_refs(&parsed),
+
+        Commands::ServiceWorker => service_worker::service_worker(&parsed),
+
+        Commands::ResourceHints => resource_hints::resource_hints(&parsed),
+
+        Commands::EarlyHints => early_hints::early_hints(&parsed),
+
+        Commands::Bandwidth(bandwidth_args) => bandwidth::bandwidth(&parsed, &bandwidth_args.link),
+
+        Commands::InlineUrls(inline_args) => {
+            inline_urls::inline_urls(&parsed, inline_args.top, inline_args.extract.as_deref())?
+        }
+
+        Commands::Multipart(multipart_args) => {
+            multipart::multipart(&parsed, multipart_args.extract.as_deref())?
+        }
+
+        Commands::Uploads => uploads::uploads(&parsed),
+
+        Commands::Grpc => grpc::grpc(&parsed),
+
+        Commands::Streams => streams::streams(&parsed),
+
+        Commands::DataFlows(flow_args) => {
+            data_flows::data_flows(&parsed, flow_args.country_db, flow_args.asn_db)?
+        }
+
+        Commands::Diff(ref diff_args) => {
+            let mut other_contents = read_input(&diff_args.other.display().to_string())?;
+            let mut other = parse_har(&mut other_contents)
+                .with_context(|| format!("Failed to parse {}", diff_args.other.display()))?;
+            apply_filters(&mut other, &args);
+            diff::diff(&parsed, &other, diff_args.bodies);
+        }
     }
 
     Ok(())
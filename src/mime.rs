@@ -0,0 +1,184 @@
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ResourceKind {
+    Document,
+    Script,
+    Stylesheet,
+    Image,
+    Font,
+    Media,
+    Data,
+    Other,
+}
+
+impl ResourceKind {
+    pub fn from_mime(mime: &str) -> Self {
+        let mime = essence(mime);
+
+        match mime.as_str() {
+            "text/html" | "application/xhtml+xml" => Self::Document,
+            "text/javascript"
+            | "application/javascript"
+            | "application/x-javascript"
+            | "application/ecmascript"
+            | "text/ecmascript"
+            | "application/wasm" => Self::Script,
+            "text/css" => Self::Stylesheet,
+            "application/json" | "text/json" | "application/xml" | "text/xml" | "text/plain" => {
+                Self::Data
+            }
+            "application/font-woff"
+            | "application/x-font-woff"
+            | "application/font-sfnt"
+            | "application/vnd.ms-fontobject"
+            | "application/x-font-ttf" => Self::Font,
+            _ if mime.starts_with("image/") => Self::Image,
+            _ if mime.starts_with("font/") => Self::Font,
+            _ if mime.starts_with("audio/") || mime.starts_with("video/") => Self::Media,
+            _ if mime.ends_with("+json") || mime.ends_with("+xml") => Self::Data,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl fmt::Display for ResourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Document => "document",
+            Self::Script => "script",
+            Self::Stylesheet => "stylesheet",
+            Self::Image => "image",
+            Self::Font => "font",
+            Self::Media => "media",
+            Self::Data => "data",
+            Self::Other => "other",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// How a gRPC or protobuf body is framed, going by its MIME type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Protobuf {
+    /// Length-prefixed messages, with the trailers in a final frame for gRPC-web.
+    Grpc,
+    /// gRPC-web framing, base64 encoded for clients that can't stream binary.
+    GrpcWebText,
+    /// A single message with no framing.
+    Message,
+}
+
+impl Protobuf {
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        let mime = essence(mime);
+        match mime.as_str() {
+            "application/grpc-web-text" => Some(Self::GrpcWebText),
+            _ if mime.starts_with("application/grpc-web-text+") => Some(Self::GrpcWebText),
+            "application/grpc" | "application/grpc-web" => Some(Self::Grpc),
+            _ if mime.starts_with("application/grpc+")
+                || mime.starts_with("application/grpc-web+") =>
+            {
+                Some(Self::Grpc)
+            }
+            "application/x-protobuf"
+            | "application/protobuf"
+            | "application/x-google-protobuf"
+            | "application/vnd.google.protobuf"
+            | "application/octet-stream+protobuf" => Some(Self::Message),
+            _ => None,
+        }
+    }
+}
+
+/// Strip parameters from a MIME type, i.e. `text/html; charset=utf-8` -> `text/html`.
+pub fn essence(mime: &str) -> String {
+    mime.split(';').next().unwrap_or("").trim().to_lowercase()
+}
+
+/// Guess the MIME type of a body from its magic bytes, or its leading markup for text formats.
+pub fn sniff(body: &[u8]) -> Option<&'static str> {
+    const SIGNATURES: [(&[u8], &str); 22] = [
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"\x00\x00\x01\x00", "image/x-icon"),
+        (b"\x1a\x45\xdf\xa3", "video/webm"),
+        (b"OggS", "audio/ogg"),
+        (b"ID3", "audio/mpeg"),
+        (b"%PDF-", "application/pdf"),
+        (b"PK\x03\x04", "application/zip"),
+        (b"\x1f\x8b", "application/gzip"),
+        (b"\x00asm", "application/wasm"),
+        (b"wOFF", "font/woff"),
+        (b"wOF2", "font/woff2"),
+        (b"\x00\x01\x00\x00", "font/ttf"),
+        (b"OTTO", "font/otf"),
+        (b"MZ", "application/x-msdownload"),
+        (b"\x7fELF", "application/x-executable"),
+        (b"\xfe\xed\xfa\xce", "application/x-mach-binary"),
+        (b"\xfe\xed\xfa\xcf", "application/x-mach-binary"),
+        (b"\xce\xfa\xed\xfe", "application/x-mach-binary"),
+        (b"\xcf\xfa\xed\xfe", "application/x-mach-binary"),
+    ];
+
+    if let Some((_, mime)) = SIGNATURES.iter().find(|(magic, _)| body.starts_with(magic)) {
+        return Some(mime);
+    }
+
+    // RIFF and ISO-BMFF containers name their format a few bytes in
+    if body.len() >= 12 && body.starts_with(b"RIFF") {
+        match &body[8..12] {
+            b"WEBP" => return Some("image/webp"),
+            b"WAVE" => return Some("audio/wav"),
+            _ => {}
+        }
+    }
+    if body.len() >= 12 && &body[4..8] == b"ftyp" {
+        return match &body[8..12] {
+            b"avif" | b"avis" => Some("image/avif"),
+            b"heic" | b"heix" | b"mif1" => Some("image/heic"),
+            _ => Some("video/mp4"),
+        };
+    }
+
+    // text formats, skipping a byte order mark and leading whitespace
+    let text = body.strip_prefix(b"\xef\xbb\xbf").unwrap_or(body);
+    let start = text.iter().position(|b| !b.is_ascii_whitespace())?;
+    let head: Vec<u8> = text[start..]
+        .iter()
+        .take(512)
+        .map(u8::to_ascii_lowercase)
+        .collect();
+
+    let html_tags: [&[u8]; 6] = [
+        b"<!doctype html",
+        b"<html",
+        b"<head",
+        b"<body",
+        b"<script",
+        b"<iframe",
+    ];
+    if html_tags.iter().any(|tag| head.starts_with(tag)) {
+        return Some("text/html");
+    }
+    if head.starts_with(b"<svg") {
+        return Some("image/svg+xml");
+    }
+    if head.starts_with(b"<?xml") {
+        let is_svg = head.windows(4).any(|w| w == b"<svg");
+        return Some(if is_svg {
+            "image/svg+xml"
+        } else {
+            "application/xml"
+        });
+    }
+    if (head.starts_with(b"{") || head.starts_with(b"["))
+        && serde_json::from_slice::<serde_json::Value>(&text[start..]).is_ok()
+    {
+        return Some("application/json");
+    }
+
+    None
+}
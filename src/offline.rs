@@ -0,0 +1,27 @@
+use anyhow::{Result, anyhow};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::warn;
+
+static OFFLINE: AtomicBool = AtomicBool::new(false);
+
+/// Skip every network operation, for `--offline`.
+pub fn set_offline(offline: bool) {
+    OFFLINE.store(offline, Ordering::Relaxed);
+}
+
+pub fn is_offline() -> bool {
+    OFFLINE.load(Ordering::Relaxed)
+}
+
+/// Fail commands that can't do anything useful without the network.
+pub fn ensure_online(action: &str) -> Result<()> {
+    if is_offline() {
+        return Err(anyhow!("Can't {} with --offline", action));
+    }
+    Ok(())
+}
+
+/// Say what a command is leaving out because of `--offline`.
+pub fn notice(message: &str) {
+    warn!("offline, so {}", message);
+}
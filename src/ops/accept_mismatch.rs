@@ -0,0 +1,204 @@
+use colored::Colorize;
+
+use crate::{
+    check,
+    har::{Entry, Har},
+    mime,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum MismatchKind {
+    /// A successful response in a type the Accept header didn't allow.
+    Unacceptable,
+    /// A JSON request answered with a HTML page, usually an error or login page.
+    HtmlForJson,
+}
+
+#[derive(Debug)]
+pub struct Mismatch<'a> {
+    pub request_num: usize,
+    pub entry: &'a Entry<'a>,
+    pub accept: &'a str,
+    pub content_type: String,
+    pub kind: MismatchKind,
+}
+
+#[derive(Debug)]
+struct MediaRange {
+    range: String,
+    q: f64,
+}
+
+impl MediaRange {
+    fn matches(&self, mime_type: &str) -> bool {
+        if self.range == "*/*" || self.range == mime_type {
+            return true;
+        }
+        match self.range.strip_suffix("/*") {
+            Some(main_type) => mime_type.split('/').next() == Some(main_type),
+            None => false,
+        }
+    }
+
+    // exact types outrank `type/*`, which outranks `*/*`
+    fn specificity(&self) -> u8 {
+        if self.range == "*/*" {
+            0
+        } else if self.range.ends_with("/*") {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+/// Parse an Accept header into its media ranges and their weights.
+fn parse_accept(accept: &str) -> Vec<MediaRange> {
+    accept
+        .split(',')
+        .filter_map(|part| {
+            let mut params = part.split(';');
+            let range = mime::essence(params.next()?);
+            if range.is_empty() {
+                return None;
+            }
+            let q = params
+                .filter_map(|param| param.split_once('='))
+                .find(|(name, _)| name.trim().eq_ignore_ascii_case("q"))
+                .and_then(|(_, value)| value.trim().parse().ok())
+                .unwrap_or(1.0);
+            Some(MediaRange { range, q })
+        })
+        .collect()
+}
+
+/// Whether a MIME type is acceptable, going by the weight of the most specific range matching it.
+fn acceptable(ranges: &[MediaRange], mime_type: &str) -> bool {
+    ranges
+        .iter()
+        .filter(|range| range.matches(mime_type))
+        .max_by_key(|range| range.specificity())
+        .is_some_and(|range| range.q > 0.0)
+}
+
+fn is_json(mime_type: &str) -> bool {
+    mime_type == "application/json" || mime_type == "text/json" || mime_type.ends_with("+json")
+}
+
+/// Whether the client asked for JSON rather than a page, i.e. `application/json, text/plain, */*`.
+fn wants_json(ranges: &[MediaRange]) -> bool {
+    ranges
+        .iter()
+        .any(|range| is_json(&range.range) && range.q > 0.0)
+        && !ranges.iter().any(|range| range.range == "text/html")
+}
+
+fn check_entry<'a>(i: usize, entry: &'a Entry<'a>) -> Option<Mismatch<'a>> {
+    let accept = entry.request.header("accept")?.trim();
+    let ranges = parse_accept(accept);
+    if ranges.is_empty() {
+        return None;
+    }
+    let response = &entry.response;
+    let body = response.content.as_ref().and_then(|c| c.decoded());
+    // what the body is matters more than its label when the two disagree about HTML
+    let sniffed_html = body
+        .as_deref()
+        .is_some_and(|body| mime::sniff(body) == Some("text/html"));
+    let declared = response.mime_type();
+    let content_type = match (&declared, sniffed_html) {
+        (Some(declared), true) if declared != "text/html" => {
+            format!("{} (but HTML)", declared)
+        }
+        (Some(declared), _) => declared.clone(),
+        (None, true) => "text/html".to_string(),
+        (None, false) => return None,
+    };
+
+    let html = sniffed_html || declared.as_deref() == Some("text/html");
+    let kind = if wants_json(&ranges) && html {
+        MismatchKind::HtmlForJson
+    } else if (200..300).contains(&response.status)
+        && body.as_ref().is_some_and(|body| !body.is_empty())
+        && declared
+            .as_deref()
+            .is_some_and(|declared| !acceptable(&ranges, declared))
+    {
+        MismatchKind::Unacceptable
+    } else {
+        return None;
+    };
+
+    Some(Mismatch {
+        request_num: i + 1,
+        entry,
+        accept,
+        content_type,
+        kind,
+    })
+}
+
+/// Compare each request's Accept header with the type of its response.
+pub fn find_mismatches<'a>(har: &'a Har) -> Vec<Mismatch<'a>> {
+    har.log
+        .entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, entry)| check_entry(i, entry))
+        .collect()
+}
+
+fn print_mismatches(heading: &str, mismatches: &[&Mismatch]) {
+    if mismatches.is_empty() {
+        return;
+    }
+    println!("{}:", heading.bold().blue());
+    for mismatch in mismatches {
+        let status = mismatch.entry.response.status;
+        let status = if status >= 400 || status == 0 {
+            status.to_string().red()
+        } else {
+            status.to_string().normal()
+        };
+        println!(
+            "    #{} {} {}",
+            mismatch.request_num, status, mismatch.entry.request.url
+        );
+        println!(
+            "        accepted {}, got {}",
+            mismatch.accept.dimmed(),
+            mismatch.content_type.yellow()
+        );
+    }
+    println!();
+}
+
+pub fn accept_mismatch(har: &Har) {
+    let mismatches = find_mismatches(har);
+    if mismatches.is_empty() {
+        println!(
+            "{}",
+            "Every response is a type its request accepted.".green()
+        );
+        return;
+    }
+
+    let html_for_json: Vec<&Mismatch> = mismatches
+        .iter()
+        .filter(|m| m.kind == MismatchKind::HtmlForJson)
+        .collect();
+    let unacceptable: Vec<&Mismatch> = mismatches
+        .iter()
+        .filter(|m| m.kind == MismatchKind::Unacceptable)
+        .collect();
+    print_mismatches("HTML answering JSON requests", &html_for_json);
+    print_mismatches("Successful responses in unaccepted types", &unacceptable);
+
+    check::report(mismatches.len());
+    println!(
+        "{}: {} JSON requests answered with HTML, {} responses in types not accepted",
+        "Summary".bold(),
+        html_for_json.len(),
+        unacceptable.len()
+    );
+}
@@ -0,0 +1,566 @@
+use anyhow::{Context, Result};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use regex::{Regex, RegexBuilder};
+use serde_json::Value;
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+use tldextract::TldExtractor;
+use url::{Url, form_urlencoded};
+
+use crate::{
+    body::Body,
+    domain,
+    har::{BASE64_LENIENT, Cookie, Entry, Har, Header},
+};
+
+use super::multipart;
+
+// query and form parameters whose names contain these identify a user
+const IDENTIFIER_HINTS: [&str; 12] = [
+    "user", "uid", "email", "account", "login", "customer", "member", "sess", "token", "auth",
+    "secret", "pass",
+];
+
+// shorter values are too likely to appear by chance to be replaced in free text, unless they
+// were sent under an identifying name
+const MIN_VALUE_LEN: usize = 8;
+
+// the `@` may be percent-encoded, as in a query string
+const EMAIL_PATTERN: &str =
+    r"[A-Za-z0-9._%+-]+(?:@|%40)[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}";
+
+// headers whose values are URLs, whose query strings are anonymized like the request's
+const URL_HEADERS: [&str; 5] = [
+    "referer",
+    "location",
+    "origin",
+    "content-location",
+    "access-control-allow-origin",
+];
+const IPV4_PATTERN: &str = r"\b(?:\d{1,3}\.){3}\d{1,3}\b";
+
+/// Fake values handed out in the order the real ones are first seen, so the same input
+/// always becomes the same pseudonym within a file.
+#[derive(Debug, Default)]
+pub struct Pseudonyms {
+    domains: HashMap<String, String>,
+    hosts: HashMap<String, String>,
+    ips: HashMap<IpAddr, IpAddr>,
+    values: HashMap<String, String>,
+    emails: HashMap<String, String>,
+}
+
+impl Pseudonyms {
+    fn ip(&mut self, ip: IpAddr) -> IpAddr {
+        let n = self.ips.len() as u32 + 1;
+        *self.ips.entry(ip).or_insert_with(|| match ip {
+            // private and documentation ranges, so nothing real is pointed at
+            IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::from(0x0a00_0000 + n)),
+            IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(
+                0x2001_0db8_0000_0000_0000_0000_0000_0000 + n as u128,
+            )),
+        })
+    }
+
+    /// Keep hosts of the same site together, i.e. `www.a.com` and `api.a.com` become
+    /// `host1.site1.example` and `host2.site1.example`.
+    fn host(&mut self, host: &str, tld_extractor: &TldExtractor) -> String {
+        if let Some(ip) = domain::ip_literal(host) {
+            return self.ip(ip).to_string();
+        }
+
+        let host = host.to_lowercase();
+        if let Some(fake) = self.hosts.get(&host) {
+            return fake.clone();
+        }
+
+        let registrable =
+            domain::registrable_domain(&host, tld_extractor).unwrap_or_else(|| host.clone());
+        let n = self.domains.len() + 1;
+        let site = self
+            .domains
+            .entry(registrable.clone())
+            .or_insert_with(|| format!("site{}.example", n))
+            .clone();
+
+        let fake = if registrable == host {
+            site
+        } else {
+            format!("host{}.{}", self.hosts.len() + 1, site)
+        };
+        self.hosts.insert(host, fake.clone());
+        fake
+    }
+
+    fn value(&mut self, value: &str) -> String {
+        if value.is_empty() {
+            return String::new();
+        }
+        let n = self.values.len() + 1;
+        self.values
+            .entry(value.to_string())
+            .or_insert_with(|| format!("value{}", n))
+            .clone()
+    }
+
+    fn email(&mut self, email: &str) -> String {
+        let n = self.emails.len() + 1;
+        let fake = self
+            .emails
+            .entry(email.to_lowercase().replace("%40", "@"))
+            .or_insert_with(|| format!("user{}@example.com", n));
+        if email.contains('@') {
+            fake.clone()
+        } else {
+            fake.replace('@', "%40")
+        }
+    }
+
+    pub fn hosts(&self) -> usize {
+        self.hosts.len()
+    }
+
+    pub fn ips(&self) -> usize {
+        self.ips.len()
+    }
+
+    pub fn identifiers(&self) -> usize {
+        self.values.len() + self.emails.len()
+    }
+}
+
+struct Anonymizer {
+    tld_extractor: TldExtractor,
+    pseudonyms: Pseudonyms,
+    // emails, IPv4 addresses and every host and value seen in the structured fields
+    matcher: Option<Regex>,
+    // values sent under an identifying name, which are matched in free text however short
+    named_values: HashSet<String>,
+}
+
+fn is_identifier_name(name: &str) -> bool {
+    let name = name.to_lowercase();
+    IDENTIFIER_HINTS.iter().any(|hint| name.contains(hint))
+}
+
+fn replace(field: &mut Cow<'_, str>, value: Option<String>) {
+    if let Some(value) = value {
+        *field = Cow::Owned(value);
+    }
+}
+
+// a host or value only counts when it isn't part of a longer word, i.e. `a.com` in `data.com`,
+// though it may follow a percent escape, as in an encoded `https%3A%2F%2Fa.com`
+fn on_boundary(text: &str, start: usize, end: usize) -> bool {
+    let escaped = text[..start]
+        .get(start.saturating_sub(3)..)
+        .is_some_and(|s| s.starts_with('%'));
+    let before = text[..start].chars().next_back();
+    let after = text[end..].chars().next();
+    (escaped || !before.is_some_and(|c| c.is_alphanumeric() || c == '-' || c == '.'))
+        && !after.is_some_and(|c| c.is_alphanumeric() || c == '-')
+}
+
+impl Anonymizer {
+    /// Give every host, IP, cookie value and credential a pseudonym up front, so they're
+    /// recognised wherever else they turn up, such as in headers or bodies.
+    fn collect(&mut self, entry: &Entry) {
+        for url in [&*entry.request.url, &*entry.response.redirect_url] {
+            if let Some(host) = Url::parse(url)
+                .ok()
+                .and_then(|u| u.host_str().map(String::from))
+            {
+                self.pseudonyms.host(&host, &self.tld_extractor);
+            }
+        }
+        if let Some(ip) = entry.server_ip() {
+            self.pseudonyms.ip(ip);
+        }
+
+        let cookies = entry.request.cookies.iter().chain(&entry.response.cookies);
+        for cookie in cookies {
+            self.pseudonyms.value(&cookie.value);
+        }
+        let headers = entry.request.headers.iter().chain(&entry.response.headers);
+        for header in headers {
+            match &*header.name.to_lowercase() {
+                "cookie" => {
+                    for pair in header.value.split(';') {
+                        if let Some((_, value)) = pair.split_once('=') {
+                            self.pseudonyms.value(value.trim());
+                        }
+                    }
+                }
+                "set-cookie" => {
+                    for line in header.value.lines() {
+                        let pair = line.split(';').next().unwrap_or_default();
+                        if let Some((_, value)) = pair.split_once('=') {
+                            self.pseudonyms.value(value.trim());
+                        }
+                    }
+                }
+                "authorization" | "proxy-authorization" => {
+                    let (_, credentials) =
+                        header.value.split_once(' ').unwrap_or(("", &header.value));
+                    self.pseudonyms.value(credentials.trim());
+                }
+                _ => {}
+            }
+        }
+
+        for param in &entry.request.query_string {
+            if is_identifier_name(&param.name) {
+                self.named_value(&param.value);
+            }
+        }
+        let params = entry
+            .request
+            .post_data
+            .iter()
+            .flat_map(|p| p.params.iter().flatten());
+        for param in params {
+            if let Some(value) = param
+                .value
+                .as_deref()
+                .filter(|_| is_identifier_name(&param.name))
+            {
+                self.named_value(value);
+            }
+        }
+        // multipart fields are replaced wherever they appear in the body
+        let parts = entry
+            .request
+            .post_data
+            .as_ref()
+            .and_then(multipart::parts)
+            .unwrap_or_default();
+        for part in parts {
+            if part.name.as_deref().is_some_and(is_identifier_name) && !part.is_file() {
+                self.named_value(part.body);
+            }
+        }
+    }
+
+    fn named_value(&mut self, value: &str) {
+        self.pseudonyms.value(value);
+        self.named_values.insert(value.to_string());
+    }
+
+    fn build_matcher(&mut self) -> Result<()> {
+        let mut hosts: Vec<String> = self
+            .pseudonyms
+            .hosts
+            .keys()
+            .chain(self.pseudonyms.domains.keys())
+            .cloned()
+            .collect();
+        hosts.extend(
+            self.pseudonyms
+                .ips
+                .keys()
+                .filter(|ip| ip.is_ipv6())
+                .map(|ip| ip.to_string()),
+        );
+        let mut values: Vec<&String> = self
+            .pseudonyms
+            .values
+            .keys()
+            .filter(|value| value.len() >= MIN_VALUE_LEN || self.named_values.contains(*value))
+            .collect();
+
+        // alternatives are tried in order, so the longest has to come first
+        hosts.sort_by_key(|host| std::cmp::Reverse(host.len()));
+        values.sort_by_key(|value| std::cmp::Reverse(value.len()));
+
+        let mut alternatives = vec![
+            format!("(?P<email>{})", EMAIL_PATTERN),
+            format!("(?P<ipv4>{})", IPV4_PATTERN),
+        ];
+        if !hosts.is_empty() {
+            let hosts: Vec<String> = hosts.iter().map(|h| regex::escape(h)).collect();
+            alternatives.push(format!("(?P<host>(?i:{}))", hosts.join("|")));
+        }
+        if !values.is_empty() {
+            let values: Vec<String> = values.iter().map(|v| regex::escape(v)).collect();
+            alternatives.push(format!("(?P<value>{})", values.join("|")));
+        }
+
+        let matcher = RegexBuilder::new(&alternatives.join("|"))
+            .size_limit(1 << 28)
+            .build()
+            .context("Failed to build the anonymization pattern")?;
+        self.matcher = Some(matcher);
+        Ok(())
+    }
+
+    /// Replace every known host and value, email and IPv4 address in free text, or `None` if
+    /// there were none.
+    fn text(&mut self, text: &str) -> Option<String> {
+        let matcher = self.matcher.as_ref()?;
+
+        let mut anonymized = String::new();
+        let mut last = 0;
+        for caps in matcher.captures_iter(text) {
+            let m = caps.get(0).unwrap();
+            let fake = if caps.name("email").is_some() {
+                self.pseudonyms.email(m.as_str())
+            } else if caps.name("ipv4").is_some() {
+                match m.as_str().parse() {
+                    Ok(ip) => self.pseudonyms.ip(ip).to_string(),
+                    Err(_) => continue,
+                }
+            } else if !on_boundary(text, m.start(), m.end()) {
+                continue;
+            } else if caps.name("host").is_some() {
+                self.pseudonyms.host(m.as_str(), &self.tld_extractor)
+            } else {
+                self.pseudonyms.value(m.as_str())
+            };
+
+            anonymized.push_str(&text[last..m.start()]);
+            anonymized.push_str(&fake);
+            last = m.end();
+        }
+
+        if last == 0 {
+            return None;
+        }
+        anonymized.push_str(&text[last..]);
+        Some(anonymized)
+    }
+
+    /// Replace the values of identifying parameters in a query string or form body.
+    fn query(&mut self, query: &str) -> Option<String> {
+        let mut changed = false;
+        let pairs: Vec<String> = query
+            .split('&')
+            .map(|pair| {
+                let Some((name, value)) = pair.split_once('=') else {
+                    return pair.to_string();
+                };
+                let decoded: String = form_urlencoded::parse(pair.as_bytes())
+                    .next()
+                    .map(|(_, v)| v.into_owned())
+                    .unwrap_or_else(|| value.to_string());
+                if decoded.is_empty() || !is_identifier_name(name) {
+                    return pair.to_string();
+                }
+                changed = true;
+                format!("{}={}", name, self.pseudonyms.value(&decoded))
+            })
+            .collect();
+        changed.then(|| pairs.join("&"))
+    }
+
+    fn url(&mut self, url: &str) -> Option<String> {
+        let Ok(mut parsed) = Url::parse(url) else {
+            return self.text(url);
+        };
+
+        // parameters go first, as free text replacement would leave them already pseudonymised
+        let mut changed = false;
+        if let Some(query) = parsed.query().and_then(|q| self.query(q)) {
+            parsed.set_query(Some(&query));
+            changed = true;
+        }
+        if !parsed.username().is_empty() {
+            let username = self.pseudonyms.value(parsed.username());
+            let _ = parsed.set_username(&username);
+            changed = true;
+        }
+        if let Some(password) = parsed.password().map(|p| self.pseudonyms.value(p)) {
+            let _ = parsed.set_password(Some(&password));
+            changed = true;
+        }
+
+        let current = if changed {
+            parsed.to_string()
+        } else {
+            url.to_string()
+        };
+        match self.text(&current) {
+            Some(anonymized) => Some(anonymized),
+            None => changed.then_some(current),
+        }
+    }
+
+    fn header(&mut self, header: &mut Header) {
+        let anonymized = match &*header.name.to_lowercase() {
+            "cookie" => Some(
+                header
+                    .value
+                    .split(';')
+                    .map(|pair| match pair.split_once('=') {
+                        Some((name, value)) => {
+                            format!("{}={}", name, self.pseudonyms.value(value.trim()))
+                        }
+                        None => pair.to_string(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(";"),
+            ),
+            "set-cookie" => Some(
+                header
+                    .value
+                    .lines()
+                    .map(|line| {
+                        let (pair, attributes) = line.split_once(';').unwrap_or((line, ""));
+                        let pair = match pair.split_once('=') {
+                            Some((name, value)) => {
+                                format!("{}={}", name, self.pseudonyms.value(value.trim()))
+                            }
+                            None => pair.to_string(),
+                        };
+                        if attributes.is_empty() {
+                            return pair;
+                        }
+                        let attributes = self
+                            .text(attributes)
+                            .unwrap_or_else(|| attributes.to_string());
+                        format!("{};{}", pair, attributes)
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            "authorization" | "proxy-authorization" => match header.value.split_once(' ') {
+                Some((scheme, credentials)) => Some(format!(
+                    "{} {}",
+                    scheme,
+                    self.pseudonyms.value(credentials.trim())
+                )),
+                None => Some(self.pseudonyms.value(&header.value)),
+            },
+            name if URL_HEADERS.contains(&name) => self.url(&header.value),
+            _ => self.text(&header.value),
+        };
+        replace(&mut header.value, anonymized);
+    }
+
+    fn cookie(&mut self, cookie: &mut Cookie) {
+        let value = self.pseudonyms.value(&cookie.value);
+        cookie.value = Cow::Owned(value);
+        if let Some(domain) = &mut cookie.domain {
+            let anonymized = self.text(domain);
+            replace(domain, anonymized);
+        }
+    }
+
+    fn json(&mut self, value: &mut Value) {
+        match value {
+            Value::String(s) => {
+                if let Some(anonymized) = self.text(s) {
+                    *s = anonymized;
+                }
+            }
+            Value::Array(values) => values.iter_mut().for_each(|v| self.json(v)),
+            Value::Object(map) => map.values_mut().for_each(|v| self.json(v)),
+            _ => {}
+        }
+    }
+
+    fn entry(&mut self, entry: &mut Entry) {
+        if let Some(ip) = &mut entry.server_ip_address {
+            let anonymized = self.text(ip);
+            replace(ip, anonymized);
+        }
+        entry.extra.values_mut().for_each(|v| self.json(v));
+
+        let request = &mut entry.request;
+        let anonymized = self.url(&request.url);
+        replace(&mut request.url, anonymized);
+        request.headers.iter_mut().for_each(|h| self.header(h));
+        request.cookies.iter_mut().for_each(|c| self.cookie(c));
+        for param in &mut request.query_string {
+            let anonymized = if is_identifier_name(&param.name) {
+                Some(self.pseudonyms.value(&param.value))
+            } else {
+                self.text(&param.value)
+            };
+            replace(&mut param.value, anonymized);
+        }
+        if let Some(post_data) = &mut request.post_data {
+            let mut text = post_data.text.to_string();
+            if post_data
+                .mime_type
+                .starts_with("application/x-www-form-urlencoded")
+            {
+                if let Some(anonymized) = self.query(&text) {
+                    text = anonymized;
+                }
+            }
+            if let Some(anonymized) = self.text(&text) {
+                text = anonymized;
+            }
+            if text != *post_data.text {
+                post_data.text = Body::from(text);
+            }
+
+            for param in post_data.params.iter_mut().flatten() {
+                let identifier = is_identifier_name(&param.name);
+                if let Some(value) = &mut param.value {
+                    let anonymized = if identifier {
+                        Some(self.pseudonyms.value(value))
+                    } else {
+                        self.text(value)
+                    };
+                    replace(value, anonymized);
+                }
+            }
+        }
+        request.extra.values_mut().for_each(|v| self.json(v));
+
+        let response = &mut entry.response;
+        let anonymized = self.url(&response.redirect_url);
+        replace(&mut response.redirect_url, anonymized);
+        response.headers.iter_mut().for_each(|h| self.header(h));
+        response.cookies.iter_mut().for_each(|c| self.cookie(c));
+        if let Some(content) = &mut response.content {
+            let encoded = content.encoding.as_deref() == Some("base64");
+            let anonymized = match content.text.as_deref() {
+                // binary bodies are left alone, as text in them can't be told from chance bytes
+                Some(text) if encoded => BASE64_LENIENT
+                    .decode(text.trim())
+                    .ok()
+                    .and_then(|bytes| String::from_utf8(bytes).ok())
+                    .and_then(|decoded| self.text(&decoded))
+                    .map(|anonymized| STANDARD.encode(anonymized)),
+                Some(text) => self.text(text),
+                None => None,
+            };
+            if let Some(anonymized) = anonymized {
+                content.text = Some(Body::from(anonymized));
+            }
+        }
+        response.extra.values_mut().for_each(|v| self.json(v));
+    }
+}
+
+/// Replace the capture's domains, IPs, user identifiers and cookie values with pseudonyms in
+/// place, returning the pseudonyms used.
+pub fn anonymize(har: &mut Har) -> Result<Pseudonyms> {
+    let mut anonymizer = Anonymizer {
+        tld_extractor: domain::build_tld_extractor(),
+        pseudonyms: Pseudonyms::default(),
+        matcher: None,
+        named_values: HashSet::new(),
+    };
+
+    for entry in &har.log.entries {
+        anonymizer.collect(entry);
+    }
+    anonymizer.build_matcher()?;
+
+    for page in har.log.pages.iter_mut().flatten() {
+        let anonymized = anonymizer.text(&page.title);
+        replace(&mut page.title, anonymized);
+        page.extra.values_mut().for_each(|v| anonymizer.json(v));
+    }
+    for entry in &mut har.log.entries {
+        anonymizer.entry(entry);
+    }
+
+    Ok(anonymizer.pseudonyms)
+}
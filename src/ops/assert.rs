@@ -0,0 +1,222 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+use tldextract::TldExtractor;
+
+use crate::{
+    check,
+    domain::{self, FirstParty},
+    format,
+    har::Har,
+};
+
+use super::budget::Check;
+
+/// What a known-good capture looked like, for later captures to be checked against.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct Baseline {
+    // the sites counted as first party when it was recorded, the reported one first, so later
+    // captures are judged the same way; empty in baselines recorded before it was kept
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub first_party: Vec<String>,
+    // the site's own hosts, which should all still be requested
+    pub domains: BTreeSet<String>,
+    // registrable domains of the third parties the site may load from
+    pub third_parties: BTreeSet<String>,
+    pub max_total_bytes: u64,
+    // keyed by resource kind, i.e. `script`
+    pub max_bytes: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Default)]
+struct Traffic {
+    domains: BTreeSet<String>,
+    third_parties: BTreeSet<String>,
+    total_bytes: u64,
+    bytes: BTreeMap<String, u64>,
+}
+
+fn traffic(har: &Har, tld_extractor: &TldExtractor, first_party: Option<&FirstParty>) -> Traffic {
+    let mut traffic = Traffic::default();
+    for entry in &har.log.entries {
+        let bytes = entry.response.body_bytes();
+        traffic.total_bytes += bytes;
+        *traffic
+            .bytes
+            .entry(entry.response.resource_kind().to_string())
+            .or_default() += bytes;
+
+        let Some(host) = entry.request.host() else {
+            continue;
+        };
+        // IP addresses have no registrable domain, so they're expected like the site's own hosts
+        let site = domain::ip_literal(&host)
+            .is_none()
+            .then(|| domain::registrable_domain(&host, tld_extractor))
+            .flatten();
+        match site {
+            Some(site) if first_party.is_some_and(|fp| !fp.contains(&site)) => {
+                traffic.third_parties.insert(site);
+            }
+            _ => {
+                traffic.domains.insert(host);
+            }
+        }
+    }
+    traffic
+}
+
+fn with_headroom(bytes: u64, headroom: f64) -> u64 {
+    (bytes as f64 * (1.0 + headroom / 100.0)).ceil() as u64
+}
+
+/// Record a baseline from a known-good HAR, allowing sizes to grow by `headroom` percent.
+pub fn record(har: &Har, headroom: f64) -> Baseline {
+    let tld_extractor = domain::build_tld_extractor();
+    let first_party = domain::first_party(har, &tld_extractor);
+    let traffic = traffic(har, &tld_extractor, first_party.as_ref());
+    Baseline {
+        first_party: first_party
+            .map(|fp| {
+                let others = fp.sites().iter().filter(|site| **site != fp.primary);
+                std::iter::once(fp.primary.clone())
+                    .chain(others.cloned())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        domains: traffic.domains,
+        third_parties: traffic.third_parties,
+        max_total_bytes: with_headroom(traffic.total_bytes, headroom),
+        max_bytes: traffic
+            .bytes
+            .into_iter()
+            .map(|(kind, bytes)| (kind, with_headroom(bytes, headroom)))
+            .collect(),
+    }
+}
+
+fn list_check(name: &str, unexpected: Vec<&String>, limit: &str) -> Check {
+    Check {
+        name: name.to_string(),
+        actual: if unexpected.is_empty() {
+            "none".to_string()
+        } else {
+            unexpected
+                .iter()
+                .map(|s| s.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        },
+        limit: limit.to_string(),
+        passed: unexpected.is_empty(),
+    }
+}
+
+fn bytes_check(name: &str, actual: u64, limit: u64) -> Check {
+    Check {
+        name: name.to_string(),
+        actual: format::bytes(actual),
+        limit: format::bytes(limit),
+        passed: actual <= limit,
+    }
+}
+
+/// Check a HAR against a baseline, with the first party it was recorded with.
+pub fn evaluate(har: &Har, baseline: &Baseline) -> Vec<Check> {
+    let tld_extractor = domain::build_tld_extractor();
+    let first_party = FirstParty::from_sites(&baseline.first_party)
+        .or_else(|| domain::first_party(har, &tld_extractor));
+    let traffic = traffic(har, &tld_extractor, first_party.as_ref());
+
+    let mut checks = vec![
+        list_check(
+            "new domains",
+            traffic.domains.difference(&baseline.domains).collect(),
+            "none",
+        ),
+        list_check(
+            "missing domains",
+            baseline.domains.difference(&traffic.domains).collect(),
+            "none",
+        ),
+        list_check(
+            "unexpected third parties",
+            traffic
+                .third_parties
+                .difference(&baseline.third_parties)
+                .collect(),
+            "none",
+        ),
+        bytes_check("total bytes", traffic.total_bytes, baseline.max_total_bytes),
+    ];
+
+    for (kind, bytes) in &traffic.bytes {
+        // a kind the baseline never loaded is allowed nothing
+        let limit = baseline.max_bytes.get(kind).copied().unwrap_or(0);
+        checks.push(bytes_check(&format!("{} bytes", kind), *bytes, limit));
+    }
+    checks
+}
+
+pub fn load_baseline(path: &Path) -> Result<Baseline> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read file: {:?}", path))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse baseline file: {:?}", path))
+}
+
+/// Record a baseline from the HAR when `record` is set, otherwise check the HAR against it and
+/// report each failed assertion as a finding.
+pub fn assert(har: &Har, baseline_path: &Path, record_baseline: bool, headroom: f64) -> Result<()> {
+    if record_baseline {
+        let baseline = record(har, headroom);
+        let json = serde_json::to_string_pretty(&baseline)?;
+        fs::write(baseline_path, json + "\n")
+            .with_context(|| format!("Failed to write baseline file: {:?}", baseline_path))?;
+        println!(
+            "{} baseline of {} domains and {} third parties to {:?}",
+            "Recorded".green().bold(),
+            baseline.domains.len(),
+            baseline.third_parties.len(),
+            baseline_path
+        );
+        return Ok(());
+    }
+
+    let baseline = load_baseline(baseline_path)?;
+    let checks = evaluate(har, &baseline);
+    for check in &checks {
+        let status = if check.passed {
+            "PASS".green().bold()
+        } else {
+            "FAIL".red().bold()
+        };
+        println!(
+            "{} {}: {} (baseline {})",
+            status, check.name, check.actual, check.limit
+        );
+    }
+    println!();
+
+    let failures = checks.iter().filter(|c| !c.passed).count();
+    let result = if failures == 0 {
+        "PASS".green().bold()
+    } else {
+        "FAIL".red().bold()
+    };
+    println!(
+        "{}: {}, {} assertions checked, {} failed",
+        "Summary".bold(),
+        result,
+        checks.len(),
+        failures
+    );
+    check::report(failures);
+    Ok(())
+}
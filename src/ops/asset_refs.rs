@@ -0,0 +1,314 @@
+use colored::Colorize;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use url::Url;
+
+use crate::{
+    check,
+    har::{Entry, Har},
+    mime::ResourceKind,
+};
+
+// `<link>` relations that make the browser fetch the target, unlike `canonical` or `alternate`
+const FETCHED_RELS: [&str; 7] = [
+    "stylesheet",
+    "icon",
+    "apple-touch-icon",
+    "preload",
+    "modulepreload",
+    "manifest",
+    "prefetch",
+];
+
+// a tag attribute and its double-quoted, single-quoted or bare value
+const ATTRIBUTE_PATTERN: &str =
+    r#"\s([A-Za-z_:][-A-Za-z0-9_:.]*)\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+))"#;
+
+// schemes that don't show up as requests
+const INLINE_SCHEMES: [&str; 5] = ["data:", "blob:", "javascript:", "about:", "mailto:"];
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct AssetRef {
+    pub url: String,
+    pub tag: String,
+    // fetched only when needed, so not being fetched isn't necessarily a problem
+    pub deferred: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct DocumentRefs<'a> {
+    pub request_num: usize,
+    pub url: &'a str,
+    pub refs: BTreeSet<AssetRef>,
+}
+
+#[derive(Debug)]
+pub struct Unreferenced<'a> {
+    pub request_num: usize,
+    pub url: &'a str,
+    pub kind: ResourceKind,
+}
+
+/// A tag's attributes by lowercased name, with `&amp;` unescaped in their values.
+fn attributes(tag: &str, attribute_regex: &Regex) -> BTreeMap<String, String> {
+    attribute_regex
+        .captures_iter(tag)
+        .filter_map(|captures| {
+            let value = captures.get(2).or(captures.get(3)).or(captures.get(4))?;
+            Some((
+                captures[1].to_lowercase(),
+                value.as_str().replace("&amp;", "&"),
+            ))
+        })
+        .collect()
+}
+
+/// The attributes of each tag with a name in a HTML document, i.e. `meta`.
+pub fn tags(html: &str, name: &str) -> Vec<BTreeMap<String, String>> {
+    let tag_regex =
+        Regex::new(&format!(r"(?is)<{}\b[^>]*>", regex::escape(name))).expect("valid tag regex");
+    let attribute_regex = Regex::new(ATTRIBUTE_PATTERN).expect("valid attribute regex");
+    tag_regex
+        .find_iter(html)
+        .map(|tag| attributes(tag.as_str(), &attribute_regex))
+        .collect()
+}
+
+/// The attributes of each `<link>` tag in a HTML document.
+pub fn link_tags(html: &str) -> Vec<BTreeMap<String, String>> {
+    tags(html, "link")
+}
+
+fn strip_fragment(url: &str) -> &str {
+    url.split('#').next().unwrap_or(url)
+}
+
+/// Extract the asset URLs a HTML document references, resolved against its URL or `<base>`.
+pub fn extract_refs(html: &str, document_url: &str) -> BTreeSet<AssetRef> {
+    let tag_regex = Regex::new(r"(?is)<(img|script|link|source|iframe|video|audio|base)\b[^>]*>")
+        .expect("valid tag regex");
+    let attribute_regex = Regex::new(ATTRIBUTE_PATTERN).expect("valid attribute regex");
+    let Ok(mut base) = Url::parse(document_url) else {
+        return BTreeSet::new();
+    };
+
+    let mut refs = BTreeSet::new();
+    for captures in tag_regex.captures_iter(html) {
+        let tag = &captures[0];
+        let name = captures[1].to_lowercase();
+        let attributes = attributes(tag, &attribute_regex);
+        let attribute = |name: &str| attributes.get(name).cloned();
+        let rel = attribute("rel").unwrap_or_default().to_lowercase();
+
+        let mut urls = Vec::new();
+        match name.as_str() {
+            "base" => {
+                if let Some(href) = attribute("href") {
+                    if let Ok(url) = base.join(&href) {
+                        base = url;
+                    }
+                }
+                continue;
+            }
+            "link" => {
+                if rel.split_whitespace().any(|r| FETCHED_RELS.contains(&r)) {
+                    urls.extend(attribute("href"));
+                }
+            }
+            _ => {
+                urls.extend(attribute("src"));
+                // `1x`/`640w` candidates, of which the browser only fetches one
+                if let Some(srcset) = attribute("srcset") {
+                    urls.extend(
+                        srcset
+                            .split(',')
+                            .filter_map(|c| c.split_whitespace().next())
+                            .map(String::from),
+                    );
+                }
+            }
+        }
+
+        let deferred = attribute("loading").is_some_and(|l| l.eq_ignore_ascii_case("lazy"))
+            || rel.contains("prefetch")
+            || attributes.contains_key("srcset");
+        for url in urls {
+            let url = url.trim();
+            if url.is_empty()
+                || url.starts_with('#')
+                || INLINE_SCHEMES
+                    .iter()
+                    .any(|scheme| url.to_lowercase().starts_with(scheme))
+            {
+                continue;
+            }
+            if let Ok(resolved) = base.join(url) {
+                refs.insert(AssetRef {
+                    url: strip_fragment(resolved.as_str()).to_string(),
+                    tag: name.clone(),
+                    deferred,
+                });
+            }
+        }
+    }
+    refs
+}
+
+/// Parse the captured HTML documents for the assets they reference.
+pub fn find_refs<'a>(har: &'a Har) -> Vec<DocumentRefs<'a>> {
+    har.log
+        .entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.response.resource_kind() == ResourceKind::Document)
+        .filter_map(|(i, entry)| {
+            let body = entry.response.content.as_ref()?.decoded_text()?;
+            Some(DocumentRefs {
+                request_num: i + 1,
+                url: &entry.request.url,
+                refs: extract_refs(&body, &entry.request.url),
+            })
+        })
+        .collect()
+}
+
+/// Whether a script asked for the entry, going by Chrome's `_initiator`.
+fn initiated_by_script(entry: &Entry) -> bool {
+    entry
+        .extra
+        .get("_initiator")
+        .and_then(|initiator| initiator.get("type"))
+        .and_then(|t| t.as_str())
+        .is_some_and(|t| t == "script")
+}
+
+pub fn asset_refs(har: &Har) {
+    let documents = find_refs(har);
+    if documents.is_empty() {
+        println!(
+            "{}",
+            "No HTML documents with captured bodies found.".yellow()
+        );
+        return;
+    }
+
+    // URL -> entry, by the last time it was requested
+    let fetched: BTreeMap<&str, (usize, &Entry)> = har
+        .log
+        .entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| (strip_fragment(&entry.request.url), (i + 1, entry)))
+        .collect();
+    let referenced: BTreeSet<&str> = documents
+        .iter()
+        .flat_map(|doc| doc.refs.iter().map(|r| r.url.as_str()))
+        .collect();
+
+    let mut missing_count = 0;
+    let mut failed_count = 0;
+    for document in &documents {
+        let missing: Vec<&AssetRef> = document
+            .refs
+            .iter()
+            .filter(|r| !fetched.contains_key(r.url.as_str()))
+            .collect();
+        // a status of 0 is a request the browser blocked or abandoned
+        let failed: Vec<(&AssetRef, usize, u16)> = document
+            .refs
+            .iter()
+            .filter_map(|r| {
+                let (num, entry) = fetched.get(r.url.as_str())?;
+                let status = entry.response.status;
+                (status == 0 || status >= 400).then_some((r, *num, status))
+            })
+            .collect();
+        if missing.is_empty() && failed.is_empty() {
+            continue;
+        }
+
+        println!(
+            "#{} {} ({} references):",
+            document.request_num,
+            document.url.bold().blue(),
+            document.refs.len()
+        );
+        for asset in &missing {
+            let note = if asset.deferred {
+                " (lazy or responsive, may not have been needed)".normal()
+            } else {
+                missing_count += 1;
+                "".normal()
+            };
+            println!(
+                "    {} <{}> {}{}",
+                "never fetched".red(),
+                asset.tag,
+                asset.url,
+                note
+            );
+        }
+        for (asset, num, status) in &failed {
+            failed_count += 1;
+            let status = if *status == 0 {
+                "blocked".to_string()
+            } else {
+                status.to_string()
+            };
+            println!(
+                "    {} <{}> #{} {}",
+                status.yellow(),
+                asset.tag,
+                num,
+                asset.url
+            );
+        }
+        println!();
+    }
+
+    let unreferenced: Vec<Unreferenced> = har
+        .log
+        .entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| {
+            matches!(
+                entry.response.resource_kind(),
+                ResourceKind::Script
+                    | ResourceKind::Stylesheet
+                    | ResourceKind::Image
+                    | ResourceKind::Media
+            ) && entry.request.url.starts_with("http")
+                && !referenced.contains(strip_fragment(&entry.request.url))
+                && !initiated_by_script(entry)
+        })
+        .map(|(i, entry)| Unreferenced {
+            request_num: i + 1,
+            url: &entry.request.url,
+            kind: entry.response.resource_kind(),
+        })
+        .collect();
+    if !unreferenced.is_empty() {
+        println!("{}:", "Fetched but not referenced by markup".bold().blue());
+        for asset in &unreferenced {
+            println!("    #{} {} ({})", asset.request_num, asset.url, asset.kind);
+        }
+        println!(
+            "    {}",
+            "These may be loaded by stylesheets or scripts, or be left over from old markup."
+                .dimmed()
+        );
+        println!();
+    }
+
+    check::report(missing_count + failed_count);
+    println!(
+        "{}: {} documents with {} references, {} never fetched, {} failed, {} fetched assets unreferenced",
+        "Summary".bold(),
+        documents.len(),
+        referenced.len(),
+        missing_count,
+        failed_count,
+        unreferenced.len()
+    );
+}
@@ -0,0 +1,366 @@
+use colored::Colorize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use url::Url;
+
+use crate::{
+    domain,
+    har::{Entry, Har, PostData},
+};
+
+// parameters whose values are credentials in their own right
+const SECRET_PARAMS: [&str; 6] = [
+    "code",
+    "access_token",
+    "id_token",
+    "refresh_token",
+    "password",
+    "client_secret",
+];
+
+#[derive(Debug)]
+pub struct AuthStep {
+    pub request_num: usize,
+    pub method: String,
+    pub url: String,
+    pub description: String,
+}
+
+#[derive(Debug, Default)]
+pub struct AuthFlows {
+    pub steps: Vec<AuthStep>,
+    pub issues: Vec<String>,
+}
+
+/// Get the parameters of a form-encoded or JSON request body.
+pub fn body_params(post_data: &PostData) -> Vec<(String, String)> {
+    if let Ok(Value::Object(object)) = serde_json::from_str::<Value>(&post_data.text) {
+        return object
+            .into_iter()
+            .map(|(k, v)| match v {
+                Value::String(s) => (k, s),
+                v => (k, v.to_string()),
+            })
+            .collect();
+    }
+
+    url::form_urlencoded::parse(post_data.text.trim().as_bytes())
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect()
+}
+
+fn response_json(entry: &Entry) -> Option<serde_json::Map<String, Value>> {
+    let text = entry.response.content.as_ref()?.decoded_text()?;
+    match serde_json::from_str(&text).ok()? {
+        Value::Object(object) => Some(object),
+        _ => None,
+    }
+}
+
+/// Parameters in the query string and fragment, where implicit flows return tokens.
+fn url_params(url: &Url) -> BTreeMap<String, String> {
+    let mut params: BTreeMap<String, String> = url.query_pairs().into_owned().collect();
+    if let Some(fragment) = url.fragment() {
+        params.extend(url::form_urlencoded::parse(fragment.as_bytes()).into_owned());
+    }
+    params
+}
+
+pub fn is_local(url: &Url) -> bool {
+    matches!(url.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"))
+}
+
+/// Describe the authentication step an entry performs, if any, noting issues along the way.
+fn classify(
+    entry: &Entry,
+    url: &Url,
+    redirect_uris: &mut BTreeSet<String>,
+    issues: &mut Vec<String>,
+    num: usize,
+) -> Option<String> {
+    let params = url_params(url);
+    let body = entry
+        .request
+        .post_data
+        .as_ref()
+        .map(body_params)
+        .unwrap_or_default();
+    let has_body_param = |name: &str| body.iter().any(|(k, _)| k == name);
+    let path = url.path().to_lowercase();
+
+    if path.ends_with("/.well-known/openid-configuration") {
+        return Some("OIDC discovery".to_string());
+    }
+    if path.ends_with("/jwks") || path.ends_with("jwks.json") || path.ends_with("/certs") {
+        return Some("fetch signing keys (JWKS)".to_string());
+    }
+
+    if params.contains_key("response_type") && params.contains_key("client_id") {
+        let response_type = &params["response_type"];
+        let mut description = format!(
+            "authorization request (response_type={}, client_id={})",
+            response_type, params["client_id"]
+        );
+        if let Some(redirect_uri) = params.get("redirect_uri") {
+            description.push_str(&format!(", redirect_uri={}", redirect_uri));
+            redirect_uris.insert(redirect_uri.clone());
+            if let Ok(redirect) = Url::parse(redirect_uri) {
+                if redirect.scheme() == "http" && !is_local(&redirect) {
+                    issues_push(
+                        issues,
+                        num,
+                        format!("redirect_uri {} is not HTTPS", redirect_uri),
+                    );
+                }
+            }
+        }
+        if !params.contains_key("state") {
+            issues_push(
+                issues,
+                num,
+                "authorization request has no state parameter (CSRF)".to_string(),
+            );
+        }
+        if response_type.contains("code") && !params.contains_key("code_challenge") {
+            issues_push(
+                issues,
+                num,
+                "code flow without PKCE code_challenge".to_string(),
+            );
+        }
+        if response_type.contains("token") {
+            issues_push(
+                issues,
+                num,
+                format!(
+                    "implicit flow returns tokens in the URL (response_type={})",
+                    response_type
+                ),
+            );
+        }
+        return Some(description);
+    }
+
+    if let Some(grant_type) = body.iter().find(|(k, _)| k == "grant_type").map(|(_, v)| v) {
+        let mut description = format!("token request (grant_type={})", grant_type);
+        if let Some(tokens) = response_json(entry) {
+            let issued: Vec<&str> = ["access_token", "id_token", "refresh_token"]
+                .into_iter()
+                .filter(|t| tokens.contains_key(*t))
+                .collect();
+            if !issued.is_empty() {
+                description.push_str(&format!(" -> issued {}", issued.join(", ")));
+            }
+        }
+        return Some(description);
+    }
+
+    if has_body_param("SAMLRequest") || params.contains_key("SAMLRequest") {
+        return Some("SAML authentication request".to_string());
+    }
+    if has_body_param("SAMLResponse") || params.contains_key("SAMLResponse") {
+        return Some("SAML assertion posted to service provider".to_string());
+    }
+
+    let without_query = format!(
+        "{}://{}{}",
+        url.scheme(),
+        url.host_str().unwrap_or_default(),
+        url.path()
+    );
+    if params.contains_key("code")
+        && (params.contains_key("state") || redirect_uris.contains(&without_query))
+    {
+        return Some("authorization code returned to redirect_uri".to_string());
+    }
+    if params.contains_key("id_token") || params.contains_key("access_token") {
+        return Some("tokens returned in URL".to_string());
+    }
+    if params.contains_key("error") && params.contains_key("state") {
+        return Some(format!("authorization error ({})", params["error"]));
+    }
+
+    if path.contains("userinfo") {
+        return Some("fetch user info".to_string());
+    }
+    if has_body_param("password") || has_body_param("passwd") {
+        return Some("credentials submitted".to_string());
+    }
+    if path.contains("logout") || path.contains("end_session") {
+        return Some("logout".to_string());
+    }
+
+    None
+}
+
+/// Collect issued tokens, as value -> (request number, issuing host).
+fn issued_tokens(har: &Har) -> BTreeMap<String, (usize, String)> {
+    let mut tokens = BTreeMap::new();
+    for (i, entry) in har.log.entries.iter().enumerate() {
+        let Some(json) = response_json(entry) else {
+            continue;
+        };
+        let Some(host) = entry.request.host() else {
+            continue;
+        };
+        for name in ["access_token", "id_token"] {
+            if let Some(Value::String(token)) = json.get(name) {
+                tokens.insert(token.clone(), (i + 1, host.clone()));
+            }
+        }
+    }
+    tokens
+}
+
+pub fn find_auth_flows(har: &Har) -> AuthFlows {
+    let tld_extractor = domain::build_tld_extractor();
+    let first_party = domain::first_party(har, &tld_extractor);
+    let tokens = issued_tokens(har);
+    let site = |host: &str| {
+        domain::registrable_domain(host, &tld_extractor).unwrap_or_else(|| host.to_string())
+    };
+
+    let mut flows = AuthFlows::default();
+    let mut redirect_uris = BTreeSet::new();
+
+    for (i, entry) in har.log.entries.iter().enumerate() {
+        let num = i + 1;
+        let Ok(url) = Url::parse(&entry.request.url) else {
+            continue;
+        };
+        let Some(host) = url.host_str() else {
+            continue;
+        };
+
+        if let Some(description) = classify(entry, &url, &mut redirect_uris, &mut flows.issues, num)
+        {
+            flows.steps.push(AuthStep {
+                request_num: num,
+                method: entry.request.method.to_string(),
+                url: entry.request.url.to_string(),
+                description,
+            });
+        }
+
+        // anything secret going over plaintext
+        let params = url_params(&url);
+        let body = entry
+            .request
+            .post_data
+            .as_ref()
+            .map(body_params)
+            .unwrap_or_default();
+        let secrets: BTreeSet<&str> = params
+            .keys()
+            .map(String::as_str)
+            .chain(body.iter().map(|(k, _)| k.as_str()))
+            .filter(|k| SECRET_PARAMS.contains(k))
+            .collect();
+        let bearer = entry
+            .request
+            .header("authorization")
+            .filter(|v| v.to_lowercase().starts_with("bearer "))
+            .map(|v| v[7..].trim());
+
+        if url.scheme() == "http" && !is_local(&url) {
+            for secret in &secrets {
+                issues_push(
+                    &mut flows.issues,
+                    num,
+                    format!("{} sent over HTTP to {}", secret, host),
+                );
+            }
+            if bearer.is_some() {
+                issues_push(
+                    &mut flows.issues,
+                    num,
+                    format!("bearer token sent over HTTP to {}", host),
+                );
+            }
+        }
+
+        // tokens in the URL end up in logs, history and Referer headers
+        for name in ["access_token", "id_token", "refresh_token", "password"] {
+            if url.query_pairs().any(|(k, _)| k == name) {
+                issues_push(
+                    &mut flows.issues,
+                    num,
+                    format!("{} sent in the query string", name),
+                );
+            }
+        }
+
+        // issued tokens should only go back to the issuer or the first party
+        let sent_tokens = bearer
+            .into_iter()
+            .chain(params.values().map(String::as_str))
+            .chain(body.iter().map(|(_, v)| v.as_str()));
+        for token in sent_tokens {
+            let Some((issued_num, issuer)) = tokens.get(token) else {
+                continue;
+            };
+            let receiver = site(host);
+            if !domain::same_owner(&receiver, &site(issuer))
+                && !first_party
+                    .as_ref()
+                    .is_some_and(|fp| fp.contains(&receiver))
+            {
+                issues_push(
+                    &mut flows.issues,
+                    num,
+                    format!(
+                        "token issued by {} (#{}) sent to {}",
+                        issuer, issued_num, host
+                    ),
+                );
+            }
+        }
+    }
+
+    flows
+}
+
+fn issues_push(issues: &mut Vec<String>, num: usize, issue: String) {
+    let issue = format!("#{}: {}", num, issue);
+    if !issues.contains(&issue) {
+        issues.push(issue);
+    }
+}
+
+pub fn auth_flows(har: &Har) {
+    let flows = find_auth_flows(har);
+
+    if flows.steps.is_empty() {
+        println!("{}", "No authentication flows found.".green());
+    } else {
+        println!("{}:", "Authentication steps".bold().blue());
+        for (i, step) in flows.steps.iter().enumerate() {
+            if i > 0 {
+                println!("    {}", "|".dimmed());
+            }
+            println!(
+                "    #{} {} {}",
+                step.request_num,
+                step.method.purple().bold(),
+                step.url
+            );
+            println!("    {} {}", "->".dimmed(), step.description.cyan());
+        }
+        println!();
+    }
+
+    if !flows.issues.is_empty() {
+        println!("{}:", "Issues".bold().red());
+        for issue in &flows.issues {
+            println!("    {}", issue);
+        }
+        println!();
+    }
+
+    println!(
+        "{}: {} authentication steps, {} issues",
+        "Summary".bold(),
+        flows.steps.len(),
+        flows.issues.len()
+    );
+}
@@ -0,0 +1,179 @@
+use colored::Colorize;
+use std::{collections::BTreeSet, fmt};
+
+use crate::{format, har::Har, sizes};
+
+// browsers open at most this many connections to an origin over HTTP/1
+const PARALLEL_REQUESTS: usize = 6;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    pub name: String,
+    pub down_kbps: f64,
+    pub up_kbps: f64,
+    pub rtt_ms: f64,
+}
+
+impl fmt::Display for Link {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({} down, {} up, {} ms RTT)",
+            self.name,
+            speed(self.down_kbps),
+            speed(self.up_kbps),
+            self.rtt_ms
+        )
+    }
+}
+
+fn speed(kbps: f64) -> String {
+    if kbps >= 1000.0 {
+        format!("{} Mbps", kbps / 1000.0)
+    } else {
+        format!("{} Kbps", kbps)
+    }
+}
+
+// WebPageTest's connection profiles, as (name, down Kbps, up Kbps, RTT ms)
+const PROFILES: [(&str, f64, f64, f64); 6] = [
+    ("slow-3g", 400.0, 400.0, 400.0),
+    ("3g", 1600.0, 768.0, 300.0),
+    ("4g", 9000.0, 9000.0, 170.0),
+    ("lte", 12000.0, 12000.0, 70.0),
+    ("cable", 5000.0, 1000.0, 28.0),
+    ("fiber", 20000.0, 5000.0, 4.0),
+];
+
+/// Parse a link profile by name, i.e. `3g`, or as `DOWN/UP/RTT` in Kbps and milliseconds, i.e.
+/// `2000/500/100`.
+pub fn parse_link(s: &str) -> Result<Link, String> {
+    let s = s.trim().to_lowercase();
+    if let Some((name, down, up, rtt)) = PROFILES.iter().find(|(name, ..)| *name == s) {
+        return Ok(Link {
+            name: name.to_string(),
+            down_kbps: *down,
+            up_kbps: *up,
+            rtt_ms: *rtt,
+        });
+    }
+
+    let numbers: Vec<f64> = s
+        .split('/')
+        .map(|n| n.trim().parse::<f64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| {
+            let names: Vec<&str> = PROFILES.iter().map(|(name, ..)| *name).collect();
+            format!(
+                "unknown link '{}', expected one of {} or DOWN/UP/RTT in Kbps and ms",
+                s,
+                names.join(", ")
+            )
+        })?;
+    match numbers[..] {
+        [down, up, rtt] if down > 0.0 && up > 0.0 && rtt >= 0.0 => Ok(Link {
+            name: s.clone(),
+            down_kbps: down,
+            up_kbps: up,
+            rtt_ms: rtt,
+        }),
+        _ => Err(format!(
+            "invalid link '{}', expected positive DOWN/UP/RTT in Kbps and ms",
+            s
+        )),
+    }
+}
+
+/// Estimate how long the capture's requests take over a link: the bytes over its bandwidth, a
+/// round trip per batch of parallel requests, and the two round trips of connecting, three over
+/// TLS, which origins do in parallel. It leaves out server time and what the page waits on, so
+/// it's a floor.
+pub fn estimate_ms(link: &Link, sent: u64, received: u64, requests: usize, tls: bool) -> f64 {
+    let transfer_ms = sent as f64 * 8.0 / link.up_kbps + received as f64 * 8.0 / link.down_kbps;
+    let setup_round_trips = if tls { 3 } else { 2 };
+    let request_round_trips = requests.div_ceil(PARALLEL_REQUESTS);
+    transfer_ms + (setup_round_trips + request_round_trips) as f64 * link.rtt_ms
+}
+
+fn percent(part: u64, whole: u64) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64 * 100.0
+    }
+}
+
+pub fn bandwidth(har: &Har, links: &[Link]) {
+    let network: Vec<_> = har
+        .log
+        .entries
+        .iter()
+        .filter(|entry| entry.request.url.starts_with("http"))
+        .collect();
+    if network.is_empty() {
+        println!("{}", "No network requests found.".yellow());
+        return;
+    }
+
+    let mut total = sizes::Sizes::default();
+    let mut origins = BTreeSet::new();
+    for entry in &network {
+        total += sizes::entry_sizes(entry);
+        if let Ok(url) = url::Url::parse(&entry.request.url) {
+            origins.insert(url.origin().ascii_serialization());
+        }
+    }
+
+    println!("{}:", "Bytes".bold().blue());
+    let row = |name: &str, headers: u64, body: u64| {
+        println!(
+            "    {}: {} ({} headers, {} body)",
+            name,
+            format::bytes(headers + body),
+            format::bytes(headers),
+            format::bytes(body)
+        );
+    };
+    row("Sent", total.request_headers, total.request_body);
+    row("Received", total.response_headers, total.response_body);
+    println!(
+        "    Headers: {:.1}% of the {} transferred",
+        percent(total.headers(), total.total()),
+        format::bytes(total.total())
+    );
+    if total.estimated > 0 {
+        println!(
+            "    {}",
+            format!(
+                "{} unknown sizes estimated from the headers and content",
+                total.estimated
+            )
+            .dimmed()
+        );
+    }
+    println!();
+
+    let tls = origins.iter().any(|origin| origin.starts_with("https:"));
+    let mut slowest = 0.0;
+    println!("{}:", "Estimated load times".bold().blue());
+    for link in links {
+        let ms = estimate_ms(link, total.request(), total.response(), network.len(), tls);
+        slowest = f64::max(slowest, ms);
+        println!("    {}: {:.1} s", link, ms / 1000.0);
+    }
+    println!(
+        "    {}",
+        "Transfer and round trips only, without server time or rendering.".dimmed()
+    );
+    println!();
+
+    println!(
+        "{}: {} requests to {} origins sent {} and received {}, {:.1} s at the slowest link",
+        "Summary".bold(),
+        network.len(),
+        origins.len(),
+        format::bytes(total.request()),
+        format::bytes(total.response()),
+        slowest / 1000.0
+    );
+}
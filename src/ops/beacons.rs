@@ -0,0 +1,251 @@
+use colored::Colorize;
+use std::collections::{BTreeMap, BTreeSet};
+use url::Url;
+
+use crate::{
+    domain,
+    har::{Entry, Har},
+    mime::{self, ResourceKind},
+};
+
+// query strings this long on a pixel are carrying data rather than cache-busting
+const LARGE_QUERY_LEN: usize = 100;
+
+// well known collection endpoints as (provider, host suffix, path prefix)
+const ANALYTICS_ENDPOINTS: [(&str, &str, &str); 30] = [
+    ("Google Analytics", "google-analytics.com", "/"),
+    ("Google Analytics", "analytics.google.com", "/g/collect"),
+    ("Google Ads", "doubleclick.net", "/"),
+    ("Google Ads", "googleadservices.com", "/pagead/conversion"),
+    ("Google Tag Manager", "googletagmanager.com", "/td"),
+    ("Meta Pixel", "facebook.com", "/tr"),
+    ("Meta Pixel", "facebook.net", "/tr"),
+    ("Segment", "api.segment.io", "/"),
+    ("Mixpanel", "mixpanel.com", "/track"),
+    ("Amplitude", "amplitude.com", "/2/httpapi"),
+    ("Amplitude", "api2.amplitude.com", "/"),
+    ("Heap", "heapanalytics.com", "/"),
+    ("Hotjar", "hotjar.com", "/"),
+    ("Microsoft Clarity", "clarity.ms", "/collect"),
+    ("Bing Ads", "bat.bing.com", "/action"),
+    ("LinkedIn Insight", "px.ads.linkedin.com", "/"),
+    ("TikTok Pixel", "analytics.tiktok.com", "/"),
+    ("X Pixel", "analytics.twitter.com", "/"),
+    ("Pinterest Tag", "ct.pinterest.com", "/"),
+    ("Reddit Pixel", "alb.reddit.com", "/"),
+    ("Snap Pixel", "tr.snapchat.com", "/"),
+    ("Adobe Analytics", "omtrdc.net", "/b/ss"),
+    ("Adobe Analytics", "2o7.net", "/b/ss"),
+    ("Yandex Metrica", "mc.yandex.ru", "/watch"),
+    ("Quantcast", "pixel.quantserve.com", "/"),
+    ("Comscore", "scorecardresearch.com", "/"),
+    ("New Relic", "bam.nr-data.net", "/"),
+    ("Datadog RUM", "browser-intake-datadoghq.com", "/"),
+    ("Plausible", "plausible.io", "/api/event"),
+    ("Matomo", "", "/matomo.php"),
+];
+
+#[derive(Debug, Default)]
+pub struct ProviderBeacons {
+    pub hosts: BTreeSet<String>,
+    pub reasons: BTreeMap<&'static str, usize>,
+    pub fields: BTreeSet<String>,
+    pub count: usize,
+}
+
+/// The analytics or advertising provider a well known collection endpoint belongs to.
+pub fn known_provider(url: &Url) -> Option<&'static str> {
+    let host = url.host_str()?;
+    let path = url.path();
+
+    ANALYTICS_ENDPOINTS
+        .iter()
+        .find(|(_, suffix, prefix)| {
+            let host_matches =
+                suffix.is_empty() || host == *suffix || host.ends_with(&format!(".{}", suffix));
+            host_matches && path.starts_with(prefix)
+        })
+        .map(|(provider, _, _)| *provider)
+}
+
+/// Read the dimensions from a GIF or PNG header.
+fn image_dimensions(body: &[u8]) -> Option<(u32, u32)> {
+    if body.starts_with(b"GIF8") && body.len() >= 10 {
+        let width = u16::from_le_bytes([body[6], body[7]]);
+        let height = u16::from_le_bytes([body[8], body[9]]);
+        return Some((width as u32, height as u32));
+    }
+
+    if body.starts_with(b"\x89PNG\r\n\x1a\n") && body.len() >= 24 {
+        let width = u32::from_be_bytes([body[16], body[17], body[18], body[19]]);
+        let height = u32::from_be_bytes([body[20], body[21], body[22], body[23]]);
+        return Some((width, height));
+    }
+
+    None
+}
+
+fn has_empty_response(entry: &Entry) -> bool {
+    entry.response.status == 204
+        || entry
+            .response
+            .content
+            .as_ref()
+            .and_then(|c| c.decoded())
+            .is_none_or(|body| body.is_empty())
+}
+
+/// Why an entry looks like a beacon, if it does.
+fn beacon_reason(entry: &Entry, url: &Url) -> Option<&'static str> {
+    // the tag library itself is served from the same hosts as its beacons
+    let kind = entry.response.resource_kind();
+    if matches!(
+        kind,
+        ResourceKind::Document
+            | ResourceKind::Script
+            | ResourceKind::Stylesheet
+            | ResourceKind::Font
+    ) {
+        return None;
+    }
+
+    if known_provider(url).is_some() {
+        return Some("analytics endpoint");
+    }
+
+    if kind == ResourceKind::Image {
+        let dimensions = entry
+            .response
+            .content
+            .as_ref()
+            .and_then(|c| c.decoded())
+            .and_then(|body| image_dimensions(&body));
+        if dimensions == Some((1, 1)) {
+            return Some("1x1 image");
+        }
+    }
+
+    // sendBeacon() fires and forgets, so servers answer with nothing
+    if entry.request.method == "POST"
+        && entry.request.post_data.is_some()
+        && has_empty_response(entry)
+    {
+        return Some("beacon POST");
+    }
+
+    let query_len = url.query().map(str::len).unwrap_or(0);
+    let is_gif = entry.response.mime_type().as_deref() == Some("image/gif");
+    if query_len >= LARGE_QUERY_LEN && (is_gif || entry.response.status == 204) {
+        return Some("pixel with large query");
+    }
+
+    None
+}
+
+/// Collect the names of the fields sent in the query string and body.
+fn transmitted_fields(entry: &Entry, url: &Url) -> BTreeSet<String> {
+    let mut fields: BTreeSet<String> = url.query_pairs().map(|(name, _)| name.into()).collect();
+
+    let Some(post_data) = &entry.request.post_data else {
+        return fields;
+    };
+
+    if let Some(params) = &post_data.params {
+        fields.extend(params.iter().map(|p| p.name.to_string()));
+    }
+
+    let body = post_data.text.trim();
+    match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(serde_json::Value::Object(object)) => fields.extend(object.keys().cloned()),
+        // batched events are usually an array of objects with the same shape
+        Ok(serde_json::Value::Array(items)) => {
+            for item in items {
+                if let serde_json::Value::Object(object) = item {
+                    fields.extend(object.keys().cloned());
+                }
+            }
+        }
+        Ok(_) => {}
+        Err(_) => {
+            if mime::essence(&post_data.mime_type) == "application/x-www-form-urlencoded"
+                || (body.contains('=') && !body.contains(char::is_whitespace))
+            {
+                fields.extend(
+                    url::form_urlencoded::parse(body.as_bytes()).map(|(name, _)| name.into()),
+                );
+            }
+        }
+    }
+
+    fields
+}
+
+/// Find beacons, grouped by the analytics provider or registrable domain receiving them.
+pub fn find_beacons(har: &Har) -> BTreeMap<String, ProviderBeacons> {
+    let tld_extractor = domain::build_tld_extractor();
+    let mut providers: BTreeMap<String, ProviderBeacons> = BTreeMap::new();
+
+    for entry in &har.log.entries {
+        let Ok(url) = Url::parse(&entry.request.url) else {
+            continue;
+        };
+        let Some(host) = url.host_str() else {
+            continue;
+        };
+        let Some(reason) = beacon_reason(entry, &url) else {
+            continue;
+        };
+
+        let provider = known_provider(&url)
+            .map(str::to_string)
+            .or_else(|| domain::registrable_domain(host, &tld_extractor))
+            .unwrap_or_else(|| host.to_string());
+
+        let beacons = providers.entry(provider).or_default();
+        beacons.hosts.insert(host.to_string());
+        *beacons.reasons.entry(reason).or_insert(0) += 1;
+        beacons.fields.extend(transmitted_fields(entry, &url));
+        beacons.count += 1;
+    }
+
+    providers
+}
+
+pub fn beacons(har: &Har) {
+    let providers = find_beacons(har);
+
+    if providers.is_empty() {
+        println!("{}", "No beacons found.".green());
+        return;
+    }
+
+    for (provider, beacons) in &providers {
+        println!("{}:", provider.bold().blue());
+
+        let hosts: Vec<&str> = beacons.hosts.iter().map(String::as_str).collect();
+        println!("    Hosts: {}", hosts.join(", "));
+
+        let reasons: Vec<String> = beacons
+            .reasons
+            .iter()
+            .map(|(reason, count)| format!("{} ({})", reason, count))
+            .collect();
+        println!("    Detected as: {}", reasons.join(", "));
+
+        if beacons.fields.is_empty() {
+            println!("    Fields: {}", "none".dimmed());
+        } else {
+            let fields: Vec<&str> = beacons.fields.iter().map(String::as_str).collect();
+            println!("    Fields: {}", fields.join(", ").yellow());
+        }
+        println!();
+    }
+
+    let total: usize = providers.values().map(|b| b.count).sum();
+    println!(
+        "{}: {} beacons sent to {} providers",
+        "Summary".bold(),
+        total,
+        providers.len()
+    );
+}
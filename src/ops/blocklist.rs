@@ -1,63 +1,424 @@
 use anyhow::{Context, Result};
 use colored::Colorize;
-use directories::ProjectDirs;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use indicatif::{MultiProgress, ProgressBar};
+use ipnet::IpNet;
+use rayon::prelude::*;
+use regex::Regex;
 use reqwest::Client;
+use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs,
+    net::IpAddr,
     path::{Path, PathBuf},
-    time::Duration,
+    sync::OnceLock,
 };
 use tokio::io::AsyncWriteExt;
+use tracing::warn;
 
-use crate::har::Har;
+use crate::{check, dirs, domain, format, har::Har, offline, progress};
 
 use super::list_domains;
 
-const BLOCKLISTS: [(&str, &str); 7] = [
+// (url, file name, category)
+const BLOCKLISTS: [(&str, &str, &str); 8] = [
     (
         "https://github.com/mullvad/dns-blocklists/raw/refs/heads/main/output/doh/doh_adblock.txt",
         "mullvad_doh_adblock.txt",
+        "ads",
     ),
     (
         "https://github.com/mullvad/dns-blocklists/raw/refs/heads/main/output/doh/doh_adult.txt",
         "mullvad_doh_adult.txt",
+        "adult",
     ),
     (
         "https://github.com/mullvad/dns-blocklists/raw/refs/heads/main/output/doh/doh_gambling.txt",
         "mullvad_doh_gambling.txt",
+        "gambling",
     ),
     (
         "https://github.com/mullvad/dns-blocklists/raw/refs/heads/main/output/doh/doh_privacy.txt",
         "mullvad_doh_privacy.txt",
+        "privacy",
     ),
     (
         "https://github.com/mullvad/dns-blocklists/raw/refs/heads/main/output/doh/doh_social.txt",
         "mullvad_doh_social.txt",
+        "social",
     ),
     (
         "https://v.firebog.net/hosts/Easyprivacy.txt",
         "firebog_easy_privacy.txt",
+        "privacy",
     ),
     (
         "https://v.firebog.net/hosts/AdguardDNS.txt",
         "adguard_dns.txt",
+        "ads",
+    ),
+    (
+        "https://easylist.to/easylist/easyprivacy.txt",
+        "easyprivacy.txt",
+        "privacy",
     ),
 ];
 
-fn get_blocklists_dir() -> Result<PathBuf> {
-    let proj_dirs = ProjectDirs::from("com", "peterc-s", "harper")
-        .context("Failed to determine platform-specific project directories.")?;
+const BUNDLED_TRACKERS: &str = include_str!("../../data/trackers.txt");
+
+// what Adblock's `^` separator placeholder matches: anything but a letter, digit or `_-.%`
+const SEPARATOR: &str = r"(?:[^\w.%-]|$)";
+
+// bumped whenever the matcher's layout changes, so older cached ones are rebuilt
+const MATCHER_VERSION: u32 = 1;
+const MATCHER_FILE: &str = "matcher.bin";
+
+/// An Adblock-style rule such as `||tracker.com^/pixel` or `/analytics.js`, or a `/regex/`,
+/// matched against whole request URLs.
+#[derive(Serialize, Deserialize)]
+struct UrlRule {
+    text: String,
+    pattern: String,
+    // index of the list the rule came from
+    list: usize,
+    // most rules are never tried, so they're only compiled once a URL shares their token
+    #[serde(skip)]
+    regex: OnceLock<Option<Regex>>,
+}
+
+impl UrlRule {
+    fn is_match(&self, url: &str) -> bool {
+        self.regex
+            .get_or_init(|| Regex::new(&self.pattern).ok())
+            .as_ref()
+            .is_some_and(|regex| regex.is_match(url))
+    }
+}
+
+/// URL rules indexed by a word each needs the URL to contain, so a URL is only tried against
+/// the rules sharing one of its words rather than the whole list.
+#[derive(Default, Serialize, Deserialize)]
+struct UrlRules {
+    by_token: HashMap<String, Vec<UrlRule>>,
+    untokenized: Vec<UrlRule>,
+}
+
+impl UrlRules {
+    fn insert(&mut self, rule: UrlRule, token: Option<String>) {
+        match token {
+            Some(token) => self.by_token.entry(token).or_default().push(rule),
+            None => self.untokenized.push(rule),
+        }
+    }
+
+    /// Find the first rule of each list that matches a URL, keyed by list.
+    fn find(&self, url: &str) -> BTreeMap<usize, &UrlRule> {
+        let url_lower = url.to_lowercase();
+        let tokens: HashSet<&str> = url_lower
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|t| !t.is_empty())
+            .collect();
+
+        let mut found = BTreeMap::new();
+        let candidates = tokens
+            .iter()
+            .filter_map(|token| self.by_token.get(*token))
+            .flatten()
+            .chain(&self.untokenized);
+        for rule in candidates {
+            if !found.contains_key(&rule.list) && rule.is_match(url) {
+                found.insert(rule.list, rule);
+            }
+        }
+        found
+    }
+}
+
+/// The rules of every list merged together, so each host and URL is looked up once rather
+/// than once per list. Lists are identified by bits in the masks, so there can be up to 32.
+#[derive(Default, Serialize, Deserialize)]
+struct Matcher {
+    version: u32,
+    // (name, category, size) of each list, the size to tell when one's been replaced
+    lists: Vec<(String, String, u64)>,
+    // (domain, mask of the lists it's on), sorted by domain so a cached matcher loads without
+    // rebuilding a hash table of every listed domain
+    domains: Vec<(String, u32)>,
+    networks: Vec<(IpNet, u32)>,
+    urls: UrlRules,
+}
+
+/// Translate an Adblock filter pattern into a regex over the whole URL.
+fn adblock_regex(pattern: &str) -> String {
+    let mut regex = String::from("(?i)");
+    let mut rest = pattern;
+    if let Some(after) = rest.strip_prefix("||") {
+        // the rest starts at a label boundary of the host
+        regex.push_str(r"^[a-z][a-z0-9+.-]*://(?:[^/?#]*\.)?");
+        rest = after;
+    } else if let Some(after) = rest.strip_prefix('|') {
+        regex.push('^');
+        rest = after;
+    }
+    let (rest, end) = match rest.strip_suffix('|') {
+        Some(rest) => (rest, "$"),
+        None => (rest, ""),
+    };
+
+    for c in rest.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '^' => regex.push_str(SEPARATOR),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push_str(end);
+    regex
+}
+
+/// Pick the longest word of a pattern that any matching URL must contain as a whole word.
+/// Words touching a `*` or an unanchored end of the pattern may only be part of one.
+fn adblock_token(pattern: &str) -> Option<String> {
+    let start_anchored = pattern.starts_with('|');
+    let end_anchored = pattern.ends_with('|') || pattern.ends_with('^');
+    let pattern = pattern.trim_start_matches('|').trim_end_matches('|');
+
+    let mut best: Option<&str> = None;
+    let mut start = 0;
+    for (i, c) in pattern.char_indices().chain([(pattern.len(), '\0')]) {
+        if c.is_ascii_alphanumeric() {
+            continue;
+        }
+        let word = &pattern[start..i];
+        let bounded_before = if start == 0 {
+            start_anchored
+        } else {
+            !pattern[..start].ends_with('*')
+        };
+        let bounded_after = if i == pattern.len() {
+            end_anchored
+        } else {
+            c != '*'
+        };
+        if word.len() >= 2
+            && bounded_before
+            && bounded_after
+            && best.is_none_or(|b| word.len() > b.len())
+        {
+            best = Some(word);
+        }
+        start = i + c.len_utf8();
+    }
+    best.map(str::to_lowercase)
+}
+
+impl Matcher {
+    fn add_list(&mut self, name: &str, category: &str, content: &str) {
+        let list = self.lists.len();
+        let bit = 1 << list;
+        self.lists
+            .push((name.to_string(), category.to_string(), content.len() as u64));
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('!') || line.starts_with('#') {
+                continue;
+            }
+            // list headers, exceptions and element hiding rules don't block requests
+            if line.starts_with('[')
+                || line.starts_with("@@")
+                || ["##", "#@#", "#?#", "#$#"].iter().any(|m| line.contains(m))
+            {
+                continue;
+            }
+
+            if line.len() > 2 && line.starts_with('/') && line.ends_with('/') {
+                let rule = UrlRule {
+                    text: line.to_string(),
+                    pattern: format!("(?i){}", &line[1..line.len() - 1]),
+                    list,
+                    regex: OnceLock::new(),
+                };
+                self.urls.insert(rule, None);
+                continue;
+            }
+
+            // options such as `$third-party` can't be judged from a HAR, so rules match regardless
+            let pattern = line.split_once('$').map_or(line, |(pattern, _)| pattern);
+            if pattern.is_empty() {
+                continue;
+            }
+
+            if let Ok(network) = pattern.parse::<IpNet>() {
+                self.networks.push((network, bit));
+            } else if let Ok(ip) = pattern.parse::<IpAddr>() {
+                self.networks.push((IpNet::from(ip), bit));
+            } else if let Some(host) = pattern
+                .strip_prefix("||")
+                .map(|p| p.strip_suffix('^').unwrap_or(p))
+                .filter(|host| !host.contains(['/', '*', '^', '|']))
+            {
+                self.domains.push((domain::to_ascii(host), bit));
+            } else if pattern.contains(['/', '*', '^', '|']) {
+                let rule = UrlRule {
+                    text: line.to_string(),
+                    pattern: adblock_regex(pattern),
+                    list,
+                    regex: OnceLock::new(),
+                };
+                self.urls.insert(rule, adblock_token(pattern));
+            } else {
+                // lists may hold internationalised names in either form, URLs always use punycode
+                self.domains.push((domain::to_ascii(pattern), bit));
+            }
+        }
+    }
+
+    /// Get the mask of the lists a host or any of its parent domains is on.
+    fn domain_lists(&self, host: &str) -> u32 {
+        let mut lists = 0;
+        let mut suffix = host;
+        loop {
+            if let Ok(i) = self
+                .domains
+                .binary_search_by(|(domain, _)| domain.as_str().cmp(suffix))
+            {
+                lists |= self.domains[i].1;
+            }
+            match suffix.split_once('.') {
+                Some((_, parent)) => suffix = parent,
+                None => return lists,
+            }
+        }
+    }
+
+    /// Get the first network on each list containing an address, keyed by list.
+    fn networks_containing(&self, ip: &IpAddr) -> BTreeMap<usize, &IpNet> {
+        let mut found = BTreeMap::new();
+        for (network, mask) in &self.networks {
+            if !network.contains(ip) {
+                continue;
+            }
+            for list in 0..self.lists.len() {
+                if mask & (1 << list) != 0 {
+                    found.entry(list).or_insert(network);
+                }
+            }
+        }
+        found
+    }
+}
+
+fn build_matcher(lists: &[(&str, String, &str)]) -> Matcher {
+    let mut matcher = Matcher {
+        version: MATCHER_VERSION,
+        ..Default::default()
+    };
+    for (name, content, category) in lists {
+        matcher.add_list(name, category, content);
+    }
+
+    // merge domains on several lists into one entry
+    matcher.domains.par_sort_unstable();
+    matcher
+        .domains
+        .dedup_by(|(domain, mask), (kept, kept_mask)| {
+            if domain == kept {
+                *kept_mask |= *mask;
+            }
+            domain == kept
+        });
+    matcher
+}
+
+/// Read the downloaded lists, returning (name, content, category) for each.
+fn read_blocklists(blocklists_dir: &Path) -> Result<Vec<(&'static str, String, &'static str)>> {
+    let mut lists = Vec::new();
+    for (_, filename, category) in BLOCKLISTS {
+        let path = blocklists_dir.join(filename);
+        if !path.exists() {
+            continue;
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read blocklist: {:?}", path))?;
+        lists.push((filename, content, category));
+    }
+    Ok(lists)
+}
+
+fn save_matcher(matcher: &Matcher, blocklists_dir: &Path) -> Result<()> {
+    let path = blocklists_dir.join(MATCHER_FILE);
+    let bytes = bincode::serde::encode_to_vec(matcher, bincode::config::standard())
+        .context("Failed to encode blocklist matcher")?;
+    fs::write(&path, bytes).with_context(|| format!("Failed to write {:?}", path))
+}
+
+/// Load the matcher built from the downloaded lists, as long as none of them have changed
+/// since.
+fn load_cached_matcher(blocklists_dir: &Path) -> Option<Matcher> {
+    let path = blocklists_dir.join(MATCHER_FILE);
+    let built = fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+
+    let mut present = Vec::new();
+    for (_, filename, _) in BLOCKLISTS {
+        let Ok(metadata) = fs::metadata(blocklists_dir.join(filename)) else {
+            continue;
+        };
+        if metadata.modified().ok()? > built {
+            return None;
+        }
+        present.push((filename, metadata.len()));
+    }
+
+    let bytes = fs::read(&path).ok()?;
+    let (matcher, _): (Matcher, _) =
+        bincode::serde::decode_from_slice(&bytes, bincode::config::standard()).ok()?;
+    let lists: Vec<(&str, u64)> = matcher
+        .lists
+        .iter()
+        .map(|(name, _, size)| (name.as_str(), *size))
+        .collect();
+    (matcher.version == MATCHER_VERSION && lists == present).then_some(matcher)
+}
 
-    let data_dir = proj_dirs.data_dir();
-    let blocklists_dir = data_dir.join("blocklists");
+/// Get a matcher for the downloaded lists, building and caching it if they've changed, or for
+/// the bundled tracker list if none are downloaded.
+fn load_matcher() -> Result<Matcher> {
+    let blocklists_dir = get_blocklists_dir()?;
+    let matcher = match load_cached_matcher(&blocklists_dir) {
+        Some(matcher) => matcher,
+        None => {
+            let lists = read_blocklists(&blocklists_dir)?;
+            if lists.is_empty() {
+                warn!(
+                    "using the bundled tracker list, run `harper - get-block-lists` for the full blocklists"
+                );
+                let bundled = [("bundled trackers", BUNDLED_TRACKERS.to_string(), "privacy")];
+                return Ok(build_matcher(&bundled));
+            }
+
+            let pb = progress::spinner("Building blocklist matcher");
+            let matcher = build_matcher(&lists);
+            pb.finish_and_clear();
+
+            // failing to cache only makes the next run slower
+            let _ = save_matcher(&matcher, &blocklists_dir);
+            matcher
+        }
+    };
 
-    if !blocklists_dir.exists() {
-        fs::create_dir_all(&blocklists_dir).context("Failed to create blocklists directory")?;
+    if matcher.lists.len() < BLOCKLISTS.len() {
+        warn!(
+            "{} of {} blocklists are missing, run `harper - get-block-lists` to download them",
+            BLOCKLISTS.len() - matcher.lists.len(),
+            BLOCKLISTS.len()
+        );
     }
+    Ok(matcher)
+}
 
-    Ok(blocklists_dir)
+fn get_blocklists_dir() -> Result<PathBuf> {
+    dirs::get_data_dir("blocklists")
 }
 
 async fn download_blocklist(
@@ -108,22 +469,16 @@ async fn download_blocklist(
 }
 
 pub async fn download_all_blocklists() -> Result<()> {
+    offline::ensure_online("download blocklists")?;
     let client = Client::new();
     let blocklists_dir = get_blocklists_dir()?;
     let multi = MultiProgress::new();
 
     // parallel download handles
     let mut handles = vec![];
-    let style = ProgressStyle::default_bar()
-        .template("{spinner:.green} [{elapsed_precise}] {msg:25!} [{bar:20.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
-        .unwrap()
-        .progress_chars("##-");
-
-    for (url, path) in BLOCKLISTS {
-        let pb = multi.add(ProgressBar::new(0));
-        pb.set_style(style.clone());
+    for (url, path, _) in BLOCKLISTS {
+        let pb = multi.add(progress::bytes(0));
         pb.set_message(path.to_string()); // Set blocklist name here
-        pb.enable_steady_tick(Duration::from_millis(100));
 
         let client = client.clone();
         let blocklists_dir = blocklists_dir.clone();
@@ -141,6 +496,12 @@ pub async fn download_all_blocklists() -> Result<()> {
         handle.await??;
     }
 
+    // build the matcher now so the first check doesn't have to
+    let pb = progress::spinner("Building blocklist matcher");
+    let matcher = build_matcher(&read_blocklists(&blocklists_dir)?);
+    save_matcher(&matcher, &blocklists_dir)?;
+    pb.finish_and_clear();
+
     Ok(())
 }
 
@@ -151,51 +512,221 @@ pub fn remove_blocklists() -> Result<()> {
 }
 
 pub fn check_blocklists(har: &Har) -> Result<()> {
-    let domains = list_domains::list_domains(har);
-    let blocklists_dir = get_blocklists_dir()?;
+    let matcher = load_matcher()?;
 
-    for (_, filename) in BLOCKLISTS.iter() {
-        let mut blocklist_domains = HashSet::new();
+    // IP literals are matched against networks below, not as domain suffixes
+    let domains: Vec<String> = list_domains::list_domains(har)
+        .into_iter()
+        .filter(|domain| domain::ip_literal(domain).is_none())
+        .collect();
 
-        let path = blocklists_dir.join(filename);
-        let content = fs::read_to_string(&path).with_context(|| {
-            format!(
-                "Failed to read blocklist: {:?}\nHave you run {}?\n{}",
-                path,
-                "harper - get-block-lists".green(),
-                "Caused by".red().bold()
-            )
-        })?;
+    // IP literal hosts and the addresses names were served from, to match against CIDR entries
+    let mut ips: BTreeMap<IpAddr, BTreeSet<String>> = BTreeMap::new();
+    for entry in &har.log.entries {
+        let Some(host) = entry.request.host() else {
+            continue;
+        };
+        if let Some(ip) = domain::ip_literal(&host) {
+            ips.entry(ip).or_default();
+        } else if let Some(ip) = entry.server_ip() {
+            ips.entry(ip).or_default().insert(host);
+        }
+    }
 
-        for line in content.lines() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('!') || line.starts_with('#') {
+    // each URL once, with the first request that fetched it
+    let mut urls: Vec<(usize, &str)> = Vec::new();
+    let mut seen_urls = HashSet::new();
+    for (i, entry) in har.log.entries.iter().enumerate() {
+        if seen_urls.insert(&*entry.request.url) {
+            urls.push((i + 1, &entry.request.url));
+        }
+    }
+
+    // match everything against every list at once, then report list by list
+    let domain_lists: Vec<(&str, String, u32)> = domains
+        .par_iter()
+        .map(|domain| {
+            let lower = domain.to_lowercase();
+            let lists = matcher.domain_lists(&lower);
+            (domain.as_str(), lower, lists)
+        })
+        .collect();
+    let ip_networks: Vec<(&IpAddr, &BTreeSet<String>, BTreeMap<usize, &IpNet>)> = ips
+        .iter()
+        .map(|(ip, hosts)| (ip, hosts, matcher.networks_containing(ip)))
+        .collect();
+    let url_rules: Vec<(usize, &str, BTreeMap<usize, &UrlRule>)> = urls
+        .par_iter()
+        .map(|(request_num, url)| (*request_num, *url, matcher.urls.find(url)))
+        .collect();
+
+    // the host and address of each entry, to tell which lists it was found on
+    let entry_hosts: Vec<(Option<String>, Option<IpAddr>)> = har
+        .log
+        .entries
+        .par_iter()
+        .map(|entry| {
+            let host = entry.request.host().map(|h| h.to_lowercase());
+            let ip = host
+                .as_deref()
+                .and_then(domain::ip_literal)
+                .or_else(|| entry.server_ip());
+            (host, ip)
+        })
+        .collect();
+
+    // the categories of the lists each entry was found on
+    let mut entry_categories: Vec<BTreeSet<&str>> = vec![BTreeSet::new(); har.log.entries.len()];
+
+    for (list, (filename, category, _)) in matcher.lists.iter().enumerate() {
+        let mut found_hosts = HashSet::new();
+        let mut found_ips = HashSet::new();
+        let mut found_urls = HashSet::new();
+
+        println!("{}: {}", "Checking blocklist".blue().bold(), filename);
+        for (domain, lower, lists) in &domain_lists {
+            if lists & (1 << list) != 0 {
+                println!("{}: {}", "Found".yellow(), domain.red());
+                found_hosts.insert(lower.as_str());
+            }
+        }
+
+        for (ip, hosts, networks) in &ip_networks {
+            let Some(network) = networks.get(&list) else {
                 continue;
+            };
+            found_ips.insert(**ip);
+            if hosts.is_empty() {
+                println!(
+                    "{}: {} (in {})",
+                    "Found".yellow(),
+                    ip.to_string().red(),
+                    network
+                );
+            } else {
+                let hosts: Vec<&str> = hosts.iter().map(String::as_str).collect();
+                println!(
+                    "{}: {} served from {} (in {})",
+                    "Found".yellow(),
+                    hosts.join(", ").red(),
+                    ip,
+                    network
+                );
             }
+        }
 
-            blocklist_domains.insert(line);
+        for (request_num, url, rules) in &url_rules {
+            if let Some(rule) = rules.get(&list) {
+                found_urls.insert(*url);
+                println!(
+                    "{}: #{} {} (matched {})",
+                    "Found".yellow(),
+                    request_num,
+                    url.red(),
+                    rule.text
+                );
+            }
         }
+        println!();
 
-        println!("{}: {}", "Checking blocklist".blue().bold(), filename);
-        for domain in &domains {
-            let domain_lower = domain.to_lowercase();
-            let parts: Vec<&str> = domain_lower.split('.').collect();
-            let mut found = false;
-
-            for i in 0..parts.len() {
-                let suffix = parts[i..].join(".");
-                if blocklist_domains.contains(&suffix.as_str()) {
-                    found = true;
-                    break;
-                }
+        for ((entry, (host, ip)), categories) in har
+            .log
+            .entries
+            .iter()
+            .zip(&entry_hosts)
+            .zip(&mut entry_categories)
+        {
+            if host
+                .as_ref()
+                .is_some_and(|h| found_hosts.contains(h.as_str()))
+                || ip.is_some_and(|ip| found_ips.contains(&ip))
+                || found_urls.contains(&*entry.request.url)
+            {
+                categories.insert(category.as_str());
             }
+        }
+    }
+
+    print_summary(har, &entry_categories);
+    Ok(())
+}
+
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        return 0.0;
+    }
+    part as f64 * 100.0 / total as f64
+}
 
-            if found {
-                println!("{}: {}", "Found".yellow(), domain.red())
+/// Print how much of the capture went to listed hosts, by category and by page.
+fn print_summary(har: &Har, entry_categories: &[BTreeSet<&str>]) {
+    let mut by_category: BTreeMap<&str, (usize, u64)> = BTreeMap::new();
+    for (entry, categories) in har.log.entries.iter().zip(entry_categories) {
+        for category in categories {
+            let totals = by_category.entry(category).or_default();
+            totals.0 += 1;
+            totals.1 += entry.response.body_bytes();
+        }
+    }
+
+    if !by_category.is_empty() {
+        println!("{}:", "Categories".bold().blue());
+        for (category, (requests, bytes)) in &by_category {
+            println!(
+                "    {}: {} requests, {}",
+                category.yellow(),
+                requests,
+                format::bytes(*bytes)
+            );
+        }
+        println!();
+    }
+
+    if let Some(pages) = har.log.pages.as_ref().filter(|pages| !pages.is_empty()) {
+        println!("{}:", "Pages".bold().blue());
+        for page in pages {
+            let (mut requests, mut listed, mut listed_bytes) = (0, 0, 0);
+            for (entry, categories) in har.log.entries.iter().zip(entry_categories) {
+                if entry.pageref.as_deref() != Some(&*page.id) {
+                    continue;
+                }
+                requests += 1;
+                if !categories.is_empty() {
+                    listed += 1;
+                    listed_bytes += entry.response.body_bytes();
+                }
             }
+            println!(
+                "    {} ({}): {} of {} requests listed, {}",
+                page.id.cyan(),
+                page.title,
+                listed,
+                requests,
+                format::bytes(listed_bytes)
+            );
         }
         println!();
     }
 
-    Ok(())
+    let (mut listed, mut listed_bytes, mut total_bytes) = (0, 0, 0);
+    for (entry, categories) in har.log.entries.iter().zip(entry_categories) {
+        let bytes = entry.response.body_bytes();
+        total_bytes += bytes;
+        if !categories.is_empty() {
+            listed += 1;
+            listed_bytes += bytes;
+        }
+    }
+    check::report(listed);
+    let requests = har.log.entries.len();
+    println!(
+        "{}: {} of {} requests ({:.1}%) and {} of {} ({:.1}%) went to listed hosts",
+        "Summary".bold(),
+        listed,
+        requests,
+        percent(listed as u64, requests as u64),
+        format::bytes(listed_bytes),
+        format::bytes(total_bytes),
+        percent(listed_bytes, total_bytes)
+    );
 }
@@ -0,0 +1,229 @@
+use anyhow::{Context, Result, anyhow};
+use colored::Colorize;
+use serde::Deserialize;
+use std::{collections::BTreeMap, fs, path::Path};
+use tracing::warn;
+
+use crate::{
+    check, domain, format,
+    har::{Entry, Har},
+};
+
+/// A size given either as a number of bytes or a string with units, i.e. `"500 KiB"`.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(try_from = "SizeValue")]
+pub struct Size(u64);
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum SizeValue {
+    Bytes(u64),
+    Text(String),
+}
+
+impl TryFrom<SizeValue> for Size {
+    type Error = anyhow::Error;
+
+    fn try_from(value: SizeValue) -> Result<Self> {
+        match value {
+            SizeValue::Bytes(n) => Ok(Size(n)),
+            SizeValue::Text(text) => parse_size(&text).map(Size),
+        }
+    }
+}
+
+fn parse_size(text: &str) -> Result<u64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let number: f64 = number
+        .parse()
+        .with_context(|| format!("Invalid size '{}'", text))?;
+
+    let multiplier = match unit.trim().to_lowercase().as_str() {
+        "" | "b" => 1.0,
+        "kb" => 1e3,
+        "kib" => 1024.0,
+        "mb" => 1e6,
+        "mib" => 1024.0 * 1024.0,
+        "gb" => 1e9,
+        "gib" => 1024.0 * 1024.0 * 1024.0,
+        _ => return Err(anyhow!("Unknown size unit in '{}'", text)),
+    };
+
+    Ok((number * multiplier) as u64)
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Limits {
+    pub bytes: Option<Size>,
+    pub requests: Option<usize>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Budget {
+    pub total_bytes: Option<Size>,
+    pub requests: Option<usize>,
+    pub third_party_bytes: Option<Size>,
+    pub third_party_requests: Option<usize>,
+    pub slowest_entry_ms: Option<f64>,
+    // keyed by resource kind (i.e. `script`), MIME type or wildcard (i.e. `image/*`)
+    #[serde(default)]
+    pub mime: BTreeMap<String, Limits>,
+}
+
+#[derive(Debug)]
+pub struct Check {
+    pub name: String,
+    pub actual: String,
+    pub limit: String,
+    pub passed: bool,
+}
+
+pub fn load_budget(path: &Path) -> Result<Budget> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read file: {:?}", path))?;
+    toml::from_str(&content).with_context(|| format!("Failed to parse budget file: {:?}", path))
+}
+
+fn matches_mime(entry: &Entry, key: &str) -> bool {
+    let key = key.to_lowercase();
+    let mime = entry.response.mime_type().unwrap_or_default();
+
+    if let Some(prefix) = key.strip_suffix("/*") {
+        return mime.split('/').next() == Some(prefix);
+    }
+    mime == key || entry.response.resource_kind().to_string() == key
+}
+
+fn check_bytes(checks: &mut Vec<Check>, name: &str, actual: u64, limit: Option<Size>) {
+    if let Some(Size(limit)) = limit {
+        checks.push(Check {
+            name: name.to_string(),
+            actual: format::bytes(actual),
+            limit: format::bytes(limit),
+            passed: actual <= limit,
+        });
+    }
+}
+
+fn check_count(checks: &mut Vec<Check>, name: &str, actual: usize, limit: Option<usize>) {
+    if let Some(limit) = limit {
+        checks.push(Check {
+            name: name.to_string(),
+            actual: actual.to_string(),
+            limit: limit.to_string(),
+            passed: actual <= limit,
+        });
+    }
+}
+
+/// Evaluate every budget that is set against the HAR.
+pub fn evaluate(har: &Har, budget: &Budget) -> Vec<Check> {
+    let tld_extractor = domain::build_tld_extractor();
+    let first_party = domain::first_party(har, &tld_extractor);
+    let entries = &har.log.entries;
+
+    let is_third_party = |entry: &&Entry| {
+        let site = entry
+            .request
+            .host()
+            .and_then(|host| domain::registrable_domain(&host, &tld_extractor));
+        match (&first_party, site) {
+            (Some(first_party), Some(site)) => !first_party.contains(&site),
+            _ => false,
+        }
+    };
+    let third_party: Vec<&Entry> = entries.iter().filter(is_third_party).collect();
+
+    let mut checks = Vec::new();
+    check_bytes(
+        &mut checks,
+        "total bytes",
+        entries.iter().map(|e| e.response.body_bytes()).sum(),
+        budget.total_bytes,
+    );
+    check_count(&mut checks, "requests", entries.len(), budget.requests);
+    check_bytes(
+        &mut checks,
+        "third-party bytes",
+        third_party.iter().map(|e| e.response.body_bytes()).sum(),
+        budget.third_party_bytes,
+    );
+    check_count(
+        &mut checks,
+        "third-party requests",
+        third_party.len(),
+        budget.third_party_requests,
+    );
+
+    if let Some(limit) = budget.slowest_entry_ms {
+        let slowest = entries.iter().max_by(|a, b| a.time.total_cmp(&b.time));
+        let actual = slowest.map(|e| e.time).unwrap_or(0.0);
+        checks.push(Check {
+            name: "slowest entry".to_string(),
+            actual: match slowest {
+                Some(entry) => format!("{:.0}ms ({})", actual, entry.request.url),
+                None => "0ms".to_string(),
+            },
+            limit: format!("{:.0}ms", limit),
+            passed: actual <= limit,
+        });
+    }
+
+    for (key, limits) in &budget.mime {
+        let matching: Vec<&Entry> = entries.iter().filter(|e| matches_mime(e, key)).collect();
+        check_bytes(
+            &mut checks,
+            &format!("{} bytes", key),
+            matching.iter().map(|e| e.response.body_bytes()).sum(),
+            limits.bytes,
+        );
+        check_count(
+            &mut checks,
+            &format!("{} requests", key),
+            matching.len(),
+            limits.requests,
+        );
+    }
+
+    checks
+}
+
+/// Print the budget report, reporting each exceeded budget as a finding.
+pub fn budget(har: &Har, config: &Path) -> Result<()> {
+    let budget = load_budget(config)?;
+    let checks = evaluate(har, &budget);
+
+    if checks.is_empty() {
+        warn!("no budgets set in {:?}", config);
+        return Ok(());
+    }
+
+    for check in &checks {
+        let status = if check.passed {
+            "PASS".green().bold()
+        } else {
+            "FAIL".red().bold()
+        };
+        println!(
+            "{} {}: {} (budget {})",
+            status, check.name, check.actual, check.limit
+        );
+    }
+    println!();
+
+    let violations = checks.iter().filter(|c| !c.passed).count();
+    println!(
+        "{}: {} budgets checked, {} exceeded",
+        "Summary".bold(),
+        checks.len(),
+        violations
+    );
+    check::report(violations);
+    Ok(())
+}
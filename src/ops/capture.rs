@@ -0,0 +1,528 @@
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::Utc;
+use colored::Colorize;
+use rcgen::{BasicConstraints, CertificateParams, DnType, IsCa, Issuer, KeyPair, KeyUsagePurpose};
+use reqwest::{Client, Method, StatusCode, Version, header::HeaderMap, redirect::Policy};
+use rustls::{
+    ServerConfig,
+    crypto::aws_lc_rs,
+    pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer},
+};
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    signal,
+};
+use tokio_rustls::TlsAcceptor;
+use tracing::warn;
+use url::Url;
+
+use crate::{
+    dirs,
+    har::Entry,
+    offline,
+    ops::import::{self, Exchange, header_value},
+};
+
+const CA_CERT_FILE: &str = "harper-ca.pem";
+const CA_KEY_FILE: &str = "harper-ca.key";
+const CA_NAME: &str = "harper capture CA";
+
+// headers that only apply to a single connection and mustn't be forwarded
+const HOP_BY_HOP_HEADERS: [&str; 9] = [
+    "connection",
+    "proxy-connection",
+    "keep-alive",
+    "transfer-encoding",
+    "te",
+    "trailer",
+    "upgrade",
+    "proxy-authorization",
+    "content-length",
+];
+
+// generous limit on the size of a request head
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+
+struct CertificateAuthority {
+    cert_der: CertificateDer<'static>,
+    issuer: Issuer<'static, KeyPair>,
+}
+
+struct Proxy {
+    ca: CertificateAuthority,
+    client: Client,
+    tls_configs: Mutex<HashMap<String, Arc<ServerConfig>>>,
+    entries: Mutex<Vec<Entry<'static>>>,
+}
+
+// the parts of an upstream response needed to record and relay it
+struct Upstream {
+    status: StatusCode,
+    version: Version,
+    headers: HeaderMap,
+    remote_addr: Option<SocketAddr>,
+    body: Vec<u8>,
+}
+
+struct RawRequest {
+    method: String,
+    target: String,
+    headers: Vec<(String, String)>,
+    head_size: usize,
+}
+
+fn ca_params() -> Result<CertificateParams> {
+    // the issuer only needs the name and usages, so the params can be rebuilt each run
+    let mut params = CertificateParams::new(Vec::<String>::new())?;
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    params.distinguished_name.push(DnType::CommonName, CA_NAME);
+    params.key_usages = vec![
+        KeyUsagePurpose::KeyCertSign,
+        KeyUsagePurpose::CrlSign,
+        KeyUsagePurpose::DigitalSignature,
+    ];
+    Ok(params)
+}
+
+/// Write the CA key readable by its owner only, since anyone else who can read it can sign
+/// certificates the browsers that trust the CA accept.
+fn write_key(path: &Path, pem: &str) -> Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(path)
+        .with_context(|| format!("Failed to write {:?}", path))?;
+    file.write_all(pem.as_bytes())
+        .with_context(|| format!("Failed to write {:?}", path))
+}
+
+/// Load the capture CA from the data directory, generating it on first use.
+fn load_ca() -> Result<(CertificateAuthority, PathBuf)> {
+    let dir = dirs::get_data_dir("capture")?;
+    // keep other users out of the directory, which also covers a key written before this was
+    #[cfg(unix)]
+    fs::set_permissions(&dir, std::os::unix::fs::PermissionsExt::from_mode(0o700))
+        .with_context(|| format!("Failed to restrict {:?}", dir))?;
+    let cert_path = dir.join(CA_CERT_FILE);
+    let key_path = dir.join(CA_KEY_FILE);
+    let params = ca_params()?;
+
+    let (key, cert_pem) = if cert_path.exists() && key_path.exists() {
+        let key_pem = fs::read_to_string(&key_path)
+            .with_context(|| format!("Failed to read {:?}", key_path))?;
+        let cert_pem = fs::read_to_string(&cert_path)
+            .with_context(|| format!("Failed to read {:?}", cert_path))?;
+        let key = KeyPair::from_pem(&key_pem).context("Failed to parse capture CA key")?;
+        (key, cert_pem)
+    } else {
+        let key = KeyPair::generate().context("Failed to generate capture CA key")?;
+        let cert_pem = params.self_signed(&key)?.pem();
+        write_key(&key_path, &key.serialize_pem())?;
+        fs::write(&cert_path, &cert_pem)
+            .with_context(|| format!("Failed to write {:?}", cert_path))?;
+        println!("{}: {:?}", "Generated CA".green().bold(), cert_path);
+        (key, cert_pem)
+    };
+
+    let cert_der = rustls::pki_types::pem::PemObject::from_pem_slice(cert_pem.as_bytes())
+        .map_err(|e| anyhow!("Failed to parse capture CA certificate: {:?}", e))?;
+
+    Ok((
+        CertificateAuthority {
+            cert_der,
+            issuer: Issuer::new(params, key),
+        },
+        cert_path,
+    ))
+}
+
+impl Proxy {
+    /// Get a TLS config presenting a certificate for `host`, signed by the capture CA.
+    fn tls_config(&self, host: &str) -> Result<Arc<ServerConfig>> {
+        if let Some(config) = self.tls_configs.lock().unwrap().get(host) {
+            return Ok(config.clone());
+        }
+
+        let key = KeyPair::generate()?;
+        let mut params = CertificateParams::new(vec![host.to_string()])?;
+        params.distinguished_name.push(DnType::CommonName, host);
+        let cert = params.signed_by(&key, &self.ca.issuer)?;
+
+        let mut config =
+            ServerConfig::builder_with_provider(Arc::new(aws_lc_rs::default_provider()))
+                .with_safe_default_protocol_versions()?
+                .with_no_client_auth()
+                .with_single_cert(
+                    vec![cert.der().clone(), self.ca.cert_der.clone()],
+                    PrivateKeyDer::Pkcs8(PrivatePkcs8KeyDer::from(key.serialize_der())),
+                )?;
+        // only HTTP/1.1 is spoken to the client
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+        let config = Arc::new(config);
+        self.tls_configs
+            .lock()
+            .unwrap()
+            .insert(host.to_string(), config.clone());
+        Ok(config)
+    }
+}
+
+/// Read a request head, returning `None` if the connection closed cleanly.
+async fn read_head<S: AsyncRead + Unpin>(reader: &mut BufReader<S>) -> Result<Option<RawRequest>> {
+    let mut head = Vec::new();
+    loop {
+        let read = reader.read_until(b'\n', &mut head).await?;
+        if read == 0 {
+            if head.is_empty() {
+                return Ok(None);
+            }
+            bail!("Connection closed mid request");
+        }
+        // tolerate stray blank lines between requests
+        if head == b"\r\n" {
+            head.clear();
+            continue;
+        }
+        if head.ends_with(b"\r\n\r\n") {
+            break;
+        }
+        if head.len() > MAX_HEAD_SIZE {
+            bail!("Request head too large");
+        }
+    }
+
+    let mut headers = [httparse::EMPTY_HEADER; 128];
+    let mut request = httparse::Request::new(&mut headers);
+    request.parse(&head).context("Malformed request")?;
+
+    Ok(Some(RawRequest {
+        method: request.method.unwrap_or("GET").to_string(),
+        target: request.path.unwrap_or("/").to_string(),
+        headers: request
+            .headers
+            .iter()
+            .map(|h| {
+                (
+                    h.name.to_string(),
+                    String::from_utf8_lossy(h.value).to_string(),
+                )
+            })
+            .collect(),
+        head_size: head.len(),
+    }))
+}
+
+/// Append `length` bytes to `body`, growing it as they arrive rather than trusting the length
+/// up front, since a bogus one would otherwise abort on allocation.
+async fn read_exactly<S: AsyncRead + Unpin>(
+    reader: &mut BufReader<S>,
+    length: usize,
+    body: &mut Vec<u8>,
+) -> Result<()> {
+    let start = body.len();
+    (&mut *reader).take(length as u64).read_to_end(body).await?;
+    if body.len() - start < length {
+        bail!(
+            "Body ended after {} of {} bytes",
+            body.len() - start,
+            length
+        );
+    }
+    Ok(())
+}
+
+async fn read_body<S: AsyncRead + Unpin>(
+    reader: &mut BufReader<S>,
+    headers: &[(String, String)],
+) -> Result<Vec<u8>> {
+    let chunked = header_value(headers, "transfer-encoding")
+        .is_some_and(|te| te.to_lowercase().contains("chunked"));
+
+    let mut body = Vec::new();
+    if chunked {
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).await?;
+            let size = line.trim().split(';').next().unwrap_or("");
+            let size = usize::from_str_radix(size, 16).context("Malformed chunk size")?;
+            if size == 0 {
+                // skip any trailers
+                loop {
+                    line.clear();
+                    reader.read_line(&mut line).await?;
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                }
+                break;
+            }
+
+            read_exactly(reader, size, &mut body).await?;
+            line.clear();
+            reader.read_line(&mut line).await?;
+        }
+    } else if let Some(length) = header_value(headers, "content-length") {
+        let length: usize = length.trim().parse().context("Malformed Content-Length")?;
+        read_exactly(reader, length, &mut body).await?;
+    }
+
+    Ok(body)
+}
+
+async fn write_response<S: AsyncWrite + Unpin>(stream: &mut S, response: &Upstream) -> Result<()> {
+    let status = response.status;
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        status.as_u16(),
+        status.canonical_reason().unwrap_or("")
+    );
+    for (name, value) in &response.headers {
+        if HOP_BY_HOP_HEADERS.contains(&name.as_str()) {
+            continue;
+        }
+        head.push_str(&format!(
+            "{}: {}\r\n",
+            name,
+            String::from_utf8_lossy(value.as_bytes())
+        ));
+    }
+    head.push_str(&format!("content-length: {}\r\n\r\n", response.body.len()));
+
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&response.body).await?;
+    stream.flush().await?;
+    Ok(())
+}
+
+async fn write_error<S: AsyncWrite + Unpin>(stream: &mut S, error: &anyhow::Error) -> Result<()> {
+    let body = format!("harper capture: {:#}\n", error);
+    let response = format!(
+        "HTTP/1.1 502 Bad Gateway\r\ncontent-type: text/plain\r\ncontent-length: {}\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    stream.write_all(response.as_bytes()).await?;
+    Ok(())
+}
+
+/// Forward requests from the client, recording each exchange.
+///
+/// `origin` is set inside a CONNECT tunnel, where request targets are only paths.
+async fn serve<S: AsyncRead + AsyncWrite + Unpin>(
+    proxy: &Proxy,
+    stream: S,
+    origin: Option<&str>,
+    first: Option<RawRequest>,
+) -> Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut next = first;
+
+    loop {
+        let request = match next.take() {
+            Some(request) => request,
+            None => match read_head(&mut reader).await? {
+                Some(request) => request,
+                None => return Ok(()),
+            },
+        };
+
+        let url = match origin {
+            Some(origin) => Url::parse(&format!("{}{}", origin, request.target)),
+            None => Url::parse(&request.target),
+        }
+        .with_context(|| format!("Invalid request target: {}", request.target))?;
+
+        let body = read_body(&mut reader, &request.headers).await?;
+        let close = header_value(&request.headers, "connection")
+            .is_some_and(|c| c.eq_ignore_ascii_case("close"));
+
+        let mut builder = proxy
+            .client
+            .request(Method::from_bytes(request.method.as_bytes())?, url.clone());
+        for (name, value) in &request.headers {
+            let name_lower = name.to_lowercase();
+            if name_lower == "host" || HOP_BY_HOP_HEADERS.contains(&name_lower.as_str()) {
+                continue;
+            }
+            builder = builder.header(name, value);
+        }
+
+        let started = Utc::now();
+        let timer = Instant::now();
+        let result = async {
+            let response = builder.body(body.clone()).send().await?;
+            Ok::<_, reqwest::Error>(Upstream {
+                status: response.status(),
+                version: response.version(),
+                headers: response.headers().clone(),
+                remote_addr: response.remote_addr(),
+                body: response.bytes().await?.to_vec(),
+            })
+        }
+        .await;
+
+        let stream = reader.get_mut();
+        match result {
+            Ok(response) => {
+                let elapsed = timer.elapsed().as_secs_f64() * 1000.0;
+                println!(
+                    "{} {} {}",
+                    format!("[{}]", response.status.as_u16()).purple().bold(),
+                    request.method,
+                    url
+                );
+                let exchange = Exchange {
+                    started,
+                    method: request.method.clone(),
+                    url: url.clone(),
+                    request_version: "HTTP/1.1".to_string(),
+                    request_headers: request.headers.clone(),
+                    request_headers_size: Some(request.head_size as i64),
+                    request_body: body.clone(),
+                    status: response.status.as_u16(),
+                    status_text: response.status.canonical_reason().unwrap_or("").to_string(),
+                    response_version: format!("{:?}", response.version),
+                    response_headers: response
+                        .headers
+                        .iter()
+                        .map(|(name, value)| {
+                            (
+                                name.to_string(),
+                                String::from_utf8_lossy(value.as_bytes()).to_string(),
+                            )
+                        })
+                        .collect(),
+                    response_body: response.body.clone(),
+                    send_ms: 0.0,
+                    wait_ms: elapsed,
+                    receive_ms: 0.0,
+                    server_ip: response.remote_addr.map(|addr| addr.ip().to_string()),
+                };
+                proxy.entries.lock().unwrap().push(exchange.into_entry());
+                write_response(stream, &response).await?;
+            }
+            Err(e) => {
+                let e = anyhow!(e).context(format!("Failed to forward request to {}", url));
+                warn!("{:#}", e);
+                write_error(stream, &e).await?;
+            }
+        }
+
+        if close {
+            return Ok(());
+        }
+    }
+}
+
+async fn handle_client(proxy: Arc<Proxy>, stream: TcpStream) -> Result<()> {
+    let mut reader = BufReader::new(stream);
+    let Some(request) = read_head(&mut reader).await? else {
+        return Ok(());
+    };
+
+    if !request.method.eq_ignore_ascii_case("CONNECT") {
+        return serve(&proxy, reader, None, Some(request)).await;
+    }
+
+    let (host, port) = match request.target.rsplit_once(':') {
+        Some((host, port)) => (host.to_string(), port.parse::<u16>()?),
+        None => (request.target.clone(), 443),
+    };
+    let host = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .to_string();
+
+    let mut stream = reader.into_inner();
+    stream
+        .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+        .await?;
+
+    let acceptor = TlsAcceptor::from(proxy.tls_config(&host)?);
+    let tls = acceptor
+        .accept(stream)
+        .await
+        .with_context(|| format!("TLS handshake with client failed for {}", host))?;
+
+    let origin = if port == 443 {
+        format!("https://{}", request.target.trim_end_matches(":443"))
+    } else {
+        format!("https://{}", request.target)
+    };
+    serve(&proxy, tls, Some(&origin), None).await
+}
+
+/// Run a recording proxy on `port` until interrupted, then write the HAR to `output`.
+pub async fn capture(port: u16, output: &Path) -> Result<()> {
+    offline::ensure_online("proxy requests")?;
+    let (ca, ca_path) = load_ca()?;
+
+    let client = Client::builder()
+        .redirect(Policy::none())
+        .no_proxy()
+        .build()
+        .context("Failed to build HTTP client")?;
+
+    let proxy = Arc::new(Proxy {
+        ca,
+        client,
+        tls_configs: Mutex::new(HashMap::new()),
+        entries: Mutex::new(Vec::new()),
+    });
+
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .await
+        .with_context(|| format!("Failed to listen on port {}", port))?;
+
+    println!(
+        "{}: http://127.0.0.1:{}",
+        "Proxy listening".blue().bold(),
+        port
+    );
+    println!(
+        "{}: trust {:?} to capture https traffic",
+        "Note".blue().bold(),
+        ca_path
+    );
+    println!("Press Ctrl-C to stop and write {:?}.\n", output);
+
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (stream, _) = accepted?;
+                let proxy = proxy.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_client(proxy, stream).await {
+                        warn!("{:#}", e);
+                    }
+                });
+            }
+            _ = signal::ctrl_c() => break,
+        }
+    }
+
+    let entries = std::mem::take(&mut *proxy.entries.lock().unwrap());
+    let count = entries.len();
+    import::write_har(entries, Some(output))?;
+    println!(
+        "\n{}: {} requests to {:?}",
+        "Saved".green().bold(),
+        count,
+        output
+    );
+
+    Ok(())
+}
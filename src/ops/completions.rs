@@ -0,0 +1,21 @@
+use anyhow::{Context, Result};
+use clap::Command;
+use clap_complete::Shell;
+use colored::Colorize;
+use std::{fs, io, path::Path};
+
+/// Print a completion script for `shell`, which completes subcommands, flags and the values of
+/// each command's enums.
+pub fn completions(mut cmd: Command, shell: Shell) {
+    let name = cmd.get_name().to_string();
+    clap_complete::generate(shell, &mut cmd, name, &mut io::stdout());
+}
+
+/// Write a man page for harper and one for each of its commands, i.e. `harper-search-for.1`.
+pub fn manpages(cmd: Command, dir: &Path) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create directory: {:?}", dir))?;
+    clap_mangen::generate_to(cmd, dir)
+        .with_context(|| format!("Failed to write man pages to: {:?}", dir))?;
+    println!("{} man pages to {:?}", "Wrote".green().bold(), dir);
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use colored::Colorize;
+use flate2::{Compression, write::GzEncoder};
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+use std::{collections::BTreeMap, io::Write};
+use tracing::warn;
+
+use crate::{
+    format,
+    har::{Entry, Har},
+    mime::ResourceKind,
+    progress,
+};
+
+// bodies this small gain less than the compression framing costs
+const MIN_BODY_BYTES: usize = 256;
+
+// what servers typically use for static assets
+const BROTLI_QUALITY: u32 = 11;
+const BROTLI_WINDOW: u32 = 22;
+
+#[derive(Debug)]
+pub struct CompressionSaving<'a> {
+    pub url: &'a str,
+    pub size: usize,
+    pub gzip: usize,
+    pub brotli: usize,
+}
+
+fn is_text_like(entry: &Entry) -> bool {
+    matches!(
+        entry.response.resource_kind(),
+        ResourceKind::Document
+            | ResourceKind::Script
+            | ResourceKind::Stylesheet
+            | ResourceKind::Data
+    ) || entry.response.mime_type().as_deref() == Some("image/svg+xml")
+}
+
+fn is_compressed(entry: &Entry) -> bool {
+    entry
+        .response
+        .header("content-encoding")
+        .is_some_and(|encoding| !encoding.trim().eq_ignore_ascii_case("identity"))
+}
+
+fn gzip_size(body: &[u8]) -> usize {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    match encoder.write_all(body).and_then(|_| encoder.finish()) {
+        Ok(compressed) => compressed.len(),
+        Err(_) => body.len(),
+    }
+}
+
+fn brotli_size(body: &[u8]) -> usize {
+    let mut compressed = Vec::new();
+    let written = {
+        let mut writer =
+            brotli::CompressorWriter::new(&mut compressed, 4096, BROTLI_QUALITY, BROTLI_WINDOW);
+        writer.write_all(body)
+    };
+    match written {
+        Ok(_) => compressed.len(),
+        Err(_) => body.len(),
+    }
+}
+
+/// Compress every uncompressed text response, returning savings per domain and how many
+/// candidates had no captured body.
+pub fn find_savings<'a>(har: &'a Har) -> (BTreeMap<String, Vec<CompressionSaving<'a>>>, usize) {
+    let candidates: Vec<&Entry> = har
+        .log
+        .entries
+        .iter()
+        .filter(|entry| {
+            entry.request.url.starts_with("http") && is_text_like(entry) && !is_compressed(entry)
+        })
+        .collect();
+
+    // `None` for a candidate with no captured body
+    let pb = progress::items(candidates.len() as u64, "Compressing bodies");
+    let results: Vec<Option<(String, CompressionSaving)>> = candidates
+        .into_par_iter()
+        .progress_with(pb.clone())
+        .filter_map(|entry| {
+            let host = entry.request.host()?;
+            let Some(body) = entry.response.content.as_ref().and_then(|c| c.decoded()) else {
+                return Some(None);
+            };
+            if body.len() < MIN_BODY_BYTES {
+                return None;
+            }
+
+            let gzip = gzip_size(&body);
+            let brotli = brotli_size(&body);
+            if gzip.min(brotli) >= body.len() {
+                return None;
+            }
+
+            Some(Some((
+                host,
+                CompressionSaving {
+                    url: &entry.request.url,
+                    size: body.len(),
+                    gzip,
+                    brotli,
+                },
+            )))
+        })
+        .collect();
+    pb.finish_and_clear();
+
+    let mut domains: BTreeMap<String, Vec<CompressionSaving>> = BTreeMap::new();
+    let mut missing_bodies = 0;
+    for result in results {
+        match result {
+            Some((host, saving)) => domains.entry(host).or_default().push(saving),
+            None => missing_bodies += 1,
+        }
+    }
+
+    (domains, missing_bodies)
+}
+
+fn saving(size: usize, compressed: usize) -> String {
+    let saved = size.saturating_sub(compressed);
+    format!(
+        "{} (-{}, {:.0}%)",
+        format::bytes(compressed as u64),
+        format::bytes(saved as u64),
+        saved as f64 * 100.0 / size.max(1) as f64
+    )
+}
+
+pub fn compression(har: &Har) {
+    let (domains, missing_bodies) = find_savings(har);
+
+    if missing_bodies > 0 {
+        warn!(
+            "{} uncompressed text responses have no captured body",
+            missing_bodies
+        );
+    }
+
+    if domains.is_empty() {
+        println!("{}", "No uncompressed text responses found.".green());
+        return;
+    }
+
+    let mut names: Vec<&String> = domains.keys().collect();
+    names.sort_by_key(|x| x.chars().rev().collect::<String>());
+
+    let (mut total_size, mut total_gzip, mut total_brotli) = (0usize, 0, 0);
+    for name in names {
+        let savings = &domains[name];
+        let size: usize = savings.iter().map(|s| s.size).sum();
+        let gzip: usize = savings.iter().map(|s| s.gzip).sum();
+        let brotli: usize = savings.iter().map(|s| s.brotli).sum();
+        total_size += size;
+        total_gzip += gzip;
+        total_brotli += brotli;
+
+        println!("{}:", name.bold().blue());
+        for s in savings {
+            println!("    {} ({})", s.url, format::bytes(s.size as u64));
+            println!("        gzip:   {}", saving(s.size, s.gzip));
+            println!("        brotli: {}", saving(s.size, s.brotli));
+        }
+        println!(
+            "    {}: gzip {}, brotli {}",
+            "Total".bold(),
+            saving(size, gzip),
+            saving(size, brotli).green()
+        );
+        println!();
+    }
+
+    let count: usize = domains.values().map(Vec::len).sum();
+    println!(
+        "{}: {} uncompressed text responses ({}), gzip would save {}, brotli {}",
+        "Summary".bold(),
+        count,
+        format::bytes(total_size as u64),
+        format::bytes(total_size.saturating_sub(total_gzip) as u64),
+        format::bytes(total_size.saturating_sub(total_brotli) as u64)
+    );
+}
@@ -0,0 +1,251 @@
+use chrono::{DateTime, FixedOffset};
+use colored::Colorize;
+use std::collections::BTreeMap;
+use url::Url;
+
+use crate::{
+    check,
+    har::{Entry, Har},
+};
+
+use super::beacons;
+
+// consent management platforms as (name, host suffix), which serve their banners from these
+const CMP_HOSTS: [(&str, &str); 17] = [
+    ("OneTrust", "cookielaw.org"),
+    ("OneTrust", "onetrust.com"),
+    ("OneTrust", "optanon.blob.core.windows.net"),
+    ("Didomi", "privacy-center.org"),
+    ("Didomi", "didomi.io"),
+    ("Quantcast Choice", "cmp.quantcast.com"),
+    ("Quantcast Choice", "quantcast.mgr.consensu.org"),
+    ("Cookiebot", "cookiebot.com"),
+    ("TrustArc", "trustarc.com"),
+    ("Usercentrics", "usercentrics.eu"),
+    ("Sourcepoint", "privacy-mgmt.com"),
+    ("Sourcepoint", "sourcepoint.mgr.consensu.org"),
+    ("Osano", "osano.com"),
+    ("CookieYes", "cdn-cookieyes.com"),
+    ("Termly", "termly.io"),
+    ("iubenda", "iubenda.com"),
+    ("IAB TCF CMP", "mgr.consensu.org"),
+];
+
+// path fragments of CMP GETs that record a visitor's choice rather than load the banner, which
+// is usually under a `consent` path too
+const CONSENT_PATHS: [&str; 3] = ["logconsent", "consentreceipt", "/choice/"];
+
+// cookies CMPs store the choice in once it's made
+const CONSENT_COOKIES: [&str; 5] = [
+    "euconsent-v2",
+    "OptanonAlertBoxClosed",
+    "CookieConsent",
+    "didomi_token",
+    "uc_settings",
+];
+
+// ad-tech hosts that aren't collection endpoints, so beacons doesn't know them
+const AD_TECH_HOSTS: [(&str, &str); 12] = [
+    ("Xandr", "adnxs.com"),
+    ("Criteo", "criteo.com"),
+    ("Criteo", "criteo.net"),
+    ("Magnite", "rubiconproject.com"),
+    ("PubMatic", "pubmatic.com"),
+    ("Amazon Ads", "amazon-adsystem.com"),
+    ("Taboola", "taboola.com"),
+    ("Outbrain", "outbrain.com"),
+    ("The Trade Desk", "adsrvr.org"),
+    ("OpenX", "openx.net"),
+    ("Index Exchange", "casalemedia.com"),
+    ("Google Ads", "googlesyndication.com"),
+];
+
+#[derive(Debug)]
+pub struct TimedRequest<'a> {
+    pub request_num: usize,
+    pub url: &'a str,
+    pub started: DateTime<FixedOffset>,
+}
+
+#[derive(Debug, Default)]
+pub struct ConsentReport<'a> {
+    // platform -> its requests
+    pub platforms: BTreeMap<&'static str, Vec<TimedRequest<'a>>>,
+    // the first request showing a choice was made
+    pub consent: Option<(TimedRequest<'a>, &'static str)>,
+    // provider -> its requests
+    pub tracking: BTreeMap<&'static str, Vec<TimedRequest<'a>>>,
+}
+
+impl ConsentReport<'_> {
+    /// Tracking requests that started before consent was given, or all of them if it never was.
+    pub fn before_consent(&self) -> impl Iterator<Item = (&'static str, &TimedRequest<'_>)> {
+        let consented = self.consent.as_ref().map(|(request, _)| request.started);
+        self.tracking.iter().flat_map(move |(provider, requests)| {
+            requests
+                .iter()
+                .filter(move |request| consented.is_none_or(|at| request.started < at))
+                .map(move |request| (*provider, request))
+        })
+    }
+}
+
+fn host_matches(host: &str, suffix: &str) -> bool {
+    host == suffix || host.ends_with(&format!(".{}", suffix))
+}
+
+fn cmp(host: &str) -> Option<&'static str> {
+    CMP_HOSTS
+        .iter()
+        .find(|(_, suffix)| host_matches(host, suffix))
+        .map(|(name, _)| *name)
+}
+
+fn tracking_provider(url: &Url) -> Option<&'static str> {
+    let host = url.host_str()?;
+    beacons::known_provider(url).or_else(|| {
+        AD_TECH_HOSTS
+            .iter()
+            .find(|(_, suffix)| host_matches(host, suffix))
+            .map(|(name, _)| *name)
+    })
+}
+
+/// Why an entry shows that the visitor has made a consent choice, if it does.
+fn consent_signal(entry: &Entry, url: &Url) -> Option<&'static str> {
+    let host = url.host_str()?;
+    let path = url.path().to_lowercase();
+    let records_choice =
+        entry.request.method == "POST" || CONSENT_PATHS.iter().any(|p| path.contains(p));
+    if cmp(host).is_some() && records_choice {
+        return Some("consent recorded with the CMP");
+    }
+
+    // a TCF consent string passed on to vendors
+    let tcf_string = url
+        .query_pairs()
+        .any(|(name, value)| name == "gdpr_consent" && !value.is_empty());
+    if tcf_string {
+        return Some("TCF consent string sent");
+    }
+
+    let cookie_header = entry.request.header("cookie").unwrap_or_default();
+    let has_cookie = CONSENT_COOKIES.iter().any(|name| {
+        entry.request.cookies.iter().any(|c| c.name == *name)
+            || cookie_header
+                .split(';')
+                .any(|c| c.trim_start().starts_with(&format!("{}=", name)))
+    });
+    if has_cookie {
+        return Some("consent cookie sent");
+    }
+    None
+}
+
+/// Find consent management platforms, the first sign of consent being given and the tracking
+/// requests in the capture.
+pub fn find_consent<'a>(har: &'a Har) -> ConsentReport<'a> {
+    let mut report = ConsentReport::default();
+    for (i, entry) in har.log.entries.iter().enumerate() {
+        let Ok(url) = Url::parse(&entry.request.url) else {
+            continue;
+        };
+        let Some(host) = url.host_str() else {
+            continue;
+        };
+        let request = || TimedRequest {
+            request_num: i + 1,
+            url: &entry.request.url,
+            started: *entry.started_date_time,
+        };
+
+        if let Some(platform) = cmp(host) {
+            report
+                .platforms
+                .entry(platform)
+                .or_default()
+                .push(request());
+        }
+        if let Some(provider) = tracking_provider(&url) {
+            report.tracking.entry(provider).or_default().push(request());
+        }
+        if let Some(signal) = consent_signal(entry, &url) {
+            let earlier = report
+                .consent
+                .as_ref()
+                .is_none_or(|(first, _)| *entry.started_date_time < first.started);
+            if earlier {
+                report.consent = Some((request(), signal));
+            }
+        }
+    }
+    report
+}
+
+pub fn consent(har: &Har) {
+    let report = find_consent(har);
+    let start = har.log.entries.iter().map(|e| *e.started_date_time).min();
+    let offset = |request: &TimedRequest| {
+        start.map_or(0, |start| (request.started - start).num_milliseconds())
+    };
+
+    println!("{}:", "Consent platforms".bold().blue());
+    if report.platforms.is_empty() {
+        println!("    {}", "None found".yellow());
+    }
+    for (platform, requests) in &report.platforms {
+        println!(
+            "    {} ({} requests, first #{} at +{} ms)",
+            platform.cyan(),
+            requests.len(),
+            requests[0].request_num,
+            offset(&requests[0])
+        );
+    }
+    println!();
+
+    println!("{}:", "Consent".bold().blue());
+    match &report.consent {
+        Some((request, signal)) => println!(
+            "    #{} {} at +{} ms ({})",
+            request.request_num,
+            request.url,
+            offset(request),
+            signal
+        ),
+        None => println!("    {}", "No sign of consent being given".yellow()),
+    }
+    println!();
+
+    let mut before: BTreeMap<&str, Vec<&TimedRequest>> = BTreeMap::new();
+    for (provider, request) in report.before_consent() {
+        before.entry(provider).or_default().push(request);
+    }
+    if !before.is_empty() {
+        println!("{}:", "Tracking before consent".bold().red());
+        for (provider, requests) in &before {
+            println!("    {} ({}):", provider.yellow(), requests.len());
+            for request in requests {
+                println!(
+                    "        #{} {} at +{} ms",
+                    request.request_num,
+                    request.url,
+                    offset(request)
+                );
+            }
+        }
+        println!();
+    }
+
+    let tracking: usize = report.tracking.values().map(Vec::len).sum();
+    let early: usize = before.values().map(Vec::len).sum();
+    check::report(early);
+    println!(
+        "{}: {} consent platforms, {} tracking requests to {} providers, {} before consent",
+        "Summary".bold(),
+        report.platforms.len(),
+        tracking,
+        report.tracking.len(),
+        early
+    );
+}
@@ -0,0 +1,234 @@
+use colored::Colorize;
+use std::collections::{BTreeMap, BTreeSet};
+use tldextract::TldExtractor;
+use url::Url;
+
+use crate::{domain, har::Har};
+
+// shorter values are too likely to collide by chance, i.e. `1` or `true`
+const MIN_IDENTIFIER_LEN: usize = 8;
+
+// (name, value)
+pub type Cookie = (String, String);
+
+#[derive(Debug, Default)]
+pub struct CookieLineage {
+    pub set_by: BTreeSet<String>,
+    pub sent_to: BTreeSet<String>,
+}
+
+#[derive(Debug)]
+pub struct CookieSync {
+    pub cookie: String,
+    pub value: String,
+    pub origin: String,
+    // (receiving host, query parameter)
+    pub sent_to: BTreeSet<(String, String)>,
+}
+
+fn parse_set_cookie(value: &str) -> Option<Cookie> {
+    let pair = value.split(';').next()?;
+    let (name, value) = pair.split_once('=')?;
+    Some((name.trim().to_string(), value.trim().to_string()))
+}
+
+pub fn parse_cookie_header(value: &str) -> Vec<Cookie> {
+    value
+        .split(';')
+        .filter_map(|pair| {
+            let (name, value) = pair.split_once('=')?;
+            Some((name.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+fn is_identifier(value: &str) -> bool {
+    value.len() >= MIN_IDENTIFIER_LEN
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-".contains(c))
+        && value.chars().any(|c| c.is_ascii_digit())
+}
+
+/// Map each (cookie name, value) to the hosts that set it and the hosts it was sent to.
+pub fn build_lineage(har: &Har) -> BTreeMap<Cookie, CookieLineage> {
+    let mut lineage: BTreeMap<Cookie, CookieLineage> = BTreeMap::new();
+
+    for entry in &har.log.entries {
+        let Some(host) = entry.request.host() else {
+            continue;
+        };
+
+        // exporters may fold several Set-Cookie headers into one separated by newlines
+        let mut set: Vec<Cookie> = entry
+            .response
+            .headers
+            .iter()
+            .filter(|h| h.name.eq_ignore_ascii_case("set-cookie"))
+            .flat_map(|h| h.value.lines().filter_map(parse_set_cookie))
+            .collect();
+        set.extend(
+            entry
+                .response
+                .cookies
+                .iter()
+                .map(|c| (c.name.to_string(), c.value.to_string())),
+        );
+
+        let mut sent: Vec<Cookie> = entry
+            .request
+            .headers
+            .iter()
+            .filter(|h| h.name.eq_ignore_ascii_case("cookie"))
+            .flat_map(|h| parse_cookie_header(&h.value))
+            .collect();
+        sent.extend(
+            entry
+                .request
+                .cookies
+                .iter()
+                .map(|c| (c.name.to_string(), c.value.to_string())),
+        );
+
+        for cookie in set {
+            lineage
+                .entry(cookie)
+                .or_default()
+                .set_by
+                .insert(host.clone());
+        }
+        for cookie in sent {
+            lineage
+                .entry(cookie)
+                .or_default()
+                .sent_to
+                .insert(host.clone());
+        }
+    }
+
+    lineage
+}
+
+/// Find cookie identifiers that were passed in query strings to other registrable domains.
+pub fn find_syncs(
+    har: &Har,
+    lineage: &BTreeMap<Cookie, CookieLineage>,
+    tld_extractor: &TldExtractor,
+) -> Vec<CookieSync> {
+    let mut syncs = Vec::new();
+
+    for ((name, value), cookie) in lineage {
+        if !is_identifier(value) {
+            continue;
+        }
+
+        // the cookie belongs to whoever set it, or failing that whoever it was sent to
+        let Some(origin_host) = cookie.set_by.iter().chain(&cookie.sent_to).next() else {
+            continue;
+        };
+        let origin = domain::registrable_domain(origin_host, tld_extractor)
+            .unwrap_or_else(|| origin_host.clone());
+
+        let mut sent_to = BTreeSet::new();
+        for entry in &har.log.entries {
+            let Ok(url) = Url::parse(&entry.request.url) else {
+                continue;
+            };
+            let Some(host) = url.host_str() else {
+                continue;
+            };
+            let receiver =
+                domain::registrable_domain(host, tld_extractor).unwrap_or_else(|| host.to_string());
+            if domain::same_owner(&receiver, &origin) {
+                continue;
+            }
+
+            for (param, param_value) in url.query_pairs() {
+                let shared = param_value.contains(value.as_str())
+                    || (is_identifier(&param_value) && value.contains(param_value.as_ref()));
+                if shared {
+                    sent_to.insert((host.to_string(), param.to_string()));
+                }
+            }
+        }
+
+        if !sent_to.is_empty() {
+            syncs.push(CookieSync {
+                cookie: name.clone(),
+                value: value.clone(),
+                origin,
+                sent_to,
+            });
+        }
+    }
+
+    syncs
+}
+
+pub fn cookie_lineage(har: &Har) {
+    let tld_extractor = domain::build_tld_extractor();
+    let lineage = build_lineage(har);
+
+    if lineage.is_empty() {
+        println!("{}", "No cookies found.".green());
+        return;
+    }
+
+    // group cookies by the host that set them
+    let mut by_setter: BTreeMap<String, Vec<(&Cookie, &CookieLineage)>> = BTreeMap::new();
+    for (cookie, cookie_lineage) in &lineage {
+        let setter = match cookie_lineage.set_by.iter().next() {
+            Some(host) => host.clone(),
+            None => "(not set in capture)".to_string(),
+        };
+        by_setter
+            .entry(setter)
+            .or_default()
+            .push((cookie, cookie_lineage));
+    }
+
+    let mut setters: Vec<&String> = by_setter.keys().collect();
+    setters.sort_by_key(|x| x.chars().rev().collect::<String>());
+
+    for setter in setters {
+        println!("{}:", setter.bold().blue());
+        for ((name, value), cookie_lineage) in &by_setter[setter] {
+            println!("    {}={}", name.purple().bold(), value);
+            if cookie_lineage.set_by.len() > 1 {
+                let others: Vec<&str> = cookie_lineage.set_by.iter().map(String::as_str).collect();
+                println!("        Set by: {}", others.join(", "));
+            }
+            if cookie_lineage.sent_to.is_empty() {
+                println!("        Sent to: {}", "never".yellow());
+            } else {
+                let hosts: Vec<&str> = cookie_lineage.sent_to.iter().map(String::as_str).collect();
+                println!("        Sent to: {}", hosts.join(", "));
+            }
+        }
+        println!();
+    }
+
+    let syncs = find_syncs(har, &lineage, &tld_extractor);
+    if !syncs.is_empty() {
+        println!("{}:", "Cookie syncing".bold().red());
+        for sync in &syncs {
+            println!(
+                "    {}={} from {}",
+                sync.cookie.purple().bold(),
+                sync.value,
+                sync.origin.cyan()
+            );
+            for (host, param) in &sync.sent_to {
+                println!("        {} in ?{}", host, param);
+            }
+        }
+        println!();
+    }
+
+    println!(
+        "{}: {} cookie values, {} synced to other domains",
+        "Summary".bold(),
+        lineage.len(),
+        syncs.len()
+    );
+}
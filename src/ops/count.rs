@@ -0,0 +1,170 @@
+use colored::Colorize;
+use serde::Serialize;
+use std::{cmp::Reverse, collections::HashMap};
+
+use crate::{
+    har::{Entry, Har},
+    lenient_url,
+};
+
+use super::protocols;
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum CountKey {
+    /// The URL scheme, i.e. `https`.
+    Scheme,
+    /// The request method.
+    Method,
+    /// The response status, i.e. `404`.
+    Status,
+    /// The response status class, i.e. `4xx`.
+    StatusClass,
+    /// The response MIME type without parameters.
+    Mime,
+    /// The request host.
+    Domain,
+    /// The negotiated HTTP version, i.e. `HTTP/2`.
+    HttpVersion,
+}
+
+impl CountKey {
+    fn name(self) -> &'static str {
+        match self {
+            CountKey::Scheme => "scheme",
+            CountKey::Method => "method",
+            CountKey::Status => "status",
+            CountKey::StatusClass => "status class",
+            CountKey::Mime => "MIME type",
+            CountKey::Domain => "domain",
+            CountKey::HttpVersion => "HTTP version",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Group {
+    pub key: String,
+    pub count: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<Group>,
+}
+
+pub fn status_class(status: u16) -> String {
+    match status {
+        // the request never got a response
+        0 => "failed".to_string(),
+        status => format!("{}xx", status / 100),
+    }
+}
+
+/// The value an entry is counted under for a key.
+pub fn key(entry: &Entry, key: CountKey) -> String {
+    match key {
+        CountKey::Scheme => lenient_url::parse(&entry.request.url)
+            .scheme
+            .unwrap_or_else(|| "Bad URL".into()),
+        CountKey::Method => entry.request.method.to_uppercase(),
+        CountKey::Status => entry.response.status.to_string(),
+        CountKey::StatusClass => status_class(entry.response.status),
+        CountKey::Mime => entry
+            .response
+            .mime_type()
+            .unwrap_or_else(|| "unknown".into()),
+        CountKey::Domain => lenient_url::host(&entry.request.url).unwrap_or_else(|| "none".into()),
+        CountKey::HttpVersion => {
+            // the response version is what was negotiated, but some exporters leave it empty
+            let version = if entry.response.http_version.is_empty() {
+                &entry.request.http_version
+            } else {
+                &entry.response.http_version
+            };
+            protocols::normalise_version(version)
+        }
+    }
+}
+
+fn group<'a>(entries: &[&'a Entry<'a>], keys: &[CountKey]) -> Vec<Group> {
+    let Some((&first, rest)) = keys.split_first() else {
+        return Vec::new();
+    };
+
+    let mut grouped: HashMap<String, Vec<&Entry>> = HashMap::new();
+    for entry in entries {
+        grouped.entry(key(entry, first)).or_default().push(entry);
+    }
+    let mut groups: Vec<Group> = grouped
+        .into_iter()
+        .map(|(key, entries)| Group {
+            key,
+            count: entries.len(),
+            groups: group(&entries, rest),
+        })
+        .collect();
+    // most requests first, ties in key order so runs are stable
+    groups.sort_by(|a, b| (Reverse(a.count), &a.key).cmp(&(Reverse(b.count), &b.key)));
+    groups
+}
+
+/// Count the entries by a key, and within each group by a second one if given.
+pub fn count(har: &Har, by: CountKey, then_by: Option<CountKey>) -> Vec<Group> {
+    let entries: Vec<&Entry> = har.log.entries.iter().collect();
+    let keys: Vec<CountKey> = std::iter::once(by).chain(then_by).collect();
+    group(&entries, &keys)
+}
+
+fn percent(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64 * 100.0
+    }
+}
+
+fn print_groups(groups: &[Group], total: usize, top: Option<usize>, indent: usize) {
+    let shown = top.unwrap_or(groups.len());
+    for group in groups.iter().take(shown) {
+        let key = if indent == 1 {
+            group.key.bold()
+        } else {
+            group.key.normal()
+        };
+        println!(
+            "{}{}: {} ({:.1}%)",
+            "    ".repeat(indent),
+            key,
+            group.count,
+            percent(group.count, total)
+        );
+        print_groups(&group.groups, group.count, top, indent + 1);
+    }
+    if groups.len() > shown {
+        println!(
+            "{}{}",
+            "    ".repeat(indent),
+            format!("... and {} more", groups.len() - shown).dimmed()
+        );
+    }
+}
+
+pub fn print_counts(groups: &[Group], by: CountKey, then_by: Option<CountKey>, top: Option<usize>) {
+    if groups.is_empty() {
+        println!("{}", "No requests found.".yellow());
+        return;
+    }
+
+    let heading = match then_by {
+        Some(then_by) => format!("Requests by {} then {}", by.name(), then_by.name()),
+        None => format!("Requests by {}", by.name()),
+    };
+    let total: usize = groups.iter().map(|g| g.count).sum();
+    println!("{}:", heading.bold().blue());
+    print_groups(groups, total, top, 1);
+    println!();
+
+    println!(
+        "{}: {} requests in {} groups",
+        "Summary".bold(),
+        total,
+        groups.len()
+    );
+}
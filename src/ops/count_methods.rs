@@ -0,0 +1,165 @@
+use colored::Colorize;
+use serde::Serialize;
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BTreeSet},
+};
+
+use crate::{domain, har::Har};
+
+use super::count;
+
+// methods that change state on the server, so are worth a look when sent to third parties
+const WRITE_METHODS: [&str; 4] = ["POST", "PUT", "PATCH", "DELETE"];
+
+#[derive(Debug, Default, Serialize)]
+pub struct MethodCounts {
+    pub method: String,
+    pub count: usize,
+    // status class, i.e. `2xx` or `failed`, -> requests
+    pub statuses: BTreeMap<String, usize>,
+    pub domains: BTreeMap<String, usize>,
+    // domains outside the first party -> requests
+    pub third_parties: BTreeMap<String, usize>,
+}
+
+impl MethodCounts {
+    pub fn is_write(&self) -> bool {
+        WRITE_METHODS.contains(&self.method.as_str())
+    }
+}
+
+/// Count requests by method, with their status classes and domains, most used method first.
+pub fn get_counts(har: &Har) -> Vec<MethodCounts> {
+    let tld_extractor = domain::build_tld_extractor();
+    let first_party = domain::first_party(har, &tld_extractor);
+
+    let mut methods: BTreeMap<String, MethodCounts> = BTreeMap::new();
+    for entry in &har.log.entries {
+        let method = entry.request.method.to_uppercase();
+        let counts = methods
+            .entry(method.clone())
+            .or_insert_with(|| MethodCounts {
+                method,
+                ..Default::default()
+            });
+        counts.count += 1;
+        *counts
+            .statuses
+            .entry(count::status_class(entry.response.status))
+            .or_default() += 1;
+
+        let Some(host) = entry.request.host() else {
+            continue;
+        };
+        if first_party
+            .as_ref()
+            .is_some_and(|first_party| !first_party.contains_host(&host, &tld_extractor))
+        {
+            *counts.third_parties.entry(host.clone()).or_default() += 1;
+        }
+        *counts.domains.entry(host).or_default() += 1;
+    }
+
+    let mut counts: Vec<MethodCounts> = methods.into_values().collect();
+    counts.sort_by_key(|c| Reverse(c.count));
+    counts
+}
+
+fn top_domains(domains: &BTreeMap<String, usize>, top: usize) -> Vec<(&String, &usize)> {
+    let mut domains: Vec<(&String, &usize)> = domains.iter().collect();
+    domains.sort_by_key(|(_, count)| Reverse(**count));
+    domains.truncate(top);
+    domains
+}
+
+pub fn count_methods(har: &Har, top: usize) {
+    let counts = get_counts(har);
+    if counts.is_empty() {
+        println!("{}", "No requests found.".yellow());
+        return;
+    }
+    let total: usize = counts.iter().map(|c| c.count).sum();
+
+    println!("{}:", "Methods".bold().blue());
+    for counts in &counts {
+        let method = if counts.is_write() {
+            counts.method.yellow()
+        } else {
+            counts.method.normal()
+        };
+        println!(
+            "    {}: {} ({:.1}%)",
+            method,
+            counts.count,
+            counts.count as f64 / total as f64 * 100.0
+        );
+    }
+    println!();
+
+    let classes: BTreeSet<&String> = counts.iter().flat_map(|c| c.statuses.keys()).collect();
+    let width = counts.iter().map(|c| c.method.len()).max().unwrap_or(0);
+    println!("{}:", "Methods by status".bold().blue());
+    println!(
+        "    {:width$} {}",
+        "",
+        classes
+            .iter()
+            .map(|class| format!("{:>6}", class))
+            .collect::<String>()
+            .dimmed()
+    );
+    for counts in &counts {
+        let row: String = classes
+            .iter()
+            .map(|class| format!("{:>6}", counts.statuses.get(*class).unwrap_or(&0)))
+            .collect();
+        println!("    {:width$} {}", counts.method, row);
+    }
+    println!();
+
+    println!("{}:", "Methods by domain".bold().blue());
+    for counts in &counts {
+        let domains = top_domains(&counts.domains, top)
+            .iter()
+            .map(|(domain, count)| format!("{} ({})", domain, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let more = counts.domains.len().saturating_sub(top);
+        let more = if more > 0 {
+            format!(" and {} more", more).dimmed().to_string()
+        } else {
+            String::new()
+        };
+        println!("    {}: {}{}", counts.method, domains, more);
+    }
+    println!();
+
+    let writes: Vec<&MethodCounts> = counts
+        .iter()
+        .filter(|c| c.is_write() && !c.third_parties.is_empty())
+        .collect();
+    let third_party_writes: usize = writes.iter().flat_map(|c| c.third_parties.values()).sum();
+    if !writes.is_empty() {
+        println!("{}:", "Writes to third parties".bold().blue());
+        for counts in &writes {
+            for (domain, requests) in top_domains(&counts.third_parties, top) {
+                println!(
+                    "    {} {}: {} requests",
+                    counts.method.yellow(),
+                    domain::display_host(domain),
+                    requests
+                );
+            }
+        }
+        println!();
+    }
+
+    println!(
+        "{}: {} requests with {} methods, {} writes to third parties",
+        "Summary".bold(),
+        total,
+        counts.len(),
+        third_party_writes
+    );
+}
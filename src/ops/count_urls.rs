@@ -1,12 +1,69 @@
-use crate::Har;
-use std::{collections::HashMap, net::IpAddr};
+use crate::{
+    domain, format,
+    har::{Entry, Har},
+    lenient_url,
+};
+use std::collections::HashMap;
 use tldextract::TldExtractor;
+use tracing::warn;
 use url::Url;
 
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum TreeDepth {
+    /// Stop at the host.
+    Domains,
+
+    /// Continue below the host into URL path segments.
+    Paths,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Metric {
+    /// Number of requests.
+    Requests,
+
+    /// Response bytes transferred.
+    Bytes,
+
+    /// Total time spent in requests.
+    Time,
+}
+
+impl Metric {
+    pub fn value(&self, node: &DomainNode) -> u64 {
+        match self {
+            Metric::Requests => node.count as u64,
+            Metric::Bytes => node.bytes,
+            Metric::Time => node.time.round() as u64,
+        }
+    }
+
+    pub fn format(&self, value: u64) -> String {
+        match self {
+            Metric::Requests => value.to_string(),
+            Metric::Bytes => format::bytes(value),
+            Metric::Time => format!("{}ms", value),
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct DomainNode {
     pub count: usize,
+    pub bytes: u64,
+    // milliseconds
+    pub time: f64,
     pub children: HashMap<String, DomainNode>,
+    // children removed by pruning, kept so their totals can still be shown
+    pub pruned: Vec<DomainNode>,
+}
+
+impl DomainNode {
+    fn add(&mut self, entry: &Entry) {
+        self.count += 1;
+        self.bytes += entry.response.body_bytes();
+        self.time += entry.time.max(0.0);
+    }
 }
 
 pub fn build_domain_tree(
@@ -14,34 +71,37 @@ pub fn build_domain_tree(
     tree: &mut DomainNode,
     tld_extractor: &TldExtractor,
     merge_tld: bool,
+    depth: TreeDepth,
+    max_depth: Option<usize>,
 ) {
     // iterate through URLs in entries in HAR
     for entry in &har.log.entries {
-        let url = &entry.request.url;
-        process_url(url, tree, tld_extractor, merge_tld);
+        process_url(entry, tree, tld_extractor, merge_tld, depth, max_depth);
     }
 }
 
 fn process_url(
-    url_str: &str,
+    entry: &Entry,
     tree: &mut DomainNode,
     tld_extractor: &TldExtractor,
     merge_tld: bool,
+    depth: TreeDepth,
+    max_depth: Option<usize>,
 ) {
-    // parse URL
-    let Ok(parsed_url) = Url::parse(url_str) else {
-        eprintln!("Failed to parse URL: {}", url_str);
-        return;
-    };
+    // parse URL, recovering what can be from malformed ones
+    let url_str = &entry.request.url;
+    let parsed = lenient_url::parse(url_str);
 
     // get parts of URL
-    let parts = if parsed_url.scheme() == "data" {
+    let mut parts = if parsed.scheme.as_deref() == Some("data") {
         // if using data scheme, use "data:" as though it were a TLD
         vec!["data:".to_string()]
     } else {
-        // get host from parsed url
-        let Some(host) = parsed_url.host_str() else {
-            eprintln!("URL has no host: {}", parsed_url);
+        // get host from parsed url, unparseable URLs are summarised at the end of the run
+        let Some(host) = parsed.host.as_deref() else {
+            if parsed.url.is_some() {
+                warn!(url = %url_str, "URL has no host");
+            }
             return;
         };
 
@@ -49,23 +109,31 @@ fn process_url(
         get_domain_parts(host, tld_extractor, merge_tld)
     };
 
-    // add the parts to the tree
+    // paths only come from URLs that parsed
+    if let Some(parsed_url) = &parsed.url {
+        if depth == TreeDepth::Paths && !parsed_url.cannot_be_a_base() {
+            parts.extend(get_path_parts(parsed_url, max_depth));
+        }
+    }
+
+    // add the parts to the tree, the root holding the totals
+    tree.add(entry);
     let mut current = tree;
     for part in parts {
         current = current.children.entry(part).or_default();
-        current.count += 1;
+        current.add(entry);
     }
 }
 
 fn get_domain_parts(host: &str, tld_extractor: &TldExtractor, merge_tld: bool) -> Vec<String> {
     // handle IP addresses
-    if let Ok(ip) = host.parse::<IpAddr>() {
+    if let Some(ip) = domain::ip_literal(host) {
         return vec![format!("ip:{}", ip)];
     }
 
     // handle invalid results
     let Ok(extracted) = tld_extractor.extract(host) else {
-        eprintln!("Failed to extract TLD from: {}", host);
+        warn!(host, "failed to extract TLD");
         return vec![format!("invalid:{}", host)];
     };
 
@@ -100,22 +168,114 @@ fn get_domain_parts(host: &str, tld_extractor: &TldExtractor, merge_tld: bool) -
     parts
 }
 
-pub fn print_tree<F, K>(node: &DomainNode, sort_closure: &mut F)
+fn get_path_parts(url: &Url, max_depth: Option<usize>) -> Vec<String> {
+    let Some(segments) = url.path_segments() else {
+        return Vec::new();
+    };
+
+    // a trailing slash leaves an empty segment, which is only worth keeping for the root
+    let mut parts: Vec<String> = segments.map(|s| format!("/{}", s)).collect();
+    if parts.len() > 1 && parts.last().is_some_and(|p| p == "/") {
+        parts.pop();
+    }
+    parts.truncate(max_depth.unwrap_or(usize::MAX));
+
+    parts
+}
+
+/// Remove children below `min_count` requests and keep only the `top` largest children, by
+/// `metric`, at each level.
+pub fn prune(node: &mut DomainNode, top: Option<usize>, min_count: usize, metric: Metric) {
+    let mut children: Vec<(String, DomainNode)> = node.children.drain().collect();
+    children.sort_by(|(a_name, a), (b_name, b)| {
+        metric
+            .value(b)
+            .cmp(&metric.value(a))
+            .then(a_name.cmp(b_name))
+    });
+
+    let mut kept = 0;
+    for (name, mut child) in children {
+        if child.count < min_count || top.is_some_and(|top| kept >= top) {
+            node.pruned.push(child);
+            continue;
+        }
+        kept += 1;
+        prune(&mut child, top, min_count, metric);
+        node.children.insert(name, child);
+    }
+}
+
+/// Flatten the tree into fully-qualified names, one per leaf.
+pub fn flatten(node: &DomainNode) -> Vec<(String, &DomainNode)> {
+    let mut rows = Vec::new();
+    flatten_level(node, &mut Vec::new(), &mut rows);
+    rows
+}
+
+fn flatten_level<'a>(
+    node: &'a DomainNode,
+    parts: &mut Vec<&'a str>,
+    rows: &mut Vec<(String, &'a DomainNode)>,
+) {
+    for (key, child) in &node.children {
+        parts.push(key);
+        if child.children.is_empty() {
+            // host parts are stored from the TLD down, path parts follow them in order
+            let (path, host): (Vec<&str>, Vec<&str>) =
+                parts.iter().partition(|p| p.starts_with('/'));
+            let host: Vec<&str> = host.into_iter().rev().filter(|p| !p.is_empty()).collect();
+            rows.push((format!("{}{}", host.join("."), path.concat()), child));
+        } else {
+            flatten_level(child, parts, rows);
+        }
+        parts.pop();
+    }
+}
+
+pub fn print_flat(rows: &[(String, &DomainNode)], total: &DomainNode, metric: Metric) {
+    let total = metric.value(total);
+    let names: Vec<String> = rows.iter().map(|(name, _)| display_name(name)).collect();
+    let width = names.iter().map(|n| n.chars().count()).max().unwrap_or(0);
+    for ((_, node), name) in rows.iter().zip(&names) {
+        let value = metric.value(node);
+        println!(
+            "{:<width$} {:>10} {:>6.2}%",
+            name,
+            metric.format(value),
+            value as f64 * 100.0 / total.max(1) as f64,
+            width = width
+        );
+    }
+}
+
+/// Show the Unicode form of punycode labels, leaving paths and IPs alone.
+fn display_name(name: &str) -> String {
+    match name.split_once('/') {
+        Some((host, _)) if !host.is_empty() => {
+            format!("{}{}", domain::display_host(host), &name[host.len()..])
+        }
+        Some(_) => name.to_string(),
+        None => domain::display_host(name),
+    }
+}
+
+pub fn print_tree<F, K>(node: &DomainNode, metric: Metric, sort_closure: &mut F)
 where
     F: FnMut(&(&String, &DomainNode)) -> K,
     K: Ord,
 {
     // recursively print the tree levels
-    print_level(&node.children, 0, sort_closure);
+    print_level(node, 0, metric, sort_closure);
 }
 
-fn print_level<F, K>(children: &HashMap<String, DomainNode>, depth: usize, sort_closure: &mut F)
+fn print_level<F, K>(node: &DomainNode, depth: usize, metric: Metric, sort_closure: &mut F)
 where
     F: FnMut(&(&String, &DomainNode)) -> K,
     K: Ord,
 {
     // get entries as a vector
-    let mut entries: Vec<_> = children.iter().collect();
+    let mut entries: Vec<_> = node.children.iter().collect();
     // sort them
     entries.sort_by_key(|e| sort_closure(e));
 
@@ -123,9 +283,25 @@ where
     for (key, node) in entries {
         // print each entry
         let indent = "    ".repeat(depth);
-        println!("{}{} ({})", indent, key, node.count);
+        println!(
+            "{}{} ({})",
+            indent,
+            display_name(key),
+            metric.format(metric.value(node))
+        );
 
         // print its children
-        print_level(&node.children, depth + 1, sort_closure);
+        print_level(node, depth + 1, metric, sort_closure);
+    }
+
+    if !node.pruned.is_empty() {
+        let indent = "    ".repeat(depth);
+        let total = node.pruned.iter().map(|n| metric.value(n)).sum();
+        println!(
+            "{}... {} more ({})",
+            indent,
+            node.pruned.len(),
+            metric.format(total)
+        );
     }
 }
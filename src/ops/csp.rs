@@ -0,0 +1,241 @@
+use colored::Colorize;
+use std::collections::{BTreeMap, BTreeSet};
+use url::Url;
+
+use crate::{
+    check,
+    har::{Entry, Har},
+    mime::ResourceKind,
+};
+
+// directives that don't fall back to default-src
+const NON_FALLBACK_DIRECTIVES: [&str; 3] = ["base-uri", "form-action", "frame-ancestors"];
+
+// directives that can execute code and so shouldn't be broad
+const SCRIPT_DIRECTIVES: [&str; 4] = ["default-src", "script-src", "script-src-elem", "object-src"];
+
+pub type Policy = BTreeMap<String, Vec<String>>;
+
+pub fn parse_policy(value: &str) -> Policy {
+    value
+        .split(';')
+        .filter_map(|directive| {
+            let mut parts = directive.split_whitespace();
+            let name = parts.next()?.to_lowercase();
+            Some((name, parts.map(String::from).collect()))
+        })
+        .collect()
+}
+
+pub fn analyze_policy(policy: &Policy) -> Vec<String> {
+    let mut issues = Vec::new();
+
+    for directive in SCRIPT_DIRECTIVES {
+        let Some(sources) = policy.get(directive) else {
+            continue;
+        };
+
+        let has_nonce_or_hash = sources.iter().any(|s| {
+            let s = s.trim_matches('\'');
+            s.starts_with("nonce-")
+                || s.starts_with("sha256-")
+                || s.starts_with("sha384-")
+                || s.starts_with("sha512-")
+        });
+
+        for source in sources {
+            match source.to_lowercase().as_str() {
+                "'unsafe-inline'" if !has_nonce_or_hash => {
+                    issues.push(format!("{} allows 'unsafe-inline'", directive))
+                }
+                "'unsafe-eval'" => issues.push(format!("{} allows 'unsafe-eval'", directive)),
+                "*" => issues.push(format!("{} allows any origin (*)", directive)),
+                "http:" | "https:" | "data:" | "blob:" => {
+                    issues.push(format!("{} allows any {} source", directive, source))
+                }
+                s if s.starts_with("http://") => {
+                    issues.push(format!("{} allows insecure origin {}", directive, source))
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if !policy.contains_key("default-src") {
+        issues.push("missing default-src".to_string());
+
+        if !policy.contains_key("script-src") {
+            issues.push("missing script-src, scripts are unrestricted".to_string());
+        }
+        if !policy.contains_key("object-src") {
+            issues.push("missing object-src, plugins are unrestricted".to_string());
+        }
+    }
+
+    for directive in NON_FALLBACK_DIRECTIVES {
+        if !policy.contains_key(directive) {
+            issues.push(format!("missing {}", directive));
+        }
+    }
+
+    issues
+}
+
+pub fn csp_analyze(har: &Har) {
+    // group identical policies so each is only reported once
+    let mut policies: BTreeMap<(String, bool), BTreeSet<String>> = BTreeMap::new();
+    let mut documents_without_csp = BTreeSet::new();
+
+    for entry in &har.log.entries {
+        let response = &entry.response;
+        let enforced = response.header("content-security-policy");
+        let report_only = response.header("content-security-policy-report-only");
+
+        if let Some(value) = enforced {
+            policies
+                .entry((value.to_string(), false))
+                .or_default()
+                .insert(entry.request.url.to_string());
+        }
+
+        if let Some(value) = report_only {
+            policies
+                .entry((value.to_string(), true))
+                .or_default()
+                .insert(entry.request.url.to_string());
+        }
+
+        if enforced.is_none()
+            && response.resource_kind() == ResourceKind::Document
+            && (200..300).contains(&response.status)
+        {
+            documents_without_csp.insert(entry.request.url.clone());
+        }
+    }
+
+    for ((value, report_only), urls) in &policies {
+        let title = if *report_only {
+            "Report-only policy"
+        } else {
+            "Policy"
+        };
+        println!("{}: {}", title.bold().blue(), value);
+
+        for url in urls {
+            println!("    {}", url.cyan());
+        }
+
+        let issues = analyze_policy(&parse_policy(value));
+        check::report(issues.len());
+        if issues.is_empty() {
+            println!("{}", "No weaknesses found.".green());
+        }
+        for issue in issues {
+            println!("{}: {}", "Weakness".yellow(), issue);
+        }
+        println!();
+    }
+
+    if !documents_without_csp.is_empty() {
+        check::report(documents_without_csp.len());
+        println!("{}:", "Documents without an enforced CSP".bold().red());
+        for url in documents_without_csp {
+            println!("    {}", url);
+        }
+        println!();
+    }
+}
+
+fn directive_for(entry: &Entry, page_url: &str) -> Option<&'static str> {
+    let directive = match entry.response.resource_kind() {
+        // the page itself isn't a subresource
+        ResourceKind::Document if entry.request.url == page_url => return None,
+        ResourceKind::Document => "frame-src",
+        ResourceKind::Script => "script-src",
+        ResourceKind::Stylesheet => "style-src",
+        ResourceKind::Image => "img-src",
+        ResourceKind::Font => "font-src",
+        ResourceKind::Media => "media-src",
+        ResourceKind::Data | ResourceKind::Other => "connect-src",
+    };
+    Some(directive)
+}
+
+fn source_for(url: &str, page_origin: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    match url.scheme() {
+        "data" | "blob" => Some(format!("{}:", url.scheme())),
+        "http" | "https" | "ws" | "wss" => {
+            let origin = url.origin().ascii_serialization();
+            if origin == page_origin {
+                Some("'self'".to_string())
+            } else {
+                Some(origin)
+            }
+        }
+        _ => None,
+    }
+}
+
+pub fn generate_policies(har: &Har) -> Vec<(String, String)> {
+    // group entries by the page that loaded them
+    let mut pages: BTreeMap<Option<&str>, Vec<&Entry>> = BTreeMap::new();
+    for entry in &har.log.entries {
+        pages
+            .entry(entry.pageref.as_deref())
+            .or_default()
+            .push(entry);
+    }
+
+    let mut policies = Vec::new();
+    for (pageref, entries) in pages {
+        let page_url = entries
+            .iter()
+            .find(|e| e.response.resource_kind() == ResourceKind::Document)
+            .or(entries.first())
+            .map(|e| e.request.url.to_string())
+            .unwrap_or_default();
+        let page_origin = Url::parse(&page_url)
+            .map(|url| url.origin().ascii_serialization())
+            .unwrap_or_default();
+
+        let mut directives: BTreeMap<&str, BTreeSet<String>> = BTreeMap::new();
+        directives.insert("default-src", BTreeSet::from(["'self'".to_string()]));
+        directives.insert("object-src", BTreeSet::from(["'none'".to_string()]));
+        directives.insert("base-uri", BTreeSet::from(["'self'".to_string()]));
+        directives.insert("frame-ancestors", BTreeSet::from(["'self'".to_string()]));
+
+        for entry in entries {
+            let Some(directive) = directive_for(entry, &page_url) else {
+                continue;
+            };
+            if let Some(source) = source_for(&entry.request.url, &page_origin) {
+                directives.entry(directive).or_default().insert(source);
+            }
+        }
+
+        let policy = directives
+            .iter()
+            .map(|(directive, sources)| {
+                let sources: Vec<&str> = sources.iter().map(String::as_str).collect();
+                format!("{} {}", directive, sources.join(" "))
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        let name = pageref
+            .map(str::to_string)
+            .unwrap_or_else(|| page_url.clone());
+        policies.push((name, policy));
+    }
+
+    policies
+}
+
+pub fn csp_generate(har: &Har) {
+    for (page, policy) in generate_policies(har) {
+        println!("{}:", page.bold().blue());
+        println!("Content-Security-Policy: {}", policy);
+        println!();
+    }
+}
@@ -0,0 +1,183 @@
+use anyhow::{Context, Result, anyhow};
+use chrono::{NaiveDateTime, Utc};
+use colored::Colorize;
+use reqwest::Client;
+use serde::Deserialize;
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashSet},
+};
+use url::Url;
+
+use crate::{domain, har::Har, offline};
+
+const CRT_SH_URL: &str = "https://crt.sh/";
+
+// how many recent certificates to list before summarising
+const MAX_RECENT: usize = 20;
+
+#[derive(Debug, Deserialize)]
+pub struct Certificate {
+    pub id: u64,
+    pub issuer_name: String,
+    // every name on the certificate, one per line
+    pub name_value: String,
+    pub serial_number: String,
+    pub not_before: NaiveDateTime,
+    pub not_after: NaiveDateTime,
+}
+
+impl Certificate {
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.name_value
+            .lines()
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+    }
+
+    pub fn is_wildcard(&self) -> bool {
+        self.names().any(|name| name.starts_with("*."))
+    }
+
+    /// The organisation or common name of the issuer, rather than its whole DN.
+    pub fn issuer(&self) -> &str {
+        let field = |key: &str| {
+            self.issuer_name
+                .split(", ")
+                .find_map(|part| part.strip_prefix(key))
+        };
+        field("O=")
+            .or_else(|| field("CN="))
+            .unwrap_or(&self.issuer_name)
+            .trim_matches('"')
+    }
+}
+
+/// Get the certificates crt.sh has logged for a search, i.e. `%.example.com`.
+async fn query(client: &Client, search: &str) -> Result<Vec<Certificate>> {
+    let url = Url::parse_with_params(CRT_SH_URL, [("q", search), ("output", "json")])?;
+    let text = client
+        .get(url)
+        .send()
+        .await
+        .with_context(|| format!("Failed to send request to {}", CRT_SH_URL))?
+        .error_for_status()?
+        .text()
+        .await
+        .context("Failed to read crt.sh response")?;
+
+    serde_json::from_str(&text).context("Failed to parse crt.sh response")
+}
+
+/// Get the certificates crt.sh has logged for a domain and its subdomains, once each, with the
+/// most recently issued first.
+pub async fn lookup(domain: &str) -> Result<Vec<Certificate>> {
+    // `%.` only matches subdomains, so certificates naming just the apex need their own query
+    let client = Client::new();
+    let mut certificates = query(&client, domain).await?;
+    certificates.extend(query(&client, &format!("%.{}", domain)).await?);
+
+    // precertificates and their final certificates share a serial number, and certificates
+    // naming both the apex and a subdomain turn up in both queries
+    let mut seen = HashSet::new();
+    certificates.sort_by_key(|c| (Reverse(c.not_before), c.id));
+    certificates.retain(|c| seen.insert(c.serial_number.clone()));
+    Ok(certificates)
+}
+
+/// List the certificates issued for the capture's first-party domain from Certificate
+/// Transparency logs, highlighting those issued in the last `recent_days` days and wildcards.
+pub async fn print_ct(har: &Har<'_>, recent_days: i64) -> Result<()> {
+    offline::ensure_online("query Certificate Transparency logs")?;
+
+    let tld_extractor = domain::build_tld_extractor();
+    let first_party = domain::first_party_domain(har, &tld_extractor)
+        .ok_or_else(|| anyhow!("Could not determine the first-party domain of the HAR"))?;
+
+    let certificates = lookup(&first_party).await?;
+    if certificates.is_empty() {
+        println!(
+            "{}",
+            format!("No certificates found for {}.", first_party).green()
+        );
+        return Ok(());
+    }
+
+    let now = Utc::now().naive_utc();
+    let recent: Vec<&Certificate> = certificates
+        .iter()
+        .filter(|c| (now - c.not_before).num_days() < recent_days)
+        .collect();
+
+    println!(
+        "{}:",
+        format!("Issued in the last {} days", recent_days)
+            .bold()
+            .blue()
+    );
+    if recent.is_empty() {
+        println!("    None");
+    }
+    for certificate in recent.iter().take(MAX_RECENT) {
+        let names: Vec<&str> = certificate.names().collect();
+        let names = names.join(", ");
+        println!(
+            "    {} {} ({}, expires {})",
+            certificate.not_before.format("%Y-%m-%d"),
+            if certificate.is_wildcard() {
+                names.yellow()
+            } else {
+                names.cyan()
+            },
+            certificate.issuer(),
+            certificate.not_after.format("%Y-%m-%d")
+        );
+    }
+    if recent.len() > MAX_RECENT {
+        println!("    ... and {} more", recent.len() - MAX_RECENT);
+    }
+    println!();
+
+    let mut wildcards: BTreeMap<&str, (usize, NaiveDateTime)> = BTreeMap::new();
+    for certificate in &certificates {
+        for name in certificate.names().filter(|name| name.starts_with("*.")) {
+            let (count, latest) = wildcards.entry(name).or_insert((0, certificate.not_before));
+            *count += 1;
+            *latest = (*latest).max(certificate.not_before);
+        }
+    }
+    if !wildcards.is_empty() {
+        println!("{}:", "Wildcards".bold().blue());
+        for (name, (count, latest)) in &wildcards {
+            println!(
+                "    {} ({} certificates, latest {})",
+                name.yellow(),
+                count,
+                latest.format("%Y-%m-%d")
+            );
+        }
+        println!();
+    }
+
+    let mut issuers: BTreeMap<&str, usize> = BTreeMap::new();
+    for certificate in &certificates {
+        *issuers.entry(certificate.issuer()).or_insert(0) += 1;
+    }
+    println!("{}:", "Issuers".bold().blue());
+    for (issuer, count) in &issuers {
+        println!("    {} ({})", issuer, count);
+    }
+    println!();
+
+    println!(
+        "{}: {} certificates for {}, {} issued in the last {} days, {} wildcard names, {} issuers",
+        "Summary".bold(),
+        certificates.len(),
+        first_party,
+        recent.len(),
+        recent_days,
+        wildcards.len(),
+        issuers.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,160 @@
+use anyhow::Result;
+use colored::Colorize;
+use rayon::prelude::*;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+
+use crate::{domain, har::Har};
+
+use super::{ip_info::GeoDatabases, pii};
+
+// what requests carry besides PII classes
+const COOKIES: &str = "cookies";
+
+#[derive(Debug, Default)]
+pub struct Flow {
+    pub third_party: bool,
+    pub countries: BTreeSet<String>,
+    pub organisations: BTreeSet<String>,
+    pub requests: usize,
+    // cookies and PII classes sent
+    pub data: BTreeSet<String>,
+}
+
+/// Find the recipients of requests carrying cookies or PII, keyed by (page, registrable domain),
+/// with where their servers are and who runs them.
+pub fn find_flows(
+    har: &Har,
+    dbs: &GeoDatabases,
+    patterns: &[pii::Pattern],
+) -> BTreeMap<(String, String), Flow> {
+    let tld_extractor = domain::build_tld_extractor();
+    let first_party = domain::first_party(har, &tld_extractor);
+
+    // only what was sent counts, not what came back
+    let sent: Vec<BTreeSet<&str>> = har
+        .log
+        .entries
+        .par_iter()
+        .map(|entry| {
+            pii::scan_entry(entry, patterns)
+                .into_iter()
+                .filter(|(_, location, _)| *location != "response")
+                .map(|(class, _, _)| class)
+                .collect()
+        })
+        .collect();
+
+    let mut flows: BTreeMap<(String, String), Flow> = BTreeMap::new();
+    for (entry, classes) in har.log.entries.iter().zip(sent) {
+        let has_cookies =
+            !entry.request.cookies.is_empty() || entry.request.header("cookie").is_some();
+        if !has_cookies && classes.is_empty() {
+            continue;
+        }
+        let Some(host) = entry.request.host() else {
+            continue;
+        };
+
+        let recipient = domain::registrable_domain(&host, &tld_extractor).unwrap_or(host);
+        let page = entry.pageref.as_deref().unwrap_or("no page").to_string();
+        let flow = flows
+            .entry((page, recipient.clone()))
+            .or_insert_with(|| Flow {
+                third_party: !first_party
+                    .as_ref()
+                    .is_some_and(|fp| fp.contains(&recipient)),
+                ..Default::default()
+            });
+
+        flow.requests += 1;
+        if has_cookies {
+            flow.data.insert(COOKIES.to_string());
+        }
+        flow.data.extend(classes.into_iter().map(String::from));
+        if let Some(ip) = entry.server_ip() {
+            flow.countries
+                .insert(dbs.country(ip).unwrap_or_else(|| "unknown".to_string()));
+            if let Some(network) = dbs.network(ip) {
+                flow.organisations.insert(network);
+            }
+        }
+    }
+    flows
+}
+
+fn cell(values: &BTreeSet<String>) -> String {
+    if values.is_empty() {
+        "unknown".to_string()
+    } else {
+        let values: Vec<String> = values.iter().map(|v| v.replace('|', "\\|")).collect();
+        values.join(", ")
+    }
+}
+
+pub fn data_flows(har: &Har, country_db: Option<PathBuf>, asn_db: Option<PathBuf>) -> Result<()> {
+    let dbs = GeoDatabases::open(country_db, asn_db)?;
+    let patterns = pii::load_patterns(None)?;
+    let flows = find_flows(har, &dbs, &patterns);
+
+    if flows.is_empty() {
+        println!("{}", "No requests carrying cookies or PII found.".green());
+        return Ok(());
+    }
+
+    // a markdown table, so it can be pasted into an assessment as is
+    println!("| Page | Recipient | Party | Country | Organisation | Requests | Data |");
+    println!("| --- | --- | --- | --- | --- | --- | --- |");
+    for ((page, recipient), flow) in &flows {
+        println!(
+            "| {} | {} | {} | {} | {} | {} | {} |",
+            page,
+            recipient,
+            if flow.third_party { "third" } else { "first" },
+            cell(&flow.countries),
+            cell(&flow.organisations),
+            flow.requests,
+            cell(&flow.data)
+        );
+    }
+    println!();
+
+    // country -> (recipients, requests)
+    let mut countries: BTreeMap<&str, (BTreeSet<&str>, usize)> = BTreeMap::new();
+    for ((_, recipient), flow) in &flows {
+        for country in &flow.countries {
+            let (recipients, requests) = countries.entry(country).or_default();
+            recipients.insert(recipient);
+            *requests += flow.requests;
+        }
+    }
+    if !countries.is_empty() {
+        println!("{}:", "Countries".bold().blue());
+        for (country, (recipients, requests)) in &countries {
+            println!(
+                "    {}: {} recipients, {} requests",
+                country.cyan(),
+                recipients.len(),
+                requests
+            );
+        }
+        println!();
+    }
+
+    let recipients: BTreeSet<&str> = flows.keys().map(|(_, r)| r.as_str()).collect();
+    let third_parties: BTreeSet<&str> = flows
+        .iter()
+        .filter(|(_, flow)| flow.third_party)
+        .map(|((_, r), _)| r.as_str())
+        .collect();
+    println!(
+        "{}: {} recipients of cookies or PII, {} third-party, in {} countries",
+        "Summary".bold(),
+        recipients.len(),
+        third_parties.len(),
+        countries.len()
+    );
+    Ok(())
+}
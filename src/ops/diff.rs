@@ -0,0 +1,316 @@
+use colored::Colorize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::har::{Entry, Har};
+
+// lines of unchanged context around each change in text diffs
+const CONTEXT: usize = 3;
+
+// the line diff keeps a table of this many cells at most, larger bodies are only reported
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+// how many lines of changes to print per body
+const MAX_CHANGE_LINES: usize = 50;
+
+// JSON values longer than this are cut short in structural diffs
+const MAX_VALUE_LEN: usize = 80;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Index the first entry for each method and URL.
+fn index_entries<'a>(har: &'a Har) -> BTreeMap<(&'a str, &'a str), &'a Entry<'a>> {
+    let mut entries = BTreeMap::new();
+    for entry in &har.log.entries {
+        entries
+            .entry((&*entry.request.method, &*entry.request.url))
+            .or_insert(entry);
+    }
+    entries
+}
+
+fn short_value(value: &Value) -> String {
+    let text = value.to_string();
+    if text.chars().count() > MAX_VALUE_LEN {
+        let cut: String = text.chars().take(MAX_VALUE_LEN).collect();
+        format!("{}...", cut)
+    } else {
+        text
+    }
+}
+
+/// Compare two JSON values, describing each changed, added and removed path.
+pub fn json_diff(path: &str, old: &Value, new: &Value, changes: &mut Vec<String>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for key in keys {
+                let path = format!("{}.{}", path, key);
+                match (old.get(key), new.get(key)) {
+                    (Some(old), Some(new)) => json_diff(&path, old, new, changes),
+                    (Some(old), None) => changes.push(
+                        format!("- {}: {}", path, short_value(old))
+                            .red()
+                            .to_string(),
+                    ),
+                    (None, Some(new)) => changes.push(
+                        format!("+ {}: {}", path, short_value(new))
+                            .green()
+                            .to_string(),
+                    ),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for i in 0..old.len().max(new.len()) {
+                let path = format!("{}[{}]", path, i);
+                match (old.get(i), new.get(i)) {
+                    (Some(old), Some(new)) => json_diff(&path, old, new, changes),
+                    (Some(old), None) => changes.push(
+                        format!("- {}: {}", path, short_value(old))
+                            .red()
+                            .to_string(),
+                    ),
+                    (None, Some(new)) => changes.push(
+                        format!("+ {}: {}", path, short_value(new))
+                            .green()
+                            .to_string(),
+                    ),
+                    (None, None) => {}
+                }
+            }
+        }
+        (old, new) if old != new => changes.push(
+            format!("~ {}: {} -> {}", path, short_value(old), short_value(new))
+                .yellow()
+                .to_string(),
+        ),
+        _ => {}
+    }
+}
+
+/// Line operations turning `old` into `new`, from their longest common subsequence.
+fn line_ops(old: &[&str], new: &[&str]) -> Vec<Op> {
+    let (n, m) = (old.len(), new.len());
+    // lengths[i][j] is the LCS of old[i..] and new[j..]
+    let mut lengths = vec![0u32; (n + 1) * (m + 1)];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i * (m + 1) + j] = if old[i] == new[j] {
+                lengths[(i + 1) * (m + 1) + j + 1] + 1
+            } else {
+                lengths[(i + 1) * (m + 1) + j].max(lengths[i * (m + 1) + j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && old[i] == new[j] {
+            ops.push(Op::Equal);
+            i += 1;
+            j += 1;
+        } else if i < n
+            && (j == m || lengths[(i + 1) * (m + 1) + j] >= lengths[i * (m + 1) + j + 1])
+        {
+            // deletions before insertions, as diff prints them
+            ops.push(Op::Delete);
+            i += 1;
+        } else {
+            ops.push(Op::Insert);
+            j += 1;
+        }
+    }
+    ops
+}
+
+/// Unified diff of two texts, or `None` if they're too large to compare line by line.
+pub fn text_diff(old: &str, new: &str) -> Option<Vec<String>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    if (old.len() + 1).saturating_mul(new.len() + 1) > MAX_DIFF_CELLS {
+        return None;
+    }
+    let ops = line_ops(&old, &new);
+
+    // where each op is in the old and new lines
+    let mut positions = Vec::with_capacity(ops.len());
+    let (mut i, mut j) = (0, 0);
+    for op in &ops {
+        positions.push((i, j));
+        match op {
+            Op::Equal => {
+                i += 1;
+                j += 1;
+            }
+            Op::Delete => i += 1,
+            Op::Insert => j += 1,
+        }
+    }
+
+    // group changes whose context would overlap into hunks
+    let changes: Vec<usize> = (0..ops.len()).filter(|k| ops[*k] != Op::Equal).collect();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for k in changes {
+        let start = k.saturating_sub(CONTEXT);
+        let end = (k + CONTEXT + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut lines = Vec::new();
+    for (start, end) in hunks {
+        let ops = &ops[start..end];
+        let (old_start, new_start) = positions[start];
+        let old_len = ops.iter().filter(|op| **op != Op::Insert).count();
+        let new_len = ops.iter().filter(|op| **op != Op::Delete).count();
+        lines.push(
+            format!(
+                "@@ -{},{} +{},{} @@",
+                old_start + 1,
+                old_len,
+                new_start + 1,
+                new_len
+            )
+            .cyan()
+            .to_string(),
+        );
+        for (op, (i, j)) in ops.iter().zip(&positions[start..end]) {
+            lines.push(match op {
+                Op::Equal => format!(" {}", old[*i]),
+                Op::Delete => format!("-{}", old[*i]).red().to_string(),
+                Op::Insert => format!("+{}", new[*j]).green().to_string(),
+            });
+        }
+    }
+    Some(lines)
+}
+
+/// Describe how the response bodies of two entries differ, or `None` if they're the same.
+fn body_changes(old: &Entry, new: &Entry) -> Option<Vec<String>> {
+    let old = old.response.content.as_ref().and_then(|c| c.decoded())?;
+    let new = new.response.content.as_ref().and_then(|c| c.decoded())?;
+    if old == new {
+        return None;
+    }
+
+    if let (Ok(old), Ok(new)) = (
+        serde_json::from_slice::<Value>(&old),
+        serde_json::from_slice::<Value>(&new),
+    ) {
+        let mut changes = Vec::new();
+        json_diff("$", &old, &new, &mut changes);
+        // the same JSON, formatted differently
+        return (!changes.is_empty()).then_some(changes);
+    }
+
+    match (std::str::from_utf8(&old), std::str::from_utf8(&new)) {
+        (Ok(old_text), Ok(new_text)) => Some(text_diff(old_text, new_text).unwrap_or_else(|| {
+            vec![format!(
+                "bodies differ ({} -> {} lines, too large to compare)",
+                old_text.lines().count(),
+                new_text.lines().count()
+            )]
+        })),
+        _ => Some(vec![format!(
+            "binary bodies differ ({} -> {} bytes)",
+            old.len(),
+            new.len()
+        )]),
+    }
+}
+
+/// Compare two captures by method and URL, listing requests only in one of them and status
+/// changes, and with `bodies` how the responses to requests in both changed.
+pub fn diff(old: &Har, new: &Har, bodies: bool) {
+    let old_entries = index_entries(old);
+    let new_entries = index_entries(new);
+
+    let removed: Vec<&(&str, &str)> = old_entries
+        .keys()
+        .filter(|key| !new_entries.contains_key(*key))
+        .collect();
+    let added: Vec<&(&str, &str)> = new_entries
+        .keys()
+        .filter(|key| !old_entries.contains_key(*key))
+        .collect();
+
+    if !removed.is_empty() {
+        println!("{}:", "Only in the first capture".bold().blue());
+        for (method, url) in &removed {
+            println!("    {} {}", method, url.red());
+        }
+        println!();
+    }
+    if !added.is_empty() {
+        println!("{}:", "Only in the second capture".bold().blue());
+        for (method, url) in &added {
+            println!("    {} {}", method, url.green());
+        }
+        println!();
+    }
+
+    let mut status_changes = 0;
+    let mut changed_bodies = 0;
+    for (key, old_entry) in &old_entries {
+        let Some(new_entry) = new_entries.get(key) else {
+            continue;
+        };
+        let (method, url) = key;
+
+        let old_status = old_entry.response.status;
+        let new_status = new_entry.response.status;
+        let changes = if bodies {
+            body_changes(old_entry, new_entry)
+        } else {
+            None
+        };
+        if old_status == new_status && changes.is_none() {
+            continue;
+        }
+
+        println!("{} {}:", method.bold().blue(), url.bold().blue());
+        if old_status != new_status {
+            status_changes += 1;
+            println!(
+                "    Status: {} -> {}",
+                old_status,
+                new_status.to_string().yellow()
+            );
+        }
+        if let Some(changes) = changes {
+            changed_bodies += 1;
+            for line in changes.iter().take(MAX_CHANGE_LINES) {
+                println!("    {}", line);
+            }
+            if changes.len() > MAX_CHANGE_LINES {
+                println!(
+                    "    ... and {} more lines",
+                    changes.len() - MAX_CHANGE_LINES
+                );
+            }
+        }
+        println!();
+    }
+
+    print!(
+        "{}: {} requests only in the first capture, {} only in the second, {} status changes",
+        "Summary".bold(),
+        removed.len(),
+        added.len(),
+        status_changes
+    );
+    if bodies {
+        print!(", {} bodies changed", changed_bodies);
+    }
+    println!();
+}
@@ -1,100 +1,738 @@
-use anyhow::Result;
-use colored::Colorize;
+use anyhow::{Context, Result, anyhow};
+use colored::{ColoredString, Colorize};
 use hickory_resolver::{
     Resolver, TokioResolver,
-    proto::rr::{Record, RecordType},
+    config::{NameServerConfig, ResolverConfig},
+    name_server::TokioConnectionProvider,
+    proto::{
+        op::{Edns, Message, MessageType, OpCode, Query, ResponseCode},
+        rr::{Name, Record, RecordType},
+        xfer::Protocol,
+    },
+    system_conf,
+};
+use indicatif::ProgressBar;
+use serde_json::{Value, json};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpStream, UdpSocket},
+    time::{Instant, timeout, timeout_at},
+};
+
+use std::{
+    collections::BTreeSet,
+    net::{IpAddr, SocketAddr},
+    str::FromStr,
+    sync::OnceLock,
+    time::Duration,
 };
 
-use crate::har::Har;
+use crate::{cache, domain, har::Har, offline, progress};
 
 use super::list_domains;
 
-pub async fn dnssec_audit(har: &Har) -> Result<()> {
-    let mut domains: Vec<String> = list_domains::list_domains(har);
-    domains.sort_by_key(|x| x.chars().rev().collect::<String>());
+// what the DNSSEC audit asks each host for, and each registrable domain
+const HOST_TYPES: [RecordType; 2] = [RecordType::A, RecordType::AAAA];
+const ZONE_TYPES: [RecordType; 2] = [RecordType::DNSKEY, RecordType::DS];
 
-    let resolver = Resolver::builder_tokio()?.build();
+// EDNS buffer size to advertise, enough for most DNSKEY answers with their signatures
+const EDNS_PAYLOAD: u16 = 4096;
 
-    for domain in domains {
-        let resp = resolver.lookup(domain.clone() + ".", RecordType::ANY);
-        let Ok(resp) = resp.await else {
-            println!("{}: {}", domain.bold(), "DNS lookup failed".red());
-            continue;
-        };
+// the resolver library's defaults
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+pub const DEFAULT_RETRIES: usize = 2;
+
+#[derive(Debug)]
+pub struct DnsSettings {
+    // nameservers to ask instead of the system's
+    pub servers: Vec<SocketAddr>,
+    pub timeout: Duration,
+    pub retries: usize,
+}
+
+static SETTINGS: OnceLock<DnsSettings> = OnceLock::new();
+
+/// Set the nameservers, timeout and retries every DNS command uses.
+pub fn configure(settings: DnsSettings) {
+    let _ = SETTINGS.set(settings);
+}
+
+fn settings() -> &'static DnsSettings {
+    SETTINGS.get_or_init(|| DnsSettings {
+        servers: Vec::new(),
+        timeout: DEFAULT_TIMEOUT,
+        retries: DEFAULT_RETRIES,
+    })
+}
+
+/// `DEFAULT_TIMEOUT` written as `--dns-timeout` takes it, for the argument's default.
+pub fn default_timeout_arg() -> &'static str {
+    static ARG: OnceLock<String> = OnceLock::new();
+    ARG.get_or_init(|| match DEFAULT_TIMEOUT.subsec_millis() {
+        0 => format!("{}s", DEFAULT_TIMEOUT.as_secs()),
+        _ => format!("{}ms", DEFAULT_TIMEOUT.as_millis()),
+    })
+}
+
+/// Parse a nameserver address, the port defaulting to 53, i.e. `1.1.1.1` or `[2606:4700::1111]:53`.
+pub fn parse_dns_server(s: &str) -> Result<SocketAddr, String> {
+    let s = s.trim();
+    if let Ok(addr) = s.parse::<SocketAddr>() {
+        return Ok(addr);
+    }
+    domain::ip_literal(s)
+        .map(|ip| SocketAddr::new(ip, 53))
+        .ok_or_else(|| {
+            format!(
+                "invalid DNS server '{}', expected an IP address and optional port",
+                s
+            )
+        })
+}
+
+pub fn build_resolver() -> Result<TokioResolver> {
+    let settings = settings();
+    let mut builder = if settings.servers.is_empty() {
+        Resolver::builder_tokio().context(
+            "Failed to read the system's DNS config, give a nameserver with --dns-server",
+        )?
+    } else {
+        // UDP first, with TCP for truncated answers
+        let name_servers: Vec<NameServerConfig> = settings
+            .servers
+            .iter()
+            .flat_map(|addr| {
+                [Protocol::Udp, Protocol::Tcp]
+                    .map(|protocol| NameServerConfig::new(*addr, protocol))
+            })
+            .collect();
+        Resolver::builder_with_config(
+            ResolverConfig::from_parts(None, Vec::new(), name_servers),
+            TokioConnectionProvider::default(),
+        )
+    };
+    builder.options_mut().timeout = settings.timeout;
+    builder.options_mut().attempts = settings.retries;
+    Ok(builder.build())
+}
+
+/// Look up records of a single type for a name, ignoring any other records in the answer.
+pub async fn lookup_type(resolver: &TokioResolver, name: &str, rt: RecordType) -> Vec<Record> {
+    let fqdn = format!("{}.", name.trim_end_matches('.'));
+
+    cache::dns(format!("{} {}", fqdn, rt), async {
+        match resolver.lookup(&fqdn, rt).await {
+            Ok(response) => response
+                .records()
+                .iter()
+                .filter(|record| record.record_type() == rt)
+                .cloned()
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    })
+    .await
+}
+
+fn reverse_key(ip: IpAddr) -> String {
+    format!("{} PTR", ip)
+}
+
+/// Look up the PTR records of an address.
+pub async fn reverse_lookup(resolver: &TokioResolver, ip: IpAddr) -> Vec<Record> {
+    cache::dns(reverse_key(ip), async {
+        match resolver.reverse_lookup(ip).await {
+            Ok(response) => response.as_lookup().records().to_vec(),
+            Err(_) => Vec::new(),
+        }
+    })
+    .await
+}
+
+/// Split the hosts in the HAR into domain names and IP literals, which have nothing to resolve.
+fn names_and_ips(har: &Har<'_>) -> (Vec<String>, Vec<IpAddr>) {
+    let mut names = Vec::new();
+    let mut ips = Vec::new();
+    for host in list_domains::list_domains(har) {
+        match domain::ip_literal(&host) {
+            Some(ip) => ips.push(ip),
+            None => names.push(host),
+        }
+    }
+    (names, ips)
+}
+
+fn print_skipped_ips(ips: &[IpAddr], hint: &str) {
+    if !ips.is_empty() {
+        let ips: Vec<String> = ips.iter().map(IpAddr::to_string).collect();
+        println!(
+            "{}",
+            format!("Skipped IP literals{}: {}", hint, ips.join(", ")).dimmed()
+        );
+    }
+}
+
+/// What a query with the DNSSEC OK bit set got back.
+#[derive(Debug)]
+struct SignedAnswer {
+    // records of the type asked for, without their signatures
+    records: Vec<Record>,
+    signed: bool,
+    // the resolver validated the answer, which isn't known for cached answers
+    authenticated: bool,
+    // the answer came back without EDNS, so the resolver can't have sent signatures
+    no_edns: bool,
+}
+
+/// The nameservers given with `--dns-server`, or failing those the system's, in the order
+/// they're tried.
+fn nameservers() -> Result<Vec<SocketAddr>> {
+    if !settings().servers.is_empty() {
+        return Ok(settings().servers.clone());
+    }
+    let (config, _) = system_conf::read_system_conf()
+        .context("Failed to read the system's DNS config, give a nameserver with --dns-server")?;
+    let mut servers: Vec<SocketAddr> = Vec::new();
+    for ns in config.name_servers() {
+        if !servers.contains(&ns.socket_addr) {
+            servers.push(ns.socket_addr);
+        }
+    }
+    if servers.is_empty() {
+        return Err(anyhow!(
+            "No nameservers configured, give one with --dns-server"
+        ));
+    }
+    Ok(servers)
+}
+
+async fn exchange_udp(server: SocketAddr, query: &[u8]) -> Result<Vec<u8>> {
+    let bind: SocketAddr = if server.is_ipv4() {
+        ([0, 0, 0, 0], 0).into()
+    } else {
+        ([0u16; 8], 0).into()
+    };
+    let socket = UdpSocket::bind(bind).await?;
+    let settings = settings();
+    let mut buffer = vec![0; EDNS_PAYLOAD as usize];
+    // a lost datagram is only noticed by the timeout, so send again
+    for attempt in 0..=settings.retries {
+        socket.send_to(query, server).await?;
+        let deadline = Instant::now() + settings.timeout;
+        // anything from elsewhere, or answering another query, is dropped rather than trusted
+        loop {
+            match timeout_at(deadline, socket.recv_from(&mut buffer)).await {
+                Ok(received) => {
+                    let (len, from) = received?;
+                    if from != server || len < 2 || buffer[..2] != query[..2] {
+                        continue;
+                    }
+                    buffer.truncate(len);
+                    return Ok(buffer);
+                }
+                Err(_) if attempt < settings.retries => break,
+                Err(elapsed) => return Err(elapsed.into()),
+            }
+        }
+    }
+    unreachable!("the last attempt returns")
+}
+
+async fn exchange_tcp(server: SocketAddr, query: &[u8]) -> Result<Vec<u8>> {
+    let mut stream = timeout(settings().timeout, TcpStream::connect(server)).await??;
+    let len = u16::try_from(query.len())?;
+    stream.write_all(&len.to_be_bytes()).await?;
+    stream.write_all(query).await?;
+    let mut len = [0; 2];
+    timeout(settings().timeout, stream.read_exact(&mut len)).await??;
+    let mut buffer = vec![0; u16::from_be_bytes(len) as usize];
+    timeout(settings().timeout, stream.read_exact(&mut buffer)).await??;
+    Ok(buffer)
+}
+
+/// Ask one nameserver for one record type with the DNSSEC OK bit set, so signatures come back
+/// alongside the records, retrying over TCP if the answer is truncated. The resolver library
+/// doesn't set the bit unless it validates itself, and ANY queries are refused by many resolvers
+/// (RFC 8482). Returns every record in the answer, signatures included.
+async fn exchange_signed(
+    server: SocketAddr,
+    fqdn: &Name,
+    rt: RecordType,
+) -> Result<(Vec<Record>, bool, bool)> {
+    let mut edns = Edns::new();
+    edns.set_max_payload(EDNS_PAYLOAD).set_dnssec_ok(true);
+    let mut message = Message::new();
+    message
+        .set_id(rand::random())
+        .set_message_type(MessageType::Query)
+        .set_op_code(OpCode::Query)
+        .set_recursion_desired(true)
+        .add_query(Query::query(fqdn.clone(), rt))
+        .set_edns(edns);
+    let query = message.to_vec()?;
+
+    let mut response = Message::from_vec(&exchange_udp(server, &query).await?)?;
+    if response.truncated() {
+        response = Message::from_vec(&exchange_tcp(server, &query).await?)?;
+    }
+    if !matches!(
+        response.response_code(),
+        ResponseCode::NoError | ResponseCode::NXDomain
+    ) {
+        return Err(anyhow!("{} for {} {}", response.response_code(), fqdn, rt));
+    }
+    Ok((
+        response.answers().to_vec(),
+        response.authentic_data(),
+        response.extensions().is_none(),
+    ))
+}
+
+/// Look up one record type with its signatures, from the DNS cache or else from each nameserver
+/// in turn until one answers. `None` when offline and the answer was never cached.
+async fn query_signed(
+    servers: &[SocketAddr],
+    name: &str,
+    rt: RecordType,
+) -> Option<Result<SignedAnswer>> {
+    let fqdn = match Name::from_ascii(format!("{}.", name.trim_end_matches('.'))) {
+        Ok(fqdn) => fqdn,
+        Err(e) => return Some(Err(e.into())),
+    };
+    let key = format!("{} {} +dnssec", fqdn, rt);
+    if offline::is_offline() && !cache::has_dns(&key) {
+        return None;
+    }
+    let mut flags = (false, false);
+    let mut failure = None;
+    let answers = cache::dns(key, async {
+        for server in servers {
+            match exchange_signed(*server, &fqdn, rt).await {
+                Ok((answers, authenticated, no_edns)) => {
+                    flags = (authenticated, no_edns);
+                    failure = None;
+                    return answers;
+                }
+                Err(e) => failure = Some(e),
+            }
+        }
+        Vec::new()
+    })
+    .await;
+    if let (true, Some(e)) = (answers.is_empty(), failure) {
+        return Some(Err(e));
+    }
 
-        let mut sig_found = false;
+    let (authenticated, no_edns) = flags;
+    Some(Ok(SignedAnswer {
+        records: answers
+            .iter()
+            .filter(|r| r.record_type() == rt)
+            .cloned()
+            .collect(),
+        signed: answers.iter().any(|r| r.record_type() == RecordType::RRSIG),
+        authenticated,
+        no_edns,
+    }))
+}
+
+type SignedAnswers = Vec<(String, Vec<(RecordType, Option<Result<SignedAnswer>>)>)>;
 
-        for record in resp.records() {
-            sig_found |= record.record_type() == RecordType::RRSIG;
+async fn query_all_signed(
+    servers: &[SocketAddr],
+    names: &[String],
+    types: &[RecordType],
+    pb: &ProgressBar,
+) -> SignedAnswers {
+    let mut results = Vec::new();
+    for name in names {
+        let mut answers = Vec::new();
+        for rt in types {
+            answers.push((*rt, query_signed(servers, name, *rt).await));
+            pb.inc(1);
         }
+        results.push((name.clone(), answers));
+    }
+    results
+}
 
-        if sig_found {
-            println!("{}: {}", domain.bold(), "Signature found.".green())
-        } else {
-            println!("{}: {}", domain.bold(), "No signature found.".yellow())
+fn signing_status(answer: &Option<Result<SignedAnswer>>) -> ColoredString {
+    match answer {
+        None => "not cached".dimmed(),
+        Some(Err(_)) => "lookup failed".red(),
+        Some(Ok(answer)) if answer.records.is_empty() => "none".dimmed(),
+        Some(Ok(answer)) if answer.signed && answer.authenticated => "signed, validated".green(),
+        Some(Ok(answer)) if answer.signed => "signed".green(),
+        Some(Ok(_)) => "unsigned".yellow(),
+    }
+}
+
+pub async fn dnssec_audit(har: &Har<'_>) -> Result<()> {
+    if offline::is_offline() {
+        offline::notice("only cached DNSSEC answers are shown.");
+    }
+
+    let (domains, ips) = names_and_ips(har);
+    let servers = nameservers()?;
+    let tld_extractor = domain::build_tld_extractor();
+
+    // host records, then the zone keys and delegation of each registrable domain once
+    let zones: BTreeSet<String> = domains
+        .iter()
+        .map(|host| domain::registrable_domain(host, &tld_extractor).unwrap_or(host.clone()))
+        .collect();
+
+    // look everything up first so results aren't printed under the progress bar
+    let lookups = domains.len() * HOST_TYPES.len() + zones.len() * ZONE_TYPES.len();
+    let pb = progress::items(lookups as u64, "Looking up signatures");
+    let zones: Vec<String> = zones.into_iter().collect();
+    let zone_results = query_all_signed(&servers, &zones, &ZONE_TYPES, &pb).await;
+    let host_results = query_all_signed(&servers, &domains, &HOST_TYPES, &pb).await;
+    pb.finish_and_clear();
+
+    let mut signed_zones = 0;
+    let mut stripped = false;
+    let mut no_edns = false;
+    println!("{}:", "Zones".bold().blue());
+    for (zone, answers) in &zone_results {
+        let statuses: Vec<String> = answers
+            .iter()
+            .map(|(rt, answer)| format!("{} {}", rt, signing_status(answer)))
+            .collect();
+        println!(
+            "    {}: {}",
+            domain::display_host(zone),
+            statuses.join(", ")
+        );
+
+        let has = |wanted: RecordType| {
+            answers.iter().any(|(rt, answer)| {
+                *rt == wanted && matches!(answer, Some(Ok(a)) if !a.records.is_empty())
+            })
+        };
+        if has(RecordType::DS) && has(RecordType::DNSKEY) {
+            signed_zones += 1;
+        }
+        for (_, answer) in answers {
+            if let Some(Ok(answer)) = answer {
+                // keys without signatures over them only happen when something drops them
+                stripped |= !answer.records.is_empty() && !answer.signed;
+                no_edns |= answer.no_edns;
+            }
         }
     }
+    println!();
+
+    println!("{}:", "Hosts".bold().blue());
+    let mut signed_hosts = 0;
+    for (host, answers) in &host_results {
+        let statuses: Vec<String> = answers
+            .iter()
+            .map(|(rt, answer)| format!("{} {}", rt, signing_status(answer)))
+            .collect();
+        if answers
+            .iter()
+            .any(|(_, answer)| matches!(answer, Some(Ok(a)) if a.signed))
+        {
+            signed_hosts += 1;
+        }
+        no_edns |= answers
+            .iter()
+            .any(|(_, answer)| matches!(answer, Some(Ok(a)) if a.no_edns));
+        println!(
+            "    {}: {}",
+            domain::display_host(host),
+            statuses.join(", ")
+        );
+    }
+    println!();
+    print_skipped_ips(&ips, "");
+
+    if no_edns || stripped {
+        println!(
+            "{}: the resolver at {} {}, so unsigned results may be false negatives. Use a \
+             DNSSEC-aware resolver to check.",
+            "Note".yellow().bold(),
+            servers
+                .iter()
+                .map(SocketAddr::to_string)
+                .collect::<Vec<_>>()
+                .join(" or "),
+            if no_edns {
+                "doesn't support EDNS"
+            } else {
+                "strips DNSSEC signatures"
+            }
+        );
+        println!();
+    }
+
+    println!(
+        "{}: {} of {} zones signed with a DS and DNSKEY, {} of {} hosts with signed records",
+        "Summary".bold(),
+        signed_zones,
+        zone_results.len(),
+        signed_hosts,
+        host_results.len()
+    );
 
     Ok(())
 }
 
-async fn get_dns_records(resolver: &TokioResolver, domain: &str) -> Vec<Record> {
-    let record_types = vec![
-        RecordType::A,
-        RecordType::AAAA,
-        RecordType::ANAME,
-        RecordType::CNAME,
-        RecordType::DNSKEY,
-        RecordType::DS,
-        RecordType::MX,
-        RecordType::NS,
-        RecordType::PTR,
-        RecordType::RRSIG,
-        RecordType::SOA,
-        RecordType::SRV,
-        RecordType::TXT,
-    ];
+// what `dns-lookup` asks for without `--types`
+pub const DEFAULT_TYPES: [RecordType; 13] = [
+    RecordType::A,
+    RecordType::AAAA,
+    RecordType::ANAME,
+    RecordType::CNAME,
+    RecordType::DNSKEY,
+    RecordType::DS,
+    RecordType::MX,
+    RecordType::NS,
+    RecordType::PTR,
+    RecordType::RRSIG,
+    RecordType::SOA,
+    RecordType::SRV,
+    RecordType::TXT,
+];
 
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum DnsFormat {
+    /// Coloured records grouped by host.
+    Text,
+    /// Hosts and their records as one document.
+    Json,
+    /// Zone file lines, i.e. `example.com. 300 IN A 192.0.2.1`.
+    Zone,
+}
+
+/// Parse a record type, i.e. `AAAA` or `txt`.
+pub fn parse_record_type(s: &str) -> Result<RecordType, String> {
+    match RecordType::from_str(&s.trim().to_uppercase()) {
+        Ok(RecordType::Unknown(_)) | Err(_) => Err(format!("unknown record type '{}'", s)),
+        Ok(rt) => Ok(rt),
+    }
+}
+
+/// Look up the records of a host, `None` when offline and they were never cached.
+async fn get_dns_records(
+    resolver: &TokioResolver,
+    domain: &str,
+    record_types: &[RecordType],
+) -> Option<Vec<Record>> {
     let fqdn = format!("{}.", domain.trim_end_matches('.'));
-    let mut records = Vec::new();
 
-    for rt in record_types {
-        if let Ok(response) = resolver.lookup(&fqdn, rt).await {
-            records.extend(response.records().iter().cloned());
+    // the full set keeps the key it has always been cached under
+    let types = if record_types == DEFAULT_TYPES {
+        "*".to_string()
+    } else {
+        let types: Vec<String> = record_types.iter().map(RecordType::to_string).collect();
+        types.join(",")
+    };
+    let key = format!("{} {}", fqdn, types);
+    if offline::is_offline() && !cache::has_dns(&key) {
+        return None;
+    }
+    let records = cache::dns(key, async {
+        let mut records = Vec::new();
+        for rt in record_types {
+            if let Ok(response) = resolver.lookup(&fqdn, *rt).await {
+                records.extend(response.records().iter().cloned());
+            }
+        }
+        records
+    })
+    .await;
+    Some(records)
+}
+
+type Lookups = Vec<(String, Option<Vec<Record>>)>;
+
+fn record_json(record: &Record) -> Value {
+    json!({
+        "name": record.name().to_string(),
+        "type": record.record_type().to_string(),
+        "ttl": record.ttl(),
+        "data": record.data().to_string(),
+    })
+}
+
+fn print_zone(name: &str, records: &[Record]) {
+    println!("; {}", domain::display_host(name));
+    for record in records {
+        println!(
+            "{}\t{}\tIN\t{}\t{}",
+            record.name(),
+            record.ttl(),
+            record.record_type(),
+            record.data()
+        );
+    }
+    if records.is_empty() {
+        println!("; no records");
+    }
+}
+
+/// Print the hosts that resolve to nothing, those with addresses but no IPv6 one, and offline,
+/// those that weren't cached.
+fn print_missing(results: &Lookups, format: DnsFormat) -> Result<()> {
+    let has = |records: &[Record], rt: RecordType| records.iter().any(|r| r.record_type() == rt);
+    let hosts = |missing: &dyn Fn(&Option<Vec<Record>>) -> bool| -> Vec<&str> {
+        results
+            .iter()
+            .filter(|(_, records)| missing(records))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    };
+    let no_records = hosts(&|records| records.as_ref().is_some_and(|r| r.is_empty()));
+    let no_aaaa = hosts(&|records| {
+        records
+            .as_ref()
+            .is_some_and(|r| has(r, RecordType::A) && !has(r, RecordType::AAAA))
+    });
+    let not_cached = hosts(&|records| records.is_none());
+
+    match format {
+        DnsFormat::Json => {
+            let missing = json!({
+                "no_records": no_records,
+                "no_aaaa": no_aaaa,
+                "not_cached": not_cached,
+            });
+            println!("{}", serde_json::to_string_pretty(&missing)?);
         }
+        DnsFormat::Zone => unreachable!("--only-missing with --format zone is rejected on parsing"),
+        DnsFormat::Text => {
+            for (heading, names) in [
+                ("No records", &no_records),
+                ("No AAAA", &no_aaaa),
+                ("Not cached", &not_cached),
+            ] {
+                if names.is_empty() {
+                    continue;
+                }
+                println!("{}:", heading.bold().blue());
+                for name in names {
+                    println!("    {}", domain::display_host(name));
+                }
+                println!();
+            }
+            if no_records.is_empty() && no_aaaa.is_empty() && not_cached.is_empty() {
+                println!("{}", "Every host has records, including AAAA.".green());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_records(records: &[Record]) {
+    for record in records {
+        println!(
+            "[{:6}] {} - TTL: {} - {}",
+            format!("{}", record.record_type()).purple().bold(),
+            record.name().to_string().cyan(),
+            record.ttl().to_string().yellow(),
+            record.data()
+        );
     }
 
-    records
+    if records.is_empty() {
+        println!("{}", "No DNS records found".red());
+    }
 }
 
-pub async fn dns_lookup(har: &Har) -> Result<()> {
-    let mut domains: Vec<String> = list_domains::list_domains(har);
-    domains.sort_by_key(|x| x.chars().rev().collect::<String>());
+pub async fn dns_lookup(
+    har: &Har<'_>,
+    reverse: bool,
+    types: &[RecordType],
+    format: DnsFormat,
+    only_missing: bool,
+) -> Result<()> {
+    if offline::is_offline() {
+        offline::notice("only cached DNS answers are shown.");
+    }
 
-    let resolver = Resolver::builder_tokio()?.build();
+    let (domains, ips) = names_and_ips(har);
+    let mut types = if types.is_empty() {
+        DEFAULT_TYPES.to_vec()
+    } else {
+        types.to_vec()
+    };
+    // finding hosts without IPv6 needs their addresses
+    if only_missing {
+        for rt in [RecordType::A, RecordType::AAAA] {
+            if !types.contains(&rt) {
+                types.push(rt);
+            }
+        }
+    }
 
-    for domain in domains {
-        println!("{}:", domain.bold().blue());
+    let resolver = build_resolver()?;
 
-        let mut found_records = false;
+    let lookups = domains.len() + if reverse { ips.len() } else { 0 };
+    let pb = progress::items(lookups as u64, "Looking up records");
+    let mut results = Vec::new();
+    for domain in domains {
+        let records = get_dns_records(&resolver, &domain, &types).await;
+        results.push((domain, records));
+        pb.inc(1);
+    }
 
-        for record in get_dns_records(&resolver, &domain).await {
-            found_records = true;
-            println!(
-                "[{:6}] {} - TTL: {} - {}",
-                format!("{}", record.record_type()).purple().bold(),
-                record.name().to_string().cyan(),
-                record.ttl().to_string().yellow(),
-                record.data()
-            );
+    if reverse {
+        for ip in &ips {
+            let records = if offline::is_offline() && !cache::has_dns(&reverse_key(*ip)) {
+                None
+            } else {
+                Some(reverse_lookup(&resolver, *ip).await)
+            };
+            results.push((ip.to_string(), records));
+            pb.inc(1);
         }
+    }
+    pb.finish_and_clear();
 
-        if !found_records {
-            println!("{}", "No DNS records found".red());
+    if only_missing {
+        return print_missing(&results, format);
+    }
+    match format {
+        DnsFormat::Json => {
+            let hosts: Vec<Value> = results
+                .iter()
+                .map(|(name, records)| {
+                    // `null` for hosts not in the cache when offline
+                    let records: Option<Vec<Value>> = records
+                        .as_ref()
+                        .map(|records| records.iter().map(record_json).collect());
+                    json!({ "name": name, "records": records })
+                })
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&hosts)?);
+            return Ok(());
         }
+        DnsFormat::Zone => {
+            for (name, records) in &results {
+                match records {
+                    Some(records) => print_zone(name, records),
+                    None => println!("; {}\n; not cached", domain::display_host(name)),
+                }
+            }
+            return Ok(());
+        }
+        DnsFormat::Text => {}
+    }
 
+    for (name, records) in results {
+        println!("{}:", domain::display_host(&name).bold().blue());
+        match records {
+            Some(records) => print_records(&records),
+            None => println!("{}", "Not cached".dimmed()),
+        }
         println!();
     }
 
+    if !reverse {
+        print_skipped_ips(&ips, " (use --reverse to look up their PTR records)");
+    }
+
     Ok(())
 }
@@ -0,0 +1,267 @@
+use anyhow::{Result, anyhow};
+use colored::Colorize;
+use hickory_resolver::{
+    TokioResolver,
+    proto::rr::{Record, RecordType},
+};
+use indicatif::ProgressBar;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{domain, har::Har, offline, progress};
+
+use super::dns;
+
+// selectors commonly used by mail providers, DKIM keys can't be enumerated
+const DKIM_SELECTORS: [&str; 10] = [
+    "default",
+    "google",
+    "selector1",
+    "selector2",
+    "k1",
+    "s1",
+    "s2",
+    "mail",
+    "dkim",
+    "mxvault",
+];
+
+/// Group the first-party hosts in the HAR by the first-party site they're on, the primary site
+/// first, so every declared site is audited even if the HAR never requested it.
+fn first_party_hosts(har: &Har<'_>) -> Result<Vec<(String, BTreeSet<String>)>> {
+    let tld_extractor = domain::build_tld_extractor();
+    let first_party = domain::first_party(har, &tld_extractor)
+        .ok_or_else(|| anyhow!("Could not determine the first-party domain of the HAR"))?;
+
+    let mut sites: Vec<(String, BTreeSet<String>)> = std::iter::once(&first_party.primary)
+        .chain(
+            first_party
+                .sites()
+                .iter()
+                .filter(|site| **site != first_party.primary),
+        )
+        .map(|site| (site.clone(), BTreeSet::new()))
+        .collect();
+    for host in har.log.entries.iter().filter_map(|e| e.request.host()) {
+        let Some(site) = first_party.site_of(&host, &tld_extractor) else {
+            continue;
+        };
+        if let Some((_, hosts)) = sites.iter_mut().find(|(s, _)| s == site) {
+            hosts.insert(host);
+        }
+    }
+    Ok(sites)
+}
+
+fn print_record(label: &str, record: &Record) {
+    println!(
+        "[{:6}] {} - {}",
+        label.purple().bold(),
+        record.name().to_string().cyan(),
+        record.data()
+    );
+}
+
+/// Get the TXT records of a name whose content starts with `prefix`.
+async fn txt_with_prefix(resolver: &TokioResolver, name: &str, prefix: &str) -> Vec<Record> {
+    dns::lookup_type(resolver, name, RecordType::TXT)
+        .await
+        .into_iter()
+        .filter(|record| record.data().to_string().to_lowercase().starts_with(prefix))
+        .collect()
+}
+
+fn spf_posture(spf: &[Record]) -> String {
+    let Some(record) = spf.first() else {
+        return "no SPF record".red().to_string();
+    };
+
+    let policy = record.data().to_string();
+    if spf.len() > 1 {
+        "multiple SPF records (invalid)".red().to_string()
+    } else if policy.contains("-all") {
+        "SPF hard fail".green().to_string()
+    } else if policy.contains("~all") {
+        "SPF soft fail".yellow().to_string()
+    } else {
+        "SPF permits any sender".red().to_string()
+    }
+}
+
+fn dmarc_posture(dmarc: &[Record]) -> String {
+    let Some(record) = dmarc.first() else {
+        return "no DMARC record".red().to_string();
+    };
+
+    let policy = record.data().to_string().to_lowercase();
+    let tags: BTreeMap<&str, &str> = policy
+        .split(';')
+        .filter_map(|tag| tag.trim().split_once('='))
+        .map(|(k, v)| (k.trim(), v.trim()))
+        .collect();
+
+    match tags.get("p") {
+        Some(&"reject") => "DMARC reject".green().to_string(),
+        Some(&"quarantine") => "DMARC quarantine".yellow().to_string(),
+        Some(p) => format!("DMARC {}", p).red().to_string(),
+        None => "DMARC record without policy".red().to_string(),
+    }
+}
+
+/// The records found for one first-party site.
+#[derive(Debug)]
+struct SiteRecords<'a> {
+    found: Vec<(&'static str, Record)>,
+    caa: Vec<Record>,
+    dane_hosts: Vec<&'a String>,
+    mx: Vec<Record>,
+    spf: Vec<Record>,
+    dmarc: Vec<Record>,
+    dkim_selectors: Vec<&'static str>,
+}
+
+async fn look_up_site<'a>(
+    resolver: &TokioResolver,
+    site: &str,
+    hosts: &'a BTreeSet<String>,
+    pb: &ProgressBar,
+) -> SiteRecords<'a> {
+    let mut found = Vec::new();
+
+    // certificate issuance
+    let caa = dns::lookup_type(resolver, site, RecordType::CAA).await;
+    found.extend(caa.iter().map(|record| ("CAA", record.clone())));
+    pb.inc(1);
+
+    let mut dane_hosts = Vec::new();
+    for host in hosts {
+        let tlsa =
+            dns::lookup_type(resolver, &format!("_443._tcp.{}", host), RecordType::TLSA).await;
+        found.extend(tlsa.iter().map(|record| ("TLSA", record.clone())));
+        if !tlsa.is_empty() {
+            dane_hosts.push(host);
+        }
+        pb.inc(1);
+    }
+
+    // mail policy
+    let mx = dns::lookup_type(resolver, site, RecordType::MX).await;
+    found.extend(mx.iter().map(|record| ("MX", record.clone())));
+    pb.inc(1);
+
+    let spf = txt_with_prefix(resolver, site, "v=spf1").await;
+    found.extend(spf.iter().map(|record| ("SPF", record.clone())));
+    pb.inc(1);
+
+    let dmarc = txt_with_prefix(resolver, &format!("_dmarc.{}", site), "v=dmarc1").await;
+    found.extend(dmarc.iter().map(|record| ("DMARC", record.clone())));
+    pb.inc(1);
+
+    let mut dkim_selectors = Vec::new();
+    for selector in DKIM_SELECTORS {
+        let name = format!("{}._domainkey.{}", selector, site);
+        let dkim = dns::lookup_type(resolver, &name, RecordType::TXT).await;
+        found.extend(dkim.iter().map(|record| ("DKIM", record.clone())));
+        if !dkim.is_empty() {
+            dkim_selectors.push(selector);
+        }
+        pb.inc(1);
+    }
+
+    SiteRecords {
+        found,
+        caa,
+        dane_hosts,
+        mx,
+        spf,
+        dmarc,
+        dkim_selectors,
+    }
+}
+
+fn print_site(site: &str, hosts: &BTreeSet<String>, records: &SiteRecords) {
+    println!("{}:", site.bold().blue());
+    for (label, record) in &records.found {
+        print_record(label, record);
+    }
+
+    println!();
+    println!("{}:", "Certificate issuance".bold());
+    if records.caa.is_empty() {
+        println!(
+            "    {}",
+            "No CAA records, any CA may issue certificates".yellow()
+        );
+    } else {
+        println!("    {}", "Issuance restricted by CAA records".green());
+    }
+
+    if records.dane_hosts.is_empty() {
+        println!(
+            "    {}",
+            format!("No TLSA records for {} first-party hosts", hosts.len()).yellow()
+        );
+    } else {
+        println!(
+            "    {}",
+            format!(
+                "DANE configured for {}/{} hosts",
+                records.dane_hosts.len(),
+                hosts.len()
+            )
+            .green()
+        );
+    }
+
+    println!("{}:", "Mail".bold());
+    if records.mx.is_empty() {
+        println!(
+            "    {}",
+            "No MX records, domain doesn't receive mail".cyan()
+        );
+    }
+    println!("    {}", spf_posture(&records.spf));
+    println!("    {}", dmarc_posture(&records.dmarc));
+    if records.dkim_selectors.is_empty() {
+        println!("    {}", "No DKIM keys for common selectors".yellow());
+    } else {
+        println!(
+            "    {}",
+            format!(
+                "DKIM keys for selectors: {}",
+                records.dkim_selectors.join(", ")
+            )
+            .green()
+        );
+    }
+}
+
+/// Audit the DNS security and mail posture of every first-party site.
+pub async fn dns_security(har: &Har<'_>) -> Result<()> {
+    if offline::is_offline() {
+        offline::notice("only cached DNS answers are shown.");
+    }
+
+    let sites = first_party_hosts(har)?;
+    let resolver = dns::build_resolver()?;
+
+    // records are printed once every lookup is done so they aren't drawn under the progress bar
+    let lookups: usize = sites
+        .iter()
+        .map(|(_, hosts)| 4 + hosts.len() + DKIM_SELECTORS.len())
+        .sum();
+    let pb = progress::items(lookups as u64, "Looking up records");
+    let mut audited = Vec::new();
+    for (site, hosts) in &sites {
+        audited.push(look_up_site(&resolver, site, hosts, &pb).await);
+    }
+    pb.finish_and_clear();
+
+    for (i, ((site, hosts), records)) in sites.iter().zip(&audited).enumerate() {
+        if i > 0 {
+            println!();
+        }
+        print_site(site, hosts, records);
+    }
+
+    Ok(())
+}
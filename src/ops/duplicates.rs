@@ -0,0 +1,167 @@
+use colored::Colorize;
+use rayon::prelude::*;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+};
+
+use crate::{
+    cache, format,
+    har::{Entry, Har},
+    mime::ResourceKind,
+};
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateGroup<'a> {
+    pub method: &'a str,
+    pub url: &'a str,
+    pub request_nums: Vec<usize>,
+    pub identical_responses: bool,
+    pub wasted_bytes: u64,
+    pub is_api: bool,
+}
+
+fn hash_request(entry: &Entry) -> [u8; 32] {
+    let request = &entry.request;
+    let mut hasher = Sha256::new();
+    hasher.update(request.method.as_bytes());
+    hasher.update([0]);
+    hasher.update(request.url.as_bytes());
+    hasher.update([0]);
+    if let Some(post_data) = &request.post_data {
+        hasher.update(post_data.text.as_bytes());
+    }
+    hasher.finalize().into()
+}
+
+fn hash_response(entry: &Entry) -> Option<[u8; 32]> {
+    let content = entry.response.content.as_ref()?;
+    let status = entry.response.status.to_be_bytes();
+    let encoded = [
+        &status[..],
+        content.encoding.as_deref().unwrap_or_default().as_bytes(),
+        content.text.as_deref()?.as_bytes(),
+    ];
+    // kept apart from the hashes command's digests, which leave out the status
+    let (digest, _) = cache::body_hash("status+sha256", &encoded, || {
+        let body = content.decoded()?;
+        let mut hasher = Sha256::new();
+        hasher.update(status);
+        hasher.update(&body);
+        Some((hasher.finalize().into(), body.len()))
+    })?;
+    Some(digest)
+}
+
+pub fn find_duplicates<'a>(har: &'a Har) -> Vec<DuplicateGroup<'a>> {
+    // group entries by identical request, keeping first-seen order
+    let hashes: Vec<[u8; 32]> = har.log.entries.par_iter().map(hash_request).collect();
+    let mut groups: HashMap<[u8; 32], Vec<(usize, &Entry)>> = HashMap::new();
+    for (i, (entry, hash)) in har.log.entries.iter().zip(hashes).enumerate() {
+        // data URIs never hit the network
+        if entry.request.url.starts_with("data:") {
+            continue;
+        }
+        groups.entry(hash).or_default().push((i + 1, entry));
+    }
+
+    let mut duplicates: Vec<DuplicateGroup> = groups
+        .into_values()
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .filter(|group| group.len() > 1)
+        .map(|group| {
+            let (_, first) = group[0];
+            let first_hash = hash_response(first);
+
+            // only responses identical to the first fetch are wasted
+            let mut identical_responses = true;
+            let mut wasted_bytes = 0;
+            for (_, entry) in &group[1..] {
+                let hash = hash_response(entry);
+                if hash.is_some() && hash == first_hash {
+                    wasted_bytes += entry.response.body_bytes();
+                } else {
+                    identical_responses = false;
+                }
+            }
+
+            DuplicateGroup {
+                method: &first.request.method,
+                url: &first.request.url,
+                request_nums: group.iter().map(|(i, _)| *i).collect(),
+                identical_responses,
+                wasted_bytes,
+                is_api: first.response.resource_kind() == ResourceKind::Data,
+            }
+        })
+        .collect();
+
+    // groups come out of the map in no particular order, so ties go to the earliest request
+    duplicates.sort_by_key(|d| {
+        (
+            Reverse(d.wasted_bytes),
+            Reverse(d.request_nums.len()),
+            d.request_nums[0],
+        )
+    });
+    duplicates
+}
+
+pub fn print_duplicates(har: &Har) {
+    let duplicates = find_duplicates(har);
+
+    if duplicates.is_empty() {
+        println!("{}", "No duplicate requests found.".green());
+        return;
+    }
+
+    let mut total_wasted = 0;
+    let mut api_calls: BTreeMap<&str, usize> = BTreeMap::new();
+
+    for dup in &duplicates {
+        println!(
+            "{} {} ({} times)",
+            dup.method.purple().bold(),
+            dup.url.cyan(),
+            dup.request_nums.len()
+        );
+        println!(
+            "    Requests: {}",
+            dup.request_nums
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        if dup.identical_responses {
+            println!("    Responses: {}", "identical".yellow());
+        } else {
+            println!("    Responses: {}", "differ".green());
+        }
+        println!("    Wasted: {}", format::bytes(dup.wasted_bytes));
+        println!();
+
+        total_wasted += dup.wasted_bytes;
+        if dup.is_api && dup.identical_responses {
+            *api_calls.entry(dup.url).or_insert(0) += dup.request_nums.len() - 1;
+        }
+    }
+
+    println!(
+        "{}: {} duplicated requests, {} wasted",
+        "Summary".bold(),
+        duplicates.len(),
+        format::bytes(total_wasted)
+    );
+
+    if !api_calls.is_empty() {
+        println!("{}:", "Repeated identical API calls".bold().yellow());
+        for (url, repeats) in api_calls {
+            println!("    {} ({} redundant)", url, repeats);
+        }
+    }
+}
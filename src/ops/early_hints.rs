@@ -0,0 +1,241 @@
+use chrono::{DateTime, FixedOffset};
+use colored::Colorize;
+use url::Url;
+
+use crate::har::{Entry, Har};
+
+use super::resource_hints::{self, Hint};
+
+#[derive(Debug)]
+pub struct HintedResource<'a> {
+    pub hint: Hint,
+    // the first request for it on the page, as (request number, entry)
+    pub fetched: Option<(usize, &'a Entry<'a>)>,
+    // how long before the HTML's first byte the request started, if it did
+    pub head_start_ms: Option<f64>,
+}
+
+impl HintedResource<'_> {
+    /// Time the early start saved, at most the time the request took.
+    pub fn saved_ms(&self) -> f64 {
+        match (self.head_start_ms, self.fetched) {
+            (Some(head_start), Some((_, entry))) => head_start.min(entry.time.max(0.0)),
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct EarlyHints<'a> {
+    // the 103 response
+    pub request_num: usize,
+    pub url: &'a str,
+    pub resources: Vec<HintedResource<'a>>,
+}
+
+#[derive(Debug)]
+pub struct Push<'a> {
+    pub request_num: usize,
+    pub url: &'a str,
+    // a later request for the same URL, so the push went unused
+    pub requested_again: Option<usize>,
+    // a push saves the browser the round trip of asking for it
+    pub saved_ms: Option<f64>,
+}
+
+fn ms_between(from: DateTime<FixedOffset>, to: DateTime<FixedOffset>) -> f64 {
+    (to - from).num_microseconds().unwrap_or(0) as f64 / 1000.0
+}
+
+fn same_url(a: &str, b: &str) -> bool {
+    a.split('#').next() == b.split('#').next()
+}
+
+/// Estimate a connection's round trip from the TCP handshake, which takes one.
+fn round_trip_ms(entry: &Entry) -> Option<f64> {
+    let timings = entry.timings.as_ref()?;
+    let connect = timings.connect.filter(|ms| *ms > 0.0)?;
+    let tcp = connect - timings.ssl.filter(|ms| *ms > 0.0).unwrap_or(0.0);
+    (tcp > 0.0).then_some(tcp)
+}
+
+/// Find 103 Early Hints responses, which exporters that record them keep as their own entries
+/// before the final response, and check whether the resources they hinted started loading
+/// before the HTML arrived.
+pub fn find_early_hints<'a>(har: &'a Har) -> Vec<EarlyHints<'a>> {
+    let entries = &har.log.entries;
+    let mut found = Vec::new();
+    for (i, interim) in entries.iter().enumerate() {
+        if interim.response.status != 103 {
+            continue;
+        }
+        let Ok(base) = Url::parse(&interim.request.url) else {
+            continue;
+        };
+
+        // the final response to the same request
+        let document = entries[i + 1..].iter().find(|entry| {
+            entry.response.status != 103 && same_url(&entry.request.url, &interim.request.url)
+        });
+        let first_byte = document.map(|document| {
+            *document.started_date_time
+                + chrono::Duration::microseconds((document.first_byte_ms() * 1000.0) as i64)
+        });
+
+        let resources = interim
+            .response
+            .headers
+            .iter()
+            .filter(|h| h.name.eq_ignore_ascii_case("link"))
+            .flat_map(|h| resource_hints::parse_link_header(&h.value, &base))
+            .filter(|hint| hint.rel == "preload" || hint.rel == "modulepreload")
+            .map(|hint| {
+                let fetched = entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| {
+                        interim.pageref.is_none() || entry.pageref == interim.pageref
+                    })
+                    .find(|(_, entry)| {
+                        entry.response.status != 103
+                            && same_url(&entry.request.url, hint.url.as_str())
+                    })
+                    .map(|(j, entry)| (j + 1, entry));
+                let head_start_ms = match (fetched, first_byte) {
+                    (Some((_, entry)), Some(first_byte)) => {
+                        Some(ms_between(*entry.started_date_time, first_byte))
+                            .filter(|ms| *ms > 0.0)
+                    }
+                    _ => None,
+                };
+                HintedResource {
+                    hint,
+                    fetched,
+                    head_start_ms,
+                }
+            })
+            .collect();
+
+        found.push(EarlyHints {
+            request_num: i + 1,
+            url: &interim.request.url,
+            resources,
+        });
+    }
+    found
+}
+
+/// Find resources the server pushed over HTTP/2, as WebPageTest marks them.
+pub fn find_pushes<'a>(har: &'a Har) -> Vec<Push<'a>> {
+    let entries = &har.log.entries;
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.was_pushed())
+        .map(|(i, pushed)| {
+            let requested_again = entries
+                .iter()
+                .enumerate()
+                .skip(i + 1)
+                .find(|(_, entry)| {
+                    !entry.was_pushed() && same_url(&entry.request.url, &pushed.request.url)
+                })
+                .map(|(j, _)| j + 1);
+            // the round trip of the page's connection, from the first request on it
+            let saved_ms = entries
+                .iter()
+                .filter(|entry| entry.connection.is_some() && entry.connection == pushed.connection)
+                .find_map(round_trip_ms);
+            Push {
+                request_num: i + 1,
+                url: &pushed.request.url,
+                requested_again,
+                saved_ms: saved_ms.filter(|_| requested_again.is_none()),
+            }
+        })
+        .collect()
+}
+
+pub fn early_hints(har: &Har) {
+    let hints = find_early_hints(har);
+    let pushes = find_pushes(har);
+    if hints.is_empty() && pushes.is_empty() {
+        println!(
+            "{}",
+            "No 103 Early Hints or server pushes recorded in the HAR.".yellow()
+        );
+        return;
+    }
+
+    let mut hinted = 0;
+    let mut benefited = 0;
+    let mut hints_saved = 0.0;
+    for early in &hints {
+        println!(
+            "#{} {} ({}):",
+            early.request_num,
+            early.url.bold().blue(),
+            "103 Early Hints".cyan()
+        );
+        for resource in &early.resources {
+            hinted += 1;
+            let status = match (resource.fetched, resource.head_start_ms) {
+                (None, _) => "never fetched".red(),
+                (Some((num, _)), None) => {
+                    format!("#{} started after the HTML arrived", num).yellow()
+                }
+                (Some((num, _)), Some(head_start)) => {
+                    benefited += 1;
+                    hints_saved += resource.saved_ms();
+                    format!(
+                        "#{} started {:.0} ms before the HTML, ~{:.0} ms saved",
+                        num,
+                        head_start,
+                        resource.saved_ms()
+                    )
+                    .green()
+                }
+            };
+            println!(
+                "    {} {}: {}",
+                resource.hint.rel, resource.hint.url, status
+            );
+        }
+        if early.resources.is_empty() {
+            println!("    {}", "No preloads hinted".yellow());
+        }
+        println!();
+    }
+
+    let mut pushes_saved = 0.0;
+    if !pushes.is_empty() {
+        println!("{}:", "Server pushes".bold().blue());
+        for push in &pushes {
+            let status = match (push.requested_again, push.saved_ms) {
+                (Some(num), _) => format!("requested again by #{}, wasted", num).red(),
+                (None, Some(saved)) => {
+                    pushes_saved += saved;
+                    format!("~{:.0} ms saved", saved).green()
+                }
+                (None, None) => "saving unknown".normal(),
+            };
+            println!("    #{} {}: {}", push.request_num, push.url, status);
+        }
+        println!();
+    }
+
+    let wasted_pushes = pushes
+        .iter()
+        .filter(|p| p.requested_again.is_some())
+        .count();
+    println!(
+        "{}: {} of {} early hinted resources started early (~{:.0} ms saved), {} pushes with {} wasted (~{:.0} ms saved)",
+        "Summary".bold(),
+        benefited,
+        hinted,
+        hints_saved,
+        pushes.len(),
+        wasted_pushes,
+        pushes_saved
+    );
+}
@@ -0,0 +1,217 @@
+use colored::Colorize;
+use rayon::prelude::*;
+use regex::Regex;
+use std::collections::BTreeMap;
+use url::Url;
+
+use crate::{
+    har::{Entry, Har},
+    ops::cookie_lineage,
+};
+
+// names whose values are random by design and not worth reporting
+const DEFAULT_ALLOWED: [&str; 8] = [
+    "nonce",
+    "integrity",
+    "csrf",
+    "_csrf",
+    "csrf_token",
+    "csrfmiddlewaretoken",
+    "authenticity_token",
+    "__requestverificationtoken",
+];
+
+// how much of a value to show
+const MAX_DISPLAY_LEN: usize = 60;
+
+#[derive(Debug)]
+pub struct HighEntropyValue {
+    pub location: String,
+    pub value: String,
+    pub entropy: f64,
+    pub encoding: &'static str,
+}
+
+#[derive(Debug)]
+pub struct EntropyOptions {
+    pub threshold: f64,
+    pub min_length: usize,
+    pub allowed: Vec<String>,
+}
+
+/// Shannon entropy in bits per character.
+pub fn shannon_entropy(value: &str) -> f64 {
+    let mut counts: BTreeMap<char, usize> = BTreeMap::new();
+    for c in value.chars() {
+        *counts.entry(c).or_insert(0) += 1;
+    }
+
+    let len = value.chars().count() as f64;
+    counts
+        .values()
+        .map(|count| {
+            let p = *count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+fn guess_encoding(value: &str) -> &'static str {
+    if value.chars().all(|c| c.is_ascii_hexdigit()) {
+        "hex"
+    } else if value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "-_".contains(c))
+    {
+        "base64url"
+    } else if value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "+/=".contains(c))
+    {
+        "base64"
+    } else {
+        "mixed"
+    }
+}
+
+impl EntropyOptions {
+    fn is_allowed(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        DEFAULT_ALLOWED.contains(&name.as_str())
+            || self.allowed.iter().any(|a| a.eq_ignore_ascii_case(&name))
+    }
+
+    /// Check a value, scaling the threshold down for hex which can carry at most 4 bits a character.
+    fn check(&self, location: String, value: &str) -> Option<HighEntropyValue> {
+        if value.len() < self.min_length {
+            return None;
+        }
+
+        let encoding = guess_encoding(value);
+        let threshold = match encoding {
+            "hex" => self.threshold * 4.0 / 6.0,
+            _ => self.threshold,
+        };
+        let entropy = shannon_entropy(value);
+        (entropy >= threshold).then(|| HighEntropyValue {
+            location,
+            value: value.to_string(),
+            entropy,
+            encoding,
+        })
+    }
+}
+
+fn scan_entry(
+    entry: &Entry,
+    url: &Url,
+    options: &EntropyOptions,
+    segment_regex: &Regex,
+) -> Vec<HighEntropyValue> {
+    let mut found = Vec::new();
+
+    for (name, value) in url.query_pairs() {
+        if !options.is_allowed(&name) {
+            found.extend(options.check(format!("query {}", name), &value));
+        }
+    }
+
+    let cookies = entry
+        .request
+        .headers
+        .iter()
+        .filter(|h| h.name.eq_ignore_ascii_case("cookie"))
+        .flat_map(|h| cookie_lineage::parse_cookie_header(&h.value));
+    for (name, value) in cookies {
+        if !options.is_allowed(&name) {
+            found.extend(options.check(format!("cookie {}", name), &value));
+        }
+    }
+
+    if let Some(post_data) = &entry.request.post_data {
+        for segment in segment_regex.find_iter(&post_data.text) {
+            found.extend(options.check("body".to_string(), segment.as_str()));
+        }
+    }
+
+    found
+}
+
+pub fn find_high_entropy(
+    har: &Har,
+    options: &EntropyOptions,
+) -> BTreeMap<String, Vec<HighEntropyValue>> {
+    // runs of characters that make up encoded blobs
+    let segment_regex = Regex::new(r"[A-Za-z0-9+/=_-]+").unwrap();
+    let scanned: Vec<(String, Vec<HighEntropyValue>)> = har
+        .log
+        .entries
+        .par_iter()
+        .filter_map(|entry| {
+            let url = Url::parse(&entry.request.url).ok()?;
+            let host = url.host_str()?.to_string();
+            let found = scan_entry(entry, &url, options, &segment_regex);
+            (!found.is_empty()).then_some((host, found))
+        })
+        .collect();
+
+    let mut domains: BTreeMap<String, Vec<HighEntropyValue>> = BTreeMap::new();
+    for (host, found) in scanned {
+        let values = domains.entry(host).or_default();
+        for value in found {
+            // the same token is usually sent with every request
+            if !values
+                .iter()
+                .any(|v| v.location == value.location && v.value == value.value)
+            {
+                values.push(value);
+            }
+        }
+    }
+
+    for values in domains.values_mut() {
+        values.sort_by(|a, b| b.entropy.total_cmp(&a.entropy));
+    }
+    domains
+}
+
+pub fn entropy(har: &Har, options: &EntropyOptions) {
+    let domains = find_high_entropy(har, options);
+
+    if domains.is_empty() {
+        println!("{}", "No high-entropy values found.".green());
+        return;
+    }
+
+    let mut names: Vec<&String> = domains.keys().collect();
+    names.sort_by_key(|x| x.chars().rev().collect::<String>());
+
+    for name in names {
+        println!("{}:", name.bold().blue());
+        for value in &domains[name] {
+            let shown: String = value.value.chars().take(MAX_DISPLAY_LEN).collect();
+            let ellipsis = if value.value.chars().count() > MAX_DISPLAY_LEN {
+                "..."
+            } else {
+                ""
+            };
+            println!(
+                "    {} {:.2} bits/char, {} chars, {}",
+                value.location.purple().bold(),
+                value.entropy,
+                value.value.chars().count(),
+                value.encoding
+            );
+            println!("        {}{}", shown, ellipsis);
+        }
+        println!();
+    }
+
+    let total: usize = domains.values().map(Vec::len).sum();
+    println!(
+        "{}: {} high-entropy values sent to {} domains",
+        "Summary".bold(),
+        total,
+        domains.len()
+    );
+}
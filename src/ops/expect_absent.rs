@@ -0,0 +1,56 @@
+use colored::Colorize;
+
+use crate::check;
+
+use super::search_for::{Needles, SearchResult, Transform};
+
+/// Report each needle as absent or where it was found, as a finding for each request it was
+/// found in.
+pub fn expect_absent(needles: &Needles, results: &[(Option<Transform>, Vec<SearchResult>)]) {
+    let mut failed = 0;
+    let mut found_in = 0;
+    for label in needles.labels() {
+        // request number -> how it was found there
+        let mut requests: Vec<(usize, &str)> = Vec::new();
+        for (transform, matches) in results {
+            for result in matches {
+                if result.patterns.iter().any(|p| p == label) {
+                    requests.push((result.request_num, transform.map_or("as is", |t| t.name())));
+                }
+            }
+        }
+
+        if requests.is_empty() {
+            println!("{} {}: absent", "PASS".green().bold(), label);
+            continue;
+        }
+        failed += 1;
+        found_in += requests.len();
+        requests.sort();
+        let requests: Vec<String> = requests
+            .iter()
+            .map(|(num, how)| format!("#{} ({})", num, how))
+            .collect();
+        println!(
+            "{} {}: found in {}",
+            "FAIL".red().bold(),
+            label,
+            requests.join(", ")
+        );
+    }
+    println!();
+
+    let result = if failed == 0 {
+        "PASS".green().bold()
+    } else {
+        "FAIL".red().bold()
+    };
+    println!(
+        "{}: {}, {} of {} patterns found",
+        "Summary".bold(),
+        result,
+        failed,
+        needles.labels().count()
+    );
+    check::report(found_in);
+}
@@ -0,0 +1,397 @@
+use anyhow::{Context, Result};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write,
+    fs,
+    path::Path,
+};
+use url::Url;
+
+use crate::har::{Entry, Har};
+
+// gaps shorter than this are the browser fetching in parallel, not the user thinking
+const MIN_THINK_TIME: f64 = 0.1;
+
+// headers whose values are credentials and are pulled out into variables
+const SECRET_HEADERS: [&str; 6] = [
+    "authorization",
+    "cookie",
+    "x-api-key",
+    "x-auth-token",
+    "x-csrf-token",
+    "x-xsrf-token",
+];
+
+// headers k6 sets itself or which only make sense for the original connection
+const SKIPPED_HEADERS: [&str; 5] = [
+    "host",
+    "content-length",
+    "connection",
+    "accept-encoding",
+    "keep-alive",
+];
+
+/// Write `content` to `output`, or stdout if not given.
+pub fn write_output(content: &str, output: Option<&Path>) -> Result<()> {
+    match output {
+        Some(path) => {
+            fs::write(path, content).with_context(|| format!("Failed to write {:?}", path))
+        }
+        None => {
+            print!("{}", content);
+            Ok(())
+        }
+    }
+}
+
+fn start_time(entry: &Entry) -> f64 {
+    entry.started_date_time.timestamp_millis() as f64 / 1000.0
+}
+
+fn js_string(s: &str) -> String {
+    // JSON strings are valid JS string literals
+    serde_json::to_string(s).unwrap_or_default()
+}
+
+/// Name variables after their header, numbering them if a header has several values.
+fn variable_names(har: &Har) -> HashMap<(String, String), String> {
+    let mut values: Vec<(String, String)> = Vec::new();
+    for entry in &har.log.entries {
+        for header in &entry.request.headers {
+            let name = header.name.to_lowercase();
+            let pair = (name.clone(), header.value.to_string());
+            if SECRET_HEADERS.contains(&name.as_str()) && !values.contains(&pair) {
+                values.push(pair);
+            }
+        }
+    }
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (name, _) in &values {
+        *counts.entry(name).or_insert(0) += 1;
+    }
+
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    let mut names = HashMap::new();
+    for (name, value) in &values {
+        let base = name.replace('-', "_").to_uppercase();
+        let index = seen.entry(name).or_insert(0);
+        *index += 1;
+
+        let variable = if counts[name.as_str()] > 1 {
+            format!("{}_{}", base, index)
+        } else {
+            base
+        };
+        names.insert((name.clone(), value.clone()), variable);
+    }
+
+    names
+}
+
+/// Generate a k6 script replaying the requests in order, with the captured think-times.
+pub fn k6_script(har: &Har) -> String {
+    let variables = variable_names(har);
+    let mut script = String::new();
+
+    let _ = writeln!(script, "import http from 'k6/http';");
+    let _ = writeln!(script, "import {{ check, sleep }} from 'k6';");
+    let _ = writeln!(script);
+    let _ = writeln!(
+        script,
+        "export const options = {{ vus: 1, iterations: 1 }};"
+    );
+
+    if !variables.is_empty() {
+        let mut sorted: Vec<(&String, &(String, String))> =
+            variables.iter().map(|(k, v)| (v, k)).collect();
+        sorted.sort();
+
+        let _ = writeln!(script);
+        let _ = writeln!(
+            script,
+            "// captured credentials, override with e.g. `k6 run -e {}=...`",
+            sorted[0].0
+        );
+        for (variable, (_, value)) in sorted {
+            let _ = writeln!(
+                script,
+                "const {} = __ENV.{} || {};",
+                variable,
+                variable,
+                js_string(value)
+            );
+        }
+    }
+
+    let _ = writeln!(script);
+    let _ = writeln!(script, "export default function () {{");
+    let _ = writeln!(script, "  let res;");
+
+    let mut previous_start: Option<f64> = None;
+    for entry in &har.log.entries {
+        let request = &entry.request;
+        // data URIs never hit the network
+        if !request.url.starts_with("http") {
+            continue;
+        }
+
+        let start = start_time(entry);
+        if let Some(previous) = previous_start {
+            let gap = start - previous;
+            if gap >= MIN_THINK_TIME {
+                let _ = writeln!(script, "  sleep({:.2});", gap);
+            }
+        }
+        previous_start = Some(start);
+
+        let mut headers = Vec::new();
+        for header in &request.headers {
+            let name = header.name.to_lowercase();
+            // HTTP/2 pseudo-headers aren't real headers
+            if name.starts_with(':') || SKIPPED_HEADERS.contains(&name.as_str()) {
+                continue;
+            }
+
+            let value = match variables.get(&(name, header.value.to_string())) {
+                Some(variable) => variable.clone(),
+                None => js_string(&header.value),
+            };
+            headers.push(format!("{}: {}", js_string(&header.name), value));
+        }
+
+        let body = match &request.post_data {
+            Some(post_data) if !post_data.text.is_empty() => js_string(&post_data.text),
+            _ => "null".to_string(),
+        };
+
+        let _ = writeln!(script);
+        let _ = writeln!(
+            script,
+            "  res = http.request({}, {}, {}, {{",
+            js_string(&request.method),
+            js_string(&request.url),
+            body
+        );
+        if headers.is_empty() {
+            let _ = writeln!(script, "    headers: {{}},");
+        } else {
+            let _ = writeln!(script, "    headers: {{");
+            for header in headers {
+                let _ = writeln!(script, "      {},", header);
+            }
+            let _ = writeln!(script, "    }},");
+        }
+        let _ = writeln!(script, "  }});");
+        // a status of 0 means the request never completed
+        if entry.response.status != 0 {
+            let _ = writeln!(
+                script,
+                "  check(res, {{ 'status was {}': (r) => r.status === {} }});",
+                entry.response.status, entry.response.status
+            );
+        }
+    }
+
+    let _ = writeln!(script, "}}");
+    script
+}
+
+/// Render a request as a raw HTTP/1.1 message.
+fn raw_request(entry: &Entry) -> Option<String> {
+    let request = &entry.request;
+    let url = Url::parse(&request.url).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+
+    let mut target = url.path().to_string();
+    if let Some(query) = url.query() {
+        target.push('?');
+        target.push_str(query);
+    }
+
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str()?, port),
+        None => url.host_str()?.to_string(),
+    };
+    let body = request.post_data.as_ref().map(|p| &*p.text).unwrap_or("");
+
+    let mut message = format!("{} {} HTTP/1.1\r\n", request.method, target);
+    let _ = write!(message, "Host: {}\r\n", host);
+    for header in &request.headers {
+        let name = header.name.to_lowercase();
+        // framing is recomputed below, and HTTP/2 pseudo-headers don't exist in HTTP/1.1
+        if name.starts_with(':')
+            || matches!(
+                name.as_str(),
+                "host" | "content-length" | "transfer-encoding"
+            )
+        {
+            continue;
+        }
+        let _ = write!(message, "{}: {}\r\n", header.name, header.value);
+    }
+    if !body.is_empty() {
+        let _ = write!(message, "Content-Length: {}\r\n", body.len());
+    }
+    message.push_str("\r\n");
+    message.push_str(body);
+
+    Some(message)
+}
+
+/// Write each request to its own file in `dir`, returning how many were written.
+pub fn write_raw_requests(har: &Har, dir: &Path) -> Result<usize> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+
+    let mut written = 0;
+    for (i, entry) in har.log.entries.iter().enumerate() {
+        let Some(message) = raw_request(entry) else {
+            continue;
+        };
+
+        let host = entry.request.host().unwrap_or_default();
+        let name = format!("{:04}_{}_{}.http", i + 1, entry.request.method, host);
+        let path = dir.join(name.replace(['/', '\\', ':'], "_"));
+        fs::write(&path, message).with_context(|| format!("Failed to write {:?}", path))?;
+        written += 1;
+    }
+
+    Ok(written)
+}
+
+// upper bounds of the request duration histogram buckets, in seconds
+const DURATION_BUCKETS: [f64; 10] = [0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+#[derive(Default)]
+struct Durations {
+    buckets: [u64; DURATION_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+fn label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn status_class(status: u16) -> String {
+    match status {
+        // the request never got a response
+        0 => "failed".to_string(),
+        status => format!("{}xx", status / 100),
+    }
+}
+
+/// Summarise the requests in the Prometheus text format, which the pushgateway and the
+/// node_exporter textfile collector read. Unlike OpenMetrics, counters are typed under their
+/// `_total` name and there's no `# EOF`.
+pub fn metrics(har: &Har) -> String {
+    // (domain, method, status)
+    let mut requests: BTreeMap<(String, &str, u16), (u64, u64)> = BTreeMap::new();
+    let mut durations: BTreeMap<(String, &str), Durations> = BTreeMap::new();
+    let mut classes: BTreeMap<String, u64> = BTreeMap::new();
+
+    for entry in &har.log.entries {
+        let domain = entry.request.host().unwrap_or_default();
+        let method = &*entry.request.method;
+        let status = entry.response.status;
+
+        let (count, bytes) = requests
+            .entry((domain.clone(), method, status))
+            .or_default();
+        *count += 1;
+        *bytes += entry.response.body_bytes();
+
+        *classes.entry(status_class(status)).or_default() += 1;
+
+        // the HAR uses -1 when timings weren't recorded
+        if entry.time >= 0.0 {
+            let seconds = entry.time / 1000.0;
+            let histogram = durations.entry((domain, method)).or_default();
+            for (bucket, bound) in histogram.buckets.iter_mut().zip(DURATION_BUCKETS) {
+                if seconds <= bound {
+                    *bucket += 1;
+                }
+            }
+            histogram.count += 1;
+            histogram.sum += seconds;
+        }
+    }
+
+    let mut out = String::new();
+    let labels = |domain: &str, method: &str| {
+        format!(
+            "domain=\"{}\",method=\"{}\"",
+            label_value(domain),
+            label_value(method)
+        )
+    };
+
+    out.push_str("# HELP harper_requests_total Requests in the capture.\n");
+    out.push_str("# TYPE harper_requests_total counter\n");
+    for ((domain, method, status), (count, _)) in &requests {
+        let _ = writeln!(
+            out,
+            "harper_requests_total{{{},status=\"{}\"}} {}",
+            labels(domain, method),
+            status,
+            count
+        );
+    }
+
+    out.push_str("# HELP harper_response_bytes_total Response body bytes in the capture.\n");
+    out.push_str("# TYPE harper_response_bytes_total counter\n");
+    for ((domain, method, status), (_, bytes)) in &requests {
+        let _ = writeln!(
+            out,
+            "harper_response_bytes_total{{{},status=\"{}\"}} {}",
+            labels(domain, method),
+            status,
+            bytes
+        );
+    }
+
+    out.push_str("# HELP harper_responses_total Responses by status class.\n");
+    out.push_str("# TYPE harper_responses_total counter\n");
+    for (class, count) in &classes {
+        let _ = writeln!(
+            out,
+            "harper_responses_total{{class=\"{}\"}} {}",
+            class, count
+        );
+    }
+
+    out.push_str("# HELP harper_request_duration_seconds Total time of each request.\n");
+    out.push_str("# TYPE harper_request_duration_seconds histogram\n");
+    for ((domain, method), histogram) in &durations {
+        let labels = labels(domain, method);
+        for (bucket, bound) in histogram.buckets.iter().zip(DURATION_BUCKETS) {
+            let _ = writeln!(
+                out,
+                "harper_request_duration_seconds_bucket{{{},le=\"{:?}\"}} {}",
+                labels, bound, bucket
+            );
+        }
+        let _ = writeln!(
+            out,
+            "harper_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+            labels, histogram.count
+        );
+        let _ = writeln!(
+            out,
+            "harper_request_duration_seconds_count{{{}}} {}",
+            labels, histogram.count
+        );
+        let _ = writeln!(
+            out,
+            "harper_request_duration_seconds_sum{{{}}} {}",
+            labels, histogram.sum
+        );
+    }
+
+    out
+}
@@ -1,17 +1,230 @@
-use crate::Har;
-use chrono::{DateTime, Local};
+use crate::{Har, domain, format};
+use chrono::{DateTime, FixedOffset, Local};
+use std::time::Duration;
+
+use super::selector::{self, Selector};
+
+/// A point in time, either absolute or relative to the start (`+30s`) or end (`-5m`) of the capture.
+#[derive(Debug, Clone, Copy)]
+pub enum TimeBound {
+    Absolute(DateTime<FixedOffset>),
+    FromStart(Duration),
+    FromEnd(Duration),
+}
+
+impl TimeBound {
+    fn resolve(
+        &self,
+        start: DateTime<FixedOffset>,
+        end: DateTime<FixedOffset>,
+    ) -> Option<DateTime<FixedOffset>> {
+        let offset = |d: &Duration| chrono::Duration::from_std(*d).ok();
+        match self {
+            TimeBound::Absolute(time) => Some(*time),
+            TimeBound::FromStart(d) => start.checked_add_signed(offset(d)?),
+            TimeBound::FromEnd(d) => end.checked_sub_signed(offset(d)?),
+        }
+    }
+}
+
+pub fn parse_time_bound(s: &str) -> Result<TimeBound, String> {
+    let s = s.trim();
+    if let Some(relative) = s.strip_prefix('+') {
+        return format::parse_duration(relative).map(TimeBound::FromStart);
+    }
+    if let Some(relative) = s.strip_prefix('-') {
+        return format::parse_duration(relative).map(TimeBound::FromEnd);
+    }
+
+    s.parse::<DateTime<Local>>()
+        .map(|time| TimeBound::Absolute(time.fixed_offset()))
+        .map_err(|e| format!("invalid time '{}': {}", s, e))
+}
+
+/// Parse `start..end`, where either side may be left open.
+pub fn parse_time_window(s: &str) -> Result<(Option<TimeBound>, Option<TimeBound>), String> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| format!("invalid window '{}', expected start..end", s))?;
+    let bound = |b: &str| {
+        if b.trim().is_empty() {
+            Ok(None)
+        } else {
+            parse_time_bound(b).map(Some)
+        }
+    };
+    Ok((bound(start)?, bound(end)?))
+}
+
+/// Keep entries started between the bounds, resolving relative bounds against the whole capture.
+pub fn filter_by_time(har: &mut Har, after: Option<TimeBound>, before: Option<TimeBound>) {
+    if after.is_none() && before.is_none() {
+        return;
+    }
+
+    let times: Vec<DateTime<FixedOffset>> = har
+        .log
+        .entries
+        .iter()
+        .map(|entry| *entry.started_date_time)
+        .collect();
+    let (Some(start), Some(end)) = (times.iter().min().copied(), times.iter().max().copied())
+    else {
+        har.log.entries.clear();
+        return;
+    };
+    let after = after.and_then(|b| b.resolve(start, end));
+    let before = before.and_then(|b| b.resolve(start, end));
+
+    har.log.entries.retain(|entry| {
+        let start_time = *entry.started_date_time;
+        after.is_none_or(|t| start_time >= t) && before.is_none_or(|t| start_time <= t)
+    });
+}
+
+pub fn filter_by_selector(har: &mut Har, selector: &Selector) {
+    har.log.entries.retain(|entry| selector.matches(entry));
+}
+
+/// Match a host against a domain pattern: a glob if it has wildcards, otherwise the domain
+/// itself or its subdomains.
+fn domain_matches(pattern: &str, host: &str) -> bool {
+    // hosts are always punycode, so patterns given in Unicode are converted label by label
+    let pattern: Vec<String> = pattern
+        .trim_end_matches('.')
+        .split('.')
+        .map(domain::to_ascii)
+        .collect();
+    let pattern = pattern.join(".");
+    if pattern.contains(['*', '?']) {
+        return selector::glob_match(&pattern, host);
+    }
+
+    let host = host.to_lowercase();
+    host == pattern || host.ends_with(&format!(".{}", pattern))
+}
+
+pub fn filter_by_domains(har: &mut Har, include: &[String], exclude: &[String]) {
+    if include.is_empty() && exclude.is_empty() {
+        return;
+    }
 
-pub fn filter_by_time(har: &mut Har, time: DateTime<Local>, after: bool) {
     har.log.entries.retain(|entry| {
-        let start_time = match DateTime::parse_from_rfc3339(&entry.started_date_time) {
-            Ok(t) => t,
-            Err(_) => return false,
+        let Some(host) = entry.request.host() else {
+            // entries without a host (i.e. data URIs) can't match an include list
+            return include.is_empty();
         };
+        let matches = |pattern: &String| domain_matches(pattern, &host);
 
-        if after {
-            start_time >= time
-        } else {
-            start_time <= time
+        (include.is_empty() || include.iter().any(matches)) && !exclude.iter().any(matches)
+    });
+}
+
+/// Parse a status code, class or range, i.e. `404`, `4xx` or `500-599`, into an inclusive range.
+pub fn parse_status_range(s: &str) -> Result<(u16, u16), String> {
+    let s = s.trim().to_lowercase();
+
+    if let Some(class) = s.strip_suffix("xx") {
+        let class: u16 = class
+            .parse()
+            .ok()
+            .filter(|c| (1..=5).contains(c))
+            .ok_or_else(|| format!("invalid status class '{}', expected 1xx to 5xx", s))?;
+        return Ok((class * 100, class * 100 + 99));
+    }
+
+    let parse = |code: &str| {
+        code.trim()
+            .parse::<u16>()
+            .map_err(|_| format!("invalid status '{}'", code))
+    };
+    match s.split_once('-') {
+        Some((low, high)) => {
+            let (low, high) = (parse(low)?, parse(high)?);
+            if low > high {
+                return Err(format!("invalid status range '{}'", s));
+            }
+            Ok((low, high))
+        }
+        None => parse(&s).map(|code| (code, code)),
+    }
+}
+
+pub fn filter_by_method(har: &mut Har, methods: &[String]) {
+    if methods.is_empty() {
+        return;
+    }
+    har.log.entries.retain(|entry| {
+        methods
+            .iter()
+            .any(|m| m.eq_ignore_ascii_case(&entry.request.method))
+    });
+}
+
+pub fn filter_by_status(har: &mut Har, ranges: &[(u16, u16)]) {
+    if ranges.is_empty() {
+        return;
+    }
+    har.log.entries.retain(|entry| {
+        let status = entry.response.status;
+        ranges
+            .iter()
+            .any(|(low, high)| (*low..=*high).contains(&status))
+    });
+}
+
+/// Keep entries whose MIME type or resource kind matches one of the globs, i.e. `image/*` or `script`.
+pub fn filter_by_mime(har: &mut Har, mimes: &[String]) {
+    if mimes.is_empty() {
+        return;
+    }
+    har.log.entries.retain(|entry| {
+        let mime = entry.response.mime_type().unwrap_or_default();
+        let kind = entry.response.resource_kind().to_string();
+        mimes.iter().any(|pattern| {
+            selector::glob_match(pattern, &mime) || selector::glob_match(pattern, &kind)
+        })
+    });
+}
+
+/// Parse an entry number or inclusive range, i.e. `73`, `10..50` or `10..`, numbered from 1.
+pub fn parse_entry_range(s: &str) -> Result<(usize, usize), String> {
+    let parse = |n: &str| match n.trim().parse::<usize>() {
+        Ok(0) | Err(_) => Err(format!("invalid entry number '{}'", n.trim())),
+        Ok(n) => Ok(n),
+    };
+
+    match s.split_once("..") {
+        Some((start, end)) => {
+            let start = if start.trim().is_empty() {
+                1
+            } else {
+                parse(start)?
+            };
+            let end = if end.trim().is_empty() {
+                usize::MAX
+            } else {
+                parse(end)?
+            };
+            if start > end {
+                return Err(format!("invalid entry range '{}'", s));
+            }
+            Ok((start, end))
         }
+        None => parse(s).map(|n| (n, n)),
+    }
+}
+
+/// Keep the entries at the given positions, numbered as `search-for` reports them.
+pub fn filter_by_entries(har: &mut Har, ranges: &[(usize, usize)]) {
+    if ranges.is_empty() {
+        return;
+    }
+    let mut number = 0;
+    har.log.entries.retain(|_| {
+        number += 1;
+        ranges
+            .iter()
+            .any(|(start, end)| (*start..=*end).contains(&number))
     });
 }
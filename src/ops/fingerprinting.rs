@@ -0,0 +1,137 @@
+use colored::Colorize;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+
+use crate::{check, har::Har, mime::ResourceKind};
+
+// API usage that fingerprinting scripts rely on as (category, signature, weight), weighted by
+// how rarely ordinary scripts need it
+const SIGNATURES: [(&str, &str, u32); 22] = [
+    ("canvas", "toDataURL", 2),
+    ("canvas", "getImageData", 1),
+    ("canvas", "isPointInPath", 2),
+    ("webgl", "WEBGL_debug_renderer_info", 4),
+    ("webgl", "UNMASKED_RENDERER_WEBGL", 4),
+    ("webgl", "UNMASKED_VENDOR_WEBGL", 4),
+    ("webgl", "getSupportedExtensions", 1),
+    ("webgl", "getShaderPrecisionFormat", 2),
+    ("audio", "OfflineAudioContext", 3),
+    ("audio", "createDynamicsCompressor", 3),
+    ("audio", "createOscillator", 1),
+    ("fonts", "queryLocalFonts", 4),
+    ("fonts", "document.fonts.check", 2),
+    ("fonts", "measureText", 1),
+    ("device", "hardwareConcurrency", 1),
+    ("device", "deviceMemory", 1),
+    ("device", "getBattery", 2),
+    ("device", "enumerateDevices", 2),
+    ("device", "navigator.plugins", 1),
+    ("device", "colorDepth", 1),
+    ("library", "FingerprintJS", 6),
+    ("library", "ClientJS", 6),
+];
+
+// scripts scoring this much combine enough signals to be fingerprinting rather than using an
+// API or two for their own purposes
+const LIKELY_SCORE: u32 = 6;
+
+#[derive(Debug)]
+pub struct ScriptScore<'a> {
+    pub request_num: usize,
+    pub url: &'a str,
+    pub score: u32,
+    // category -> signatures found
+    pub signals: BTreeMap<&'static str, Vec<&'static str>>,
+}
+
+fn score_script(body: &str) -> (u32, BTreeMap<&'static str, Vec<&'static str>>) {
+    let mut score = 0;
+    let mut signals: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (category, signature, weight) in SIGNATURES {
+        if body.contains(signature) {
+            score += weight;
+            signals.entry(category).or_default().push(signature);
+        }
+    }
+    (score, signals)
+}
+
+/// Score each script body by the fingerprinting APIs it uses, keeping those that use any.
+pub fn find_fingerprinting<'a>(har: &'a Har) -> Vec<ScriptScore<'a>> {
+    har.log
+        .entries
+        .par_iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.response.resource_kind() == ResourceKind::Script)
+        .filter_map(|(i, entry)| {
+            let body = entry.response.content.as_ref()?.decoded_text()?;
+            let (score, signals) = score_script(&body);
+            (score > 0).then_some(ScriptScore {
+                request_num: i + 1,
+                url: &entry.request.url,
+                score,
+                signals,
+            })
+        })
+        .collect()
+}
+
+pub fn fingerprinting(har: &Har) {
+    let scripts = find_fingerprinting(har);
+    if scripts.is_empty() {
+        println!("{}", "No fingerprinting signals found.".green());
+        return;
+    }
+
+    let mut domains: BTreeMap<String, Vec<&ScriptScore>> = BTreeMap::new();
+    for script in &scripts {
+        let host = har.log.entries[script.request_num - 1]
+            .request
+            .host()
+            .unwrap_or_default();
+        domains.entry(host).or_default().push(script);
+    }
+
+    let mut likely_domains = 0;
+    for (domain, scripts) in &domains {
+        let likely = scripts.iter().any(|s| s.score >= LIKELY_SCORE);
+        if likely {
+            likely_domains += 1;
+            println!(
+                "{} {}:",
+                domain.bold().blue(),
+                "(likely fingerprinting)".red()
+            );
+        } else {
+            println!("{}:", domain.bold().blue());
+        }
+
+        for script in scripts {
+            let score = format!("score {}", script.score);
+            println!(
+                "    #{} {} ({})",
+                script.request_num,
+                script.url.cyan(),
+                if script.score >= LIKELY_SCORE {
+                    score.red()
+                } else {
+                    score.yellow()
+                }
+            );
+            for (category, signatures) in &script.signals {
+                println!("        {}: {}", category, signatures.join(", "));
+            }
+        }
+        println!();
+    }
+
+    let likely = scripts.iter().filter(|s| s.score >= LIKELY_SCORE).count();
+    check::report(likely);
+    println!(
+        "{}: {} scripts with fingerprinting signals, {} likely fingerprinting from {} domains",
+        "Summary".bold(),
+        scripts.len(),
+        likely,
+        likely_domains
+    );
+}
@@ -0,0 +1,262 @@
+use colored::Colorize;
+use std::collections::BTreeMap;
+use url::Url;
+
+use crate::{
+    format,
+    har::{Entry, Har},
+    mime::ResourceKind,
+};
+
+const FONT_EXTENSIONS: [&str; 5] = ["woff2", "woff", "ttf", "otf", "eot"];
+
+// parts of a font file name that name a weight or style rather than the family
+const VARIANT_WORDS: [&str; 24] = [
+    "thin",
+    "hairline",
+    "extralight",
+    "ultralight",
+    "light",
+    "regular",
+    "normal",
+    "book",
+    "medium",
+    "semibold",
+    "demibold",
+    "bold",
+    "extrabold",
+    "ultrabold",
+    "black",
+    "heavy",
+    "italic",
+    "oblique",
+    "variable",
+    "vf",
+    "latin",
+    "ext",
+    "subset",
+    "webfont",
+];
+
+// a Latin subset of a typical text face is about this size as WOFF2
+const SUBSET_BYTES: u64 = 20 * 1024;
+
+// variants a page usually needs, i.e. regular and bold
+const TYPICAL_VARIANTS: usize = 2;
+
+#[derive(Debug)]
+pub struct Font<'a> {
+    pub request_num: usize,
+    pub url: &'a str,
+    pub domain: String,
+    pub format: String,
+    pub variant: String,
+    pub bytes: u64,
+    pub cache_control: Option<&'a str>,
+}
+
+#[derive(Debug, Default)]
+pub struct Savings {
+    pub subsetting: u64,
+    pub variants: u64,
+    pub woff2: u64,
+}
+
+/// Split a font file name into its family and the weight or style it's for, i.e.
+/// `Roboto-BoldItalic.woff2` -> (`roboto`, `bold italic`).
+fn family_and_variant(stem: &str) -> (String, String) {
+    // split camel case and numbered weights into words
+    let mut words: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut previous: Option<char> = None;
+    for c in stem.chars() {
+        let boundary = !c.is_alphanumeric()
+            || previous.is_some_and(|p| {
+                (p.is_lowercase() && c.is_uppercase()) || (p.is_alphabetic() != c.is_alphabetic())
+            });
+        if boundary && !current.is_empty() {
+            words.push(current.to_lowercase());
+            current.clear();
+        }
+        if c.is_alphanumeric() {
+            current.push(c);
+        }
+        previous = Some(c);
+    }
+    if !current.is_empty() {
+        words.push(current.to_lowercase());
+    }
+
+    let is_variant = |word: &str| {
+        VARIANT_WORDS.contains(&word)
+            || (word.len() == 3 && word.ends_with("00") && word.chars().all(|c| c.is_ascii_digit()))
+    };
+    let (variant, family): (Vec<String>, Vec<String>) =
+        words.into_iter().partition(|word| is_variant(word));
+
+    let variant = if variant.is_empty() {
+        "regular".to_string()
+    } else {
+        variant.join(" ")
+    };
+    (family.join(" "), variant)
+}
+
+fn font_format(entry: &Entry, path: &str) -> Option<String> {
+    let extension = path.rsplit_once('.').map(|(_, ext)| ext.to_lowercase());
+    if let Some(extension) = extension.filter(|ext| FONT_EXTENSIONS.contains(&ext.as_str())) {
+        return Some(extension);
+    }
+
+    // font/woff2 and application/font-woff style MIME types
+    if entry.response.resource_kind() == ResourceKind::Font {
+        let mime = entry.response.mime_type().unwrap_or_default();
+        let subtype = mime.rsplit(['/', '-']).next().unwrap_or("font");
+        return Some(subtype.to_string());
+    }
+    None
+}
+
+/// Find the web fonts downloaded in the HAR, grouped by family.
+pub fn find_fonts<'a>(har: &'a Har) -> BTreeMap<String, Vec<Font<'a>>> {
+    let mut families: BTreeMap<String, Vec<Font>> = BTreeMap::new();
+    for (i, entry) in har.log.entries.iter().enumerate() {
+        let Ok(url) = Url::parse(&entry.request.url) else {
+            continue;
+        };
+        let Some(format) = font_format(entry, url.path()) else {
+            continue;
+        };
+
+        let file_name = url.path_segments().and_then(|mut s| s.next_back());
+        let stem = file_name
+            .map(|name| name.rsplit_once('.').map_or(name, |(stem, _)| stem))
+            .unwrap_or_default();
+        let (mut family, variant) = family_and_variant(stem);
+        // Google Fonts and similar name files by hash, so fall back to where they came from
+        if family.is_empty() || family.len() > 24 {
+            family = url.host_str().unwrap_or("unknown").to_string();
+        }
+
+        let fonts = families.entry(family).or_default();
+        // the same file requested twice isn't another variant
+        if fonts.iter().any(|font| font.url == entry.request.url) {
+            continue;
+        }
+        fonts.push(Font {
+            request_num: i + 1,
+            url: &entry.request.url,
+            domain: url.host_str().unwrap_or_default().to_string(),
+            format,
+            variant,
+            bytes: entry.response.body_bytes(),
+            cache_control: entry.response.header("cache-control"),
+        });
+    }
+    families
+}
+
+/// Estimate the bytes that subsetting, fetching fewer variants and converting to WOFF2 would
+/// save. The estimates overlap, so they shouldn't be added together.
+pub fn estimate_savings(fonts: &[Font]) -> Savings {
+    let mut savings = Savings::default();
+    for (i, font) in fonts.iter().enumerate() {
+        savings.subsetting += font.bytes.saturating_sub(SUBSET_BYTES);
+        // variants beyond the first few fetched are likely only used for a little text
+        if i >= TYPICAL_VARIANTS {
+            savings.variants += font.bytes;
+        }
+        // rough compression gains of WOFF2 over each format
+        savings.woff2 += match font.format.as_str() {
+            "ttf" | "otf" | "eot" | "sfnt" => font.bytes / 2,
+            "woff" => font.bytes * 3 / 10,
+            _ => 0,
+        };
+    }
+    savings
+}
+
+pub fn fonts(har: &Har) {
+    let families = find_fonts(har);
+    if families.is_empty() {
+        println!("{}", "No web fonts found.".green());
+        return;
+    }
+
+    let mut total = Savings::default();
+    let mut count = 0;
+    let mut bytes = 0;
+    for (family, fonts) in &families {
+        let family_bytes: u64 = fonts.iter().map(|f| f.bytes).sum();
+        println!(
+            "{} ({}, {}):",
+            family.bold().blue(),
+            fonts.len(),
+            format::bytes(family_bytes)
+        );
+
+        for font in fonts {
+            let cache = match font.cache_control {
+                Some(value) if value.contains("no-store") || value.contains("no-cache") => {
+                    value.yellow()
+                }
+                Some(value) => value.normal(),
+                None => "no cache-control".yellow(),
+            };
+            let format = if font.format == "woff2" {
+                font.format.normal()
+            } else {
+                font.format.yellow()
+            };
+            println!(
+                "    #{} {} {} ({}, {}, {})",
+                font.request_num,
+                font.variant,
+                font.url.cyan(),
+                format::bytes(font.bytes),
+                format,
+                cache
+            );
+        }
+
+        let domains: Vec<&str> = {
+            let mut domains: Vec<&str> = fonts.iter().map(|f| &*f.domain).collect();
+            domains.sort();
+            domains.dedup();
+            domains
+        };
+        println!("    Domains: {}", domains.join(", "));
+        if fonts.len() > TYPICAL_VARIANTS {
+            println!(
+                "    {}",
+                format!(
+                    "{} variants fetched, consider fewer or a variable font",
+                    fonts.len()
+                )
+                .yellow()
+            );
+        }
+
+        let savings = estimate_savings(fonts);
+        total.subsetting += savings.subsetting;
+        total.variants += savings.variants;
+        total.woff2 += savings.woff2;
+        count += fonts.len();
+        bytes += family_bytes;
+        println!();
+    }
+
+    println!("{}:", "Estimated savings".bold().blue());
+    println!("    Subsetting: {}", format::bytes(total.subsetting));
+    println!("    Fewer variants: {}", format::bytes(total.variants));
+    println!("    WOFF2: {}", format::bytes(total.woff2));
+    println!();
+
+    println!(
+        "{}: {} fonts in {} families, {}",
+        "Summary".bold(),
+        count,
+        families.len(),
+        format::bytes(bytes)
+    );
+}
@@ -0,0 +1,308 @@
+use colored::Colorize;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use url::Url;
+
+use crate::{
+    format,
+    har::{Entry, Har},
+    mime::ResourceKind,
+};
+
+use super::{asset_refs, auth_flows, multipart};
+
+// hidden field names that hold anti-forgery tokens
+const CSRF_PATTERN: &str =
+    r"(?i)csrf|xsrf|authenticity_token|__requestverificationtoken|_token$|^token$|nonce|form_key";
+
+// longest hidden value shown before it's cut short
+const MAX_VALUE_CHARS: usize = 40;
+
+#[derive(Debug, Clone)]
+pub struct Field {
+    pub name: String,
+    pub kind: String,
+    pub value: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Submission {
+    pub request_num: usize,
+    // the form's field names the request sent
+    pub sent: BTreeSet<String>,
+    // whether the CSRF token sent was the one the form carried
+    pub token_matches: Option<bool>,
+}
+
+#[derive(Debug)]
+pub struct Form {
+    pub action: String,
+    pub method: String,
+    pub fields: Vec<Field>,
+    pub csrf_field: Option<String>,
+    pub submission: Option<Submission>,
+}
+
+#[derive(Debug)]
+pub struct DocumentForms<'a> {
+    pub request_num: usize,
+    pub url: &'a str,
+    pub forms: Vec<Form>,
+}
+
+impl Form {
+    pub fn hidden(&self) -> impl Iterator<Item = &Field> {
+        self.fields.iter().filter(|f| f.kind == "hidden")
+    }
+}
+
+/// Parse the forms in a HTML document, with their actions resolved against its URL.
+pub fn parse_forms(html: &str, document_url: &Url, csrf_regex: &Regex) -> Vec<Form> {
+    let form_regex = Regex::new(r"(?is)(<form\b[^>]*>)(.*?)(?:</form\s*>|$)").expect("valid regex");
+    let field_regex =
+        Regex::new(r"(?is)<(input|select|textarea|button)\b[^>]*>").expect("valid regex");
+
+    form_regex
+        .captures_iter(html)
+        .map(|captures| {
+            let attributes = asset_refs::tags(&captures[1], "form")
+                .pop()
+                .unwrap_or_default();
+            // an empty or missing action submits to the document itself
+            let action = attributes
+                .get("action")
+                .map(|a| a.trim())
+                .filter(|a| !a.is_empty())
+                .and_then(|a| document_url.join(a).ok())
+                .unwrap_or_else(|| document_url.clone());
+            let method = attributes
+                .get("method")
+                .map(|m| m.trim().to_uppercase())
+                .filter(|m| m == "POST" || m == "DIALOG")
+                .unwrap_or_else(|| "GET".to_string());
+
+            let fields: Vec<Field> = field_regex
+                .captures_iter(&captures[2])
+                .filter_map(|field| {
+                    let tag = field[1].to_lowercase();
+                    let attributes = asset_refs::tags(&field[0], &tag).pop()?;
+                    let name = attributes.get("name")?.clone();
+                    let kind = match tag.as_str() {
+                        "input" => attributes
+                            .get("type")
+                            .map_or("text".to_string(), |t| t.to_lowercase()),
+                        _ => tag,
+                    };
+                    Some(Field {
+                        name,
+                        kind,
+                        value: attributes.get("value").cloned(),
+                    })
+                })
+                .collect();
+            let csrf_field = fields
+                .iter()
+                .find(|f| f.kind == "hidden" && csrf_regex.is_match(&f.name))
+                .map(|f| f.name.clone());
+
+            Form {
+                action: action.to_string(),
+                method,
+                fields,
+                csrf_field,
+                submission: None,
+            }
+        })
+        .collect()
+}
+
+/// The parameters a request sent, from its body or, for GET forms, its query string.
+fn sent_params(entry: &Entry) -> BTreeMap<String, String> {
+    let mut params = BTreeMap::new();
+    if let Ok(url) = Url::parse(&entry.request.url) {
+        params.extend(
+            url.query_pairs()
+                .map(|(k, v)| (k.into_owned(), v.into_owned())),
+        );
+    }
+    if let Some(post_data) = &entry.request.post_data {
+        params.extend(auth_flows::body_params(post_data));
+        for param in post_data.params.iter().flatten() {
+            params.insert(
+                param.name.to_string(),
+                param.value.as_deref().unwrap_or_default().to_string(),
+            );
+        }
+        // exporters often leave multipart fields out of params
+        for part in multipart::parts(post_data).into_iter().flatten() {
+            if let Some(name) = part.name {
+                params.entry(name).or_insert_with(|| part.body.to_string());
+            }
+        }
+    }
+    params
+}
+
+fn without_query(url: &str) -> &str {
+    url.split(['?', '#']).next().unwrap_or(url)
+}
+
+/// The first request after the document that looks like the form being submitted.
+fn find_submission(form: &Form, entries: &[Entry], after: usize) -> Option<Submission> {
+    let names: BTreeSet<&str> = form.fields.iter().map(|f| f.name.as_str()).collect();
+    entries
+        .iter()
+        .enumerate()
+        .skip(after + 1)
+        .filter(|(_, entry)| {
+            entry.request.method.eq_ignore_ascii_case(&form.method)
+                && without_query(&entry.request.url) == without_query(&form.action)
+        })
+        .find_map(|(i, entry)| {
+            let params = sent_params(entry);
+            let sent: BTreeSet<String> = params
+                .keys()
+                .filter(|name| names.contains(name.as_str()))
+                .cloned()
+                .collect();
+            // a GET to the same URL without any of the fields is just navigation
+            if sent.is_empty() && !names.is_empty() {
+                return None;
+            }
+            let token_matches = form.csrf_field.as_ref().map(|name| {
+                let expected = form
+                    .fields
+                    .iter()
+                    .find(|f| &f.name == name)
+                    .and_then(|f| f.value.as_ref());
+                params.contains_key(name) && params.get(name) == expected
+            });
+            Some(Submission {
+                request_num: i + 1,
+                sent,
+                token_matches,
+            })
+        })
+}
+
+/// Find the forms in captured HTML documents and the requests that submitted them.
+pub fn find_forms<'a>(har: &'a Har) -> Vec<DocumentForms<'a>> {
+    let csrf_regex = Regex::new(CSRF_PATTERN).expect("valid CSRF regex");
+    let entries = &har.log.entries;
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.response.resource_kind() == ResourceKind::Document)
+        .filter_map(|(i, entry)| {
+            let body = entry.response.content.as_ref()?.decoded_text()?;
+            let url = Url::parse(&entry.request.url).ok()?;
+            let mut forms = parse_forms(&body, &url, &csrf_regex);
+            if forms.is_empty() {
+                return None;
+            }
+            for form in &mut forms {
+                form.submission = find_submission(form, entries, i);
+            }
+            Some(DocumentForms {
+                request_num: i + 1,
+                url: &entry.request.url,
+                forms,
+            })
+        })
+        .collect()
+}
+
+fn shorten(value: &str) -> String {
+    if value.chars().count() <= MAX_VALUE_CHARS {
+        return value.to_string();
+    }
+    let cut: String = value.chars().take(MAX_VALUE_CHARS).collect();
+    format!("{}...", cut)
+}
+
+pub fn forms(har: &Har) {
+    let documents = find_forms(har);
+    if documents.is_empty() {
+        println!("{}", "No forms found in captured HTML documents.".yellow());
+        return;
+    }
+
+    let mut form_count = 0;
+    let mut with_csrf = 0;
+    let mut submitted = 0;
+    let mut unprotected_posts = 0;
+    for document in &documents {
+        println!("#{} {}:", document.request_num, document.url.bold().blue());
+        for form in &document.forms {
+            form_count += 1;
+            let hidden = form.hidden().count();
+            println!(
+                "    {} {} ({} fields, {} hidden)",
+                form.method.bold(),
+                form.action,
+                form.fields.len(),
+                hidden
+            );
+
+            for field in form.hidden() {
+                let value = field.value.as_deref().unwrap_or_default();
+                if form.csrf_field.as_ref() == Some(&field.name) {
+                    println!(
+                        "        hidden {} = {} {}",
+                        field.name,
+                        format::redact(value),
+                        "(CSRF token)".green()
+                    );
+                } else {
+                    println!("        hidden {} = {}", field.name, shorten(value));
+                }
+            }
+            let visible: Vec<String> = form
+                .fields
+                .iter()
+                .filter(|f| f.kind != "hidden")
+                .map(|f| format!("{} ({})", f.name, f.kind))
+                .collect();
+            if !visible.is_empty() {
+                println!("        fields: {}", visible.join(", "));
+            }
+
+            if form.csrf_field.is_some() {
+                with_csrf += 1;
+            } else if form.method == "POST" {
+                unprotected_posts += 1;
+                println!("        {}", "POST form without a CSRF token".yellow());
+            }
+
+            match &form.submission {
+                Some(submission) => {
+                    submitted += 1;
+                    let token = match submission.token_matches {
+                        Some(true) => ", with the form's CSRF token".green(),
+                        Some(false) => ", without the form's CSRF token".red(),
+                        None => "".normal(),
+                    };
+                    println!(
+                        "        submitted by #{} with {} of {} fields{}",
+                        submission.request_num,
+                        submission.sent.len(),
+                        form.fields.len(),
+                        token
+                    );
+                }
+                None => println!("        {}", "not submitted in the capture".dimmed()),
+            }
+        }
+        println!();
+    }
+
+    println!(
+        "{}: {} forms on {} documents, {} with CSRF tokens, {} POST forms without, {} submitted",
+        "Summary".bold(),
+        form_count,
+        documents.len(),
+        with_csrf,
+        unprotected_posts,
+        submitted
+    );
+}
@@ -0,0 +1,330 @@
+use base64::Engine;
+use colored::Colorize;
+use std::collections::BTreeMap;
+use url::Url;
+
+use crate::{
+    format,
+    har::{BASE64_LENIENT, Entry, Har},
+    mime::{self, Protobuf},
+};
+
+// a frame is a flags byte then a big-endian u32 length
+const FRAME_HEADER_BYTES: usize = 5;
+const FLAG_COMPRESSED: u8 = 0x01;
+const FLAG_TRAILERS: u8 = 0x80;
+
+// the names of gRPC status codes, indexed by code
+const STATUS_NAMES: [&str; 17] = [
+    "OK",
+    "CANCELLED",
+    "UNKNOWN",
+    "INVALID_ARGUMENT",
+    "DEADLINE_EXCEEDED",
+    "NOT_FOUND",
+    "ALREADY_EXISTS",
+    "PERMISSION_DENIED",
+    "RESOURCE_EXHAUSTED",
+    "FAILED_PRECONDITION",
+    "ABORTED",
+    "OUT_OF_RANGE",
+    "UNIMPLEMENTED",
+    "INTERNAL",
+    "UNAVAILABLE",
+    "DATA_LOSS",
+    "UNAUTHENTICATED",
+];
+
+#[derive(Debug, Default)]
+pub struct Messages {
+    pub count: usize,
+    pub bytes: u64,
+    pub compressed: usize,
+    // trailers sent in the body, as gRPC-web does
+    pub trailers: BTreeMap<String, String>,
+}
+
+#[derive(Debug)]
+pub struct Call<'a> {
+    pub request_num: usize,
+    pub entry: &'a Entry<'a>,
+    // `package.Service/Method` for gRPC, else the URL path
+    pub method: String,
+    // the gRPC or protobuf type it was recognised by
+    pub content_type: String,
+    // `None` where the body is missing or its framing doesn't add up
+    pub request: Option<Messages>,
+    pub response: Option<Messages>,
+    pub status: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct MethodStats {
+    pub calls: usize,
+    pub request_messages: usize,
+    pub request_bytes: u64,
+    pub response_messages: usize,
+    pub response_bytes: u64,
+    // request numbers of the calls whose framing doesn't add up
+    pub undecodable: Vec<usize>,
+    pub statuses: BTreeMap<String, usize>,
+}
+
+/// The name of a gRPC status code, i.e. `5` -> `NOT_FOUND`.
+pub fn status_name(code: &str) -> String {
+    code.trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|code| STATUS_NAMES.get(code))
+        .map_or_else(|| code.trim().to_string(), |name| name.to_string())
+}
+
+/// Decode a gRPC-web-text body. Each chunk a server flushes is base64 encoded on its own, so
+/// padding can turn up part way through.
+fn decode_text(body: &[u8]) -> Option<Vec<u8>> {
+    let cleaned: Vec<u8> = body
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+    let mut decoded = Vec::new();
+    let mut start = 0;
+    for i in 0..cleaned.len() {
+        let chunk_end = cleaned[i] == b'=' && cleaned.get(i + 1).is_none_or(|b| *b != b'=');
+        if chunk_end || i + 1 == cleaned.len() {
+            decoded.extend(BASE64_LENIENT.decode(&cleaned[start..=i]).ok()?);
+            start = i + 1;
+        }
+    }
+    Some(decoded)
+}
+
+/// Split a body into its length-prefixed frames, `None` if the lengths don't add up to the body.
+pub fn parse_frames(body: &[u8], framing: Protobuf) -> Option<Messages> {
+    let decoded;
+    let mut rest = match framing {
+        Protobuf::Message => {
+            return Some(Messages {
+                count: usize::from(!body.is_empty()),
+                bytes: body.len() as u64,
+                ..Default::default()
+            });
+        }
+        Protobuf::GrpcWebText => {
+            decoded = decode_text(body)?;
+            &decoded[..]
+        }
+        Protobuf::Grpc => body,
+    };
+
+    let mut messages = Messages::default();
+    while !rest.is_empty() {
+        if rest.len() < FRAME_HEADER_BYTES {
+            return None;
+        }
+        let flags = rest[0];
+        let length = u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]) as usize;
+        let payload = rest.get(FRAME_HEADER_BYTES..FRAME_HEADER_BYTES + length)?;
+        if flags & FLAG_TRAILERS != 0 {
+            for line in String::from_utf8_lossy(payload).lines() {
+                if let Some((name, value)) = line.split_once(':') {
+                    messages
+                        .trailers
+                        .insert(name.trim().to_lowercase(), value.trim().to_string());
+                }
+            }
+        } else {
+            messages.count += 1;
+            messages.bytes += length as u64;
+            if flags & FLAG_COMPRESSED != 0 {
+                messages.compressed += 1;
+            }
+        }
+        rest = &rest[FRAME_HEADER_BYTES + length..];
+    }
+    Some(messages)
+}
+
+/// The RPC a URL calls. gRPC paths are `/package.Service/Method`, possibly behind a prefix.
+fn rpc_method(url: &str, framing: Protobuf) -> String {
+    let path = Url::parse(url)
+        .map(|u| u.path().to_string())
+        .unwrap_or_else(|_| url.to_string());
+    if framing == Protobuf::Message {
+        return path;
+    }
+    let mut segments = path.rsplit('/').filter(|s| !s.is_empty());
+    match (segments.next(), segments.next()) {
+        (Some(method), Some(service)) => format!("{}/{}", service, method),
+        _ => path,
+    }
+}
+
+fn check_entry<'a>(i: usize, entry: &'a Entry<'a>) -> Option<Call<'a>> {
+    let request_type = entry
+        .request
+        .post_data
+        .as_ref()
+        .map(|p| p.mime_type.to_string())
+        .or_else(|| entry.request.header("content-type").map(String::from));
+    let response_type = entry.response.mime_type();
+    let request_framing = request_type.as_deref().and_then(Protobuf::from_mime);
+    let response_framing = response_type.as_deref().and_then(Protobuf::from_mime);
+    let framing = request_framing.or(response_framing)?;
+    let content_type = match request_framing {
+        Some(_) => request_type,
+        None => response_type,
+    }
+    .map(|t| mime::essence(&t))
+    .unwrap_or_default();
+
+    let request = entry
+        .request
+        .post_data
+        .as_ref()
+        .and_then(|p| parse_frames(p.text.as_bytes(), request_framing.unwrap_or(framing)));
+    let response = entry
+        .response
+        .content
+        .as_ref()
+        .and_then(|c| c.decoded())
+        .and_then(|body| parse_frames(&body, response_framing.unwrap_or(framing)));
+    // plain gRPC sends its status in trailers, which HARs record as headers
+    let status = entry
+        .response
+        .header("grpc-status")
+        .map(String::from)
+        .or_else(|| response.as_ref()?.trailers.get("grpc-status").cloned())
+        .map(|code| status_name(&code));
+
+    Some(Call {
+        request_num: i + 1,
+        entry,
+        method: rpc_method(&entry.request.url, framing),
+        content_type,
+        request,
+        response,
+        status,
+    })
+}
+
+/// Find the gRPC and protobuf requests and decode the framing of their bodies.
+pub fn find_calls<'a>(har: &'a Har) -> Vec<Call<'a>> {
+    har.log
+        .entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, entry)| check_entry(i, entry))
+        .collect()
+}
+
+/// Total the calls, messages and bytes for each RPC method.
+pub fn method_stats(calls: &[Call]) -> BTreeMap<String, MethodStats> {
+    let mut methods: BTreeMap<String, MethodStats> = BTreeMap::new();
+    for call in calls {
+        let stats = methods.entry(call.method.clone()).or_default();
+        stats.calls += 1;
+        if let Some(request) = &call.request {
+            stats.request_messages += request.count;
+            stats.request_bytes += request.bytes;
+        }
+        if let Some(response) = &call.response {
+            stats.response_messages += response.count;
+            stats.response_bytes += response.bytes;
+        }
+        let undecodable = (call.request.is_none() && call.entry.request.post_data.is_some())
+            || (call.response.is_none() && call.entry.response.content.is_some());
+        if undecodable {
+            stats.undecodable.push(call.request_num);
+        }
+        if let Some(status) = &call.status {
+            *stats.statuses.entry(status.clone()).or_default() += 1;
+        }
+    }
+    methods
+}
+
+pub fn grpc(har: &Har) {
+    let calls = find_calls(har);
+    if calls.is_empty() {
+        println!("{}", "No gRPC or protobuf requests found.".yellow());
+        return;
+    }
+
+    let methods = method_stats(&calls);
+    let mut sorted: Vec<(&String, &MethodStats)> = methods.iter().collect();
+    sorted.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.calls));
+    println!("{}:", "RPC methods".bold().blue());
+    for (method, stats) in &sorted {
+        println!(
+            "    {}: {} calls, {} messages sent ({}), {} received ({})",
+            method.bold(),
+            stats.calls,
+            stats.request_messages,
+            format::bytes(stats.request_bytes),
+            stats.response_messages,
+            format::bytes(stats.response_bytes)
+        );
+        if !stats.statuses.is_empty() {
+            let statuses: Vec<String> = stats
+                .statuses
+                .iter()
+                .map(|(status, count)| {
+                    let status = if status == "OK" {
+                        status.green()
+                    } else {
+                        status.red()
+                    };
+                    format!("{} {}", status, count)
+                })
+                .collect();
+            println!("        statuses: {}", statuses.join(", "));
+        }
+        if !stats.undecodable.is_empty() {
+            let requests: Vec<String> = stats
+                .undecodable
+                .iter()
+                .map(|n| format!("#{}", n))
+                .collect();
+            println!(
+                "        {} {}",
+                "framing doesn't add up in".yellow(),
+                requests.join(", ")
+            );
+        }
+    }
+    println!();
+
+    let mut content_types: BTreeMap<String, usize> = BTreeMap::new();
+    for call in &calls {
+        *content_types.entry(call.content_type.clone()).or_default() += 1;
+    }
+    println!("{}:", "Content types".bold().blue());
+    for (content_type, count) in &content_types {
+        println!("    {}: {}", content_type, count);
+    }
+    println!();
+
+    let sent: u64 = methods.values().map(|s| s.request_bytes).sum();
+    let received: u64 = methods.values().map(|s| s.response_bytes).sum();
+    let compressed = calls
+        .iter()
+        .flat_map(|c| [&c.request, &c.response])
+        .flatten()
+        .filter(|m| m.compressed > 0)
+        .count();
+    let failed = calls
+        .iter()
+        .filter(|c| c.status.as_deref().is_some_and(|s| s != "OK"))
+        .count();
+    println!(
+        "{}: {} calls to {} methods, {} sent and {} received in messages, {} failed, {} bodies with compressed messages",
+        "Summary".bold(),
+        calls.len(),
+        methods.len(),
+        format::bytes(sent),
+        format::bytes(received),
+        failed,
+        compressed
+    );
+}
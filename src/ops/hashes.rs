@@ -0,0 +1,249 @@
+use anyhow::{Context, Result, anyhow};
+use colored::Colorize;
+use rayon::prelude::*;
+use sha2::{Digest, Sha256};
+use std::{cmp::Reverse, collections::HashMap, fs, path::Path};
+use url::Url;
+
+use crate::{
+    cache, check, format,
+    har::{Entry, Har},
+};
+
+// how many URLs to list per body before summarising
+const MAX_URLS: usize = 5;
+
+#[derive(Debug)]
+pub struct BodyGroup<'a> {
+    pub sha256: String,
+    pub size: usize,
+    pub request_nums: Vec<usize>,
+    pub urls: Vec<&'a str>,
+    // labels from the hash list, if the hash is on it, empty where it has none
+    pub listed: Option<Vec<String>>,
+}
+
+#[derive(Debug)]
+pub struct Mismatch<'a> {
+    pub request_num: usize,
+    pub url: &'a str,
+    pub expected: String,
+    pub actual: String,
+}
+
+/// Read a list of SHA-256 hashes, one per line with an optional label after it, as
+/// `sha256sum` writes them. Blank lines and `#` comments are skipped, and a hash listed more
+/// than once keeps every label.
+pub fn load_hash_list(path: &Path) -> Result<HashMap<String, Vec<String>>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read hash list: {:?}", path))?;
+
+    let mut hashes: HashMap<String, Vec<String>> = HashMap::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (hash, label) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        if hash.len() != 64 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow!(
+                "Invalid SHA-256 hash on line {} of {:?}",
+                i + 1,
+                path
+            ));
+        }
+        // sha256sum marks binary mode with a `*` before the file name
+        let label = label.trim().trim_start_matches('*');
+        let labels = hashes.entry(hash.to_lowercase()).or_default();
+        if !label.is_empty() && !labels.iter().any(|l| l == label) {
+            labels.push(label.to_string());
+        }
+    }
+    Ok(hashes)
+}
+
+/// Hash a decoded response body. `cached` lets the digest come from the cache, which keys
+/// bodies by a fast non-cryptographic hash, so it's left off when checking against a hash list
+/// where a crafted collision could pass one body off as another.
+fn hash_body(entry: &Entry, cached: bool) -> Option<(String, usize)> {
+    let content = entry.response.content.as_ref()?;
+    let text = content.text.as_deref()?;
+    let hash = || {
+        let body = content.decoded()?;
+        (!body.is_empty()).then(|| (Sha256::digest(&body).into(), body.len()))
+    };
+    let (digest, size): ([u8; 32], usize) = if cached {
+        let encoded = [
+            content.encoding.as_deref().unwrap_or_default().as_bytes(),
+            text.as_bytes(),
+        ];
+        cache::body_hash("sha256", &encoded, hash)?
+    } else {
+        hash()?
+    };
+    let hex = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    Some((hex, size))
+}
+
+/// Hash every decoded response body, grouping identical ones with the most shared first.
+/// Bodies are hashed afresh rather than from the cache when there's a list to check them against.
+pub fn hash_bodies<'a>(har: &'a Har, list: &HashMap<String, Vec<String>>) -> Vec<BodyGroup<'a>> {
+    let cached = list.is_empty();
+    let hashes: Vec<Option<(String, usize)>> = har
+        .log
+        .entries
+        .par_iter()
+        .map(|entry| hash_body(entry, cached))
+        .collect();
+
+    let mut groups: HashMap<String, BodyGroup> = HashMap::new();
+    for (i, (entry, hash)) in har.log.entries.iter().zip(hashes).enumerate() {
+        let Some((sha256, size)) = hash else {
+            continue;
+        };
+        let group = groups.entry(sha256.clone()).or_insert_with(|| BodyGroup {
+            listed: list.get(&sha256).cloned(),
+            sha256,
+            size,
+            request_nums: Vec::new(),
+            urls: Vec::new(),
+        });
+        group.request_nums.push(i + 1);
+        if !group.urls.contains(&&*entry.request.url) {
+            group.urls.push(&entry.request.url);
+        }
+    }
+
+    let mut groups: Vec<BodyGroup> = groups.into_values().collect();
+    groups.sort_by_key(|g| (Reverse(g.request_nums.len()), g.request_nums[0]));
+    groups
+}
+
+/// Find responses for files named in the hash list whose bodies hash to something else, i.e.
+/// a script that no longer matches its expected hash. Labels match the full URL or the last
+/// path segment, and a label listed with several hashes matches any of them.
+pub fn find_mismatches<'a>(
+    har: &'a Har,
+    groups: &[BodyGroup],
+    list: &HashMap<String, Vec<String>>,
+) -> Vec<Mismatch<'a>> {
+    let mut expected: HashMap<&str, Vec<&str>> = HashMap::new();
+    for (hash, labels) in list {
+        for label in labels {
+            expected.entry(label).or_default().push(hash);
+        }
+    }
+    for hashes in expected.values_mut() {
+        hashes.sort();
+    }
+    if expected.is_empty() {
+        return Vec::new();
+    }
+
+    let actual: HashMap<usize, &str> = groups
+        .iter()
+        .flat_map(|g| g.request_nums.iter().map(|n| (*n, &*g.sha256)))
+        .collect();
+
+    let mut mismatches = Vec::new();
+    for (i, entry) in har.log.entries.iter().enumerate() {
+        let url = &*entry.request.url;
+        let file_name = Url::parse(url).ok().and_then(|u| {
+            u.path_segments()
+                .and_then(|mut s| s.next_back().map(String::from))
+        });
+        let wanted = expected.get(url).or_else(|| {
+            file_name
+                .as_deref()
+                .filter(|name| !name.is_empty())
+                .and_then(|name| expected.get(name))
+        });
+
+        if let (Some(wanted), Some(got)) = (wanted, actual.get(&(i + 1))) {
+            if !wanted.contains(got) {
+                mismatches.push(Mismatch {
+                    request_num: i + 1,
+                    url,
+                    expected: wanted.join(" or "),
+                    actual: got.to_string(),
+                });
+            }
+        }
+    }
+    mismatches
+}
+
+pub fn print_hashes(har: &Har, list_path: Option<&Path>) -> Result<()> {
+    let list = match list_path {
+        Some(path) => load_hash_list(path)?,
+        None => HashMap::new(),
+    };
+    let groups = hash_bodies(har, &list);
+
+    if groups.is_empty() {
+        println!("{}", "No response bodies found.".green());
+        return Ok(());
+    }
+
+    for group in &groups {
+        println!("{}:", group.sha256.bold().blue());
+        println!("    Size: {}", format::bytes(group.size as u64));
+        println!(
+            "    Requests: {}",
+            group
+                .request_nums
+                .iter()
+                .map(|i| i.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        for url in group.urls.iter().take(MAX_URLS) {
+            println!("    {}", url.cyan());
+        }
+        if group.urls.len() > MAX_URLS {
+            println!("    ... and {} more", group.urls.len() - MAX_URLS);
+        }
+        if let Some(labels) = &group.listed {
+            let labels = if labels.is_empty() {
+                "yes".to_string()
+            } else {
+                labels.join(", ")
+            };
+            println!("    {}: {}", "Listed".yellow().bold(), labels);
+        }
+        println!();
+    }
+
+    let mismatches = find_mismatches(har, &groups, &list);
+    if !mismatches.is_empty() {
+        println!("{}:", "Hash mismatches".bold().red());
+        for mismatch in &mismatches {
+            println!("    #{} {}", mismatch.request_num, mismatch.url.cyan());
+            println!("        Expected: {}", mismatch.expected);
+            println!("        Actual:   {}", mismatch.actual.yellow());
+        }
+        println!();
+    }
+
+    let bodies: usize = groups.iter().map(|g| g.request_nums.len()).sum();
+    let shared = groups.iter().filter(|g| g.request_nums.len() > 1).count();
+    let listed: usize = groups
+        .iter()
+        .filter(|g| g.listed.is_some())
+        .map(|g| g.request_nums.len())
+        .sum();
+    check::report(listed + mismatches.len());
+    print!(
+        "{}: {} bodies, {} unique, {} shared by more than one response",
+        "Summary".bold(),
+        bodies,
+        groups.len(),
+        shared
+    );
+    if list_path.is_some() {
+        print!(", {} listed, {} mismatched", listed, mismatches.len());
+    }
+    println!();
+    Ok(())
+}
@@ -0,0 +1,196 @@
+use colored::Colorize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::{check, domain, har::Har};
+
+// characters from other scripts commonly passed off as Latin letters
+const CONFUSABLES: [(char, char); 34] = [
+    ('а', 'a'),
+    ('в', 'b'),
+    ('с', 'c'),
+    ('ԁ', 'd'),
+    ('е', 'e'),
+    ('һ', 'h'),
+    ('і', 'i'),
+    ('ј', 'j'),
+    ('к', 'k'),
+    ('ӏ', 'l'),
+    ('м', 'm'),
+    ('п', 'n'),
+    ('о', 'o'),
+    ('р', 'p'),
+    ('ԛ', 'q'),
+    ('г', 'r'),
+    ('ѕ', 's'),
+    ('т', 't'),
+    ('у', 'y'),
+    ('ԝ', 'w'),
+    ('х', 'x'),
+    ('α', 'a'),
+    ('ε', 'e'),
+    ('ι', 'i'),
+    ('κ', 'k'),
+    ('ν', 'v'),
+    ('ο', 'o'),
+    ('ρ', 'p'),
+    ('τ', 't'),
+    ('υ', 'u'),
+    ('օ', 'o'),
+    ('ս', 'u'),
+    ('ց', 'g'),
+    ('ո', 'n'),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+    Other,
+}
+
+impl Script {
+    /// The script of a letter, or `None` for digits, hyphens and other shared characters.
+    fn of(c: char) -> Option<Script> {
+        match c {
+            'a'..='z' | 'A'..='Z' | '\u{00C0}'..='\u{024F}' | '\u{1E00}'..='\u{1EFF}' => {
+                Some(Script::Latin)
+            }
+            '\u{0370}'..='\u{03FF}' | '\u{1F00}'..='\u{1FFF}' => Some(Script::Greek),
+            '\u{0400}'..='\u{052F}' => Some(Script::Cyrillic),
+            '\u{0530}'..='\u{058F}' => Some(Script::Armenian),
+            _ if c.is_alphabetic() => Some(Script::Other),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Homograph {
+    pub host: String,
+    pub unicode: String,
+    pub reasons: Vec<String>,
+    // what the host reads as with lookalikes replaced by Latin letters
+    pub skeleton: Option<String>,
+    pub imitates: Option<String>,
+}
+
+fn skeleton(unicode: &str) -> String {
+    unicode
+        .chars()
+        .map(|c| {
+            CONFUSABLES
+                .iter()
+                .find(|(confusable, _)| *confusable == c)
+                .map_or(c, |(_, latin)| *latin)
+        })
+        .collect()
+}
+
+fn is_same_site(host: &str, parent: &str) -> bool {
+    host == parent || host.ends_with(&format!(".{}", parent))
+}
+
+fn check_label(label: &str, reasons: &mut Vec<String>) {
+    let scripts: BTreeSet<Script> = label.chars().filter_map(Script::of).collect();
+
+    if scripts.len() > 1 {
+        let names: Vec<String> = scripts.iter().map(|s| format!("{:?}", s)).collect();
+        reasons.push(format!("'{}' mixes {} scripts", label, names.join(" and ")));
+    } else if scripts.iter().any(|s| *s != Script::Latin) && skeleton(label).is_ascii() {
+        reasons.push(format!("'{}' is made entirely of Latin lookalikes", label));
+    }
+}
+
+/// Find internationalised hosts that mix scripts or are spelt with Latin lookalikes, returning
+/// them and how many internationalised hosts there were in total.
+pub fn find_homographs(har: &Har) -> (Vec<Homograph>, usize) {
+    let hosts: BTreeSet<String> = har
+        .log
+        .entries
+        .iter()
+        .filter_map(|entry| entry.request.host())
+        .collect();
+
+    let mut idns = 0;
+    let mut found = Vec::new();
+    for host in &hosts {
+        let Some(unicode) = domain::to_unicode(host) else {
+            continue;
+        };
+        idns += 1;
+
+        let mut reasons = Vec::new();
+        for label in unicode.split('.') {
+            check_label(label, &mut reasons);
+        }
+        if reasons.is_empty() {
+            continue;
+        }
+
+        let skeleton = Some(skeleton(&unicode)).filter(|s| s.is_ascii() && *s != unicode);
+        let imitates = skeleton.as_ref().and_then(|s| {
+            hosts
+                .iter()
+                .find(|other| is_same_site(other, s) || is_same_site(s, other))
+                .cloned()
+        });
+
+        found.push(Homograph {
+            host: host.clone(),
+            unicode,
+            reasons,
+            skeleton,
+            imitates,
+        });
+    }
+
+    (found, idns)
+}
+
+pub fn homographs(har: &Har) {
+    let (found, idns) = find_homographs(har);
+
+    if found.is_empty() {
+        println!("{}", "No homograph domains found.".green());
+        return;
+    }
+
+    let mut by_host: BTreeMap<String, &Homograph> = BTreeMap::new();
+    for homograph in &found {
+        by_host.insert(homograph.host.clone(), homograph);
+    }
+    let mut names: Vec<&String> = by_host.keys().collect();
+    names.sort_by_key(|x| x.chars().rev().collect::<String>());
+
+    for name in names {
+        let homograph = by_host[name];
+        println!("{} ({}):", homograph.host.bold().blue(), homograph.unicode);
+        for reason in &homograph.reasons {
+            println!("    {}", reason.yellow());
+        }
+        if let Some(skeleton) = &homograph.skeleton {
+            println!("    Reads as {}", skeleton.bold());
+        }
+        if let Some(imitates) = &homograph.imitates {
+            println!(
+                "    {}",
+                format!("Imitates {}, which is also in the capture", imitates)
+                    .red()
+                    .bold()
+            );
+        }
+        println!();
+    }
+
+    check::report(found.len());
+    let imitating = found.iter().filter(|h| h.imitates.is_some()).count();
+    println!(
+        "{}: {} of {} internationalised domains look like homographs, {} imitating captured hosts",
+        "Summary".bold(),
+        found.len(),
+        idns,
+        imitating
+    );
+}
@@ -0,0 +1,254 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use reqwest::Client;
+use serde::Deserialize;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+};
+use tracing::warn;
+use url::Url;
+
+use crate::{check, dirs, har::Har, offline};
+
+const PRELOAD_URL: &str = "https://raw.githubusercontent.com/chromium/chromium/main/net/http/transport_security_state_static.json";
+const PRELOAD_FILE: &str = "hsts_preload.txt";
+const BUNDLED_PRELOAD: &str = include_str!("../../data/hsts_preload.txt");
+
+// a year is the minimum max-age accepted for preloading
+const MIN_MAX_AGE: u64 = 31_536_000;
+
+#[derive(Debug, Deserialize)]
+struct ChromiumPreloadList {
+    entries: Vec<ChromiumPreloadEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChromiumPreloadEntry {
+    name: String,
+    mode: Option<String>,
+    include_subdomains: Option<bool>,
+}
+
+#[derive(Debug, Default)]
+pub struct StsHeader {
+    pub max_age: Option<u64>,
+    pub include_subdomains: bool,
+    pub preload: bool,
+}
+
+#[derive(Debug)]
+pub enum Preloaded {
+    Exact,
+    ViaParent(String),
+}
+
+fn get_preload_path() -> Result<PathBuf> {
+    Ok(dirs::get_data_dir("hsts")?.join(PRELOAD_FILE))
+}
+
+pub async fn download_preload_list() -> Result<()> {
+    offline::ensure_online("download the HSTS preload list")?;
+    println!("{}: {}", "Downloading".blue().bold(), PRELOAD_URL);
+
+    let text = Client::new()
+        .get(PRELOAD_URL)
+        .send()
+        .await
+        .with_context(|| format!("Failed to send request to {}", PRELOAD_URL))?
+        .error_for_status()?
+        .text()
+        .await
+        .context("Failed to read HSTS preload list")?;
+
+    // the chromium source contains comment lines which aren't valid JSON
+    let json: String = text
+        .lines()
+        .filter(|line| !line.trim_start().starts_with("//"))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let list: ChromiumPreloadList =
+        serde_json::from_str(&json).context("Failed to parse HSTS preload list")?;
+
+    let mut out = String::new();
+    for entry in list.entries {
+        if entry.mode.as_deref() != Some("force-https") {
+            continue;
+        }
+
+        out.push_str(&entry.name);
+        if entry.include_subdomains.unwrap_or(false) {
+            out.push_str(" include_subdomains");
+        }
+        out.push('\n');
+    }
+
+    let path = get_preload_path()?;
+    fs::write(&path, out).with_context(|| format!("Failed to write {:?}", path))?;
+    println!("{}: {:?}", "Saved".green().bold(), path);
+
+    Ok(())
+}
+
+/// Load the downloaded preload list, falling back to the bundled subset.
+fn load_preload_list() -> Result<HashMap<String, bool>> {
+    let path = get_preload_path()?;
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => {
+            warn!(
+                "using the bundled HSTS preload subset, run `harper - get-hsts-preload` for the full list"
+            );
+            BUNDLED_PRELOAD.to_string()
+        }
+    };
+
+    let mut list = HashMap::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        if let Some(name) = parts.next() {
+            let include_subdomains = parts.next() == Some("include_subdomains");
+            list.insert(name.to_lowercase(), include_subdomains);
+        }
+    }
+
+    Ok(list)
+}
+
+fn check_preloaded(domain: &str, list: &HashMap<String, bool>) -> Option<Preloaded> {
+    if list.contains_key(domain) {
+        return Some(Preloaded::Exact);
+    }
+
+    // walk up the parents looking for an include_subdomains entry
+    let mut rest = domain;
+    while let Some((_, parent)) = rest.split_once('.') {
+        if list.get(parent) == Some(&true) {
+            return Some(Preloaded::ViaParent(parent.to_string()));
+        }
+        rest = parent;
+    }
+
+    None
+}
+
+pub fn parse_sts_header(value: &str) -> StsHeader {
+    let mut sts = StsHeader::default();
+
+    for directive in value.split(';') {
+        let directive = directive.trim();
+        let (name, value) = directive.split_once('=').unwrap_or((directive, ""));
+
+        match name.trim().to_lowercase().as_str() {
+            "max-age" => sts.max_age = value.trim().trim_matches('"').parse().ok(),
+            "includesubdomains" => sts.include_subdomains = true,
+            "preload" => sts.preload = true,
+            _ => {}
+        }
+    }
+
+    sts
+}
+
+pub fn hsts_audit(har: &Har) -> Result<()> {
+    let preload_list = load_preload_list()?;
+
+    // collect the STS header sent by each https host
+    let mut hosts: BTreeMap<String, Option<StsHeader>> = BTreeMap::new();
+    for entry in &har.log.entries {
+        let Ok(url) = Url::parse(&entry.request.url) else {
+            continue;
+        };
+        let Some(host) = url.host_str() else {
+            continue;
+        };
+        if url.scheme() != "https" {
+            continue;
+        }
+
+        let sts = entry
+            .response
+            .header("strict-transport-security")
+            .map(parse_sts_header);
+
+        let current = hosts.entry(host.to_lowercase()).or_default();
+        if current.is_none() {
+            *current = sts;
+        }
+    }
+
+    let mut unprotected = 0;
+    let mut domains: Vec<&String> = hosts.keys().collect();
+    domains.sort_by_key(|x| x.chars().rev().collect::<String>());
+
+    for domain in domains {
+        let preloaded = check_preloaded(domain, &preload_list);
+
+        // a parent seen in the capture sending includeSubDomains also covers this host
+        let covering_parent = hosts.iter().find(|(parent, sts)| {
+            domain.ends_with(&format!(".{}", parent))
+                && sts.as_ref().is_some_and(|s| s.include_subdomains)
+        });
+
+        println!("{}:", domain.bold().blue());
+
+        match &preloaded {
+            Some(Preloaded::Exact) => println!("    Preloaded: {}", "yes".green()),
+            Some(Preloaded::ViaParent(parent)) => {
+                println!("    Preloaded: {}", format!("yes, via {}", parent).green())
+            }
+            None => println!("    Preloaded: {}", "no".yellow()),
+        }
+
+        match &hosts[domain] {
+            Some(sts) => {
+                let max_age = sts.max_age.unwrap_or(0);
+                let max_age_str = if max_age < MIN_MAX_AGE {
+                    format!("max-age={}", max_age).yellow()
+                } else {
+                    format!("max-age={}", max_age).green()
+                };
+                println!(
+                    "    HSTS: {}{}{}",
+                    max_age_str,
+                    if sts.include_subdomains {
+                        "; includeSubDomains"
+                    } else {
+                        ""
+                    },
+                    if sts.preload { "; preload" } else { "" }
+                );
+            }
+            None => match covering_parent {
+                Some((parent, _)) => {
+                    println!("    HSTS: {}", format!("via {}", parent).green())
+                }
+                None => println!("    HSTS: {}", "not sent".yellow()),
+            },
+        }
+
+        if preloaded.is_none() && hosts[domain].is_none() && covering_parent.is_none() {
+            unprotected += 1;
+            println!("{}", "Neither preloaded nor sending HSTS.".red());
+        }
+
+        println!();
+    }
+
+    check::report(unprotected);
+    println!(
+        "{}: {} of {} https domains unprotected against downgrade",
+        "Summary".bold(),
+        unprotected,
+        hosts.len()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,315 @@
+use colored::Colorize;
+use regex::Regex;
+use std::collections::BTreeMap;
+
+use crate::{
+    check,
+    har::{Entry, Har},
+    mime::ResourceKind,
+};
+
+use super::asset_refs;
+
+// browsers only look for a `<meta charset>` this far into the document
+const META_PRESCAN_BYTES: usize = 1024;
+
+// labels browsers treat as the same encoding, as the WHATWG Encoding Standard maps them
+const CHARSET_ALIASES: [(&str, &str); 12] = [
+    ("utf8", "utf-8"),
+    ("unicode-1-1-utf-8", "utf-8"),
+    ("iso-8859-1", "windows-1252"),
+    ("iso8859-1", "windows-1252"),
+    ("latin1", "windows-1252"),
+    ("l1", "windows-1252"),
+    ("cp1252", "windows-1252"),
+    ("us-ascii", "windows-1252"),
+    ("ascii", "windows-1252"),
+    ("shift-jis", "shift_jis"),
+    ("sjis", "shift_jis"),
+    ("x-sjis", "shift_jis"),
+];
+
+#[derive(Debug, Default)]
+pub struct TextResponse<'a> {
+    pub request_num: usize,
+    pub url: &'a str,
+    pub kind: Option<ResourceKind>,
+    // from the Content-Type header
+    pub header_charset: Option<String>,
+    // from `<meta>`, `@charset` or the XML declaration
+    pub body_charset: Option<String>,
+    pub bom: Option<&'static str>,
+    pub content_language: Option<String>,
+    // the `lang` of a document's `<html>`
+    pub html_lang: Option<String>,
+    pub problems: Vec<String>,
+}
+
+impl TextResponse<'_> {
+    /// The charset a browser would decode with, a BOM taking precedence over the header.
+    pub fn charset(&self) -> Option<&str> {
+        self.bom
+            .or(self.header_charset.as_deref())
+            .or(self.body_charset.as_deref())
+    }
+
+    /// The language it declares, the header taking precedence over the markup.
+    pub fn language(&self) -> Option<&str> {
+        self.content_language
+            .as_deref()
+            .or(self.html_lang.as_deref())
+    }
+}
+
+/// Lowercase a charset label and map aliases to the encoding they name, i.e. `UTF8` -> `utf-8`.
+pub fn normalise_charset(label: &str) -> String {
+    let label = label.trim().trim_matches(['"', '\'']).to_lowercase();
+    CHARSET_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == label)
+        .map_or(label, |(_, charset)| charset.to_string())
+}
+
+fn header_charset(content_type: &str) -> Option<String> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| normalise_charset(value))
+    })
+}
+
+fn bom(body: &[u8]) -> Option<&'static str> {
+    if body.starts_with(b"\xef\xbb\xbf") {
+        Some("utf-8")
+    } else if body.starts_with(b"\xfe\xff") {
+        Some("utf-16be")
+    } else if body.starts_with(b"\xff\xfe") {
+        Some("utf-16le")
+    } else {
+        None
+    }
+}
+
+/// The charset a HTML document's `<meta>` declares, either as `charset` or `http-equiv`.
+fn meta_charset(html: &str) -> Option<String> {
+    asset_refs::tags(html, "meta")
+        .into_iter()
+        .find_map(|attributes| {
+            if let Some(charset) = attributes.get("charset") {
+                return Some(normalise_charset(charset));
+            }
+            let http_equiv = attributes.get("http-equiv")?;
+            if !http_equiv.eq_ignore_ascii_case("content-type") {
+                return None;
+            }
+            header_charset(attributes.get("content")?)
+        })
+}
+
+/// The primary subtag of a language, i.e. `en-GB` -> `en`.
+fn primary_language(language: &str) -> String {
+    language
+        .split(['-', '_'])
+        .next()
+        .unwrap_or(language)
+        .trim()
+        .to_lowercase()
+}
+
+fn check_entry<'a>(
+    i: usize,
+    entry: &'a Entry,
+    css_charset: &Regex,
+    xml_encoding: &Regex,
+) -> Option<TextResponse<'a>> {
+    let response = &entry.response;
+    let mime_type = response.mime_type()?;
+    let kind = ResourceKind::from_mime(&mime_type);
+    let textual = mime_type.starts_with("text/")
+        || matches!(
+            kind,
+            ResourceKind::Document | ResourceKind::Script | ResourceKind::Data
+        );
+    if !textual {
+        return None;
+    }
+
+    let mut checked = TextResponse {
+        request_num: i + 1,
+        url: &entry.request.url,
+        kind: Some(kind),
+        header_charset: response.header("content-type").and_then(header_charset),
+        content_language: response
+            .header("content-language")
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty()),
+        ..Default::default()
+    };
+
+    let body = response.content.as_ref().and_then(|c| c.decoded());
+    if let Some(body) = &body {
+        checked.bom = bom(body);
+        let text = String::from_utf8_lossy(body);
+        match kind {
+            ResourceKind::Document => {
+                let prescan = &text[..text.floor_char_boundary(META_PRESCAN_BYTES)];
+                checked.body_charset = meta_charset(prescan);
+                if checked.body_charset.is_none() {
+                    if let Some(late) = meta_charset(&text) {
+                        checked.problems.push(format!(
+                            "<meta charset={}> is past the first {} bytes, so browsers miss it",
+                            late, META_PRESCAN_BYTES
+                        ));
+                    }
+                }
+                checked.html_lang = asset_refs::tags(&text, "html")
+                    .first()
+                    .and_then(|attributes| attributes.get("lang"))
+                    .map(|l| l.trim().to_string())
+                    .filter(|l| !l.is_empty());
+            }
+            ResourceKind::Stylesheet => {
+                checked.body_charset = css_charset
+                    .captures(&text)
+                    .map(|captures| normalise_charset(&captures[1]));
+            }
+            _ if mime_type.ends_with("xml") => {
+                checked.body_charset = xml_encoding
+                    .captures(&text)
+                    .map(|captures| normalise_charset(&captures[1]));
+            }
+            _ => {}
+        }
+    }
+
+    // JSON is always UTF-8 (RFC 8259), so needs no label
+    let json = mime_type.ends_with("json");
+    let non_ascii = body.as_ref().is_some_and(|body| !body.is_ascii());
+    if checked.charset().is_none() && !json && (kind == ResourceKind::Document || non_ascii) {
+        checked
+            .problems
+            .push("no charset declared, so browsers have to guess".to_string());
+    }
+
+    let declared: Vec<(&str, &str)> = [
+        ("BOM", checked.bom),
+        ("header", checked.header_charset.as_deref()),
+        ("markup", checked.body_charset.as_deref()),
+    ]
+    .into_iter()
+    .filter_map(|(source, charset)| Some((source, charset?)))
+    .collect();
+    if let Some((first_source, first)) = declared.first() {
+        for (source, charset) in &declared[1..] {
+            if charset != first {
+                checked.problems.push(format!(
+                    "{} says {} but {} says {}",
+                    first_source, first, source, charset
+                ));
+            }
+        }
+    }
+
+    if let (Some("utf-8"), Some(body)) = (checked.charset(), &body) {
+        if std::str::from_utf8(body).is_err() {
+            checked
+                .problems
+                .push("declared UTF-8 but the body isn't valid UTF-8".to_string());
+        }
+    }
+
+    if let (Some(header), Some(html)) = (&checked.content_language, &checked.html_lang) {
+        // the header can list several languages, any of which may match the markup
+        let matches = header
+            .split(',')
+            .any(|language| primary_language(language) == primary_language(html));
+        if !matches {
+            checked.problems.push(format!(
+                "Content-Language is {} but <html lang> is {}",
+                header, html
+            ));
+        }
+    }
+    if kind == ResourceKind::Document && checked.language().is_none() {
+        checked
+            .problems
+            .push("no language declared in Content-Language or <html lang>".to_string());
+    }
+
+    Some(checked)
+}
+
+/// Collect the charset and language each text response declares, and what's missing or
+/// contradictory about them.
+pub fn find_text_responses<'a>(har: &'a Har) -> Vec<TextResponse<'a>> {
+    let css_charset =
+        Regex::new(r#"^(?:\x{feff})?@charset\s+["']([^"']+)["']"#).expect("valid regex");
+    let xml_encoding = Regex::new(r#"^(?:\x{feff})?<\?xml[^>]*\bencoding\s*=\s*["']([^"']+)["']"#)
+        .expect("valid regex");
+    har.log
+        .entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.request.url.starts_with("http"))
+        .filter_map(|(i, entry)| check_entry(i, entry, &css_charset, &xml_encoding))
+        .collect()
+}
+
+fn print_counts(heading: &str, counts: &BTreeMap<String, usize>) {
+    println!("{}:", heading.bold().blue());
+    let mut counts: Vec<(&String, &usize)> = counts.iter().collect();
+    counts.sort_by_key(|(_, count)| std::cmp::Reverse(**count));
+    for (name, count) in counts {
+        println!("    {}: {}", name, count);
+    }
+    println!();
+}
+
+pub fn i18n(har: &Har) {
+    let responses = find_text_responses(har);
+    if responses.is_empty() {
+        println!("{}", "No text responses found.".yellow());
+        return;
+    }
+
+    let mut charsets: BTreeMap<String, usize> = BTreeMap::new();
+    let mut languages: BTreeMap<String, usize> = BTreeMap::new();
+    for response in &responses {
+        let charset = response.charset().unwrap_or("(none)").to_string();
+        *charsets.entry(charset).or_default() += 1;
+        if let Some(language) = response.language() {
+            *languages.entry(language.to_string()).or_default() += 1;
+        }
+    }
+    print_counts("Charsets", &charsets);
+    if !languages.is_empty() {
+        print_counts("Languages", &languages);
+    }
+
+    let problems: Vec<&TextResponse> = responses
+        .iter()
+        .filter(|r| !r.problems.is_empty())
+        .collect();
+    if !problems.is_empty() {
+        println!("{}:", "Problems".bold().blue());
+        for response in &problems {
+            let kind = response.kind.map(|k| k.to_string()).unwrap_or_default();
+            println!("    #{} {} ({}):", response.request_num, response.url, kind);
+            for problem in &response.problems {
+                println!("        {}", problem.yellow());
+            }
+        }
+        println!();
+    }
+
+    check::report(problems.len());
+    println!(
+        "{}: {} text responses in {} charsets and {} languages, {} with problems",
+        "Summary".bold(),
+        responses.len(),
+        charsets.len(),
+        languages.len(),
+        problems.len()
+    );
+}
@@ -0,0 +1,244 @@
+use anyhow::{Context, Result};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use chrono::{DateTime, Utc};
+use flate2::read::{DeflateDecoder, MultiGzDecoder, ZlibDecoder};
+use serde_json::Map;
+use std::{fs, io::Read, path::Path};
+use url::Url;
+
+use crate::har::{
+    Cache, Content, Creator, Entry, Har, Header, Log, PostData, QueryString, Request, Response,
+    Timing,
+};
+
+// decoded bodies larger than this are kept encoded, rather than letting a small compressed body
+// blow up in memory
+const MAX_DECODED_BYTES: u64 = 256 << 20;
+
+/// A single request/response pair recorded or read from another capture format.
+pub struct Exchange {
+    pub started: DateTime<Utc>,
+    pub method: String,
+    pub url: Url,
+    pub request_version: String,
+    pub request_headers: Vec<(String, String)>,
+    pub request_headers_size: Option<i64>,
+    pub request_body: Vec<u8>,
+    pub status: u16,
+    pub status_text: String,
+    pub response_version: String,
+    pub response_headers: Vec<(String, String)>,
+    pub response_body: Vec<u8>,
+    pub send_ms: f64,
+    pub wait_ms: f64,
+    pub receive_ms: f64,
+    pub server_ip: Option<String>,
+}
+
+/// Get the value of the first header matching `name`, case-insensitively.
+pub fn header_value<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// Whether a body starts with a zlib header: the deflate method and a checksum over both bytes.
+fn is_zlib(body: &[u8]) -> bool {
+    match body {
+        [cmf, flg, ..] => cmf & 0x0f == 8 && u16::from_be_bytes([*cmf, *flg]) % 31 == 0,
+        _ => false,
+    }
+}
+
+/// Undo a `Content-Encoding`, applied in the order listed, `None` for encodings that can't be
+/// undone or a body that doesn't decode.
+fn decode_body(body: &[u8], content_encoding: &str) -> Option<Vec<u8>> {
+    let mut decoded = body.to_vec();
+    for encoding in content_encoding.rsplit(',').map(str::trim) {
+        let reader: Box<dyn Read + '_> = match &*encoding.to_lowercase() {
+            "" | "identity" => continue,
+            "gzip" | "x-gzip" => Box::new(MultiGzDecoder::new(&decoded[..])),
+            // servers disagree on whether deflate has the zlib wrapper, so both are tried
+            "deflate" if is_zlib(&decoded) => Box::new(ZlibDecoder::new(&decoded[..])),
+            "deflate" => Box::new(DeflateDecoder::new(&decoded[..])),
+            "br" => Box::new(brotli::Decompressor::new(&decoded[..], 4096)),
+            _ => return None,
+        };
+        let mut out = Vec::new();
+        reader
+            .take(MAX_DECODED_BYTES + 1)
+            .read_to_end(&mut out)
+            .ok()?;
+        if out.len() as u64 > MAX_DECODED_BYTES {
+            return None;
+        }
+        decoded = out;
+    }
+    Some(decoded)
+}
+
+fn to_har_headers(headers: Vec<(String, String)>) -> Vec<Header<'static>> {
+    headers
+        .into_iter()
+        .map(|(name, value)| Header {
+            name: name.into(),
+            value: value.into(),
+            comment: None,
+            extra: Map::new(),
+        })
+        .collect()
+}
+
+impl Exchange {
+    pub fn into_entry(self) -> Entry<'static> {
+        let post_data = (!self.request_body.is_empty()).then(|| PostData {
+            mime_type: header_value(&self.request_headers, "content-type")
+                .unwrap_or("")
+                .to_string()
+                .into(),
+            params: None,
+            text: String::from_utf8_lossy(&self.request_body)
+                .into_owned()
+                .into(),
+            comment: None,
+            extra: Map::new(),
+        });
+
+        let response_mime =
+            header_value(&self.response_headers, "content-type").map(|m| m.to_string().into());
+        let redirect_url = header_value(&self.response_headers, "location")
+            .unwrap_or("")
+            .to_string()
+            .into();
+
+        // the HAR holds the decoded body, as browsers record it, with the encoded length as its
+        // bodySize; bodies that can't be decoded are kept as they were sent
+        let content_encoding =
+            header_value(&self.response_headers, "content-encoding").unwrap_or_default();
+        let decoded = decode_body(&self.response_body, content_encoding);
+        let still_encoded = decoded.is_none();
+        let decoded = decoded.unwrap_or_else(|| self.response_body.clone());
+        // keep text readable in the HAR, anything else is base64 encoded
+        let (text, encoding) = match std::str::from_utf8(&decoded) {
+            Ok(text) if !still_encoded => (text.to_string(), None),
+            _ => (BASE64_STANDARD.encode(&decoded), Some("base64".to_string())),
+        };
+        let compression = decoded.len() as i64 - self.response_body.len() as i64;
+
+        let query_string = self
+            .url
+            .query_pairs()
+            .map(|(name, value)| QueryString {
+                name: name.into_owned().into(),
+                value: value.into_owned().into(),
+                comment: None,
+                extra: Map::new(),
+            })
+            .collect();
+
+        Entry {
+            pageref: None,
+            started_date_time: self.started.fixed_offset().into(),
+            time: self.send_ms + self.wait_ms + self.receive_ms,
+            request: Request {
+                method: self.method.into(),
+                url: self.url.to_string().into(),
+                http_version: self.request_version.into(),
+                cookies: Vec::new(),
+                headers: to_har_headers(self.request_headers),
+                query_string,
+                post_data,
+                headers_size: self.request_headers_size,
+                body_size: self.request_body.len() as i64,
+                comment: None,
+                extra: Map::new(),
+            },
+            response: Response {
+                status: self.status,
+                status_text: self.status_text.into(),
+                http_version: self.response_version.into(),
+                cookies: Vec::new(),
+                headers: to_har_headers(self.response_headers),
+                redirect_url,
+                content: Some(Content {
+                    size: Some(decoded.len() as i64),
+                    compression: (compression > 0).then_some(compression),
+                    mime_type: response_mime,
+                    text: Some(text.into()),
+                    encoding: encoding.map(Into::into),
+                    comment: None,
+                    extra: Map::new(),
+                }),
+                headers_size: Some(-1),
+                body_size: self.response_body.len() as i64,
+                comment: None,
+                transfer_size: None,
+                fetched_via_service_worker: None,
+                service_worker_response_source: None,
+                extra: Map::new(),
+            },
+            cache: Cache {
+                before_request: None,
+                after_request: None,
+                comment: None,
+                extra: Map::new(),
+            },
+            timings: Some(Timing {
+                blocked: None,
+                dns: None,
+                connect: None,
+                send: self.send_ms,
+                wait: self.wait_ms,
+                receive: self.receive_ms,
+                ssl: None,
+                comment: None,
+                extra: Map::new(),
+            }),
+            server_ip_address: self.server_ip.map(Into::into),
+            connection: None,
+            comment: None,
+            from_cache: None,
+            was_pushed: None,
+            extra: Map::new(),
+        }
+    }
+}
+
+/// Convert a unix timestamp in (fractional) seconds.
+pub fn timestamp(secs: f64) -> DateTime<Utc> {
+    DateTime::from_timestamp_micros((secs * 1_000_000.0) as i64).unwrap_or_default()
+}
+
+fn build_har(entries: Vec<Entry<'static>>) -> Har<'static> {
+    Har {
+        log: Log {
+            version: "1.2".into(),
+            creator: Creator {
+                name: "harper".into(),
+                version: env!("CARGO_PKG_VERSION").into(),
+                comment: None,
+                extra: Map::new(),
+            },
+            browser: None,
+            pages: None,
+            entries,
+            comment: None,
+            extra: Map::new(),
+        },
+        extra: Map::new(),
+    }
+}
+
+/// Write the HAR to `output`, or stdout if not given.
+pub fn write_har(entries: Vec<Entry<'static>>, output: Option<&Path>) -> Result<()> {
+    let json = serde_json::to_string_pretty(&build_har(entries))?;
+
+    match output {
+        Some(path) => fs::write(path, json).with_context(|| format!("Failed to write {:?}", path)),
+        None => {
+            println!("{}", json);
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use anyhow::{Context, Result};
+use base64::Engine;
+use colored::Colorize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+use crate::{
+    format,
+    har::{BASE64_LENIENT, Entry, Har},
+    mime,
+};
+
+// the MIME type of a data URI that doesn't give one
+const DEFAULT_DATA_MIME: &str = "text/plain";
+
+// characters of a data URI to show before cutting it short
+const PREVIEW_CHARS: usize = 60;
+
+#[derive(Debug)]
+pub struct DataUri {
+    pub mime: String,
+    // `None` if the payload doesn't decode
+    pub decoded: Option<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub struct InlineRequest<'a> {
+    pub request_num: usize,
+    pub entry: &'a Entry<'a>,
+    pub mime: String,
+    // the payload, from the URL for data URIs and the captured body for blob URLs
+    pub payload: Option<Vec<u8>>,
+    // how much of the URL the payload took up, 0 for blob URLs
+    pub url_bytes: usize,
+}
+
+fn percent_decode(data: &str) -> Vec<u8> {
+    let bytes = data.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    decoded
+}
+
+/// Parse a `data:[<mediatype>][;base64],<data>` URI.
+pub fn parse_data_uri(url: &str) -> Option<DataUri> {
+    let rest = url
+        .get(..5)?
+        .eq_ignore_ascii_case("data:")
+        .then(|| &url[5..])?;
+    let (header, data) = rest.split_once(',')?;
+    let mut params = header.split(';');
+    let mime = params
+        .next()
+        .map(mime::essence)
+        .filter(|m| !m.is_empty())
+        .unwrap_or_else(|| DEFAULT_DATA_MIME.to_string());
+    let base64 = params.any(|p| p.trim().eq_ignore_ascii_case("base64"));
+
+    let decoded = if base64 {
+        let cleaned: String = String::from_utf8_lossy(&percent_decode(data))
+            .split_whitespace()
+            .collect();
+        BASE64_LENIENT.decode(cleaned).ok()
+    } else {
+        Some(percent_decode(data))
+    };
+    Some(DataUri { mime, decoded })
+}
+
+/// Find the `data:` and `blob:` requests in the HAR.
+pub fn find_inline<'a>(har: &'a Har) -> (Vec<InlineRequest<'a>>, Vec<InlineRequest<'a>>) {
+    let mut data = Vec::new();
+    let mut blobs = Vec::new();
+    for (i, entry) in har.log.entries.iter().enumerate() {
+        let url = &entry.request.url;
+        if let Some(uri) = parse_data_uri(url) {
+            data.push(InlineRequest {
+                request_num: i + 1,
+                entry,
+                mime: uri.mime,
+                payload: uri.decoded,
+                url_bytes: url.len(),
+            });
+        } else if url
+            .get(..5)
+            .is_some_and(|s| s.eq_ignore_ascii_case("blob:"))
+        {
+            blobs.push(InlineRequest {
+                request_num: i + 1,
+                entry,
+                mime: entry
+                    .response
+                    .mime_type()
+                    .map(|m| mime::essence(&m))
+                    .unwrap_or_default(),
+                payload: entry.response.content.as_ref().and_then(|c| c.decoded()),
+                url_bytes: 0,
+            });
+        }
+    }
+    (data, blobs)
+}
+
+fn payload_bytes(request: &InlineRequest) -> u64 {
+    request.payload.as_ref().map_or(0, |p| p.len() as u64)
+}
+
+/// A file extension for a MIME type, i.e. `image/svg+xml` -> `svg`.
+pub fn extension(mime: &str) -> &str {
+    match mime {
+        "image/jpeg" => "jpg",
+        "image/svg+xml" => "svg",
+        "image/x-icon" | "image/vnd.microsoft.icon" => "ico",
+        "text/plain" => "txt",
+        "text/javascript" | "application/javascript" => "js",
+        "application/octet-stream" | "" => "bin",
+        _ => {
+            let subtype = mime.rsplit('/').next().unwrap_or(mime);
+            let subtype = subtype.rsplit('+').next().unwrap_or(subtype);
+            let subtype = subtype.trim_start_matches("x-");
+            if subtype.chars().all(|c| c.is_ascii_alphanumeric()) && !subtype.is_empty() {
+                subtype
+            } else {
+                "bin"
+            }
+        }
+    }
+}
+
+/// Write each distinct payload to `dir` as `<request number>.<extension>`, returning how many
+/// were written.
+pub fn extract(requests: &[&InlineRequest], dir: &Path) -> Result<usize> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    let mut written = BTreeSet::new();
+    for request in requests {
+        let Some(payload) = &request.payload else {
+            continue;
+        };
+        if !written.insert(payload) {
+            continue;
+        }
+        let path = dir.join(format!(
+            "{}.{}",
+            request.request_num,
+            extension(&request.mime)
+        ));
+        fs::write(&path, payload).with_context(|| format!("Failed to write {:?}", path))?;
+    }
+    Ok(written.len())
+}
+
+fn preview(url: &str) -> String {
+    if url.chars().count() <= PREVIEW_CHARS {
+        return url.to_string();
+    }
+    let cut: String = url.chars().take(PREVIEW_CHARS).collect();
+    format!("{}...", cut)
+}
+
+fn print_mime_types(requests: &[InlineRequest]) {
+    // MIME type -> (requests, decoded bytes)
+    let mut mime_types: BTreeMap<&str, (usize, u64)> = BTreeMap::new();
+    for request in requests {
+        let (count, bytes) = mime_types.entry(&request.mime).or_default();
+        *count += 1;
+        *bytes += payload_bytes(request);
+    }
+    let mut mime_types: Vec<_> = mime_types.into_iter().collect();
+    mime_types.sort_by(|a, b| b.1.1.cmp(&a.1.1).then_with(|| a.0.cmp(b.0)));
+    for (mime, (count, bytes)) in mime_types {
+        let mime = if mime.is_empty() { "unknown" } else { mime };
+        println!("    {}: {} requests, {}", mime, count, format::bytes(bytes));
+    }
+}
+
+pub fn inline_urls(har: &Har, top: usize, extract_dir: Option<&Path>) -> Result<()> {
+    let (data, blobs) = find_inline(har);
+    if data.is_empty() && blobs.is_empty() {
+        println!("{}", "No data: or blob: requests found.".green());
+        return Ok(());
+    }
+
+    let data_bytes: u64 = data.iter().map(payload_bytes).sum();
+    let url_bytes: usize = data.iter().map(|r| r.url_bytes).sum();
+    let distinct: BTreeSet<&str> = data.iter().map(|r| r.entry.request.url.as_ref()).collect();
+    let undecodable = data.iter().filter(|r| r.payload.is_none()).count();
+    if !data.is_empty() {
+        println!("{}:", "data: URIs by MIME type".bold().blue());
+        print_mime_types(&data);
+        println!();
+
+        let mut largest: Vec<&InlineRequest> = data.iter().collect();
+        largest.sort_by_key(|r| std::cmp::Reverse(payload_bytes(r)));
+        let mut seen = BTreeSet::new();
+        println!("{}:", "Largest data: URIs".bold().blue());
+        for request in largest
+            .iter()
+            .filter(|r| seen.insert(r.entry.request.url.as_ref()))
+            .take(top)
+        {
+            let size = match &request.payload {
+                Some(payload) => format::bytes(payload.len() as u64),
+                None => "undecodable".red().to_string(),
+            };
+            println!(
+                "    #{} {} ({}): {}",
+                request.request_num,
+                size,
+                request.mime,
+                preview(&request.entry.request.url).dimmed()
+            );
+        }
+        println!();
+    }
+
+    if !blobs.is_empty() {
+        println!("{}:", "blob: URLs by MIME type".bold().blue());
+        print_mime_types(&blobs);
+        let uncaptured = blobs.iter().filter(|r| r.payload.is_none()).count();
+        if uncaptured > 0 {
+            println!(
+                "    {}",
+                format!("{} without a captured body", uncaptured).dimmed()
+            );
+        }
+        println!();
+    }
+
+    if let Some(dir) = extract_dir {
+        let requests: Vec<&InlineRequest> = data.iter().chain(&blobs).collect();
+        let written = extract(&requests, dir)?;
+        println!(
+            "{} {} payloads to {:?}",
+            "Extracted".green().bold(),
+            written,
+            dir
+        );
+        println!();
+    }
+
+    println!(
+        "{}: {} data: requests ({} distinct, {} undecodable) inlining {} in {} of URLs, {} blob: requests",
+        "Summary".bold(),
+        data.len(),
+        distinct.len(),
+        undecodable,
+        format::bytes(data_bytes),
+        format::bytes(url_bytes as u64),
+        blobs.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,187 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use maxminddb::{Reader, geoip2};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeSet, HashMap},
+    net::IpAddr,
+    path::PathBuf,
+};
+use tracing::warn;
+
+use crate::{dirs, har::Har, offline};
+
+use super::dns;
+
+const COUNTRY_DB: &str = "GeoLite2-Country.mmdb";
+const ASN_DB: &str = "GeoLite2-ASN.mmdb";
+
+#[derive(Debug, Default)]
+pub struct IpInfo {
+    pub requests: usize,
+    pub hosts: BTreeSet<String>,
+    pub country: Option<String>,
+    pub network: Option<String>,
+    pub ptr: Option<String>,
+}
+
+pub struct GeoDatabases {
+    country: Option<Reader<Vec<u8>>>,
+    asn: Option<Reader<Vec<u8>>>,
+}
+
+impl GeoDatabases {
+    /// Open the given databases, falling back to the ones in the data directory.
+    pub fn open(country_db: Option<PathBuf>, asn_db: Option<PathBuf>) -> Result<Self> {
+        let geoip_dir = dirs::get_data_dir("geoip")?;
+
+        let open = |path: Option<PathBuf>, default: &str| -> Result<Option<Reader<Vec<u8>>>> {
+            match path {
+                Some(path) => Reader::open_readfile(&path)
+                    .map(Some)
+                    .with_context(|| format!("Failed to open GeoIP database: {:?}", path)),
+                None => {
+                    let path = geoip_dir.join(default);
+                    Ok(Reader::open_readfile(path).ok())
+                }
+            }
+        };
+
+        let dbs = Self {
+            country: open(country_db, COUNTRY_DB)?,
+            asn: open(asn_db, ASN_DB)?,
+        };
+
+        if dbs.country.is_none() && dbs.asn.is_none() {
+            warn!(
+                "no GeoIP databases found, place {} and {} in {:?} or pass them explicitly",
+                COUNTRY_DB, ASN_DB, geoip_dir
+            );
+        }
+
+        Ok(dbs)
+    }
+
+    /// Look up the ISO country code of an IP.
+    pub fn country(&self, ip: IpAddr) -> Option<String> {
+        let record: geoip2::Country = self.country.as_ref()?.lookup(ip).ok()?;
+        record
+            .country
+            .or(record.registered_country)
+            .and_then(|c| c.iso_code)
+            .map(String::from)
+    }
+
+    /// Look up the autonomous system of an IP, formatted as `AS<number> <organisation>`.
+    pub fn network(&self, ip: IpAddr) -> Option<String> {
+        let record: geoip2::Asn = self.asn.as_ref()?.lookup(ip).ok()?;
+        match (
+            record.autonomous_system_number,
+            record.autonomous_system_organization,
+        ) {
+            (Some(asn), Some(org)) => Some(format!("AS{} {}", asn, org)),
+            (Some(asn), None) => Some(format!("AS{}", asn)),
+            (None, Some(org)) => Some(org.to_string()),
+            (None, None) => None,
+        }
+    }
+}
+
+pub async fn get_ip_info(
+    har: &Har<'_>,
+    dbs: &GeoDatabases,
+    reverse: bool,
+) -> Result<HashMap<IpAddr, IpInfo>> {
+    let mut infos: HashMap<IpAddr, IpInfo> = HashMap::new();
+
+    for entry in &har.log.entries {
+        let Some(ip) = entry.server_ip() else {
+            continue;
+        };
+
+        let info = infos.entry(ip).or_default();
+        info.requests += 1;
+        if let Some(host) = entry.request.host() {
+            info.hosts.insert(host);
+        }
+    }
+
+    if reverse && offline::is_offline() {
+        offline::notice("reverse lookups are skipped.");
+    }
+    let resolver = if reverse && !offline::is_offline() {
+        Some(dns::build_resolver()?)
+    } else {
+        None
+    };
+
+    for (ip, info) in infos.iter_mut() {
+        info.country = dbs.country(*ip);
+        info.network = dbs.network(*ip);
+
+        if let Some(resolver) = &resolver {
+            if let Ok(resp) = resolver.reverse_lookup(*ip).await {
+                info.ptr = resp
+                    .iter()
+                    .next()
+                    .map(|name| name.to_string().trim_end_matches('.').to_string());
+            }
+        }
+    }
+
+    Ok(infos)
+}
+
+pub async fn print_ip_info(
+    har: &Har<'_>,
+    country_db: Option<PathBuf>,
+    asn_db: Option<PathBuf>,
+    reverse: bool,
+) -> Result<()> {
+    let dbs = GeoDatabases::open(country_db, asn_db)?;
+    let infos = get_ip_info(har, &dbs, reverse).await?;
+
+    let mut ips: Vec<(&IpAddr, &IpInfo)> = infos.iter().collect();
+    ips.sort_by_key(|(ip, info)| (Reverse(info.requests), **ip));
+
+    let unknown = "Unknown".to_string();
+    let mut countries: HashMap<&String, usize> = HashMap::new();
+    let mut networks: HashMap<&String, usize> = HashMap::new();
+
+    for (ip, info) in &ips {
+        println!("{} ({}):", ip.to_string().bold().blue(), info.requests);
+        println!(
+            "    Hosts: {}",
+            info.hosts.iter().cloned().collect::<Vec<_>>().join(", ")
+        );
+
+        let country = info.country.as_ref().unwrap_or(&unknown);
+        let network = info.network.as_ref().unwrap_or(&unknown);
+        println!("    Country: {}", country.cyan());
+        println!("    Network: {}", network.cyan());
+
+        if reverse {
+            match &info.ptr {
+                Some(ptr) => println!("    PTR: {}", ptr.cyan()),
+                None => println!("    PTR: {}", "No PTR record".yellow()),
+            }
+        }
+
+        *countries.entry(country).or_insert(0) += info.requests;
+        *networks.entry(network).or_insert(0) += info.requests;
+        println!();
+    }
+
+    for (title, counts) in [("Countries", countries), ("Networks", networks)] {
+        let mut counts: Vec<(&String, usize)> = counts.into_iter().collect();
+        counts.sort_by_key(|(name, count)| (Reverse(*count), name.to_string()));
+
+        println!("{}:", title.bold().green());
+        for (name, count) in counts {
+            println!("    {}: {}", name, count);
+        }
+        println!();
+    }
+
+    Ok(())
+}
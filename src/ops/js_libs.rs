@@ -0,0 +1,236 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use regex::Regex;
+use serde::Deserialize;
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::PathBuf,
+};
+
+use crate::{check, har::Har, mime::ResourceKind};
+
+const BUNDLED_DB: &str = include_str!("../../data/js_libraries.json");
+
+// retire.js placeholder for the version capture group
+const VERSION_PLACEHOLDER: &str = "§§version§§";
+const VERSION_PATTERN: &str = r"[0-9][0-9.a-z_\-]+";
+// lazy variant so that suffixes like `.min.js` aren't swallowed into the version
+const VERSION_PATTERN_LAZY: &str = r"[0-9][0-9.a-z_\-]+?";
+
+#[derive(Debug, Deserialize)]
+struct Library {
+    #[serde(default)]
+    extractors: Extractors,
+    #[serde(default)]
+    vulnerabilities: Vec<Vulnerability>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Extractors {
+    #[serde(default)]
+    uri: Vec<String>,
+    #[serde(default)]
+    filecontent: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Vulnerability {
+    at_or_above: Option<String>,
+    below: Option<String>,
+    severity: Option<String>,
+    #[serde(default)]
+    identifiers: Identifiers,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+struct Identifiers {
+    #[serde(default)]
+    cve: Vec<String>,
+    #[serde(rename = "summary")]
+    summary: Option<String>,
+}
+
+struct Matcher {
+    name: String,
+    uri: Vec<Regex>,
+    filecontent: Vec<Regex>,
+    vulnerabilities: Vec<Vulnerability>,
+}
+
+fn compile(patterns: &[String]) -> Vec<Regex> {
+    patterns
+        .iter()
+        .filter_map(|p| {
+            let version = if p.ends_with(&format!("({})", VERSION_PLACEHOLDER)) {
+                VERSION_PATTERN
+            } else {
+                VERSION_PATTERN_LAZY
+            };
+            Regex::new(&p.replace(VERSION_PLACEHOLDER, version)).ok()
+        })
+        .collect()
+}
+
+fn load_matchers(db: Option<PathBuf>) -> Result<Vec<Matcher>> {
+    let content = match db {
+        Some(path) => fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read library database: {:?}", path))?,
+        None => BUNDLED_DB.to_string(),
+    };
+
+    let libraries: HashMap<String, Library> =
+        serde_json::from_str(&content).context("Failed to parse library database")?;
+
+    let mut matchers: Vec<Matcher> = libraries
+        .into_iter()
+        .map(|(name, lib)| Matcher {
+            name,
+            uri: compile(&lib.extractors.uri),
+            filecontent: compile(&lib.extractors.filecontent),
+            vulnerabilities: lib.vulnerabilities,
+        })
+        .collect();
+    matchers.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(matchers)
+}
+
+fn version_part(part: &str) -> (u64, &str) {
+    let digits = part
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(part.len());
+    (part[..digits].parse().unwrap_or(0), &part[digits..])
+}
+
+/// Compare dotted versions numerically, with pre-release suffixes sorting first.
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let a_parts: Vec<&str> = a.split(['.', '-']).collect();
+    let b_parts: Vec<&str> = b.split(['.', '-']).collect();
+
+    for i in 0..a_parts.len().max(b_parts.len()) {
+        let (a_num, a_rest) = version_part(a_parts.get(i).unwrap_or(&"0"));
+        let (b_num, b_rest) = version_part(b_parts.get(i).unwrap_or(&"0"));
+
+        let ord = a_num.cmp(&b_num).then_with(|| match (a_rest, b_rest) {
+            ("", "") => Ordering::Equal,
+            ("", _) => Ordering::Greater,
+            (_, "") => Ordering::Less,
+            _ => a_rest.cmp(b_rest),
+        });
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+
+    Ordering::Equal
+}
+
+impl Vulnerability {
+    fn affects(&self, version: &str) -> bool {
+        let above = self
+            .at_or_above
+            .as_ref()
+            .is_none_or(|min| compare_versions(version, min) != Ordering::Less);
+        let below = self
+            .below
+            .as_ref()
+            .is_none_or(|max| compare_versions(version, max) == Ordering::Less);
+        above && below
+    }
+}
+
+fn extract_version(regexes: &[Regex], haystack: &str) -> Option<String> {
+    regexes
+        .iter()
+        .find_map(|re| re.captures(haystack)?.get(1))
+        .map(|m| m.as_str().trim_end_matches(['.', '-']).to_string())
+}
+
+pub fn js_libs(har: &Har, db: Option<PathBuf>) -> Result<()> {
+    let matchers = load_matchers(db)?;
+
+    // (library, version) -> urls it was detected in
+    let mut detected: BTreeMap<(&str, String), BTreeSet<&str>> = BTreeMap::new();
+
+    for entry in &har.log.entries {
+        let url = &*entry.request.url;
+        let is_script = entry.response.resource_kind() == ResourceKind::Script;
+        let body = if is_script {
+            entry
+                .response
+                .content
+                .as_ref()
+                .and_then(|c| c.decoded_text())
+        } else {
+            None
+        };
+
+        for matcher in &matchers {
+            let version = extract_version(&matcher.uri, url).or_else(|| {
+                body.as_ref()
+                    .and_then(|body| extract_version(&matcher.filecontent, body))
+            });
+
+            if let Some(version) = version {
+                detected
+                    .entry((&matcher.name, version))
+                    .or_default()
+                    .insert(url);
+            }
+        }
+    }
+
+    if detected.is_empty() {
+        println!("{}", "No known libraries detected.".green());
+        return Ok(());
+    }
+
+    let mut vulnerable = 0;
+    for ((name, version), urls) in &detected {
+        println!("{} {}:", name.bold().blue(), version.cyan());
+        for url in urls {
+            println!("    {}", url);
+        }
+
+        let matcher = matchers.iter().find(|m| m.name == *name).unwrap();
+        let vulns: Vec<&Vulnerability> = matcher
+            .vulnerabilities
+            .iter()
+            .filter(|v| v.affects(version))
+            .collect();
+
+        if !vulns.is_empty() {
+            vulnerable += 1;
+        }
+
+        for vuln in vulns {
+            let ids = if vuln.identifiers.cve.is_empty() {
+                "No CVE".to_string()
+            } else {
+                vuln.identifiers.cve.join(", ")
+            };
+            println!(
+                "{} [{}] {}: {}",
+                "Vulnerable".red(),
+                vuln.severity.as_deref().unwrap_or("unknown").yellow(),
+                ids,
+                vuln.identifiers.summary.as_deref().unwrap_or("")
+            );
+        }
+        println!();
+    }
+
+    check::report(vulnerable);
+    println!(
+        "{}: {} libraries detected, {} with known vulnerabilities",
+        "Summary".bold(),
+        detected.len(),
+        vulnerable
+    );
+
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use colored::Colorize;
+
+use crate::{
+    domain, format,
+    har::{Entry, Har, Page},
+    mime::ResourceKind,
+};
+
+// how many render-blocking candidates to list per page
+const MAX_BLOCKING: usize = 5;
+
+// Chrome's priorities for async and deferred scripts, which don't block rendering
+const NON_BLOCKING_PRIORITIES: [&str; 2] = ["Low", "VeryLow"];
+
+#[derive(Debug, Clone)]
+pub struct Asset<'a> {
+    pub request_num: usize,
+    pub entry: &'a Entry<'a>,
+    pub kind: ResourceKind,
+    // when the download finished, in milliseconds after the page started
+    pub finished: f64,
+    pub third_party: bool,
+}
+
+#[derive(Debug)]
+pub struct PageAssets<'a> {
+    pub page: &'a Page<'a>,
+    pub largest: Option<Asset<'a>>,
+    pub blocking: Vec<Asset<'a>>,
+}
+
+/// Whether Chrome loaded the request at a priority that doesn't block rendering.
+fn low_priority(entry: &Entry) -> bool {
+    entry
+        .extra
+        .get("_priority")
+        .and_then(|p| p.as_str())
+        .is_some_and(|p| NON_BLOCKING_PRIORITIES.contains(&p))
+}
+
+/// Find each page's largest image, font or script downloaded before `onLoad`, which likely drove
+/// its Largest Contentful Paint, and the stylesheets and synchronous scripts fetched before
+/// `onContentLoad` that may have blocked rendering, slowest first.
+pub fn page_assets<'a>(har: &'a Har) -> Vec<PageAssets<'a>> {
+    let tld_extractor = domain::build_tld_extractor();
+    let Some(pages) = &har.log.pages else {
+        return Vec::new();
+    };
+
+    let mut results = Vec::new();
+    for page in pages {
+        let entries: Vec<(usize, &Entry)> = har
+            .log
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.pageref.as_deref() == Some(&*page.id))
+            .map(|(i, entry)| (i + 1, entry))
+            .collect();
+
+        let site = entries
+            .iter()
+            .find(|(_, entry)| entry.response.resource_kind() == ResourceKind::Document)
+            .and_then(|(_, entry)| entry.request.host())
+            .and_then(|host| domain::registrable_domain(&host, &tld_extractor))
+            .map(|site| domain::FirstParty::for_site(&site));
+
+        let on_load = page.page_timings.on_load.filter(|t| *t >= 0.0);
+        let on_content_load = page
+            .page_timings
+            .on_content_load
+            .filter(|t| *t >= 0.0)
+            .or(on_load);
+
+        let mut largest: Option<Asset> = None;
+        let mut blocking = Vec::new();
+        for (request_num, entry) in entries {
+            let kind = entry.response.resource_kind();
+            let started = (*entry.started_date_time - *page.started_date_time).num_milliseconds();
+            let asset = Asset {
+                request_num,
+                entry,
+                finished: started as f64 + entry.time.max(0.0),
+                third_party: match (&site, entry.request.host()) {
+                    (Some(site), Some(host)) => !site.contains_host(&host, &tld_extractor),
+                    (Some(_), None) => true,
+                    (None, _) => false,
+                },
+                kind,
+            };
+
+            let before = |limit: Option<f64>| limit.is_none_or(|limit| asset.finished <= limit);
+
+            let may_block = matches!(kind, ResourceKind::Script | ResourceKind::Stylesheet);
+            if may_block && before(on_content_load) && !low_priority(entry) {
+                blocking.push(asset.clone());
+            }
+
+            let may_paint = matches!(
+                kind,
+                ResourceKind::Image | ResourceKind::Font | ResourceKind::Script
+            );
+            let bytes = entry.response.body_bytes();
+            let is_larger = largest
+                .as_ref()
+                .is_none_or(|l| bytes > l.entry.response.body_bytes());
+            if may_paint && before(on_load) && bytes > 0 && is_larger {
+                largest = Some(asset);
+            }
+        }
+        blocking.sort_by(|a, b| b.finished.total_cmp(&a.finished));
+
+        results.push(PageAssets {
+            page,
+            largest,
+            blocking,
+        });
+    }
+    results
+}
+
+fn print_asset(asset: &Asset) {
+    println!(
+        "        #{} {} {} ({}, finished at {:.0} ms){}",
+        asset.request_num,
+        asset.kind,
+        asset.entry.request.url.cyan(),
+        format::bytes(asset.entry.response.body_bytes()),
+        asset.finished,
+        if asset.third_party {
+            format!(" {}", "third-party".yellow())
+        } else {
+            String::new()
+        }
+    );
+}
+
+pub fn lcp(har: &Har) {
+    let pages = page_assets(har);
+    if pages.is_empty() {
+        println!("{}", "No pages found.".green());
+        return;
+    }
+
+    let mut third_party_blocking = 0;
+    for page in &pages {
+        println!("{} ({}):", page.page.id.bold().blue(), page.page.title);
+        if let Some(on_load) = page.page.page_timings.on_load.filter(|t| *t >= 0.0) {
+            println!("    onLoad: {:.0} ms", on_load);
+        }
+
+        println!("    {}:", "Likely LCP asset".bold());
+        match &page.largest {
+            Some(asset) => print_asset(asset),
+            None => println!("        None found before onLoad"),
+        }
+
+        println!("    {}:", "Render-blocking candidates".bold());
+        if page.blocking.is_empty() {
+            println!("        {}", "None".green());
+        }
+        for asset in page.blocking.iter().take(MAX_BLOCKING) {
+            print_asset(asset);
+        }
+        if page.blocking.len() > MAX_BLOCKING {
+            println!(
+                "        ... and {} more",
+                page.blocking.len() - MAX_BLOCKING
+            );
+        }
+        third_party_blocking += page.blocking.iter().filter(|a| a.third_party).count();
+        println!();
+    }
+
+    println!(
+        "{}: {} pages, {} render-blocking candidates, {} from third parties",
+        "Summary".bold(),
+        pages.len(),
+        pages.iter().map(|p| p.blocking.len()).sum::<usize>(),
+        third_party_blocking
+    );
+}
@@ -1,20 +1,145 @@
-use std::collections::HashSet;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    net::IpAddr,
+};
 
-use crate::Har;
-use url::Url;
+use chrono::{DateTime, FixedOffset};
+use colored::Colorize;
+use tldextract::TldExtractor;
+
+use crate::{Har, domain, lenient_url};
 
 pub fn list_domains(har: &Har) -> Vec<String> {
     let mut urls: HashSet<String> = HashSet::new();
     for entry in &har.log.entries {
-        let url_str = &entry.request.url;
-
-        if let Some(host) = match Url::parse(url_str) {
-            Ok(parsed_url) => parsed_url.host_str().map(|x| x.to_string()),
-            Err(_) => continue,
-        } {
+        if let Some(host) = lenient_url::host(&entry.request.url) {
             urls.insert(host);
         }
     }
 
-    urls.into_iter().collect()
+    let mut urls: Vec<String> = urls.into_iter().collect();
+    urls.sort_by_key(|x| sort_key(x));
+    urls
+}
+
+/// Sort names grouped by domain, with IP literals after them in address order.
+fn sort_key(host: &str) -> (Option<IpAddr>, String) {
+    (domain::ip_literal(host), host.chars().rev().collect())
+}
+
+#[derive(Debug, Default)]
+pub struct DomainInfo {
+    pub domain: String,
+    pub count: usize,
+    pub first_seen: Option<DateTime<FixedOffset>>,
+    pub last_seen: Option<DateTime<FixedOffset>>,
+    pub ips: BTreeSet<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct ListOptions {
+    pub counts: bool,
+    pub first_seen: bool,
+    pub last_seen: bool,
+    pub ips: bool,
+    pub etld1: bool,
+}
+
+impl ListOptions {
+    /// Whether any column beyond the bare domain was asked for.
+    pub fn enriched(&self) -> bool {
+        self.counts || self.first_seen || self.last_seen || self.ips || self.etld1
+    }
+}
+
+/// Gather request counts, first and last request times and server IPs per domain.
+pub fn domain_inventory(har: &Har) -> Vec<DomainInfo> {
+    let mut domains: BTreeMap<String, DomainInfo> = BTreeMap::new();
+    for entry in &har.log.entries {
+        let Some(host) = lenient_url::host(&entry.request.url) else {
+            continue;
+        };
+        let info = domains.entry(host.clone()).or_insert_with(|| DomainInfo {
+            domain: host,
+            ..Default::default()
+        });
+
+        info.count += 1;
+        let time = *entry.started_date_time;
+        info.first_seen = Some(info.first_seen.map_or(time, |t| t.min(time)));
+        info.last_seen = Some(info.last_seen.map_or(time, |t| t.max(time)));
+        if let Some(ip) = entry.server_ip() {
+            info.ips.insert(ip.to_string());
+        }
+    }
+
+    let mut domains: Vec<DomainInfo> = domains.into_values().collect();
+    domains.sort_by_key(|x| sort_key(&x.domain));
+    domains
+}
+
+fn format_time(time: Option<DateTime<FixedOffset>>) -> String {
+    time.map(|t| t.format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn print_rows(domains: &[&DomainInfo], options: &ListOptions, indent: &str) {
+    let names: Vec<String> = domains
+        .iter()
+        .map(|d| domain::display_host(&d.domain))
+        .collect();
+    let width = names.iter().map(|n| n.chars().count()).max().unwrap_or(0);
+    for (info, name) in domains.iter().zip(&names) {
+        let mut line = format!("{}{:<width$}", indent, name, width = width);
+        if options.counts {
+            line.push_str(&format!("  {:>6}", info.count));
+        }
+        if options.first_seen {
+            line.push_str(&format!("  {}", format_time(info.first_seen)));
+        }
+        if options.last_seen {
+            line.push_str(&format!("  {}", format_time(info.last_seen)));
+        }
+        if options.ips {
+            let ips: Vec<&str> = info.ips.iter().map(String::as_str).collect();
+            line.push_str(&format!("  {}", ips.join(", ")));
+        }
+        println!("{}", line.trim_end());
+    }
+}
+
+pub fn print_inventory(har: &Har, options: &ListOptions, tld_extractor: &TldExtractor) {
+    let domains = domain_inventory(har);
+
+    if !options.etld1 {
+        print_rows(&domains.iter().collect::<Vec<_>>(), options, "");
+        return;
+    }
+
+    let mut groups: BTreeMap<String, Vec<&DomainInfo>> = BTreeMap::new();
+    for info in &domains {
+        // IP literals have no registrable domain to group under
+        let group = match domain::ip_literal(&info.domain) {
+            Some(_) => info.domain.clone(),
+            None => domain::registrable_domain(&info.domain, tld_extractor)
+                .unwrap_or_else(|| info.domain.clone()),
+        };
+        groups.entry(group).or_default().push(info);
+    }
+
+    let mut names: Vec<&String> = groups.keys().collect();
+    names.sort_by_key(|x| sort_key(x));
+
+    for name in names {
+        let hosts = &groups[name];
+        let count: usize = hosts.iter().map(|d| d.count).sum();
+        println!(
+            "{} ({} hosts, {} requests):",
+            domain::display_host(name).bold().blue(),
+            hosts.len(),
+            count
+        );
+        print_rows(hosts, options, "    ");
+        println!();
+    }
 }
@@ -0,0 +1,139 @@
+use colored::Colorize;
+use rayon::prelude::*;
+use std::collections::BTreeMap;
+
+use crate::{
+    har::{Entry, Har},
+    mime::{self, ResourceKind},
+};
+
+// content a browser will execute or render as active content if it sniffs it
+const ACTIVE_TYPES: [&str; 5] = [
+    "text/html",
+    "image/svg+xml",
+    "application/x-msdownload",
+    "application/x-executable",
+    "application/x-mach-binary",
+];
+
+#[derive(Debug)]
+pub struct Mismatch<'a> {
+    pub url: &'a str,
+    pub declared: String,
+    pub sniffed: &'static str,
+    pub severe: bool,
+    pub nosniff: bool,
+}
+
+/// Whether a declared type is a reasonable label for the sniffed content.
+fn compatible(declared: &str, sniffed: &str) -> bool {
+    if declared == sniffed {
+        return true;
+    }
+
+    let declared_kind = ResourceKind::from_mime(declared);
+    match sniffed {
+        // JSON is also valid JavaScript, and is often served as text/plain
+        "application/json" => matches!(declared_kind, ResourceKind::Data | ResourceKind::Script),
+        "application/xml" => {
+            declared_kind == ResourceKind::Data || declared_kind == ResourceKind::Document
+        }
+        "text/html" => declared_kind == ResourceKind::Document,
+        "image/svg+xml" => declared.ends_with("xml"),
+        // font MIME types have never been consistent
+        _ if sniffed.starts_with("font/") => declared_kind == ResourceKind::Font,
+        "image/x-icon" => declared.contains("icon"),
+        _ => false,
+    }
+}
+
+fn check<'a>(entry: &'a Entry) -> Option<Mismatch<'a>> {
+    let body = entry.response.content.as_ref()?.decoded()?;
+    let sniffed = mime::sniff(&body)?;
+    let declared = entry
+        .response
+        .mime_type()
+        .filter(|m| !m.is_empty())
+        .unwrap_or_else(|| "(none)".to_string());
+
+    if compatible(&declared, sniffed) {
+        return None;
+    }
+
+    // executable or renderable content under a passive label is how smuggling works
+    let severe = ACTIVE_TYPES.contains(&sniffed)
+        && !matches!(
+            ResourceKind::from_mime(&declared),
+            ResourceKind::Document | ResourceKind::Script
+        );
+    let nosniff = entry
+        .response
+        .header("x-content-type-options")
+        .is_some_and(|v| v.trim().eq_ignore_ascii_case("nosniff"));
+
+    Some(Mismatch {
+        url: &entry.request.url,
+        declared,
+        sniffed,
+        severe,
+        nosniff,
+    })
+}
+
+pub fn find_mismatches<'a>(har: &'a Har) -> BTreeMap<String, Vec<Mismatch<'a>>> {
+    let mismatches: Vec<(String, Mismatch)> = har
+        .log
+        .entries
+        .par_iter()
+        // a data URI's type is whatever the page said it was
+        .filter(|entry| entry.request.url.starts_with("http"))
+        .filter_map(|entry| Some((entry.request.host()?, check(entry)?)))
+        .collect();
+
+    let mut domains: BTreeMap<String, Vec<Mismatch>> = BTreeMap::new();
+    for (host, mismatch) in mismatches {
+        domains.entry(host).or_default().push(mismatch);
+    }
+    domains
+}
+
+pub fn mime_sniff(har: &Har) {
+    let domains = find_mismatches(har);
+
+    if domains.is_empty() {
+        println!("{}", "No MIME type mismatches found.".green());
+        return;
+    }
+
+    let mut names: Vec<&String> = domains.keys().collect();
+    names.sort_by_key(|x| x.chars().rev().collect::<String>());
+
+    for name in names {
+        println!("{}:", name.bold().blue());
+        for mismatch in &domains[name] {
+            println!("    {}", mismatch.url);
+            let line = format!(
+                "declared {}, content is {}",
+                mismatch.declared, mismatch.sniffed
+            );
+            if mismatch.severe {
+                println!("        {}", line.red().bold());
+            } else {
+                println!("        {}", line.yellow());
+            }
+            if !mismatch.nosniff {
+                println!("        {}", "no X-Content-Type-Options: nosniff".dimmed());
+            }
+        }
+        println!();
+    }
+
+    let total: usize = domains.values().map(Vec::len).sum();
+    let severe = domains.values().flatten().filter(|m| m.severe).count();
+    println!(
+        "{}: {} mismatches, {} with active content under a passive type",
+        "Summary".bold(),
+        total,
+        severe
+    );
+}
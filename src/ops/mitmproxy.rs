@@ -0,0 +1,253 @@
+use anyhow::{Context, Result, anyhow, bail};
+use std::{collections::HashMap, fs, path::Path};
+use tracing::warn;
+use url::Url;
+
+use crate::{
+    har::Entry,
+    ops::import::{self, Exchange},
+};
+
+/// A value from mitmproxy's tnetstring serialisation.
+#[derive(Debug)]
+enum TNetString {
+    Bytes(Vec<u8>),
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bool,
+    Null,
+    List(Vec<TNetString>),
+    Dict(HashMap<String, TNetString>),
+}
+
+/// Parse one tnetstring (`<length>:<payload><type>`), returning it and the remaining input.
+fn parse_tnetstring(data: &[u8]) -> Result<(TNetString, &[u8])> {
+    let colon = data
+        .iter()
+        .take(12)
+        .position(|b| *b == b':')
+        .ok_or_else(|| anyhow!("Malformed tnetstring: missing length"))?;
+    let length: usize = std::str::from_utf8(&data[..colon])?
+        .parse()
+        .context("Malformed tnetstring length")?;
+
+    let payload_start = colon + 1;
+    let type_pos = payload_start + length;
+    if type_pos >= data.len() {
+        bail!("Truncated tnetstring");
+    }
+    let payload = &data[payload_start..type_pos];
+    let rest = &data[type_pos + 1..];
+
+    let value = match data[type_pos] {
+        b',' => TNetString::Bytes(payload.to_vec()),
+        b';' => TNetString::String(String::from_utf8(payload.to_vec())?),
+        b'#' => TNetString::Int(std::str::from_utf8(payload)?.parse()?),
+        b'^' => TNetString::Float(std::str::from_utf8(payload)?.parse()?),
+        b'!' => TNetString::Bool,
+        b'~' => TNetString::Null,
+        b']' => {
+            let mut items = Vec::new();
+            let mut remaining = payload;
+            while !remaining.is_empty() {
+                let (item, next) = parse_tnetstring(remaining)?;
+                items.push(item);
+                remaining = next;
+            }
+            TNetString::List(items)
+        }
+        b'}' => {
+            let mut dict = HashMap::new();
+            let mut remaining = payload;
+            while !remaining.is_empty() {
+                let (key, next) = parse_tnetstring(remaining)?;
+                let (value, next) = parse_tnetstring(next)?;
+                let key = key
+                    .as_str()
+                    .ok_or_else(|| anyhow!("Malformed tnetstring: non-string key"))?;
+                dict.insert(key, value);
+                remaining = next;
+            }
+            TNetString::Dict(dict)
+        }
+        t => bail!("Unknown tnetstring type '{}'", t as char),
+    };
+
+    Ok((value, rest))
+}
+
+impl TNetString {
+    fn get(&self, key: &str) -> Option<&TNetString> {
+        match self {
+            TNetString::Dict(dict) => dict.get(key),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<String> {
+        match self {
+            TNetString::Bytes(b) => Some(String::from_utf8_lossy(b).to_string()),
+            TNetString::String(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+
+    fn as_bytes(&self) -> Option<Vec<u8>> {
+        match self {
+            TNetString::Bytes(b) => Some(b.clone()),
+            TNetString::String(s) => Some(s.as_bytes().to_vec()),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            TNetString::Float(f) => Some(*f),
+            TNetString::Int(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
+    fn as_list(&self) -> &[TNetString] {
+        match self {
+            TNetString::List(items) => items,
+            _ => &[],
+        }
+    }
+
+    fn str_field(&self, key: &str) -> Option<String> {
+        self.get(key).and_then(|v| v.as_str())
+    }
+
+    fn f64_field(&self, key: &str) -> Option<f64> {
+        self.get(key).and_then(|v| v.as_f64())
+    }
+
+    fn headers(&self) -> Vec<(String, String)> {
+        self.get("headers")
+            .map(|h| h.as_list())
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|pair| match pair.as_list() {
+                [name, value] => Some((name.as_str()?, value.as_str()?)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+fn flow_url(request: &TNetString) -> Result<Url> {
+    let scheme = request.str_field("scheme").unwrap_or("http".to_string());
+    let host = request
+        .str_field("host")
+        .ok_or_else(|| anyhow!("Flow request has no host"))?;
+    let port = request.f64_field("port").map(|p| p as u16);
+    let path = request.str_field("path").unwrap_or("/".to_string());
+
+    // IPv6 addresses need brackets to be a valid authority
+    let host = if host.contains(':') && !host.starts_with('[') {
+        format!("[{}]", host)
+    } else {
+        host
+    };
+
+    let default_port = match scheme.as_str() {
+        "https" => 443,
+        _ => 80,
+    };
+    let authority = match port {
+        Some(port) if port != default_port => format!("{}:{}", host, port),
+        _ => host,
+    };
+
+    Url::parse(&format!("{}://{}{}", scheme, authority, path))
+        .with_context(|| format!("Invalid flow URL: {}://{}{}", scheme, authority, path))
+}
+
+fn flow_to_exchange(flow: &TNetString) -> Result<Option<Exchange>> {
+    if flow.str_field("type").as_deref() != Some("http") {
+        return Ok(None);
+    }
+
+    let request = flow
+        .get("request")
+        .ok_or_else(|| anyhow!("Flow has no request"))?;
+    // flows that errored before a response was received can't be represented
+    let Some(response) = flow
+        .get("response")
+        .filter(|r| !matches!(r, TNetString::Null))
+    else {
+        return Ok(None);
+    };
+
+    let request_start = request.f64_field("timestamp_start").unwrap_or(0.0);
+    let request_end = request.f64_field("timestamp_end").unwrap_or(request_start);
+    let response_start = response.f64_field("timestamp_start").unwrap_or(request_end);
+    let response_end = response
+        .f64_field("timestamp_end")
+        .unwrap_or(response_start);
+
+    // newer versions record the resolved address as `peername`, older ones as `ip_address`
+    let server_ip = flow.get("server_conn").and_then(|conn| {
+        conn.get("peername")
+            .or_else(|| conn.get("ip_address"))
+            .and_then(|addr| addr.as_list().first())
+            .and_then(|ip| ip.as_str())
+    });
+
+    Ok(Some(Exchange {
+        started: import::timestamp(request_start),
+        method: request.str_field("method").unwrap_or("GET".to_string()),
+        url: flow_url(request)?,
+        request_version: request
+            .str_field("http_version")
+            .unwrap_or("HTTP/1.1".to_string()),
+        request_headers: request.headers(),
+        request_headers_size: None,
+        request_body: request
+            .get("content")
+            .and_then(|c| c.as_bytes())
+            .unwrap_or_default(),
+        status: response.f64_field("status_code").unwrap_or(0.0) as u16,
+        status_text: response.str_field("reason").unwrap_or_default(),
+        response_version: response
+            .str_field("http_version")
+            .unwrap_or("HTTP/1.1".to_string()),
+        response_headers: response.headers(),
+        response_body: response
+            .get("content")
+            .and_then(|c| c.as_bytes())
+            .unwrap_or_default(),
+        send_ms: ((request_end - request_start) * 1000.0).max(0.0),
+        wait_ms: ((response_start - request_end) * 1000.0).max(0.0),
+        receive_ms: ((response_end - response_start) * 1000.0).max(0.0),
+        server_ip,
+    }))
+}
+
+/// Read a mitmproxy flow dump (as written by `mitmdump -w`) into HAR entries.
+pub fn import_flows(path: &Path) -> Result<Vec<Entry<'static>>> {
+    let data = fs::read(path).with_context(|| format!("Failed to read file: {:?}", path))?;
+
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    let mut remaining = &data[..];
+    while !remaining.is_empty() {
+        let (flow, next) = parse_tnetstring(remaining)
+            .with_context(|| format!("Failed to parse flow {}", entries.len() + skipped + 1))?;
+        remaining = next;
+
+        match flow_to_exchange(&flow)? {
+            Some(exchange) => entries.push(exchange.into_entry()),
+            None => skipped += 1,
+        }
+    }
+
+    if skipped > 0 {
+        warn!("skipped {} non-HTTP or incomplete flows", skipped);
+    }
+
+    entries.sort_by_key(|entry| *entry.started_date_time);
+    Ok(entries)
+}
@@ -1,8 +1,71 @@
+pub mod accept_mismatch;
+pub mod anonymize;
+pub mod assert;
+pub mod asset_refs;
+pub mod auth_flows;
+pub mod bandwidth;
+pub mod beacons;
 pub mod blocklist;
+pub mod budget;
+pub mod capture;
+pub mod completions;
+pub mod compression;
+pub mod consent;
+pub mod cookie_lineage;
+pub mod count;
+pub mod count_methods;
 pub mod count_requests;
 pub mod count_schemes;
 pub mod count_urls;
+pub mod csp;
+pub mod ct;
+pub mod data_flows;
+pub mod diff;
 pub mod dns;
+pub mod dns_security;
+pub mod duplicates;
+pub mod early_hints;
+pub mod entropy;
+pub mod expect_absent;
+pub mod export;
 pub mod filter;
+pub mod fingerprinting;
+pub mod fonts;
+pub mod forms;
+pub mod grpc;
+pub mod hashes;
+pub mod homographs;
+pub mod hsts;
+pub mod i18n;
+pub mod import;
+pub mod inline_urls;
+pub mod ip_info;
+pub mod js_libs;
+pub mod lcp;
 pub mod list_domains;
+pub mod mime_sniff;
+pub mod mitmproxy;
+pub mod multipart;
+pub mod output;
+pub mod pcap;
+pub mod pii;
+pub mod polling;
+pub mod protocols;
+pub mod referrer_audit;
+pub mod resource_hints;
+pub mod reverse_dns;
+pub mod score;
+pub mod scrub;
 pub mod search_for;
+pub mod selector;
+pub mod servers;
+pub mod service_worker;
+pub mod session_tokens;
+pub mod sort;
+pub mod split;
+pub mod streams;
+pub mod timeline;
+pub mod tls_audit;
+pub mod trend;
+pub mod uploads;
+pub mod whois;
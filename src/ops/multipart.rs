@@ -0,0 +1,264 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::{collections::BTreeSet, fs, path::Path};
+
+use crate::{
+    format,
+    har::{Entry, Har, PostData},
+};
+
+use super::inline_urls;
+
+#[derive(Debug)]
+pub struct Part<'a> {
+    pub name: Option<String>,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub body: &'a str,
+}
+
+impl Part<'_> {
+    pub fn is_file(&self) -> bool {
+        self.filename.is_some()
+    }
+}
+
+#[derive(Debug)]
+pub struct MultipartRequest<'a> {
+    pub request_num: usize,
+    pub entry: &'a Entry<'a>,
+    pub parts: Vec<Part<'a>>,
+    // whether the exporter recorded the fields in `params` as well
+    pub has_params: bool,
+}
+
+/// A parameter of a header value, i.e. `boundary` in `multipart/form-data; boundary=x`, with any
+/// quotes removed.
+fn header_param(value: &str, name: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+/// The boundary of a multipart body, from its MIME type or, when an exporter dropped it, the
+/// body's first delimiter line.
+pub fn boundary(mime_type: &str, text: &str) -> Option<String> {
+    if !mime_type.trim().to_lowercase().starts_with("multipart/") {
+        return None;
+    }
+    header_param(mime_type, "boundary")
+        .filter(|b| !b.is_empty())
+        .or_else(|| {
+            let first_line = text.trim_start().lines().next()?;
+            let boundary = first_line.strip_prefix("--")?.trim_end();
+            (!boundary.is_empty()).then(|| boundary.to_string())
+        })
+}
+
+fn parse_part(section: &str) -> Part<'_> {
+    // a part's headers end at its first blank line, which may be LF-only when re-encoded
+    let (headers, body) = match (section.find("\r\n\r\n"), section.find("\n\n")) {
+        (Some(crlf), Some(lf)) if lf < crlf => (&section[..lf], &section[lf + 2..]),
+        (Some(crlf), _) => (&section[..crlf], &section[crlf + 4..]),
+        (None, Some(lf)) => (&section[..lf], &section[lf + 2..]),
+        (None, None) => ("", section),
+    };
+
+    let mut part = Part {
+        name: None,
+        filename: None,
+        content_type: None,
+        body,
+    };
+    for line in headers.lines() {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match &*name.trim().to_lowercase() {
+            "content-disposition" => {
+                part.name = header_param(value, "name");
+                part.filename = header_param(value, "filename");
+            }
+            "content-type" => part.content_type = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    part
+}
+
+/// Split a multipart body into its parts by boundary. An unterminated body, as when the capture
+/// cut it short, yields the parts up to where it stops.
+pub fn parse<'a>(text: &'a str, boundary: &str) -> Vec<Part<'a>> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut sections = text.split(delimiter.as_str());
+    // anything before the first delimiter is preamble
+    sections.next();
+    for section in sections {
+        // the closing delimiter is followed by `--`
+        if section.starts_with("--") {
+            break;
+        }
+        let section = section
+            .strip_prefix("\r\n")
+            .or_else(|| section.strip_prefix('\n'))
+            .unwrap_or(section);
+        let section = section
+            .strip_suffix("\r\n")
+            .or_else(|| section.strip_suffix('\n'))
+            .unwrap_or(section);
+        parts.push(parse_part(section));
+    }
+    parts
+}
+
+/// The parts of a request body, `None` if it isn't multipart.
+pub fn parts<'a>(post_data: &'a PostData) -> Option<Vec<Part<'a>>> {
+    let boundary = boundary(&post_data.mime_type, &post_data.text)?;
+    Some(parse(&post_data.text, &boundary))
+}
+
+/// Find the requests with a multipart body and parse their parts.
+pub fn find_multipart<'a>(har: &'a Har) -> Vec<MultipartRequest<'a>> {
+    har.log
+        .entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, entry)| {
+            let post_data = entry.request.post_data.as_ref()?;
+            let parts = parts(post_data)?;
+            Some(MultipartRequest {
+                request_num: i + 1,
+                entry,
+                parts,
+                has_params: post_data.params.as_ref().is_some_and(|p| !p.is_empty()),
+            })
+        })
+        .collect()
+}
+
+/// Keep only the characters of an uploaded file's name that are safe in a path.
+fn safe_filename(filename: &str) -> String {
+    let base = filename.rsplit(['/', '\\']).next().unwrap_or(filename);
+    base.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+/// Write each distinct uploaded file to `dir` as `<request number>-<filename>`, or
+/// `<request number>-<part number>-<filename>` where a request sends two files of the same name,
+/// returning how many were written.
+pub fn extract(requests: &[MultipartRequest], dir: &Path) -> Result<usize> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+    let mut written = BTreeSet::new();
+    for request in requests {
+        let mut names = BTreeSet::new();
+        for (j, part) in request.parts.iter().enumerate() {
+            let Some(filename) = &part.filename else {
+                continue;
+            };
+            if !written.insert(part.body) {
+                continue;
+            }
+            let mut filename = safe_filename(filename);
+            if filename.is_empty() {
+                let mime = part.content_type.as_deref().unwrap_or_default();
+                filename = format!("part{}.{}", j + 1, inline_urls::extension(mime));
+            }
+            let mut name = format!("{}-{}", request.request_num, filename);
+            if !names.insert(name.clone()) {
+                name = format!("{}-{}-{}", request.request_num, j + 1, filename);
+                names.insert(name.clone());
+            }
+            let path = dir.join(name);
+            fs::write(&path, part.body).with_context(|| format!("Failed to write {:?}", path))?;
+        }
+    }
+    Ok(written.len())
+}
+
+pub fn multipart(har: &Har, extract_dir: Option<&Path>) -> Result<()> {
+    let requests = find_multipart(har);
+    if requests.is_empty() {
+        println!("{}", "No multipart request bodies found.".yellow());
+        return Ok(());
+    }
+
+    let mut part_count = 0;
+    let mut files = 0;
+    let mut bytes = 0;
+    for request in &requests {
+        println!(
+            "#{} {} {}:",
+            request.request_num,
+            request.entry.request.method.bold(),
+            request.entry.request.url.blue()
+        );
+        if request.parts.is_empty() {
+            println!("    {}", "no parts found for the boundary".yellow());
+        }
+        for part in &request.parts {
+            part_count += 1;
+            bytes += part.body.len() as u64;
+            let name = part.name.as_deref().unwrap_or("(unnamed)");
+            let size = format::bytes(part.body.len() as u64);
+            let content_type = part
+                .content_type
+                .as_deref()
+                .map(|t| format!(", {}", t))
+                .unwrap_or_default();
+            match &part.filename {
+                Some(filename) => {
+                    files += 1;
+                    println!(
+                        "    {} = file {} ({}{})",
+                        name,
+                        filename.green(),
+                        size,
+                        content_type
+                    );
+                }
+                None => println!("    {} ({}{})", name, size, content_type),
+            }
+        }
+        if !request.has_params {
+            println!("    {}", "fields not recorded in params".dimmed());
+        }
+        println!();
+    }
+
+    if let Some(dir) = extract_dir {
+        let written = extract(&requests, dir)?;
+        println!(
+            "{} {} uploaded files to {:?}",
+            "Extracted".green().bold(),
+            written,
+            dir
+        );
+        println!();
+    }
+
+    let unrecorded = requests.iter().filter(|r| !r.has_params).count();
+    println!(
+        "{}: {} multipart requests with {} parts and {} files totalling {}, {} without params",
+        "Summary".bold(),
+        requests.len(),
+        part_count,
+        files,
+        format::bytes(bytes),
+        unrecorded
+    );
+    Ok(())
+}
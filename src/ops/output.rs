@@ -0,0 +1,90 @@
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::{Map, Value, ser::PrettyFormatter};
+
+use crate::har::Har;
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Projection {
+    /// The log's pages.
+    Pages,
+
+    /// Every entry.
+    Entries,
+
+    /// The request of every entry.
+    Requests,
+
+    /// The response of every entry.
+    Responses,
+
+    /// The timings of every entry.
+    Timings,
+
+    /// The URL of every entry.
+    Urls,
+}
+
+/// Rebuild every object with its keys in order, whether or not maps keep insertion order.
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut fields: Vec<(String, Value)> = map.into_iter().collect();
+            fields.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(
+                fields
+                    .into_iter()
+                    .map(|(k, v)| (k, sort_keys(v)))
+                    .collect::<Map<String, Value>>(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        other => other,
+    }
+}
+
+/// Print a value as JSON. Sorting goes through `Value`, otherwise fields keep the model's order.
+fn emit<T: Serialize>(value: &T, minify: bool, sort: bool) -> Result<()> {
+    if sort {
+        let value = sort_keys(serde_json::to_value(value)?);
+        return emit(&value, minify, false);
+    }
+
+    if minify {
+        println!("{}", serde_json::to_string(value)?);
+        return Ok(());
+    }
+
+    let mut buf = Vec::new();
+    let mut serializer =
+        serde_json::Serializer::with_formatter(&mut buf, PrettyFormatter::with_indent(b"    "));
+    value.serialize(&mut serializer)?;
+    println!("{}", String::from_utf8(buf)?);
+
+    Ok(())
+}
+
+pub fn output(har: &Har, only: Option<Projection>, minify: bool, sort: bool) -> Result<()> {
+    let entries = &har.log.entries;
+    match only {
+        None => emit(har, minify, sort),
+        Some(Projection::Pages) => emit(&har.log.pages, minify, sort),
+        Some(Projection::Entries) => emit(entries, minify, sort),
+        Some(Projection::Requests) => {
+            let requests: Vec<_> = entries.iter().map(|e| &e.request).collect();
+            emit(&requests, minify, sort)
+        }
+        Some(Projection::Responses) => {
+            let responses: Vec<_> = entries.iter().map(|e| &e.response).collect();
+            emit(&responses, minify, sort)
+        }
+        Some(Projection::Timings) => {
+            let timings: Vec<_> = entries.iter().map(|e| &e.timings).collect();
+            emit(&timings, minify, sort)
+        }
+        Some(Projection::Urls) => {
+            let urls: Vec<_> = entries.iter().map(|e| &e.request.url).collect();
+            emit(&urls, minify, sort)
+        }
+    }
+}
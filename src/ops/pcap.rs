@@ -0,0 +1,659 @@
+use anyhow::{Context, Result, bail};
+use std::{
+    collections::HashMap,
+    fs,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    path::Path,
+};
+use tracing::warn;
+use url::Url;
+
+use crate::{
+    har::Entry,
+    ops::import::{self, Exchange, header_value},
+};
+
+const PCAP_MAGIC_MICROS: u32 = 0xa1b2c3d4;
+const PCAP_MAGIC_NANOS: u32 = 0xa1b23c4d;
+const PCAPNG_SECTION_HEADER: u32 = 0x0a0d0d0a;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b3c4d;
+
+const PCAPNG_INTERFACE_DESCRIPTION: u32 = 1;
+const PCAPNG_SIMPLE_PACKET: u32 = 3;
+const PCAPNG_ENHANCED_PACKET: u32 = 6;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LOOP: u32 = 108;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+const TCP_SYN: u8 = 0x02;
+const TCP_ACK: u8 = 0x10;
+
+type Endpoint = (IpAddr, u16);
+
+struct Packet<'a> {
+    timestamp: f64,
+    link_type: u32,
+    data: &'a [u8],
+}
+
+struct TcpSegment<'a> {
+    src: Endpoint,
+    dst: Endpoint,
+    seq: u32,
+    flags: u8,
+    payload: &'a [u8],
+}
+
+#[derive(Default)]
+struct Direction {
+    syn_seq: Option<u32>,
+    segments: Vec<(u32, f64, Vec<u8>)>,
+}
+
+/// A reassembled byte stream and the capture time of each chunk within it.
+struct Stream {
+    data: Vec<u8>,
+    times: Vec<(usize, f64)>,
+}
+
+#[derive(Default)]
+struct Connection {
+    // the endpoint that sent the initial SYN, if it was captured
+    client: Option<Endpoint>,
+    directions: HashMap<Endpoint, Direction>,
+}
+
+struct Message {
+    head_size: usize,
+    headers: Vec<(String, String)>,
+    version: String,
+    body: Vec<u8>,
+    start: usize,
+    end: usize,
+}
+
+struct ParsedRequest {
+    method: String,
+    target: String,
+    message: Message,
+}
+
+struct ParsedResponse {
+    status: u16,
+    reason: String,
+    message: Message,
+}
+
+fn read_u16(data: &[u8], offset: usize, le: bool) -> Option<u16> {
+    let bytes: [u8; 2] = data.get(offset..offset + 2)?.try_into().ok()?;
+    Some(if le {
+        u16::from_le_bytes(bytes)
+    } else {
+        u16::from_be_bytes(bytes)
+    })
+}
+
+fn read_u32(data: &[u8], offset: usize, le: bool) -> Option<u32> {
+    let bytes: [u8; 4] = data.get(offset..offset + 4)?.try_into().ok()?;
+    Some(if le {
+        u32::from_le_bytes(bytes)
+    } else {
+        u32::from_be_bytes(bytes)
+    })
+}
+
+fn read_pcap(data: &[u8]) -> Result<Vec<Packet<'_>>> {
+    let (le, nanos) = match (read_u32(data, 0, true), read_u32(data, 0, false)) {
+        (Some(PCAP_MAGIC_MICROS), _) => (true, false),
+        (Some(PCAP_MAGIC_NANOS), _) => (true, true),
+        (_, Some(PCAP_MAGIC_MICROS)) => (false, false),
+        (_, Some(PCAP_MAGIC_NANOS)) => (false, true),
+        _ => bail!("Not a pcap file"),
+    };
+    let link_type = read_u32(data, 20, le).context("Truncated pcap header")?;
+    let divisor = if nanos { 1e9 } else { 1e6 };
+
+    let mut packets = Vec::new();
+    let mut offset = 24;
+    while offset + 16 <= data.len() {
+        let secs = read_u32(data, offset, le).unwrap_or(0);
+        let frac = read_u32(data, offset + 4, le).unwrap_or(0);
+        let length = read_u32(data, offset + 8, le).unwrap_or(0) as usize;
+        let start = offset + 16;
+        let Some(packet) = data.get(start..start + length) else {
+            break;
+        };
+
+        packets.push(Packet {
+            timestamp: secs as f64 + frac as f64 / divisor,
+            link_type,
+            data: packet,
+        });
+        offset = start + length;
+    }
+
+    Ok(packets)
+}
+
+/// Get the timestamp resolution of a pcapng interface from its `if_tsresol` option.
+fn interface_resolution(options: &[u8], le: bool) -> f64 {
+    let mut offset = 0;
+    while let (Some(code), Some(length)) = (
+        read_u16(options, offset, le),
+        read_u16(options, offset + 2, le),
+    ) {
+        let length = length as usize;
+        if code == 0 {
+            break;
+        }
+        if code == 9 && length == 1 {
+            let Some(&value) = options.get(offset + 4) else {
+                break;
+            };
+            return if value & 0x80 == 0 {
+                10f64.powi(-((value & 0x7f) as i32))
+            } else {
+                2f64.powi(-((value & 0x7f) as i32))
+            };
+        }
+        // options are padded to 32 bits
+        offset += 4 + length.div_ceil(4) * 4;
+    }
+    1e-6
+}
+
+fn read_pcapng(data: &[u8]) -> Result<Vec<Packet<'_>>> {
+    let mut packets = Vec::new();
+    // (link type, timestamp resolution) per interface in the current section
+    let mut interfaces: Vec<(u32, f64)> = Vec::new();
+    let mut le = true;
+    let mut offset = 0;
+
+    while offset + 12 <= data.len() {
+        let block_type = read_u32(data, offset, le).unwrap_or(0);
+
+        if block_type == PCAPNG_SECTION_HEADER {
+            le = read_u32(data, offset + 8, true) == Some(PCAPNG_BYTE_ORDER_MAGIC);
+            interfaces.clear();
+        }
+
+        let length = read_u32(data, offset + 4, le).context("Truncated pcapng block")? as usize;
+        if length < 12 || offset + length > data.len() {
+            break;
+        }
+        let body = &data[offset + 8..offset + length - 4];
+
+        match block_type {
+            PCAPNG_INTERFACE_DESCRIPTION => {
+                let link_type = read_u16(body, 0, le).unwrap_or(0) as u32;
+                let resolution = interface_resolution(body.get(8..).unwrap_or(&[]), le);
+                interfaces.push((link_type, resolution));
+            }
+            PCAPNG_ENHANCED_PACKET => {
+                let interface = read_u32(body, 0, le).unwrap_or(0) as usize;
+                let high = read_u32(body, 4, le).unwrap_or(0) as u64;
+                let low = read_u32(body, 8, le).unwrap_or(0) as u64;
+                let captured = read_u32(body, 12, le).unwrap_or(0) as usize;
+                let (link_type, resolution) =
+                    interfaces.get(interface).copied().unwrap_or((0, 1e-6));
+                if let Some(packet) = body.get(20..20 + captured) {
+                    packets.push(Packet {
+                        timestamp: ((high << 32) | low) as f64 * resolution,
+                        link_type,
+                        data: packet,
+                    });
+                }
+            }
+            PCAPNG_SIMPLE_PACKET => {
+                let (link_type, _) = interfaces.first().copied().unwrap_or((0, 1e-6));
+                packets.push(Packet {
+                    timestamp: 0.0,
+                    link_type,
+                    data: body.get(4..).unwrap_or(&[]),
+                });
+            }
+            _ => {}
+        }
+
+        offset += length;
+    }
+
+    Ok(packets)
+}
+
+/// Strip the link layer header, returning the IP packet.
+fn ip_payload(link_type: u32, data: &[u8]) -> Option<&[u8]> {
+    match link_type {
+        LINKTYPE_ETHERNET => {
+            let mut offset = 12;
+            let mut ethertype = read_u16(data, offset, false)?;
+            // skip any VLAN tags
+            while ethertype == 0x8100 || ethertype == 0x88a8 {
+                offset += 4;
+                ethertype = read_u16(data, offset, false)?;
+            }
+            if !matches!(ethertype, 0x0800 | 0x86dd) {
+                return None;
+            }
+            data.get(offset + 2..)
+        }
+        LINKTYPE_NULL | LINKTYPE_LOOP => data.get(4..),
+        LINKTYPE_RAW | LINKTYPE_IPV4 | LINKTYPE_IPV6 => Some(data),
+        LINKTYPE_LINUX_SLL => data.get(16..),
+        LINKTYPE_LINUX_SLL2 => data.get(20..),
+        _ => None,
+    }
+}
+
+fn parse_tcp(ip: &[u8]) -> Option<TcpSegment<'_>> {
+    let (src_ip, dst_ip, tcp): (IpAddr, IpAddr, &[u8]) = match ip.first()? >> 4 {
+        4 => {
+            let header_len = ((ip[0] & 0x0f) as usize) * 4;
+            let total_len = read_u16(ip, 2, false)? as usize;
+            if ip.get(9)? != &6 {
+                return None;
+            }
+            let src: [u8; 4] = ip.get(12..16)?.try_into().ok()?;
+            let dst: [u8; 4] = ip.get(16..20)?.try_into().ok()?;
+            // ethernet frames may be padded past the end of the IP packet
+            let end = total_len.min(ip.len());
+            (
+                Ipv4Addr::from(src).into(),
+                Ipv4Addr::from(dst).into(),
+                ip.get(header_len..end)?,
+            )
+        }
+        6 => {
+            let payload_len = read_u16(ip, 4, false)? as usize;
+            let src: [u8; 16] = ip.get(8..24)?.try_into().ok()?;
+            let dst: [u8; 16] = ip.get(24..40)?.try_into().ok()?;
+            let end = (40 + payload_len).min(ip.len());
+
+            // walk the extension headers until TCP is reached
+            let mut next_header = *ip.get(6)?;
+            let mut offset = 40;
+            while matches!(next_header, 0 | 43 | 60) {
+                next_header = *ip.get(offset)?;
+                offset += (*ip.get(offset + 1)? as usize + 1) * 8;
+            }
+            if next_header != 6 {
+                return None;
+            }
+            (
+                Ipv6Addr::from(src).into(),
+                Ipv6Addr::from(dst).into(),
+                ip.get(offset..end)?,
+            )
+        }
+        _ => return None,
+    };
+
+    let src_port = read_u16(tcp, 0, false)?;
+    let dst_port = read_u16(tcp, 2, false)?;
+    let seq = read_u32(tcp, 4, false)?;
+    let data_offset = ((tcp.get(12)? >> 4) as usize) * 4;
+    let flags = *tcp.get(13)?;
+
+    Some(TcpSegment {
+        src: (src_ip, src_port),
+        dst: (dst_ip, dst_port),
+        seq,
+        flags,
+        payload: tcp.get(data_offset..)?,
+    })
+}
+
+impl Direction {
+    /// Order the segments by sequence number, dropping retransmitted bytes.
+    fn reassemble(mut self) -> (Stream, bool) {
+        let base = self
+            .syn_seq
+            .map(|seq| seq.wrapping_add(1))
+            .or_else(|| self.segments.first().map(|(seq, _, _)| *seq))
+            .unwrap_or(0);
+
+        // offsets past 2^31 are segments from before the base, so are dropped
+        self.segments
+            .retain(|(seq, _, _)| seq.wrapping_sub(base) < 1 << 31);
+        self.segments
+            .sort_by_key(|(seq, ts, _)| (seq.wrapping_sub(base), ts.to_bits()));
+
+        let mut stream = Stream {
+            data: Vec::new(),
+            times: Vec::new(),
+        };
+        let mut gap = false;
+        for (seq, timestamp, payload) in self.segments {
+            let offset = seq.wrapping_sub(base) as usize;
+            let end = offset + payload.len();
+            if end <= stream.data.len() {
+                continue;
+            }
+            if offset > stream.data.len() {
+                gap = true;
+            }
+
+            let skip = stream.data.len().saturating_sub(offset);
+            stream.times.push((stream.data.len(), timestamp));
+            stream.data.extend_from_slice(&payload[skip..]);
+        }
+
+        (stream, gap)
+    }
+}
+
+impl Stream {
+    /// Get the capture time of the packet carrying the byte at `offset`.
+    fn time_at(&self, offset: usize) -> f64 {
+        let i = self.times.partition_point(|(start, _)| *start <= offset);
+        self.times
+            .get(i.saturating_sub(1))
+            .map(|(_, ts)| *ts)
+            .unwrap_or(0.0)
+    }
+}
+
+/// Decode a chunked body, returning it and the number of bytes consumed.
+fn decode_chunked(data: &[u8]) -> Option<(Vec<u8>, usize)> {
+    let mut body = Vec::new();
+    let mut offset = 0;
+    loop {
+        let line_end = offset + data.get(offset..)?.windows(2).position(|w| w == b"\r\n")?;
+        let size = std::str::from_utf8(&data[offset..line_end]).ok()?;
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        offset = line_end + 2;
+
+        if size == 0 {
+            // skip any trailers up to the blank line
+            loop {
+                let line_end =
+                    offset + data.get(offset..)?.windows(2).position(|w| w == b"\r\n")?;
+                let empty = line_end == offset;
+                offset = line_end + 2;
+                if empty {
+                    return Some((body, offset));
+                }
+            }
+        }
+
+        let end = offset.checked_add(size)?;
+        body.extend_from_slice(data.get(offset..end)?);
+        offset = end.checked_add(2)?;
+    }
+}
+
+fn to_headers(headers: &[httparse::Header]) -> Vec<(String, String)> {
+    headers
+        .iter()
+        .map(|h| {
+            (
+                h.name.to_string(),
+                String::from_utf8_lossy(h.value).to_string(),
+            )
+        })
+        .collect()
+}
+
+/// Read the body following a head, returning it and the offset of the end of the message.
+fn read_body(
+    data: &[u8],
+    start: usize,
+    headers: &[(String, String)],
+    read_to_end: bool,
+) -> Option<(Vec<u8>, usize)> {
+    let chunked = header_value(headers, "transfer-encoding")
+        .is_some_and(|te| te.to_lowercase().contains("chunked"));
+
+    if chunked {
+        let (body, consumed) = decode_chunked(&data[start..])?;
+        Some((body, start + consumed))
+    } else if let Some(length) = header_value(headers, "content-length") {
+        let length: usize = length.trim().parse().ok()?;
+        // keep what was captured of truncated bodies
+        let end = start
+            .checked_add(length)
+            .map_or(data.len(), |end| end.min(data.len()));
+        Some((data[start..end].to_vec(), end))
+    } else if read_to_end {
+        Some((data[start..].to_vec(), data.len()))
+    } else {
+        Some((Vec::new(), start))
+    }
+}
+
+fn version_string(version: Option<u8>) -> String {
+    format!("HTTP/1.{}", version.unwrap_or(1))
+}
+
+fn parse_requests(stream: &Stream) -> Vec<ParsedRequest> {
+    let data = &stream.data;
+    let mut requests = Vec::new();
+    let mut offset = 0;
+
+    while offset < data.len() {
+        let mut headers = [httparse::EMPTY_HEADER; 128];
+        let mut request = httparse::Request::new(&mut headers);
+        let Ok(httparse::Status::Complete(head_size)) = request.parse(&data[offset..]) else {
+            break;
+        };
+
+        let headers = to_headers(request.headers);
+        let Some((body, end)) = read_body(data, offset + head_size, &headers, false) else {
+            break;
+        };
+
+        requests.push(ParsedRequest {
+            method: request.method.unwrap_or("GET").to_string(),
+            target: request.path.unwrap_or("/").to_string(),
+            message: Message {
+                head_size,
+                version: version_string(request.version),
+                headers,
+                body,
+                start: offset,
+                end,
+            },
+        });
+        offset = end;
+    }
+
+    requests
+}
+
+fn parse_responses(stream: &Stream, requests: &[ParsedRequest]) -> Vec<ParsedResponse> {
+    let data = &stream.data;
+    let mut responses = Vec::new();
+    let mut offset = 0;
+
+    while offset < data.len() {
+        let mut headers = [httparse::EMPTY_HEADER; 128];
+        let mut response = httparse::Response::new(&mut headers);
+        let Ok(httparse::Status::Complete(head_size)) = response.parse(&data[offset..]) else {
+            break;
+        };
+
+        let status = response.code.unwrap_or(0);
+        let headers = to_headers(response.headers);
+        let is_head = requests
+            .get(responses.len())
+            .is_some_and(|r| r.method.eq_ignore_ascii_case("HEAD"));
+
+        let (body, end) = if is_head || status < 200 || status == 204 || status == 304 {
+            (Vec::new(), offset + head_size)
+        } else {
+            match read_body(data, offset + head_size, &headers, true) {
+                Some(body) => body,
+                None => break,
+            }
+        };
+
+        // interim responses like 100 Continue don't answer the request
+        if status >= 200 {
+            responses.push(ParsedResponse {
+                status,
+                reason: response.reason.unwrap_or("").to_string(),
+                message: Message {
+                    head_size,
+                    version: version_string(response.version),
+                    headers,
+                    body,
+                    start: offset,
+                    end,
+                },
+            });
+        }
+        offset = end;
+    }
+
+    responses
+}
+
+/// Pair up the HTTP requests and responses on a connection.
+fn connection_exchanges(connection: Connection, gaps: &mut usize) -> Vec<Exchange> {
+    let mut directions: Vec<(Endpoint, Stream)> = connection
+        .directions
+        .into_iter()
+        .map(|(endpoint, direction)| {
+            let (stream, gap) = direction.reassemble();
+            if gap {
+                *gaps += 1;
+            }
+            (endpoint, stream)
+        })
+        .collect();
+
+    // without the handshake, the client is the side that starts with a request
+    let client = connection.client.or_else(|| {
+        directions
+            .iter()
+            .find(|(_, stream)| {
+                let mut headers = [httparse::EMPTY_HEADER; 128];
+                httparse::Request::new(&mut headers)
+                    .parse(&stream.data)
+                    .is_ok_and(|s| s.is_complete())
+            })
+            .map(|(endpoint, _)| *endpoint)
+    });
+    let Some(client) = client else {
+        return Vec::new();
+    };
+
+    let Some(client_index) = directions.iter().position(|(e, _)| *e == client) else {
+        return Vec::new();
+    };
+    let (_, client_stream) = directions.swap_remove(client_index);
+    let Some((server, server_stream)) = directions.pop() else {
+        return Vec::new();
+    };
+
+    let requests = parse_requests(&client_stream);
+    let responses = parse_responses(&server_stream, &requests);
+
+    requests
+        .into_iter()
+        .zip(responses)
+        .filter_map(|(request, response)| {
+            let host = header_value(&request.message.headers, "host")
+                .map(String::from)
+                .unwrap_or_else(|| match server.0 {
+                    IpAddr::V6(ip) => format!("[{}]:{}", ip, server.1),
+                    IpAddr::V4(ip) => format!("{}:{}", ip, server.1),
+                });
+            let url = if request.target.starts_with("http://") {
+                Url::parse(&request.target).ok()?
+            } else {
+                Url::parse(&format!("http://{}{}", host, request.target)).ok()?
+            };
+
+            let request_start = client_stream.time_at(request.message.start);
+            let request_end = client_stream.time_at(request.message.end.saturating_sub(1));
+            let response_start = server_stream.time_at(response.message.start);
+            let response_end = server_stream.time_at(response.message.end.saturating_sub(1));
+
+            Some(Exchange {
+                started: import::timestamp(request_start),
+                method: request.method,
+                url,
+                request_version: request.message.version,
+                request_headers: request.message.headers,
+                request_headers_size: Some(request.message.head_size as i64),
+                request_body: request.message.body,
+                status: response.status,
+                status_text: response.reason,
+                response_version: response.message.version,
+                response_headers: response.message.headers,
+                response_body: response.message.body,
+                send_ms: ((request_end - request_start) * 1000.0).max(0.0),
+                wait_ms: ((response_start - request_end) * 1000.0).max(0.0),
+                receive_ms: ((response_end - response_start) * 1000.0).max(0.0),
+                server_ip: Some(server.0.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Reassemble the plaintext HTTP/1.x traffic in a pcap or pcapng capture into HAR entries.
+pub fn import_capture(path: &Path) -> Result<Vec<Entry<'static>>> {
+    let data = fs::read(path).with_context(|| format!("Failed to read file: {:?}", path))?;
+
+    let packets = if read_u32(&data, 0, true) == Some(PCAPNG_SECTION_HEADER) {
+        read_pcapng(&data)?
+    } else {
+        read_pcap(&data)?
+    };
+
+    // group segments by connection, keyed by the ordered pair of endpoints
+    let mut connections: HashMap<(Endpoint, Endpoint), Connection> = HashMap::new();
+    for packet in &packets {
+        let Some(segment) = ip_payload(packet.link_type, packet.data).and_then(parse_tcp) else {
+            continue;
+        };
+
+        let key = if segment.src < segment.dst {
+            (segment.src, segment.dst)
+        } else {
+            (segment.dst, segment.src)
+        };
+        let connection = connections.entry(key).or_default();
+        let direction = connection.directions.entry(segment.src).or_default();
+
+        if segment.flags & TCP_SYN != 0 {
+            direction.syn_seq = Some(segment.seq);
+            if segment.flags & TCP_ACK == 0 {
+                connection.client = Some(segment.src);
+            }
+        }
+
+        if !segment.payload.is_empty() {
+            direction
+                .segments
+                .push((segment.seq, packet.timestamp, segment.payload.to_vec()));
+        }
+    }
+
+    let mut entries = Vec::new();
+    let mut gaps = 0;
+    for connection in connections.into_values() {
+        entries.extend(
+            connection_exchanges(connection, &mut gaps)
+                .into_iter()
+                .map(Exchange::into_entry),
+        );
+    }
+
+    if gaps > 0 {
+        warn!(
+            "{} TCP streams had missing segments, some messages may be incomplete",
+            gaps
+        );
+    }
+
+    entries.sort_by_key(|entry| *entry.started_date_time);
+    Ok(entries)
+}
@@ -0,0 +1,230 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use rayon::prelude::*;
+use regex::Regex;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::PathBuf,
+};
+use url::{Url, form_urlencoded};
+
+use crate::{
+    check, format,
+    har::{Entry, Har},
+    mime::{self, ResourceKind},
+};
+
+const BUNDLED_PATTERNS: &str = include_str!("../../data/pii_patterns.toml");
+
+// matches of this class are only kept if they pass a Luhn check
+const CREDIT_CARD_CLASS: &str = "credit_card";
+
+pub struct Pattern {
+    class: String,
+    regex: Regex,
+}
+
+#[derive(Debug, Default)]
+pub struct PiiFindings {
+    pub locations: BTreeSet<&'static str>,
+    pub examples: BTreeSet<String>,
+    pub count: usize,
+}
+
+/// Load the bundled PII patterns, overridden or extended by a TOML file of `class = "regex"`.
+pub fn load_patterns(extra: Option<PathBuf>) -> Result<Vec<Pattern>> {
+    let mut classes: BTreeMap<String, String> =
+        toml::from_str(BUNDLED_PATTERNS).context("Failed to parse bundled PII patterns")?;
+
+    if let Some(path) = extra {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read patterns file: {:?}", path))?;
+        let user: BTreeMap<String, String> = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse patterns file: {:?}", path))?;
+        classes.extend(user);
+    }
+
+    classes
+        .into_iter()
+        .map(|(class, pattern)| {
+            let regex = Regex::new(&pattern)
+                .with_context(|| format!("Invalid pattern for class '{}'", class))?;
+            Ok(Pattern { class, regex })
+        })
+        .collect()
+}
+
+fn luhn_valid(number: &str) -> bool {
+    let digits: Vec<u32> = number.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| {
+            if i % 2 == 1 {
+                let doubled = d * 2;
+                if doubled > 9 { doubled - 9 } else { doubled }
+            } else {
+                *d
+            }
+        })
+        .sum();
+
+    sum.is_multiple_of(10)
+}
+
+/// Decode form fields into ` name=value` pairs, as the query string is.
+fn decode_form(form: &str) -> String {
+    form_urlencoded::parse(form.trim().as_bytes())
+        .map(|(name, value)| format!(" {}={}", name, value))
+        .collect()
+}
+
+/// Find the matches in one entry as (class, location, match), where the location is `url`,
+/// `header`, `body` or `response`.
+pub fn scan_entry<'p>(
+    entry: &Entry,
+    patterns: &'p [Pattern],
+) -> Vec<(&'p str, &'static str, String)> {
+    let mut haystacks: Vec<(&'static str, String)> = Vec::new();
+
+    // decode the URL so that i.e. `%40` in emails still matches
+    let url = match Url::parse(&entry.request.url) {
+        Ok(url) => {
+            let mut decoded = url.path().to_string();
+            for (name, value) in url.query_pairs() {
+                decoded.push_str(&format!(" {}={}", name, value));
+            }
+            decoded
+        }
+        Err(_) => entry.request.url.to_string(),
+    };
+    haystacks.push(("url", url));
+
+    for header in &entry.request.headers {
+        haystacks.push(("header", format!("{}: {}", header.name, header.value)));
+    }
+
+    if let Some(post_data) = &entry.request.post_data {
+        let mut body = if mime::essence(&post_data.mime_type) == "application/x-www-form-urlencoded"
+        {
+            decode_form(&post_data.text)
+        } else {
+            post_data.text.to_string()
+        };
+        // exporters record form fields in params too, mostly the same ones as in the body
+        for param in post_data.params.iter().flatten() {
+            let value = param.value.as_deref().unwrap_or_default();
+            let pair = decode_form(&format!("{}={}", param.name, value));
+            if !body.contains(pair.trim()) {
+                body.push_str(&pair);
+            }
+        }
+        haystacks.push(("body", body));
+    }
+
+    // only responses likely to carry user data, scripts are full of false positives
+    let kind = entry.response.resource_kind();
+    if matches!(kind, ResourceKind::Document | ResourceKind::Data) {
+        if let Some(text) = entry
+            .response
+            .content
+            .as_ref()
+            .and_then(|c| c.decoded_text())
+        {
+            haystacks.push(("response", text));
+        }
+    }
+
+    let mut matches = Vec::new();
+    for (location, haystack) in &haystacks {
+        for pattern in patterns {
+            for m in pattern.regex.find_iter(haystack) {
+                if pattern.class == CREDIT_CARD_CLASS && !luhn_valid(m.as_str()) {
+                    continue;
+                }
+                matches.push((pattern.class.as_str(), *location, m.as_str().to_string()));
+            }
+        }
+    }
+    matches
+}
+
+/// Scan the capture, returning findings keyed by (class, domain).
+fn scan(har: &Har, patterns: &[Pattern]) -> BTreeMap<(String, String), PiiFindings> {
+    let scanned: Vec<_> = har
+        .log
+        .entries
+        .par_iter()
+        .filter_map(|entry| Some((entry.request.host()?, scan_entry(entry, patterns))))
+        .collect();
+
+    let mut findings: BTreeMap<(String, String), PiiFindings> = BTreeMap::new();
+    for (host, matches) in scanned {
+        for (class, location, m) in matches {
+            let finding = findings
+                .entry((class.to_string(), host.clone()))
+                .or_default();
+            finding.locations.insert(location);
+            finding.examples.insert(format::redact(m.trim()));
+            finding.count += 1;
+        }
+    }
+
+    findings
+}
+
+pub fn pii(har: &Har, patterns: Option<PathBuf>) -> Result<()> {
+    let patterns = load_patterns(patterns)?;
+    let findings = scan(har, &patterns);
+
+    if findings.is_empty() {
+        println!("{}", "No PII found.".green());
+        return Ok(());
+    }
+
+    // group by class, then by the domain that received it
+    let mut by_class: BTreeMap<&str, Vec<(&String, &PiiFindings)>> = BTreeMap::new();
+    for ((class, domain), finding) in &findings {
+        by_class.entry(class).or_default().push((domain, finding));
+    }
+
+    for (class, mut domains) in by_class {
+        println!("{}:", class.bold().blue());
+        domains.sort_by_key(|(domain, _)| domain.chars().rev().collect::<String>());
+
+        for (domain, finding) in domains {
+            let locations: Vec<&str> = finding.locations.iter().copied().collect();
+            println!(
+                "    {} ({} in {})",
+                domain.cyan(),
+                finding.count,
+                locations.join(", ")
+            );
+            for example in &finding.examples {
+                println!("        {}", example);
+            }
+        }
+        println!();
+    }
+
+    check::report(findings.len());
+    let domains: BTreeSet<&String> = findings.keys().map(|(_, domain)| domain).collect();
+    println!(
+        "{}: {} PII classes found across {} domains",
+        "Summary".bold(),
+        findings
+            .keys()
+            .map(|(class, _)| class)
+            .collect::<BTreeSet<_>>()
+            .len(),
+        domains.len()
+    );
+
+    Ok(())
+}
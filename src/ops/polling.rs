@@ -0,0 +1,128 @@
+use colored::Colorize;
+use std::collections::BTreeMap;
+use url::Url;
+
+use crate::{
+    format,
+    har::{Entry, Har},
+};
+
+// requests closer together than this are the page loading, not polling
+const MIN_PERIOD_SECS: f64 = 0.5;
+
+#[derive(Debug)]
+pub struct PollingPattern {
+    pub method: String,
+    pub endpoint: String,
+    pub count: usize,
+    pub period: f64,
+    pub jitter: f64,
+    pub bytes: u64,
+    pub time_ms: f64,
+}
+
+fn start_secs(entry: &Entry) -> f64 {
+    entry.started_date_time.timestamp_millis() as f64 / 1000.0
+}
+
+/// Key entries by method and URL without the query, which often holds a cache-buster.
+fn endpoint(entry: &Entry) -> Option<(String, String)> {
+    let url = Url::parse(&entry.request.url).ok()?;
+    let host = url.host_str()?;
+    Some((
+        entry.request.method.to_string(),
+        format!("{}://{}{}", url.scheme(), host, url.path()),
+    ))
+}
+
+/// Find endpoints requested at least `min_count` times at intervals varying by at most
+/// `tolerance` (as a fraction of the mean interval).
+pub fn find_polling(har: &Har, min_count: usize, tolerance: f64) -> Vec<PollingPattern> {
+    let mut endpoints: BTreeMap<(String, String), Vec<(f64, &Entry)>> = BTreeMap::new();
+    for entry in &har.log.entries {
+        if let Some(key) = endpoint(entry) {
+            endpoints
+                .entry(key)
+                .or_default()
+                .push((start_secs(entry), entry));
+        }
+    }
+
+    let mut patterns = Vec::new();
+    for ((method, endpoint), mut requests) in endpoints {
+        if requests.len() < min_count.max(3) {
+            continue;
+        }
+        requests.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let intervals: Vec<f64> = requests.windows(2).map(|w| w[1].0 - w[0].0).collect();
+        let period = intervals.iter().sum::<f64>() / intervals.len() as f64;
+        if period < MIN_PERIOD_SECS {
+            continue;
+        }
+
+        let variance =
+            intervals.iter().map(|i| (i - period).powi(2)).sum::<f64>() / intervals.len() as f64;
+        let jitter = variance.sqrt();
+        if jitter > period * tolerance {
+            continue;
+        }
+
+        patterns.push(PollingPattern {
+            method,
+            endpoint,
+            count: requests.len(),
+            period,
+            jitter,
+            bytes: requests
+                .iter()
+                .map(|(_, e)| e.response.body_bytes() + e.request.body_size.max(0) as u64)
+                .sum(),
+            time_ms: requests.iter().map(|(_, e)| e.time.max(0.0)).sum(),
+        });
+    }
+
+    // chattiest first
+    patterns.sort_by(|a, b| a.period.total_cmp(&b.period));
+    patterns
+}
+
+pub fn polling(har: &Har, min_count: usize, tolerance: f64) {
+    let patterns = find_polling(har, min_count, tolerance);
+
+    if patterns.is_empty() {
+        println!("{}", "No polling patterns found.".green());
+        return;
+    }
+
+    for pattern in &patterns {
+        println!(
+            "{} {}:",
+            pattern.method.purple().bold(),
+            pattern.endpoint.bold().blue()
+        );
+        println!(
+            "    Every {:.2}s (jitter {:.2}s), {} requests, {:.1} per minute",
+            pattern.period,
+            pattern.jitter,
+            pattern.count,
+            60.0 / pattern.period
+        );
+        println!(
+            "    Overhead: {} transferred, {:.0}ms spent in requests",
+            format::bytes(pattern.bytes),
+            pattern.time_ms
+        );
+        println!();
+    }
+
+    let requests: usize = patterns.iter().map(|p| p.count).sum();
+    let bytes: u64 = patterns.iter().map(|p| p.bytes).sum();
+    println!(
+        "{}: {} polled endpoints, {} requests, {} transferred",
+        "Summary".bold(),
+        patterns.len(),
+        requests,
+        format::bytes(bytes)
+    );
+}
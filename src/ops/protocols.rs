@@ -0,0 +1,175 @@
+use colored::Colorize;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    net::IpAddr,
+};
+
+use crate::har::Har;
+
+// HTTP/1.x hosts with at least this many requests would gain from multiplexing
+const MIN_UPGRADE_REQUESTS: usize = 6;
+
+#[derive(Debug, Default)]
+pub struct HostProtocols {
+    pub versions: BTreeMap<String, usize>,
+    pub connections: BTreeSet<String>,
+    pub ips: BTreeSet<IpAddr>,
+    pub requests: usize,
+    pub https: bool,
+}
+
+impl HostProtocols {
+    fn only_http1(&self) -> bool {
+        self.versions.keys().all(|v| v.starts_with("HTTP/1"))
+    }
+
+    fn uses_http2(&self) -> bool {
+        self.versions.contains_key("HTTP/2")
+    }
+}
+
+/// Normalise the different spellings exporters use, i.e. `h2` and `HTTP/2.0` -> `HTTP/2`.
+pub fn normalise_version(version: &str) -> String {
+    let lower = version.trim().to_lowercase();
+    match lower.as_str() {
+        "http/1.0" => "HTTP/1.0".to_string(),
+        "http/1.1" => "HTTP/1.1".to_string(),
+        "h2" | "h2c" | "http/2" | "http/2.0" => "HTTP/2".to_string(),
+        _ if lower == "h3" || lower.starts_with("h3-") || lower.starts_with("http/3") => {
+            "HTTP/3".to_string()
+        }
+        "" => "unknown".to_string(),
+        _ => version.trim().to_string(),
+    }
+}
+
+pub fn find_protocols(har: &Har) -> BTreeMap<String, HostProtocols> {
+    let mut hosts: BTreeMap<String, HostProtocols> = BTreeMap::new();
+
+    for entry in &har.log.entries {
+        // data URIs never hit the network
+        if !entry.request.url.starts_with("http") {
+            continue;
+        }
+        let Some(host) = entry.request.host() else {
+            continue;
+        };
+
+        // the response version is what was negotiated, but some exporters leave it empty
+        let version = if entry.response.http_version.is_empty() {
+            &entry.request.http_version
+        } else {
+            &entry.response.http_version
+        };
+
+        let protocols = hosts.entry(host).or_default();
+        *protocols
+            .versions
+            .entry(normalise_version(version))
+            .or_insert(0) += 1;
+        protocols.requests += 1;
+        protocols.https |= entry.request.url.starts_with("https://");
+        if let Some(connection) = entry.connection.as_ref().filter(|c| !c.is_empty()) {
+            protocols.connections.insert(connection.to_string());
+        }
+        if let Some(ip) = entry.server_ip() {
+            protocols.ips.insert(ip);
+        }
+    }
+
+    hosts
+}
+
+pub fn protocols(har: &Har) {
+    let hosts = find_protocols(har);
+
+    if hosts.is_empty() {
+        println!("{}", "No requests found.".green());
+        return;
+    }
+
+    let mut names: Vec<&String> = hosts.keys().collect();
+    names.sort_by_key(|x| x.chars().rev().collect::<String>());
+
+    let mut totals: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut suggestions: Vec<String> = Vec::new();
+    for name in names {
+        let protocols = &hosts[name];
+        println!("{}:", name.bold().blue());
+
+        for (version, count) in &protocols.versions {
+            *totals.entry(version).or_insert(0) += count;
+            println!(
+                "    {}: {} ({:.0}%)",
+                version,
+                count,
+                *count as f64 * 100.0 / protocols.requests as f64
+            );
+        }
+        if !protocols.connections.is_empty() {
+            println!("    Connections: {}", protocols.connections.len());
+        }
+
+        if protocols.https && protocols.only_http1() && protocols.requests >= MIN_UPGRADE_REQUESTS {
+            suggestions.push(format!(
+                "{}: {} requests over HTTP/1.x, enable HTTP/2",
+                name, protocols.requests
+            ));
+        }
+        if protocols.uses_http2() && protocols.connections.len() > 1 {
+            suggestions.push(format!(
+                "{}: {} HTTP/2 connections opened, one should be reused",
+                name,
+                protocols.connections.len()
+            ));
+        }
+        println!();
+    }
+
+    // HTTP/2 hosts on the same IP can share a connection if their certificate covers both
+    let mut by_ip: BTreeMap<IpAddr, Vec<&String>> = BTreeMap::new();
+    for (name, protocols) in &hosts {
+        if protocols.uses_http2() {
+            for ip in &protocols.ips {
+                by_ip.entry(*ip).or_default().push(name);
+            }
+        }
+    }
+    for (ip, names) in by_ip {
+        let connections: BTreeSet<&String> = names
+            .iter()
+            .flat_map(|name| &hosts[*name].connections)
+            .collect();
+        if names.len() > 1 && connections.len() > 1 {
+            let names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+            suggestions.push(format!(
+                "{} share {} over {} connections, could be coalesced",
+                names.join(", "),
+                ip,
+                connections.len()
+            ));
+        }
+    }
+
+    if !suggestions.is_empty() {
+        println!("{}:", "Suggestions".bold().yellow());
+        for suggestion in &suggestions {
+            println!("    {}", suggestion);
+        }
+        println!();
+    }
+
+    let total: usize = totals.values().sum();
+    let shares: Vec<String> = totals
+        .iter()
+        .map(|(version, count)| format!("{} {:.0}%", version, *count as f64 * 100.0 / total as f64))
+        .collect();
+    let connections: BTreeSet<&String> = hosts.values().flat_map(|p| &p.connections).collect();
+    println!(
+        "{}: {}, {} connections across {} hosts",
+        "Summary".bold(),
+        shares.join(", "),
+        connections.len(),
+        hosts.len()
+    );
+}
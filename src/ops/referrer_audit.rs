@@ -0,0 +1,246 @@
+use colored::Colorize;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use url::Url;
+
+use crate::{
+    check, domain,
+    har::{Entry, Har},
+    mime::ResourceKind,
+};
+
+// policies that send the full URL to other origins
+const UNSAFE_POLICIES: [&str; 2] = ["unsafe-url", "no-referrer-when-downgrade"];
+
+const VALID_POLICIES: [&str; 8] = [
+    "no-referrer",
+    "no-referrer-when-downgrade",
+    "origin",
+    "origin-when-cross-origin",
+    "same-origin",
+    "strict-origin",
+    "strict-origin-when-cross-origin",
+    "unsafe-url",
+];
+
+// query parameters that commonly carry credentials or personal data
+const SENSITIVE_PARAMS: [&str; 20] = [
+    "token",
+    "access_token",
+    "id_token",
+    "refresh_token",
+    "code",
+    "state",
+    "session",
+    "sessionid",
+    "sid",
+    "key",
+    "api_key",
+    "apikey",
+    "auth",
+    "password",
+    "secret",
+    "signature",
+    "sig",
+    "email",
+    "user",
+    "uid",
+];
+
+// path segments at least this long and containing a digit look like identifiers
+const MIN_IDENTIFIER_LEN: usize = 16;
+
+#[derive(Debug)]
+pub struct ReferrerLeak {
+    pub referrer: String,
+    pub policy: Option<String>,
+    pub sensitive: Vec<String>,
+    pub downgrade: bool,
+    pub request_nums: BTreeSet<usize>,
+}
+
+/// Get the effective policy from a Referrer-Policy header, where the last recognised value wins.
+fn parse_policy(value: &str) -> Option<String> {
+    value
+        .split(',')
+        .map(|p| p.trim().to_lowercase())
+        .rfind(|p| VALID_POLICIES.contains(&p.as_str()))
+}
+
+fn meta_policy(entry: &Entry, meta_regex: &Regex) -> Option<String> {
+    if entry.response.resource_kind() != ResourceKind::Document {
+        return None;
+    }
+    let body = entry.response.content.as_ref()?.decoded_text()?;
+    let captures = meta_regex.captures(&body)?;
+    parse_policy(&captures[1])
+}
+
+/// The policy each document was served with, keyed by URL without its fragment.
+fn document_policies(har: &Har) -> BTreeMap<String, String> {
+    let meta_regex =
+        Regex::new(r#"(?i)<meta\s+name=["']?referrer["']?\s+content=["']?([^"'>]+)"#).unwrap();
+
+    har.log
+        .entries
+        .iter()
+        .filter_map(|entry| {
+            // a <meta> tag overrides the header
+            let policy = meta_policy(entry, &meta_regex).or_else(|| {
+                entry
+                    .response
+                    .header("referrer-policy")
+                    .and_then(parse_policy)
+            })?;
+            Some((strip_fragment(&entry.request.url), policy))
+        })
+        .collect()
+}
+
+fn strip_fragment(url: &str) -> String {
+    url.split('#').next().unwrap_or(url).to_string()
+}
+
+/// Describe what in the referrer looks sensitive.
+fn sensitive_parts(referrer: &Url) -> Vec<String> {
+    let mut parts: Vec<String> = referrer
+        .query_pairs()
+        .filter(|(name, _)| SENSITIVE_PARAMS.contains(&name.to_lowercase().as_str()))
+        .map(|(name, _)| format!("?{}", name))
+        .collect();
+
+    for segment in referrer.path_segments().into_iter().flatten() {
+        let looks_like_id = segment.len() >= MIN_IDENTIFIER_LEN
+            && segment.chars().any(|c| c.is_ascii_digit())
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "._-".contains(c));
+        let looks_like_email = segment.contains('@') || segment.contains("%40");
+        if looks_like_id || looks_like_email {
+            parts.push(format!("/{}", segment));
+        }
+    }
+
+    parts
+}
+
+/// Find requests to other sites whose Referer carries more than the origin.
+pub fn find_leaks(har: &Har) -> BTreeMap<String, Vec<ReferrerLeak>> {
+    let tld_extractor = domain::build_tld_extractor();
+    let policies = document_policies(har);
+    let site = |url: &Url| {
+        url.host_str().map(|host| {
+            domain::registrable_domain(host, &tld_extractor).unwrap_or_else(|| host.to_string())
+        })
+    };
+
+    // receiving site -> referrer -> leak
+    let mut leaks: BTreeMap<String, BTreeMap<String, ReferrerLeak>> = BTreeMap::new();
+    for (i, entry) in har.log.entries.iter().enumerate() {
+        let Some(referrer) = entry.request.header("referer") else {
+            continue;
+        };
+        let (Ok(referrer_url), Ok(request_url)) =
+            (Url::parse(referrer), Url::parse(&entry.request.url))
+        else {
+            continue;
+        };
+        let (Some(referrer_site), Some(request_site)) = (site(&referrer_url), site(&request_url))
+        else {
+            continue;
+        };
+
+        // an origin-only referrer is what the default policy sends and leaks little
+        let full_url = referrer_url.path() != "/" || referrer_url.query().is_some();
+        let downgrade = referrer_url.scheme() == "https" && request_url.scheme() == "http";
+        if domain::same_owner(&referrer_site, &request_site) || !(full_url || downgrade) {
+            continue;
+        }
+
+        let referrer = strip_fragment(referrer);
+        leaks
+            .entry(request_site)
+            .or_default()
+            .entry(referrer.clone())
+            .or_insert_with(|| ReferrerLeak {
+                policy: policies.get(&referrer).cloned(),
+                sensitive: sensitive_parts(&referrer_url),
+                downgrade,
+                referrer,
+                request_nums: BTreeSet::new(),
+            })
+            .request_nums
+            .insert(i + 1);
+    }
+
+    leaks
+        .into_iter()
+        .map(|(site, leaks)| (site, leaks.into_values().collect()))
+        .collect()
+}
+
+pub fn referrer_audit(har: &Har) {
+    let leaks = find_leaks(har);
+
+    if leaks.is_empty() {
+        println!("{}", "No referrer leaks found.".green());
+    } else {
+        let mut sites: Vec<&String> = leaks.keys().collect();
+        sites.sort_by_key(|x| x.chars().rev().collect::<String>());
+
+        for site in sites {
+            println!("{}:", site.bold().blue());
+            for leak in &leaks[site] {
+                let nums: Vec<String> = leak.request_nums.iter().map(|n| n.to_string()).collect();
+                println!("    {} (requests {})", leak.referrer, nums.join(", "));
+
+                match &leak.policy {
+                    Some(policy) if UNSAFE_POLICIES.contains(&policy.as_str()) => {
+                        println!("        Policy: {}", policy.red())
+                    }
+                    Some(policy) => println!("        Policy: {}", policy),
+                    None => println!("        Policy: {}", "not captured".dimmed()),
+                }
+                if leak.downgrade {
+                    println!("        {}", "Sent from HTTPS to HTTP".red());
+                }
+                if !leak.sensitive.is_empty() {
+                    println!(
+                        "        {}: {}",
+                        "Sensitive".red().bold(),
+                        leak.sensitive.join(", ")
+                    );
+                }
+            }
+            println!();
+        }
+    }
+
+    // documents whose policy lets any request leak the full URL
+    let unsafe_documents: Vec<(String, String)> = document_policies(har)
+        .into_iter()
+        .filter(|(_, policy)| UNSAFE_POLICIES.contains(&policy.as_str()))
+        .collect();
+    if !unsafe_documents.is_empty() {
+        println!("{}:", "Unsafe Referrer-Policy".bold().red());
+        for (url, policy) in &unsafe_documents {
+            println!("    {} ({})", url, policy);
+        }
+        println!();
+    }
+
+    let total: usize = leaks.values().map(Vec::len).sum();
+    check::report(total + unsafe_documents.len());
+    let sensitive: usize = leaks
+        .values()
+        .flatten()
+        .filter(|leak| !leak.sensitive.is_empty())
+        .count();
+    println!(
+        "{}: {} referrers leaked to {} sites, {} with sensitive values",
+        "Summary".bold(),
+        total,
+        leaks.len(),
+        sensitive
+    );
+}
@@ -0,0 +1,326 @@
+use colored::Colorize;
+use std::collections::{BTreeMap, BTreeSet};
+use url::Url;
+
+use crate::{
+    check, domain,
+    har::{Entry, Har},
+    mime::ResourceKind,
+};
+
+use super::asset_refs;
+
+// hints for the current page, prefetches are for later navigations so can't be judged here
+const HINT_RELS: [&str; 4] = ["preload", "modulepreload", "preconnect", "dns-prefetch"];
+
+// connection setup shorter than this isn't worth a preconnect
+const MIN_SETUP_MS: f64 = 20.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HintSource {
+    Header,
+    Html,
+}
+
+#[derive(Debug, Clone)]
+pub struct Hint {
+    pub rel: String,
+    pub url: Url,
+    // the `as` attribute of preloads
+    pub destination: Option<String>,
+    pub source: HintSource,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum HintUse {
+    /// Fetched once, or its origin was connected to.
+    Used,
+    /// Nothing on the page used it.
+    Unused,
+    /// Fetched more than once, so the preload wasn't matched, i.e. for a missing `crossorigin`.
+    Duplicated(usize),
+}
+
+#[derive(Debug)]
+pub struct MissingPreconnect {
+    pub origin: String,
+    // DNS, TCP and TLS time on the first request to the origin
+    pub setup_ms: f64,
+    pub first_request: usize,
+}
+
+#[derive(Debug)]
+pub struct DocumentHints<'a> {
+    pub request_num: usize,
+    pub url: &'a str,
+    pub hints: Vec<(Hint, HintUse)>,
+    pub missing: Vec<MissingPreconnect>,
+}
+
+/// Parse `Link` header values, i.e. `</app.js>; rel=preload; as=script, <https://cdn>; rel=preconnect`.
+pub fn parse_link_header(value: &str, base: &Url) -> Vec<Hint> {
+    let mut hints = Vec::new();
+    for link in value.split(',') {
+        let mut parts = link.split(';');
+        let Some(target) = parts.next().map(str::trim) else {
+            continue;
+        };
+        let Some(target) = target.strip_prefix('<').and_then(|t| t.strip_suffix('>')) else {
+            continue;
+        };
+
+        let mut rels = Vec::new();
+        let mut destination = None;
+        for param in parts {
+            let Some((name, value)) = param.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().trim_matches('"').to_lowercase();
+            match name.trim().to_lowercase().as_str() {
+                "rel" => rels.extend(value.split_whitespace().map(String::from)),
+                "as" => destination = Some(value),
+                _ => {}
+            }
+        }
+
+        let Ok(url) = base.join(target) else {
+            continue;
+        };
+        for rel in rels {
+            if HINT_RELS.contains(&rel.as_str()) {
+                hints.push(Hint {
+                    rel,
+                    url: url.clone(),
+                    destination: destination.clone(),
+                    source: HintSource::Header,
+                });
+            }
+        }
+    }
+    hints
+}
+
+fn html_hints(html: &str, base: &Url) -> Vec<Hint> {
+    let mut hints = Vec::new();
+    for attributes in asset_refs::link_tags(html) {
+        let (Some(rel), Some(href)) = (attributes.get("rel"), attributes.get("href")) else {
+            continue;
+        };
+        let Ok(url) = base.join(href.trim()) else {
+            continue;
+        };
+        for rel in rel.to_lowercase().split_whitespace() {
+            if HINT_RELS.contains(&rel) {
+                hints.push(Hint {
+                    rel: rel.to_string(),
+                    url: url.clone(),
+                    destination: attributes.get("as").map(|a| a.to_lowercase()),
+                    source: HintSource::Html,
+                });
+            }
+        }
+    }
+    hints
+}
+
+fn origin(url: &Url) -> String {
+    url.origin().ascii_serialization()
+}
+
+fn setup_ms(entry: &Entry) -> f64 {
+    entry.timings.as_ref().map_or(0.0, |t| {
+        t.dns.unwrap_or(0.0).max(0.0) + t.connect.unwrap_or(0.0).max(0.0)
+    })
+}
+
+/// Check each document's preload and preconnect hints against the requests its page made, and
+/// find third-party origins of render-critical assets that paid for a new connection unhinted.
+pub fn audit_hints<'a>(har: &'a Har) -> Vec<DocumentHints<'a>> {
+    let tld_extractor = domain::build_tld_extractor();
+    let entries = &har.log.entries;
+
+    let mut documents = Vec::new();
+    let mut pages_seen = BTreeSet::new();
+    for (i, document) in entries.iter().enumerate() {
+        if document.response.resource_kind() != ResourceKind::Document {
+            continue;
+        }
+        let Ok(base) = Url::parse(&document.request.url) else {
+            continue;
+        };
+
+        let mut hints: Vec<Hint> = document
+            .response
+            .headers
+            .iter()
+            .filter(|h| h.name.eq_ignore_ascii_case("link"))
+            .flat_map(|h| parse_link_header(&h.value, &base))
+            .collect();
+        if let Some(body) = document
+            .response
+            .content
+            .as_ref()
+            .and_then(|c| c.decoded_text())
+        {
+            hints.extend(html_hints(&body, &base));
+        }
+
+        // requests on the same page, or the whole capture when there are no pages
+        let page: Vec<(usize, &Entry, Url)> = entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| document.pageref.is_none() || entry.pageref == document.pageref)
+            .filter_map(|(j, entry)| Some((j + 1, entry, Url::parse(&entry.request.url).ok()?)))
+            .collect();
+        let page_origins: BTreeSet<String> = page.iter().map(|(_, _, url)| origin(url)).collect();
+
+        let mut seen = BTreeSet::new();
+        let mut checked = Vec::new();
+        for hint in hints {
+            // the same hint in the header and the markup is one hint
+            if !seen.insert((hint.rel.clone(), hint.url.to_string())) {
+                continue;
+            }
+            let used = match hint.rel.as_str() {
+                "preconnect" | "dns-prefetch" => {
+                    if page_origins.contains(&origin(&hint.url)) {
+                        HintUse::Used
+                    } else {
+                        HintUse::Unused
+                    }
+                }
+                _ => {
+                    let fetches = page
+                        .iter()
+                        .filter(|(_, _, url)| {
+                            url.as_str().split('#').next() == hint.url.as_str().split('#').next()
+                        })
+                        .count();
+                    match fetches {
+                        0 => HintUse::Unused,
+                        1 => HintUse::Used,
+                        n => HintUse::Duplicated(n),
+                    }
+                }
+            };
+            checked.push((hint, used));
+        }
+
+        let hinted: BTreeSet<String> = checked.iter().map(|(hint, _)| origin(&hint.url)).collect();
+        let site = base
+            .host_str()
+            .and_then(|host| domain::registrable_domain(host, &tld_extractor))
+            .map(|site| domain::FirstParty::for_site(&site));
+
+        // origin -> first request setting up a connection to it, only for the page's main
+        // document as frames and error pages share its requests
+        let mut missing: BTreeMap<String, MissingPreconnect> = BTreeMap::new();
+        let main_document = pages_seen.insert(document.pageref.as_deref());
+        for (num, entry, url) in page.iter().filter(|_| main_document) {
+            let critical = matches!(
+                entry.response.resource_kind(),
+                ResourceKind::Script | ResourceKind::Stylesheet | ResourceKind::Font
+            );
+            let third_party = match (&site, url.host_str()) {
+                (Some(site), Some(host)) => !site.contains_host(host, &tld_extractor),
+                (site, host) => site.is_some() != host.is_some(),
+            };
+            let origin = origin(url);
+            let setup = setup_ms(entry);
+            if !critical
+                || !third_party
+                || hinted.contains(&origin)
+                || missing.contains_key(&origin)
+                || setup < MIN_SETUP_MS
+            {
+                continue;
+            }
+            missing.insert(
+                origin.clone(),
+                MissingPreconnect {
+                    origin,
+                    setup_ms: setup,
+                    first_request: *num,
+                },
+            );
+        }
+
+        documents.push(DocumentHints {
+            request_num: i + 1,
+            url: &document.request.url,
+            hints: checked,
+            missing: missing.into_values().collect(),
+        });
+    }
+    documents
+}
+
+pub fn resource_hints(har: &Har) {
+    let documents = audit_hints(har);
+    let relevant: Vec<&DocumentHints> = documents
+        .iter()
+        .filter(|d| !d.hints.is_empty() || !d.missing.is_empty())
+        .collect();
+    if relevant.is_empty() {
+        println!(
+            "{}",
+            "No resource hints or missing preconnects found.".green()
+        );
+        return;
+    }
+
+    let mut hint_count = 0;
+    let mut wasted = 0;
+    let mut missing_count = 0;
+    for document in &relevant {
+        println!("#{} {}:", document.request_num, document.url.bold().blue());
+        for (hint, used) in &document.hints {
+            hint_count += 1;
+            let status = match used {
+                HintUse::Used => "used".green(),
+                HintUse::Unused => {
+                    wasted += 1;
+                    "unused".red()
+                }
+                HintUse::Duplicated(n) => {
+                    wasted += 1;
+                    format!("fetched {} times, check `as` and `crossorigin`", n).yellow()
+                }
+            };
+            let destination = hint
+                .destination
+                .as_deref()
+                .map(|d| format!(" as={}", d))
+                .unwrap_or_default();
+            let source = match hint.source {
+                HintSource::Header => "header",
+                HintSource::Html => "html",
+            };
+            println!(
+                "    {}{} {} ({}): {}",
+                hint.rel, destination, hint.url, source, status
+            );
+        }
+        for missing in &document.missing {
+            missing_count += 1;
+            println!(
+                "    {} {} (#{} spent {:.0} ms connecting)",
+                "missing preconnect".yellow(),
+                missing.origin,
+                missing.first_request,
+                missing.setup_ms
+            );
+        }
+        println!();
+    }
+
+    check::report(wasted + missing_count);
+    println!(
+        "{}: {} hints on {} documents, {} wasted, {} missing preconnects",
+        "Summary".bold(),
+        hint_count,
+        relevant.len(),
+        wasted,
+        missing_count
+    );
+}
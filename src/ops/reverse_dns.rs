@@ -0,0 +1,212 @@
+use anyhow::Result;
+use colored::Colorize;
+use hickory_resolver::{
+    TokioResolver,
+    proto::rr::{RData, RecordType},
+};
+use std::{collections::BTreeMap, net::IpAddr};
+
+use crate::{domain, har::Har, offline, progress};
+
+use super::dns;
+
+#[derive(Debug, PartialEq)]
+pub enum PtrMatch {
+    // no PTR record for the address
+    Missing,
+    // the PTR names the requested host
+    Host,
+    // the PTR is on the same registrable domain as the host
+    Site,
+    // the PTR is somewhere else entirely, e.g. a CDN or hosting provider
+    Other,
+}
+
+#[derive(Debug)]
+pub struct AddressCheck {
+    pub host: String,
+    pub ip: IpAddr,
+    pub request_nums: Vec<usize>,
+    // A or AAAA records currently returned for the host
+    pub forward: Vec<IpAddr>,
+    pub ptr_names: Vec<String>,
+    pub ptr_match: PtrMatch,
+    // whether a PTR name resolves back to the address
+    pub ptr_confirmed: bool,
+}
+
+impl AddressCheck {
+    /// The address the capture connected to isn't among the ones the host resolves to now.
+    pub fn forward_mismatch(&self) -> bool {
+        !self.forward.contains(&self.ip)
+    }
+}
+
+async fn forward_addresses(resolver: &TokioResolver, name: &str, ip: IpAddr) -> Vec<IpAddr> {
+    let rt = match ip {
+        IpAddr::V4(_) => RecordType::A,
+        IpAddr::V6(_) => RecordType::AAAA,
+    };
+    dns::lookup_type(resolver, name, rt)
+        .await
+        .iter()
+        .filter_map(|record| record.data().ip_addr())
+        .collect()
+}
+
+/// Reverse-resolve the address each host was served from and compare the PTR and the host's
+/// forward records with what the capture saw.
+pub async fn check_addresses(har: &Har<'_>) -> Result<Vec<AddressCheck>> {
+    // IP literal hosts have no name to compare against
+    let mut pairs: BTreeMap<(String, IpAddr), Vec<usize>> = BTreeMap::new();
+    for (i, entry) in har.log.entries.iter().enumerate() {
+        let (Some(host), Some(ip)) = (entry.request.host(), entry.server_ip()) else {
+            continue;
+        };
+        if domain::ip_literal(&host).is_none() {
+            pairs.entry((host, ip)).or_default().push(i + 1);
+        }
+    }
+
+    let resolver = dns::build_resolver()?;
+    let tld_extractor = domain::build_tld_extractor();
+
+    let pb = progress::items(pairs.len() as u64, "Checking addresses");
+    let mut checks = Vec::new();
+    for ((host, ip), request_nums) in pairs {
+        let forward = forward_addresses(&resolver, &host, ip).await;
+
+        let mut ptr_names: Vec<String> = dns::reverse_lookup(&resolver, ip)
+            .await
+            .iter()
+            .filter_map(|record| match record.data() {
+                RData::PTR(name) => Some(name.to_string().trim_end_matches('.').to_lowercase()),
+                _ => None,
+            })
+            .collect();
+        ptr_names.sort();
+        ptr_names.dedup();
+
+        let site = domain::registrable_domain(&host, &tld_extractor);
+        let ptr_match = if ptr_names.is_empty() {
+            PtrMatch::Missing
+        } else if ptr_names
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(&host))
+        {
+            PtrMatch::Host
+        } else if site.is_some()
+            && ptr_names
+                .iter()
+                .any(|name| domain::registrable_domain(name, &tld_extractor) == site)
+        {
+            PtrMatch::Site
+        } else {
+            PtrMatch::Other
+        };
+
+        let mut ptr_confirmed = false;
+        for name in &ptr_names {
+            if forward_addresses(&resolver, name, ip).await.contains(&ip) {
+                ptr_confirmed = true;
+                break;
+            }
+        }
+
+        checks.push(AddressCheck {
+            host,
+            ip,
+            request_nums,
+            forward,
+            ptr_names,
+            ptr_match,
+            ptr_confirmed,
+        });
+        pb.inc(1);
+    }
+    pb.finish_and_clear();
+
+    Ok(checks)
+}
+
+pub async fn print_reverse_dns(har: &Har<'_>) -> Result<()> {
+    if offline::is_offline() {
+        offline::notice("only cached DNS answers are used.");
+    }
+
+    let checks = check_addresses(har).await?;
+    if checks.is_empty() {
+        println!("{}", "No server IP addresses found.".green());
+        return Ok(());
+    }
+
+    let mut hosts: BTreeMap<&str, Vec<&AddressCheck>> = BTreeMap::new();
+    for check in &checks {
+        hosts.entry(&check.host).or_default().push(check);
+    }
+
+    for (host, checks) in hosts {
+        println!("{}:", domain::display_host(host).bold().blue());
+        for check in checks {
+            let requests: Vec<String> = check.request_nums.iter().map(|n| n.to_string()).collect();
+            println!(
+                "    {} (requests {})",
+                check.ip.to_string().cyan(),
+                requests.join(", ")
+            );
+
+            if check.forward.is_empty() {
+                println!(
+                    "        Forward: {}",
+                    "no records, stale or private DNS".red()
+                );
+            } else if check.forward_mismatch() {
+                let forward: Vec<String> = check.forward.iter().map(IpAddr::to_string).collect();
+                println!(
+                    "        Forward: {} ({})",
+                    "not among the current records, stale or spoofed DNS".red(),
+                    forward.join(", ")
+                );
+            } else {
+                println!("        Forward: {}", "matches".green());
+            }
+
+            let verdict = match check.ptr_match {
+                PtrMatch::Missing => {
+                    println!("        PTR: {}", "none".yellow());
+                    continue;
+                }
+                PtrMatch::Host => "matches".green(),
+                PtrMatch::Site => "same site".green(),
+                PtrMatch::Other => "different site, likely a CDN or hosting provider".yellow(),
+            };
+            let confirmed = if check.ptr_confirmed { "" } else { "not " };
+            println!(
+                "        PTR: {} ({}, {}forward-confirmed)",
+                check.ptr_names.join(", "),
+                verdict,
+                confirmed
+            );
+        }
+        println!();
+    }
+
+    let forward_mismatches = checks.iter().filter(|c| c.forward_mismatch()).count();
+    let other_ptrs = checks
+        .iter()
+        .filter(|c| c.ptr_match == PtrMatch::Other)
+        .count();
+    let missing_ptrs = checks
+        .iter()
+        .filter(|c| c.ptr_match == PtrMatch::Missing)
+        .count();
+    println!(
+        "{}: {} host and address pairs, {} not matching forward DNS, {} with a PTR elsewhere, {} without a PTR",
+        "Summary".bold(),
+        checks.len(),
+        forward_mismatches,
+        other_ptrs,
+        missing_ptrs
+    );
+    Ok(())
+}
@@ -0,0 +1,310 @@
+use colored::{ColoredString, Colorize};
+use std::collections::BTreeSet;
+
+use crate::{
+    domain,
+    har::{Entry, Har},
+    mime::ResourceKind,
+    ops::beacons,
+};
+
+// text bodies smaller than this gain little from compression
+const MIN_COMPRESSIBLE_BYTES: u64 = 1024;
+
+// pages needing more requests than this start to be penalised
+const REQUEST_ALLOWANCE: usize = 50;
+
+// security headers expected on first-party documents
+const SECURITY_HEADERS: [&str; 5] = [
+    "strict-transport-security",
+    "content-security-policy",
+    "x-content-type-options",
+    "x-frame-options",
+    "referrer-policy",
+];
+
+#[derive(Debug)]
+pub struct Deduction {
+    pub points: u32,
+    pub reason: String,
+}
+
+#[derive(Debug)]
+pub struct Category {
+    pub name: &'static str,
+    pub deductions: Vec<Deduction>,
+}
+
+impl Category {
+    pub fn score(&self) -> u32 {
+        100u32.saturating_sub(self.deductions.iter().map(|d| d.points).sum())
+    }
+
+    fn deduct(&mut self, points: f64, reason: String) {
+        let points = points.round() as u32;
+        if points > 0 {
+            self.deductions.push(Deduction { points, reason });
+        }
+    }
+}
+
+pub fn grade(score: u32) -> &'static str {
+    match score {
+        90.. => "A",
+        80..=89 => "B",
+        70..=79 => "C",
+        60..=69 => "D",
+        _ => "F",
+    }
+}
+
+fn coloured_grade(score: u32) -> ColoredString {
+    let text = format!("{} ({})", score, grade(score));
+    match score {
+        90.. => text.green().bold(),
+        70..=89 => text.yellow().bold(),
+        _ => text.red().bold(),
+    }
+}
+
+fn is_cacheable(entry: &Entry) -> bool {
+    if let Some(cache_control) = entry.response.header("cache-control") {
+        let cache_control = cache_control.to_lowercase();
+        if cache_control.contains("no-store") || cache_control.contains("no-cache") {
+            return false;
+        }
+        let max_age = cache_control
+            .split(',')
+            .filter_map(|d| d.trim().strip_prefix("max-age="))
+            .find_map(|v| v.trim().parse::<u64>().ok());
+        if let Some(max_age) = max_age {
+            return max_age > 0;
+        }
+    }
+    entry.response.header("expires").is_some()
+}
+
+fn sets_cookies(entry: &Entry) -> bool {
+    !entry.response.cookies.is_empty() || entry.response.header("set-cookie").is_some()
+}
+
+fn performance(entries: &[&Entry]) -> Category {
+    let mut category = Category {
+        name: "Performance",
+        deductions: Vec::new(),
+    };
+
+    let text: Vec<&&Entry> = entries
+        .iter()
+        .filter(|e| {
+            matches!(
+                e.response.resource_kind(),
+                ResourceKind::Document
+                    | ResourceKind::Script
+                    | ResourceKind::Stylesheet
+                    | ResourceKind::Data
+            ) && e.response.body_bytes() >= MIN_COMPRESSIBLE_BYTES
+        })
+        .collect();
+    let uncompressed = text
+        .iter()
+        .filter(|e| e.response.header("content-encoding").is_none())
+        .count();
+    if uncompressed > 0 {
+        category.deduct(
+            40.0 * uncompressed as f64 / text.len() as f64,
+            format!(
+                "{} of {} text responses served without compression",
+                uncompressed,
+                text.len()
+            ),
+        );
+    }
+
+    let static_entries: Vec<&&Entry> = entries
+        .iter()
+        .filter(|e| {
+            matches!(
+                e.response.resource_kind(),
+                ResourceKind::Script
+                    | ResourceKind::Stylesheet
+                    | ResourceKind::Image
+                    | ResourceKind::Font
+                    | ResourceKind::Media
+            ) && e.response.status == 200
+        })
+        .collect();
+    let uncached = static_entries.iter().filter(|e| !is_cacheable(e)).count();
+    if uncached > 0 {
+        category.deduct(
+            30.0 * uncached as f64 / static_entries.len() as f64,
+            format!(
+                "{} of {} static resources have no cache lifetime",
+                uncached,
+                static_entries.len()
+            ),
+        );
+    }
+
+    if entries.len() > REQUEST_ALLOWANCE {
+        category.deduct(
+            ((entries.len() - REQUEST_ALLOWANCE) as f64 / 5.0).min(30.0),
+            format!(
+                "{} requests, more than {}",
+                entries.len(),
+                REQUEST_ALLOWANCE
+            ),
+        );
+    }
+
+    category
+}
+
+fn privacy(har: &Har, entries: &[&Entry], third_party: &[&Entry]) -> Category {
+    let mut category = Category {
+        name: "Privacy",
+        deductions: Vec::new(),
+    };
+
+    let trackers: usize = beacons::find_beacons(har).values().map(|b| b.count).sum();
+    if trackers > 0 {
+        let share = trackers as f64 / entries.len() as f64;
+        category.deduct(
+            50.0 * (share * 4.0).min(1.0),
+            format!(
+                "{} of {} requests are tracking beacons ({:.0}%)",
+                trackers,
+                entries.len(),
+                share * 100.0
+            ),
+        );
+    }
+
+    let cookie_hosts: BTreeSet<String> = third_party
+        .iter()
+        .filter(|e| sets_cookies(e))
+        .filter_map(|e| e.request.host())
+        .collect();
+    if !cookie_hosts.is_empty() {
+        let hosts: Vec<&str> = cookie_hosts.iter().map(String::as_str).collect();
+        category.deduct(
+            (10 * cookie_hosts.len()).min(30) as f64,
+            format!("third-party cookies set by {}", hosts.join(", ")),
+        );
+    }
+
+    let third_party_hosts: BTreeSet<String> = third_party
+        .iter()
+        .filter_map(|e| e.request.host())
+        .collect();
+    if !third_party_hosts.is_empty() {
+        category.deduct(
+            (2 * third_party_hosts.len()).min(20) as f64,
+            format!("{} third-party hosts contacted", third_party_hosts.len()),
+        );
+    }
+
+    category
+}
+
+fn security(entries: &[&Entry], first_party: &[&Entry]) -> Category {
+    let mut category = Category {
+        name: "Security",
+        deductions: Vec::new(),
+    };
+
+    let insecure = entries
+        .iter()
+        .filter(|e| e.request.url.starts_with("http://") || e.request.url.starts_with("ws://"))
+        .count();
+    if insecure > 0 {
+        category.deduct(
+            20.0 + 20.0 * insecure as f64 / entries.len() as f64,
+            format!(
+                "{} of {} requests made without TLS",
+                insecure,
+                entries.len()
+            ),
+        );
+    }
+
+    let documents: Vec<&&Entry> = first_party
+        .iter()
+        .filter(|e| {
+            e.request.url.starts_with("https://")
+                && e.response.resource_kind() == ResourceKind::Document
+        })
+        .collect();
+    for header in SECURITY_HEADERS {
+        let missing = documents
+            .iter()
+            .filter(|e| e.response.header(header).is_none())
+            .count();
+        if missing > 0 {
+            category.deduct(
+                8.0 * missing as f64 / documents.len() as f64,
+                format!(
+                    "{} of {} first-party documents missing {}",
+                    missing,
+                    documents.len(),
+                    header
+                ),
+            );
+        }
+    }
+
+    category
+}
+
+/// Score the capture, returning the categories in display order.
+pub fn score_har(har: &Har) -> Vec<Category> {
+    let tld_extractor = domain::build_tld_extractor();
+    let first_party_site = domain::first_party(har, &tld_extractor);
+
+    // data URIs never hit the network
+    let entries: Vec<&Entry> = har
+        .log
+        .entries
+        .iter()
+        .filter(|e| !e.request.url.starts_with("data:"))
+        .collect();
+    let (first_party, third_party): (Vec<&Entry>, Vec<&Entry>) =
+        entries
+            .iter()
+            .partition(|e| match (&first_party_site, e.request.host()) {
+                (Some(first_party), Some(host)) => first_party.contains_host(&host, &tld_extractor),
+                (first_party, host) => first_party.is_none() && host.is_none(),
+            });
+
+    vec![
+        performance(&entries),
+        privacy(har, &entries, &third_party),
+        security(&entries, &first_party),
+    ]
+}
+
+pub fn score(har: &Har) {
+    if har.log.entries.is_empty() {
+        println!("{}", "No requests to score.".yellow());
+        return;
+    }
+
+    let categories = score_har(har);
+    for category in &categories {
+        println!(
+            "{}: {}",
+            category.name.bold().blue(),
+            coloured_grade(category.score())
+        );
+        if category.deductions.is_empty() {
+            println!("    {}", "No issues found".green());
+        }
+        for deduction in &category.deductions {
+            println!("    -{:<3} {}", deduction.points, deduction.reason);
+        }
+        println!();
+    }
+
+    let overall = categories.iter().map(Category::score).sum::<u32>() / categories.len() as u32;
+    println!("{}: {}", "Overall".bold(), coloured_grade(overall));
+}
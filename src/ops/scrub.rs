@@ -0,0 +1,111 @@
+use anyhow::{Context, Result};
+use base64::{Engine, prelude::BASE64_STANDARD};
+use regex::bytes::{Captures, Regex};
+use std::borrow::Cow;
+
+use crate::{
+    body::Body,
+    har::{Cookie, Har, Header},
+};
+
+struct Scrubber<'r> {
+    // matched against bytes, so patterns can target binary bodies too
+    pattern: Regex,
+    replacement: &'r str,
+    replaced: usize,
+}
+
+impl Scrubber<'_> {
+    /// Apply the replacement to some text, or `None` if the pattern didn't match.
+    fn text(&mut self, text: &str) -> Option<String> {
+        self.bytes(text.as_bytes()).map(|scrubbed| {
+            String::from_utf8(scrubbed)
+                .unwrap_or_else(|e| String::from_utf8_lossy(e.as_bytes()).into_owned())
+        })
+    }
+
+    fn bytes(&mut self, body: &[u8]) -> Option<Vec<u8>> {
+        let mut count = 0;
+        let scrubbed = self.pattern.replace_all(body, |caps: &Captures| {
+            count += 1;
+            let mut replaced = Vec::new();
+            caps.expand(self.replacement.as_bytes(), &mut replaced);
+            replaced
+        });
+        self.replaced += count;
+        match scrubbed {
+            Cow::Owned(scrubbed) => Some(scrubbed),
+            Cow::Borrowed(_) => None,
+        }
+    }
+
+    fn field(&mut self, field: &mut Cow<'_, str>) {
+        if let Some(scrubbed) = self.text(field) {
+            *field = Cow::Owned(scrubbed);
+        }
+    }
+
+    fn headers(&mut self, headers: &mut [Header]) {
+        for header in headers {
+            self.field(&mut header.value);
+        }
+    }
+
+    fn cookies(&mut self, cookies: &mut [Cookie]) {
+        for cookie in cookies {
+            self.field(&mut cookie.value);
+        }
+    }
+}
+
+/// Replace every match of `pattern` in the URLs, headers, cookies and bodies of the capture,
+/// returning the number of replacements made. Base64 bodies are decoded first and encoded
+/// again afterwards.
+pub fn scrub(har: &mut Har, pattern: &str, replacement: &str) -> Result<usize> {
+    let mut scrubber = Scrubber {
+        pattern: Regex::new(pattern).with_context(|| format!("Invalid pattern '{}'", pattern))?,
+        replacement,
+        replaced: 0,
+    };
+
+    for entry in &mut har.log.entries {
+        let request = &mut entry.request;
+        scrubber.field(&mut request.url);
+        scrubber.headers(&mut request.headers);
+        scrubber.cookies(&mut request.cookies);
+        for param in &mut request.query_string {
+            scrubber.field(&mut param.value);
+        }
+        if let Some(post_data) = &mut request.post_data {
+            if let Some(scrubbed) = scrubber.text(&post_data.text) {
+                post_data.text = Body::from(scrubbed);
+            }
+            for param in post_data.params.iter_mut().flatten() {
+                if let Some(value) = &mut param.value {
+                    scrubber.field(value);
+                }
+            }
+        }
+
+        let response = &mut entry.response;
+        scrubber.field(&mut response.redirect_url);
+        scrubber.headers(&mut response.headers);
+        scrubber.cookies(&mut response.cookies);
+        if let Some(content) = &mut response.content {
+            if content.encoding.as_deref() == Some("base64") {
+                let scrubbed = content.decoded().and_then(|body| scrubber.bytes(&body));
+                if let Some(scrubbed) = scrubbed {
+                    content.size = Some(scrubbed.len() as i64);
+                    content.text = Some(Body::from(BASE64_STANDARD.encode(&scrubbed)));
+                }
+            } else if let Some(text) = content.text.as_deref() {
+                if let Some(scrubbed) = scrubber.text(text) {
+                    content.size = Some(scrubbed.len() as i64);
+                    content.text = Some(Body::from(scrubbed));
+                }
+            }
+        }
+    }
+
+    Ok(scrubber.replaced)
+}
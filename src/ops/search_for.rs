@@ -1,15 +1,373 @@
-use crate::har::{Har, Request};
+use aho_corasick::AhoCorasick;
+use anyhow::{Context, Result, anyhow};
+use base64::{Engine, prelude::BASE64_STANDARD_NO_PAD};
+use colored::Colorize;
+use rayon::prelude::*;
+use regex::{Regex, RegexBuilder, RegexSet, RegexSetBuilder};
 use serde::Serialize;
+use std::{collections::BTreeSet, fs, ops::Range, path::Path};
+
+use crate::har::{Har, Request, format_timestamp};
+
+// bytes of context either side of a match in snippets
+const SNIPPET_CONTEXT: usize = 40;
+
+// shorter base64 is too likely to turn up by chance
+const MIN_BASE64_CHARS: usize = 4;
+
+// compiled size limits for search patterns, above the regex defaults so that long pattern files
+// still build, but bounded so that one pathological pattern can't take all the memory
+const REGEX_SIZE_LIMIT: usize = 1 << 26;
+const DFA_SIZE_LIMIT: usize = 1 << 26;
+
+#[derive(Debug)]
+enum Matcher {
+    Literal(String),
+    Regex(Regex),
+}
+
+/// A string or regex to search for, with the label to report its matches under.
+#[derive(Debug)]
+pub struct Needle {
+    pub label: String,
+    matcher: Matcher,
+}
+
+/// Everything to search for, checked in one pass over each field: the literals with an
+/// Aho-Corasick automaton and the regexes with a set, each alongside the indexes of its needles.
+#[derive(Debug)]
+pub struct Needles {
+    needles: Vec<Needle>,
+    literals: Option<(AhoCorasick, Vec<usize>)>,
+    regexes: Option<(RegexSet, Vec<usize>)>,
+}
+
+impl Needle {
+    pub fn literal(label: &str, literal: &str) -> Needle {
+        Needle {
+            label: label.to_string(),
+            matcher: Matcher::Literal(literal.to_string()),
+        }
+    }
+
+    pub fn regex(label: &str, pattern: &str) -> Result<Needle> {
+        let regex = RegexBuilder::new(pattern)
+            .size_limit(REGEX_SIZE_LIMIT)
+            .dfa_size_limit(DFA_SIZE_LIMIT)
+            .build()
+            .with_context(|| format!("Invalid pattern '{}'", pattern))?;
+        Ok(Needle {
+            label: label.to_string(),
+            matcher: Matcher::Regex(regex),
+        })
+    }
+
+    /// The string searched for, `None` for regexes.
+    pub fn literal_str(&self) -> Option<&str> {
+        match &self.matcher {
+            Matcher::Literal(literal) => Some(literal),
+            Matcher::Regex(_) => None,
+        }
+    }
+
+    /// Where the needle occurs in some text, without overlaps.
+    fn find_all(&self, text: &str) -> Vec<Range<usize>> {
+        match &self.matcher {
+            Matcher::Literal(literal) => text
+                .match_indices(literal.as_str())
+                .map(|(start, m)| start..start + m.len())
+                .collect(),
+            Matcher::Regex(regex) => regex.find_iter(text).map(|m| m.range()).collect(),
+        }
+    }
+}
+
+impl Needles {
+    pub fn new(needles: Vec<Needle>) -> Result<Needles> {
+        let (literal_ids, regex_ids): (Vec<usize>, Vec<usize>) =
+            (0..needles.len()).partition(|i| needles[*i].literal_str().is_some());
+
+        let literals = if literal_ids.is_empty() {
+            None
+        } else {
+            let patterns = literal_ids.iter().filter_map(|i| needles[*i].literal_str());
+            let automaton =
+                AhoCorasick::new(patterns).context("Failed to compile search strings")?;
+            Some((automaton, literal_ids))
+        };
+        let regexes = if regex_ids.is_empty() {
+            None
+        } else {
+            let patterns = regex_ids.iter().filter_map(|i| match &needles[*i].matcher {
+                Matcher::Regex(regex) => Some(regex.as_str()),
+                Matcher::Literal(_) => None,
+            });
+            let set = RegexSetBuilder::new(patterns)
+                .size_limit(REGEX_SIZE_LIMIT)
+                .dfa_size_limit(DFA_SIZE_LIMIT)
+                .build()
+                .context("Failed to compile search patterns")?;
+            Some((set, regex_ids))
+        };
+        Ok(Needles {
+            needles,
+            literals,
+            regexes,
+        })
+    }
+
+    /// The labels of the needles, in the order they were given.
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.needles.iter().map(|n| n.label.as_str())
+    }
+
+    /// The needles that occur in some text, in the order they were given.
+    fn matching(&self, text: &str) -> Vec<&Needle> {
+        let mut matched = BTreeSet::new();
+        if let Some((automaton, ids)) = &self.literals {
+            matched.extend(
+                automaton
+                    .find_overlapping_iter(text)
+                    .map(|m| ids[m.pattern().as_usize()]),
+            );
+        }
+        if let Some((set, ids)) = &self.regexes {
+            matched.extend(set.matches(text).iter().map(|i| ids[i]));
+        }
+        matched.into_iter().map(|i| &self.needles[i]).collect()
+    }
+
+    /// The literal needles as `transform` would encode them, under the same labels, or `None`
+    /// if none of them can be. Regexes can't be encoded so are left out.
+    pub fn transformed(&self, transform: Transform) -> Result<Option<Needles>> {
+        let mut encoded = Vec::new();
+        for needle in &self.needles {
+            let Some(pattern) = needle.literal_str().and_then(|l| transform.pattern(l)) else {
+                continue;
+            };
+            encoded.push(Needle::regex(&needle.label, &pattern)?);
+        }
+        if encoded.is_empty() {
+            return Ok(None);
+        }
+        Needles::new(encoded).map(Some)
+    }
+}
+
+/// An encoding a value might have been hidden under, which search-for also searches through.
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Transform {
+    /// Base64 encoded.
+    Base64,
+    /// Base64 encoded twice.
+    DoubleBase64,
+    /// Hex encoded, in either case.
+    Hex,
+    /// Percent-encoded as in a URL, with spaces as `%20` or `+`.
+    Percent,
+    /// Encoded as UTF-16LE, as text or base64 encoded.
+    #[value(name = "utf16le")]
+    Utf16Le,
+}
+
+impl Transform {
+    pub const ALL: [Transform; 5] = [
+        Transform::Base64,
+        Transform::DoubleBase64,
+        Transform::Hex,
+        Transform::Percent,
+        Transform::Utf16Le,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Transform::Base64 => "base64",
+            Transform::DoubleBase64 => "double base64",
+            Transform::Hex => "hex",
+            Transform::Percent => "percent",
+            Transform::Utf16Le => "UTF-16LE",
+        }
+    }
+
+    /// The key its results are listed under in JSON output.
+    pub fn key(self) -> &'static str {
+        match self {
+            Transform::Base64 => "base64_matches",
+            Transform::DoubleBase64 => "double_base64_matches",
+            Transform::Hex => "hex_matches",
+            Transform::Percent => "percent_matches",
+            Transform::Utf16Le => "utf16le_matches",
+        }
+    }
+
+    /// A regex for the literal as this transform encodes it, or `None` when that would only
+    /// find what searching for the literal already does.
+    fn pattern(self, literal: &str) -> Option<String> {
+        if literal.is_empty() {
+            return None;
+        }
+        match self {
+            Transform::Base64 => alternatives(base64_variants(literal.as_bytes())),
+            Transform::DoubleBase64 => alternatives(
+                base64_variants(literal.as_bytes())
+                    .iter()
+                    .flat_map(|inner| base64_variants(inner.as_bytes()))
+                    .collect(),
+            ),
+            Transform::Hex => {
+                let hex: String = literal.bytes().map(|b| format!("{:02x}", b)).collect();
+                Some(format!("(?i:{})", hex))
+            }
+            Transform::Percent => {
+                let mut pattern = String::new();
+                let mut encoded = false;
+                for c in literal.chars() {
+                    if c.is_ascii_alphanumeric() || "-._~".contains(c) {
+                        pattern.push(c);
+                        continue;
+                    }
+                    encoded = true;
+                    if c == ' ' {
+                        pattern.push_str(r"(?:%20|\+)");
+                        continue;
+                    }
+                    let mut bytes = [0; 4];
+                    for b in c.encode_utf8(&mut bytes).bytes() {
+                        pattern.push_str(&format!("%(?i:{:02x})", b));
+                    }
+                }
+                encoded.then_some(pattern)
+            }
+            Transform::Utf16Le => {
+                let bytes: Vec<u8> = literal.encode_utf16().flat_map(u16::to_le_bytes).collect();
+                let mut pattern = alternatives(base64_variants(&bytes))?;
+                // decoded as text, ASCII gains a NUL after each character, which JSON escapes
+                if literal.is_ascii() {
+                    let text: String = literal
+                        .chars()
+                        .map(|c| regex::escape(&c.to_string()) + r"\\u0000")
+                        .collect();
+                    pattern = format!("(?:{}|{})", pattern, text);
+                }
+                Some(pattern)
+            }
+        }
+    }
+}
+
+/// The characters that encode some bytes in base64 wherever they start in the encoded value,
+/// one for each of the three alignments they can have. Characters shared with the bytes either
+/// side are left off, and alignments that leave fewer than `MIN_BASE64_CHARS` are dropped.
+fn base64_variants(bytes: &[u8]) -> Vec<String> {
+    let mut variants = Vec::new();
+    for offset in 0..3 {
+        let mut padded = vec![0; offset];
+        padded.extend_from_slice(bytes);
+        let encoded = BASE64_STANDARD_NO_PAD.encode(&padded);
+        // the filler's characters, and the first it shares with the bytes
+        let skip = [0, 2, 3][offset];
+        // a partial group's last character also holds bits of whatever follows
+        let keep = padded.len() / 3 * 4 + [0, 1, 2][padded.len() % 3];
+        if let Some(variant) = encoded
+            .get(skip..keep)
+            .filter(|v| v.len() >= MIN_BASE64_CHARS)
+        {
+            variants.push(variant.to_string());
+        }
+    }
+    variants
+}
+
+/// A regex for any of some strings, `None` if there are none.
+fn alternatives(strings: Vec<String>) -> Option<String> {
+    if strings.is_empty() {
+        return None;
+    }
+    let escaped: Vec<String> = strings.iter().map(|s| regex::escape(s)).collect();
+    Some(format!("(?:{})", escaped.join("|")))
+}
+
+/// Load needles from a file with one per line, as `[label] pattern`, where the label is
+/// optional and a pattern wrapped in slashes is a regex. Blank lines and `#` comments are
+/// skipped.
+pub fn load_patterns(path: &Path) -> Result<Vec<Needle>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read patterns file: {:?}", path))?;
+
+    let mut needles = Vec::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (label, pattern) = match line.strip_prefix('[').and_then(|rest| rest.split_once(']')) {
+            Some((label, pattern)) => (Some(label.trim()), pattern.trim()),
+            None => (None, line),
+        };
+        if pattern.is_empty() {
+            return Err(anyhow!(
+                "No pattern after the label on line {} of {:?}",
+                i + 1,
+                path
+            ));
+        }
+        let needle = match pattern
+            .strip_prefix('/')
+            .and_then(|p| p.strip_suffix('/'))
+            .filter(|p| !p.is_empty())
+        {
+            Some(regex) => Needle::regex(label.unwrap_or(pattern), regex),
+            None => Ok(Needle::literal(label.unwrap_or(pattern), pattern)),
+        }
+        .with_context(|| format!("Invalid pattern on line {} of {:?}", i + 1, path))?;
+        needles.push(needle);
+    }
+    Ok(needles)
+}
+
+/// The needles for a search string and the patterns in a patterns file, either of which may be
+/// left out.
+pub fn load_needles(string: Option<&str>, patterns_file: Option<&Path>) -> Result<Needles> {
+    let mut needles = Vec::new();
+    if let Some(string) = string {
+        needles.push(Needle::literal(string, string));
+    }
+    if let Some(path) = patterns_file {
+        needles.extend(load_patterns(path)?);
+    }
+    if needles.is_empty() {
+        return Err(anyhow!("No patterns found in the patterns file"));
+    }
+    Needles::new(needles)
+}
+
+/// One occurrence of the search string in a field.
+#[derive(Clone, Debug, Serialize)]
+pub struct FieldMatch {
+    // the label of the needle that matched
+    pub pattern: String,
+    pub field: String,
+    // byte offset of the match in the field serialised as JSON
+    pub offset: usize,
+    // the exact text matched
+    pub value: String,
+    pub before: String,
+    pub after: String,
+}
 
 #[allow(dead_code)]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct SearchResult<'a> {
     pub request_num: usize,
     pub time: String,
     pub url: String,
     pub method: String,
     pub in_fields: Vec<String>,
-    pub request: &'a Request,
+    // the labels of the needles that matched
+    pub patterns: Vec<String>,
+    pub matches: Vec<FieldMatch>,
+    pub request: &'a Request<'a>,
 }
 
 macro_rules! check_fields {
@@ -17,15 +375,15 @@ macro_rules! check_fields {
         $obj:expr,
         $prefix:expr,
         [$(($field:literal, $expr:expr)),+ $(,)?],
-        $search_str:expr,
-        $in_fields:expr
+        $needles:expr,
+        $found:expr
     ) => {
         $(
             check_serialised_field(
                 $expr,
                 $field,
-                $search_str,
-                $in_fields,
+                $needles,
+                $found,
                 $prefix,
             );
         )+
@@ -34,16 +392,16 @@ macro_rules! check_fields {
         $obj:expr,
         $prefix:expr,
         [$(($field:literal, $expr:expr, maybe)),+ $(,)?],
-        $search_str:expr,
-        $in_fields:expr
+        $needles:expr,
+        $found:expr
     ) => {
         $(
             if let Some(value) = $expr {
                 check_serialised_field(
                     value,
                     $field,
-                    $search_str,
-                    $in_fields,
+                    $needles,
+                    $found,
                     $prefix,
                 );
             }
@@ -54,47 +412,69 @@ macro_rules! check_fields {
 fn check_serialised_field<T: Serialize>(
     value: &T,
     field_name: &str,
-    search_str: &str,
-    in_fields: &mut Vec<String>,
+    needles: &Needles,
+    found: &mut Vec<FieldMatch>,
     prefix: &str,
 ) {
     fn check_field(
         json_str: &str,
         field_name: &str,
-        search_str: &str,
-        in_fields: &mut Vec<String>,
+        needles: &Needles,
+        found: &mut Vec<FieldMatch>,
         prefix: &str,
     ) {
-        if json_str.contains(search_str) {
-            let field = if prefix.is_empty() {
-                field_name.to_string()
-            } else {
-                format!("{}_{}", prefix, field_name)
-            };
-            in_fields.push(field);
+        // which needles occur is found in one pass, so only those are searched again for where
+        // they are
+        let matched = needles.matching(json_str);
+        if matched.is_empty() {
+            return;
         }
+
+        let field = if prefix.is_empty() {
+            field_name.to_string()
+        } else {
+            format!("{}_{}", prefix, field_name)
+        };
+        let first = found.len();
+        for needle in matched {
+            for m in needle.find_all(json_str) {
+                let start = json_str.floor_char_boundary(m.start.saturating_sub(SNIPPET_CONTEXT));
+                let stop = json_str.ceil_char_boundary(m.end + SNIPPET_CONTEXT);
+                found.push(FieldMatch {
+                    pattern: needle.label.clone(),
+                    field: field.clone(),
+                    offset: m.start,
+                    value: json_str[m.clone()].to_string(),
+                    before: json_str[start..m.start].to_string(),
+                    after: json_str[m.end..stop].to_string(),
+                });
+            }
+        }
+        found[first..].sort_by_key(|m| m.offset);
     }
 
     if let Ok(json_str) = serde_json::to_string(value) {
-        check_field(&json_str, field_name, search_str, in_fields, prefix);
+        check_field(&json_str, field_name, needles, found, prefix);
     }
 }
-pub fn search_for<'a>(har: &'a Har, search_str: &'a str) -> Vec<SearchResult<'a>> {
+
+pub fn search_for<'a>(har: &'a Har, needles: &Needles) -> Vec<SearchResult<'a>> {
+    // collecting an indexed parallel iterator keeps the results in entry order
     har.log
         .entries
-        .iter()
+        .par_iter()
         .enumerate()
         .filter_map(|(i, entry)| {
-            let mut in_fields = Vec::new();
+            let mut found = Vec::new();
             let request = &entry.request;
             let response = &entry.response;
 
             // Entry-level fields
             check_serialised_field(
-                &entry.started_date_time,
+                &format_timestamp(&entry.started_date_time),
                 "startedDateTime",
-                search_str,
-                &mut in_fields,
+                needles,
+                &mut found,
                 "",
             );
 
@@ -111,8 +491,8 @@ pub fn search_for<'a>(har: &'a Har, search_str: &'a str) -> Vec<SearchResult<'a>
                     ("query_string", &request.query_string),
                     ("post_data", &request.post_data),
                 ],
-                search_str,
-                &mut in_fields
+                needles,
+                &mut found
             );
 
             // Response fields
@@ -128,17 +508,29 @@ pub fn search_for<'a>(har: &'a Har, search_str: &'a str) -> Vec<SearchResult<'a>
                     ("content", &response.content),
                     ("redirect_url", &response.redirect_url),
                 ],
-                search_str,
-                &mut in_fields
+                needles,
+                &mut found
             );
 
-            if !in_fields.is_empty() {
+            if !found.is_empty() {
+                let mut in_fields: Vec<String> = Vec::new();
+                let mut patterns: Vec<String> = Vec::new();
+                for field_match in &found {
+                    if !in_fields.contains(&field_match.field) {
+                        in_fields.push(field_match.field.clone());
+                    }
+                    if !patterns.contains(&field_match.pattern) {
+                        patterns.push(field_match.pattern.clone());
+                    }
+                }
                 Some(SearchResult {
                     request_num: i + 1,
-                    time: entry.started_date_time.clone(),
-                    url: request.url.clone(),
-                    method: request.method.clone(),
+                    time: format_timestamp(&entry.started_date_time),
+                    url: request.url.to_string(),
+                    method: request.method.to_string(),
                     in_fields,
+                    patterns,
+                    matches: found,
                     request: &entry.request,
                 })
             } else {
@@ -147,3 +539,76 @@ pub fn search_for<'a>(har: &'a Har, search_str: &'a str) -> Vec<SearchResult<'a>
         })
         .collect()
 }
+
+/// Search for the needles as they are and through each of the transforms, as the results for
+/// each with `None` for the needles as they are.
+pub fn search_transformed<'a>(
+    har: &'a Har,
+    needles: &Needles,
+    transforms: &[Transform],
+) -> Result<Vec<(Option<Transform>, Vec<SearchResult<'a>>)>> {
+    let mut results = vec![(None, search_for(har, needles))];
+    for &transform in transforms {
+        let found = match needles.transformed(transform)? {
+            Some(encoded) => search_for(har, &encoded),
+            None => Vec::new(),
+        };
+        results.push((Some(transform), found));
+    }
+    Ok(results)
+}
+
+/// Print the requests a search matched, with a snippet around each match if `show_matches`, and
+/// which needles matched if `show_patterns`.
+pub fn print_results(
+    results: &[SearchResult],
+    encoding: Option<&str>,
+    show_matches: bool,
+    show_patterns: bool,
+) {
+    let found = match encoding {
+        Some(encoding) => format!("Found {} in", encoding),
+        None => "Found in".to_string(),
+    };
+    for result in results {
+        println!("{} request {}:", found, result.request_num);
+        println!(
+            "Time: {}\nURL: {}\nMethod: {}\nIn fields: {:?}",
+            result.time, result.url, result.method, result.in_fields
+        );
+        if show_patterns {
+            println!("Patterns: {:?}", result.patterns);
+        }
+        if show_matches {
+            for field_match in &result.matches {
+                let label = if show_patterns {
+                    format!(" [{}]", field_match.pattern)
+                } else {
+                    String::new()
+                };
+                println!(
+                    "    {} @{}{}: ...{}{}{}...",
+                    field_match.field.cyan(),
+                    field_match.offset,
+                    label,
+                    field_match.before,
+                    field_match.value.red().bold(),
+                    field_match.after
+                );
+            }
+        }
+        println!();
+    }
+}
+
+/// Print how many requests and matches a search found.
+pub fn print_counts(results: &[SearchResult], encoding: Option<&str>) {
+    let matches: usize = results.iter().map(|r| r.matches.len()).sum();
+    let label = encoding.map(|e| format!(" {}", e)).unwrap_or_default();
+    println!(
+        "Found{} in {} requests ({} matches).",
+        label,
+        results.len(),
+        matches
+    );
+}
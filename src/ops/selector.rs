@@ -0,0 +1,325 @@
+use anyhow::{Result, anyhow, bail};
+use url::Url;
+
+use crate::har::Entry;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Method,
+    Url,
+    Domain,
+    Path,
+    Scheme,
+    Status,
+    Mime,
+    Kind,
+    Size,
+    Time,
+    HttpVersion,
+    Ip,
+    Page,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        let field = match name.to_lowercase().as_str() {
+            "method" => Self::Method,
+            "url" => Self::Url,
+            "domain" | "host" => Self::Domain,
+            "path" => Self::Path,
+            "scheme" => Self::Scheme,
+            "status" => Self::Status,
+            "mime" => Self::Mime,
+            "kind" => Self::Kind,
+            "size" => Self::Size,
+            "time" => Self::Time,
+            "http_version" | "version" => Self::HttpVersion,
+            "ip" => Self::Ip,
+            "page" | "pageref" => Self::Page,
+            _ => return None,
+        };
+        Some(field)
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, Self::Status | Self::Size | Self::Time)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Glob,
+    NotGlob,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Field, Op, String),
+}
+
+/// A parsed `--where` expression, i.e. `status>=400 && domain~"*.google.com"`.
+#[derive(Debug, Clone)]
+pub struct Selector {
+    expr: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Literal(String),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        let (token, len) = match (c, next) {
+            (c, _) if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('=', Some('=')) => (Token::Op(Op::Eq), 2),
+            ('!', Some('=')) => (Token::Op(Op::Ne), 2),
+            ('!', Some('~')) => (Token::Op(Op::NotGlob), 2),
+            ('>', Some('=')) => (Token::Op(Op::Ge), 2),
+            ('<', Some('=')) => (Token::Op(Op::Le), 2),
+            ('>', _) => (Token::Op(Op::Gt), 1),
+            ('<', _) => (Token::Op(Op::Lt), 1),
+            ('~', _) => (Token::Op(Op::Glob), 1),
+            ('!', _) => (Token::Not, 1),
+            ('(', _) => (Token::LParen, 1),
+            (')', _) => (Token::RParen, 1),
+            ('"', _) | ('\'', _) => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|x| *x == c)
+                    .ok_or_else(|| anyhow!("Unterminated string starting at position {}", i))?;
+                let literal: String = chars[i + 1..i + 1 + end].iter().collect();
+                (Token::Literal(literal), end + 2)
+            }
+            _ => {
+                let len = chars[i..]
+                    .iter()
+                    .position(|x| x.is_whitespace() || "&|=!<>~()\"'".contains(*x))
+                    .unwrap_or(chars.len() - i);
+                if len == 0 {
+                    bail!("Unexpected character '{}' at position {}", c, i);
+                }
+                (Token::Ident(chars[i..i + len].iter().collect()), len)
+            }
+        };
+
+        tokens.push(token);
+        i += len;
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut lhs = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.parse_and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut lhs = self.parse_unary()?;
+        while self.peek() == Some(&Token::And) {
+            self.next();
+            lhs = Expr::And(Box::new(lhs), Box::new(self.parse_unary()?));
+        }
+        Ok(lhs)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Not) => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LParen) => {
+                let expr = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(expr),
+                    _ => bail!("Expected ')'"),
+                }
+            }
+            Some(Token::Ident(name)) => {
+                let field =
+                    Field::parse(&name).ok_or_else(|| anyhow!("Unknown field: {}", name))?;
+
+                let op = match self.next() {
+                    Some(Token::Op(op)) => op,
+                    _ => bail!("Expected comparison operator after '{}'", name),
+                };
+
+                let value = match self.next() {
+                    Some(Token::Literal(value)) | Some(Token::Ident(value)) => value,
+                    _ => bail!("Expected value after '{}'", name),
+                };
+
+                if field.is_numeric() && !matches!(op, Op::Glob | Op::NotGlob) {
+                    value.parse::<f64>().map_err(|_| {
+                        anyhow!("Field '{}' expects a number, got '{}'", name, value)
+                    })?;
+                }
+
+                Ok(Expr::Compare(field, op, value))
+            }
+            Some(token) => bail!("Unexpected token: {:?}", token),
+            None => bail!("Unexpected end of expression"),
+        }
+    }
+}
+
+impl Selector {
+    pub fn parse(input: &str) -> Result<Self> {
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            pos: 0,
+        };
+
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            bail!("Unexpected trailing token: {:?}", token);
+        }
+
+        Ok(Self { expr })
+    }
+
+    pub fn matches(&self, entry: &Entry) -> bool {
+        eval(&self.expr, entry)
+    }
+}
+
+fn field_value(field: Field, entry: &Entry) -> String {
+    let url = Url::parse(&entry.request.url).ok();
+
+    match field {
+        Field::Method => entry.request.method.to_string(),
+        Field::Url => entry.request.url.to_string(),
+        Field::Domain => entry.request.host().unwrap_or_default(),
+        Field::Path => url.map(|u| u.path().to_string()).unwrap_or_default(),
+        Field::Scheme => url.map(|u| u.scheme().to_string()).unwrap_or_default(),
+        Field::Status => entry.response.status.to_string(),
+        Field::Mime => entry.response.mime_type().unwrap_or_default(),
+        Field::Kind => entry.response.resource_kind().to_string(),
+        Field::Size => entry.response.body_bytes().to_string(),
+        Field::Time => entry.time.to_string(),
+        Field::HttpVersion => entry.response.http_version.to_string(),
+        Field::Ip => entry
+            .server_ip()
+            .map(|ip| ip.to_string())
+            .unwrap_or_default(),
+        Field::Page => entry.pageref.as_deref().unwrap_or_default().to_string(),
+    }
+}
+
+/// Case-insensitive glob matching supporting `*` and `?`.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((bp, bt)) = backtrack {
+            p = bp + 1;
+            t = bt + 1;
+            backtrack = Some((bp, bt + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+fn eval(expr: &Expr, entry: &Entry) -> bool {
+    match expr {
+        Expr::And(lhs, rhs) => eval(lhs, entry) && eval(rhs, entry),
+        Expr::Or(lhs, rhs) => eval(lhs, entry) || eval(rhs, entry),
+        Expr::Not(inner) => !eval(inner, entry),
+        Expr::Compare(field, op, value) => {
+            let actual = field_value(*field, entry);
+
+            match op {
+                Op::Glob => glob_match(value, &actual),
+                Op::NotGlob => !glob_match(value, &actual),
+                _ if field.is_numeric() => {
+                    let (Ok(actual), Ok(value)) = (actual.parse::<f64>(), value.parse::<f64>())
+                    else {
+                        return false;
+                    };
+                    match op {
+                        Op::Eq => actual == value,
+                        Op::Ne => actual != value,
+                        Op::Gt => actual > value,
+                        Op::Ge => actual >= value,
+                        Op::Lt => actual < value,
+                        Op::Le => actual <= value,
+                        Op::Glob | Op::NotGlob => unreachable!(),
+                    }
+                }
+                _ => {
+                    let actual = actual.to_lowercase();
+                    let value = value.to_lowercase();
+                    match op {
+                        Op::Eq => actual == value,
+                        Op::Ne => actual != value,
+                        Op::Gt => actual > value,
+                        Op::Ge => actual >= value,
+                        Op::Lt => actual < value,
+                        Op::Le => actual <= value,
+                        Op::Glob | Op::NotGlob => unreachable!(),
+                    }
+                }
+            }
+        }
+    }
+}
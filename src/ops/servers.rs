@@ -0,0 +1,122 @@
+use colored::Colorize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::har::{Har, Response};
+
+// headers that directly describe server software
+const SOFTWARE_HEADERS: [&str; 4] = ["server", "x-powered-by", "via", "x-aspnet-version"];
+
+// headers whose presence identifies a CDN or hosting provider
+const CDN_HEADERS: [(&str, &str); 12] = [
+    ("cf-ray", "Cloudflare"),
+    ("cf-cache-status", "Cloudflare"),
+    ("x-amz-cf-id", "Amazon CloudFront"),
+    ("x-amz-cf-pop", "Amazon CloudFront"),
+    ("x-served-by", "Fastly"),
+    ("x-fastly-request-id", "Fastly"),
+    ("akamai-grn", "Akamai"),
+    ("x-akamai-transformed", "Akamai"),
+    ("x-azure-ref", "Azure Front Door"),
+    ("x-vercel-id", "Vercel"),
+    ("x-nf-request-id", "Netlify"),
+    ("x-github-request-id", "GitHub"),
+];
+
+#[derive(Debug, Default)]
+pub struct ServerInfo {
+    pub requests: usize,
+    pub software: BTreeMap<(String, String), usize>,
+    pub cdns: BTreeMap<String, usize>,
+}
+
+pub fn get_server_info(har: &Har) -> HashMap<String, ServerInfo> {
+    let mut servers: HashMap<String, ServerInfo> = HashMap::new();
+
+    for entry in &har.log.entries {
+        let Some(host) = entry.request.host() else {
+            continue;
+        };
+
+        let info = servers.entry(host).or_default();
+        info.requests += 1;
+
+        for name in SOFTWARE_HEADERS {
+            for value in header_values(&entry.response, name) {
+                *info
+                    .software
+                    .entry((name.to_string(), value.to_string()))
+                    .or_insert(0) += 1;
+            }
+        }
+
+        for cdn in detect_cdns(&entry.response) {
+            *info.cdns.entry(cdn.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    servers
+}
+
+fn header_values<'a>(response: &'a Response, name: &'a str) -> impl Iterator<Item = &'a str> {
+    response
+        .headers
+        .iter()
+        .filter(move |h| h.name.eq_ignore_ascii_case(name))
+        .map(|h| h.value.trim())
+}
+
+fn detect_cdns(response: &Response) -> Vec<&'static str> {
+    let mut cdns: Vec<&'static str> = CDN_HEADERS
+        .iter()
+        .filter(|(name, _)| response.header(name).is_some())
+        .map(|(_, cdn)| *cdn)
+        .collect();
+
+    // some providers only identify themselves through the server header
+    if let Some(server) = response.header("server") {
+        let server = server.to_lowercase();
+        if server.contains("cloudflare") {
+            cdns.push("Cloudflare");
+        } else if server.contains("akamai") {
+            cdns.push("Akamai");
+        } else if server == "amazons3" {
+            cdns.push("Amazon S3");
+        } else if server.contains("gws") || server.contains("gse") {
+            cdns.push("Google");
+        }
+    }
+
+    cdns.sort();
+    cdns.dedup();
+    cdns
+}
+
+pub fn print_servers(har: &Har) {
+    let servers = get_server_info(har);
+
+    let mut domains: Vec<(&String, &ServerInfo)> = servers.iter().collect();
+    domains.sort_by_key(|(x, _)| x.chars().rev().collect::<String>());
+
+    for (domain, info) in domains {
+        println!("{} ({}):", domain.bold().blue(), info.requests);
+
+        if info.software.is_empty() && info.cdns.is_empty() {
+            println!("{}", "No server identifying headers found".yellow());
+        }
+
+        for ((header, value), count) in &info.software {
+            println!(
+                "[{:16}] {} ({})",
+                header.purple().bold(),
+                value.cyan(),
+                count
+            );
+        }
+
+        for (cdn, count) in &info.cdns {
+            println!("[{:16}] {} ({})", "cdn".purple().bold(), cdn.green(), count);
+        }
+
+        println!();
+    }
+}
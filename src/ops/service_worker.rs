@@ -0,0 +1,188 @@
+use colored::Colorize;
+use std::{collections::BTreeMap, fmt};
+
+use crate::{
+    format,
+    har::{Entry, Har},
+};
+
+// how many service worker responses to list
+const MAX_LISTED: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Source {
+    /// From a service worker's Cache Storage.
+    ServiceWorkerCache,
+    /// Made up by the service worker itself.
+    ServiceWorkerGenerated,
+    /// Passed through a service worker, whose fetch the browser's HTTP cache answered.
+    ServiceWorkerHttpCache,
+    /// Passed through a service worker, which fetched it.
+    ServiceWorkerNetwork,
+    /// From the browser's memory cache.
+    MemoryCache,
+    /// From the browser's disk cache.
+    DiskCache,
+    /// Nothing was transferred, so it came from a cache the HAR doesn't name.
+    UnknownCache,
+    /// Revalidated with the server, which sent no body.
+    Revalidated,
+    Network,
+}
+
+impl Source {
+    pub fn is_service_worker(self) -> bool {
+        matches!(
+            self,
+            Source::ServiceWorkerCache
+                | Source::ServiceWorkerGenerated
+                | Source::ServiceWorkerHttpCache
+                | Source::ServiceWorkerNetwork
+        )
+    }
+
+    /// Whether the body didn't come over the network.
+    pub fn is_local(self) -> bool {
+        !matches!(
+            self,
+            Source::ServiceWorkerNetwork | Source::Revalidated | Source::Network
+        )
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Source::ServiceWorkerCache => "service worker cache storage",
+            Source::ServiceWorkerGenerated => "service worker generated",
+            Source::ServiceWorkerHttpCache => "service worker HTTP cache",
+            Source::ServiceWorkerNetwork => "service worker network",
+            Source::MemoryCache => "memory cache",
+            Source::DiskCache => "disk cache",
+            Source::UnknownCache => "cache (unnamed)",
+            Source::Revalidated => "revalidated (304)",
+            Source::Network => "network",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Where an entry's response came from, going by the fields Chrome adds and, failing those, by
+/// it having a body but nothing transferred.
+pub fn source(entry: &Entry) -> Source {
+    let response = &entry.response;
+    let via_service_worker = response.fetched_via_service_worker == Some(true)
+        || response.service_worker_response_source.is_some();
+    if via_service_worker {
+        return match response.service_worker_response_source.as_deref() {
+            Some("cache-storage") => Source::ServiceWorkerCache,
+            Some("fallback-code") => Source::ServiceWorkerGenerated,
+            Some("http-cache") => Source::ServiceWorkerHttpCache,
+            _ => Source::ServiceWorkerNetwork,
+        };
+    }
+
+    match entry.from_cache.as_deref() {
+        Some("memory") => return Source::MemoryCache,
+        Some("disk") => return Source::DiskCache,
+        _ => {}
+    }
+    if response.status == 304 {
+        return Source::Revalidated;
+    }
+
+    let content_bytes = response.content.as_ref().and_then(|c| c.size).unwrap_or(0);
+    let nothing_transferred = match response.transfer_size {
+        Some(transferred) => transferred == 0,
+        // Firefox leaves the body size at 0 for cached responses
+        None => response.body_size == 0 && entry.cache.before_request.is_some(),
+    };
+    if response.status == 200 && content_bytes > 0 && nothing_transferred {
+        return Source::UnknownCache;
+    }
+    Source::Network
+}
+
+pub fn service_worker(har: &Har) {
+    let entries = &har.log.entries;
+    if entries.is_empty() {
+        println!("{}", "No requests found.".yellow());
+        return;
+    }
+
+    // source -> (requests, content bytes)
+    let mut sources: BTreeMap<Source, (usize, u64)> = BTreeMap::new();
+    let mut served_by_worker = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let source = source(entry);
+        let bytes = entry
+            .response
+            .content
+            .as_ref()
+            .and_then(|c| c.size)
+            .unwrap_or(0)
+            .max(0) as u64;
+        let (requests, total) = sources.entry(source).or_default();
+        *requests += 1;
+        *total += bytes;
+        if source.is_service_worker() {
+            served_by_worker.push((i + 1, entry, source));
+        }
+    }
+
+    let total_bytes: u64 = sources.values().map(|(_, bytes)| bytes).sum();
+    let percent = |part: u64, whole: u64| {
+        if whole == 0 {
+            0.0
+        } else {
+            part as f64 / whole as f64 * 100.0
+        }
+    };
+
+    println!("{}:", "Sources".bold().blue());
+    for (source, (requests, bytes)) in &sources {
+        let name = if source.is_local() {
+            source.to_string().green()
+        } else {
+            source.to_string().normal()
+        };
+        println!(
+            "    {}: {} requests ({:.1}%), {} ({:.1}%)",
+            name,
+            requests,
+            percent(*requests as u64, entries.len() as u64),
+            format::bytes(*bytes),
+            percent(*bytes, total_bytes)
+        );
+    }
+    println!();
+
+    if !served_by_worker.is_empty() {
+        println!("{}:", "Served by service worker".bold().blue());
+        for (num, entry, source) in served_by_worker.iter().take(MAX_LISTED) {
+            println!("    #{} {} ({})", num, entry.request.url, source);
+        }
+        if served_by_worker.len() > MAX_LISTED {
+            println!("    ... and {} more", served_by_worker.len() - MAX_LISTED);
+        }
+        println!();
+    }
+
+    let (local_requests, local_bytes) = sources
+        .iter()
+        .filter(|(source, _)| source.is_local())
+        .fold((0, 0), |(requests, bytes), (_, (r, b))| {
+            (requests + r, bytes + b)
+        });
+    println!(
+        "{}: {} of {} requests ({:.1}%) and {} of {} ({:.1}%) served without the network, {} through a service worker",
+        "Summary".bold(),
+        local_requests,
+        entries.len(),
+        percent(local_requests as u64, entries.len() as u64),
+        format::bytes(local_bytes),
+        format::bytes(total_bytes),
+        percent(local_bytes, total_bytes),
+        served_by_worker.len()
+    );
+}
@@ -0,0 +1,203 @@
+use chrono::{DateTime, FixedOffset};
+use colored::Colorize;
+use std::collections::{BTreeMap, BTreeSet};
+use url::Url;
+
+use crate::{
+    format,
+    har::{Entry, Har},
+    ops::cookie_lineage::{self, Cookie},
+};
+
+// cookie names containing these are session identifiers whatever their value looks like
+const SESSION_COOKIE_HINTS: [&str; 8] = [
+    "sess", "sid", "token", "auth", "jwt", "login", "remember", "user",
+];
+
+// random looking values at least this long are treated as identifiers too
+const MIN_TOKEN_LEN: usize = 16;
+
+// how many paths to list per host before summarising
+const MAX_PATHS: usize = 5;
+
+#[derive(Debug, Default)]
+pub struct TokenUsage {
+    pub first_seen: Option<DateTime<FixedOffset>>,
+    pub last_seen: Option<DateTime<FixedOffset>>,
+    // the latest expiry a response gave the cookie, none for session cookies and other tokens
+    pub expires: Option<DateTime<FixedOffset>>,
+    pub requests: usize,
+    // host -> paths
+    pub sent_to: BTreeMap<String, BTreeSet<String>>,
+    pub insecure_requests: BTreeSet<usize>,
+}
+
+fn is_session_cookie((name, value): &Cookie) -> bool {
+    let name = name.to_lowercase();
+    let hinted = SESSION_COOKIE_HINTS.iter().any(|hint| name.contains(hint));
+    let random = value.len() >= MIN_TOKEN_LEN
+        && value.chars().any(|c| c.is_ascii_digit())
+        && value.chars().any(|c| c.is_ascii_alphabetic());
+    !value.is_empty() && (hinted || random)
+}
+
+/// Find the session identifiers each request carries, as (kind, value).
+fn request_tokens(entry: &Entry) -> BTreeSet<(String, String)> {
+    let mut tokens = BTreeSet::new();
+
+    let mut cookies: Vec<Cookie> = entry
+        .request
+        .headers
+        .iter()
+        .filter(|h| h.name.eq_ignore_ascii_case("cookie"))
+        .flat_map(|h| cookie_lineage::parse_cookie_header(&h.value))
+        .collect();
+    cookies.extend(
+        entry
+            .request
+            .cookies
+            .iter()
+            .map(|c| (c.name.to_string(), c.value.to_string())),
+    );
+    for cookie in cookies.into_iter().filter(is_session_cookie) {
+        tokens.insert((format!("cookie {}", cookie.0), cookie.1));
+    }
+
+    for header in &entry.request.headers {
+        if header.name.eq_ignore_ascii_case("authorization") {
+            let (scheme, credentials) =
+                header.value.split_once(' ').unwrap_or(("", &*header.value));
+            tokens.insert((
+                format!("Authorization {}", scheme).trim_end().to_string(),
+                credentials.trim().to_string(),
+            ));
+        }
+    }
+
+    tokens
+}
+
+/// Track where and when every session identifier was sent, keyed by (kind, value).
+pub fn find_session_tokens(har: &Har) -> BTreeMap<(String, String), TokenUsage> {
+    let mut usage: BTreeMap<(String, String), TokenUsage> = BTreeMap::new();
+
+    for (i, entry) in har.log.entries.iter().enumerate() {
+        let Ok(url) = Url::parse(&entry.request.url) else {
+            continue;
+        };
+        let Some(host) = url.host_str() else {
+            continue;
+        };
+        let started = *entry.started_date_time;
+
+        for token in request_tokens(entry) {
+            let token_usage = usage.entry(token).or_default();
+            token_usage.requests += 1;
+            token_usage
+                .sent_to
+                .entry(host.to_string())
+                .or_default()
+                .insert(url.path().to_string());
+
+            if token_usage.first_seen.is_none_or(|first| started < first) {
+                token_usage.first_seen = Some(started);
+            }
+            if token_usage.last_seen.is_none_or(|last| started > last) {
+                token_usage.last_seen = Some(started);
+            }
+
+            if matches!(url.scheme(), "http" | "ws") {
+                token_usage.insecure_requests.insert(i + 1);
+            }
+        }
+    }
+
+    // a token that outlives the browser session stays usable by anyone who copies it
+    for cookie in har.log.entries.iter().flat_map(|e| &e.response.cookies) {
+        let Some(expires) = cookie.expires.as_ref().and_then(|e| e.time) else {
+            continue;
+        };
+        let token = (format!("cookie {}", cookie.name), cookie.value.to_string());
+        if let Some(token_usage) = usage.get_mut(&token) {
+            if token_usage.expires.is_none_or(|latest| expires > latest) {
+                token_usage.expires = Some(expires);
+            }
+        }
+    }
+
+    usage
+}
+
+pub fn session_tokens(har: &Har) {
+    let usage = find_session_tokens(har);
+
+    if usage.is_empty() {
+        println!("{}", "No session tokens found.".green());
+        return;
+    }
+
+    for ((kind, value), token_usage) in &usage {
+        println!("{} {}:", kind.bold().blue(), format::redact(value));
+
+        let lifetime = match (token_usage.first_seen, token_usage.last_seen) {
+            (Some(first), Some(last)) => format!(
+                "{:.1}s ({} to {})",
+                (last - first).num_milliseconds() as f64 / 1000.0,
+                first.format("%H:%M:%S"),
+                last.format("%H:%M:%S")
+            ),
+            _ => "unknown".to_string(),
+        };
+        println!(
+            "    Used in {} requests over {}",
+            token_usage.requests, lifetime
+        );
+        if let Some(expires) = token_usage.expires {
+            println!(
+                "    Cookie expires {}",
+                expires.format("%Y-%m-%d %H:%M:%S %:z")
+            );
+        }
+
+        let mut hosts: Vec<&String> = token_usage.sent_to.keys().collect();
+        hosts.sort_by_key(|x| x.chars().rev().collect::<String>());
+        for host in hosts {
+            let paths = &token_usage.sent_to[host];
+            let mut shown: Vec<&str> = paths.iter().take(MAX_PATHS).map(String::as_str).collect();
+            if paths.len() > MAX_PATHS {
+                shown.push("...");
+            }
+            println!(
+                "    {}: {} ({} paths)",
+                host.cyan(),
+                shown.join(", "),
+                paths.len()
+            );
+        }
+
+        if !token_usage.insecure_requests.is_empty() {
+            let nums: Vec<String> = token_usage
+                .insecure_requests
+                .iter()
+                .map(|n| n.to_string())
+                .collect();
+            println!(
+                "    {}: sent without TLS in requests {}",
+                "Insecure".red().bold(),
+                nums.join(", ")
+            );
+        }
+        println!();
+    }
+
+    let insecure = usage
+        .values()
+        .filter(|u| !u.insecure_requests.is_empty())
+        .count();
+    println!(
+        "{}: {} session tokens, {} sent without TLS",
+        "Summary".bold(),
+        usage.len(),
+        insecure
+    );
+}
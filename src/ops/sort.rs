@@ -0,0 +1,105 @@
+use colored::Colorize;
+use std::cmp::Ordering;
+
+use crate::{
+    format,
+    har::{Entry, Har, format_timestamp},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum SortKey {
+    /// When the request started.
+    Time,
+    /// How long the request took.
+    Duration,
+    /// The response body size.
+    Size,
+    /// The request URL.
+    Url,
+    /// The response status.
+    Status,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SortField {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+/// Parse a sort key with an optional direction, i.e. `duration:desc`.
+pub fn parse_sort_field(s: &str) -> Result<SortField, String> {
+    let (key, direction) = s.split_once(':').unwrap_or((s, "asc"));
+    let key = <SortKey as clap::ValueEnum>::from_str(key.trim(), true)?;
+    let descending = match direction.trim().to_lowercase().as_str() {
+        "asc" => false,
+        "desc" => true,
+        other => {
+            return Err(format!(
+                "Invalid sort direction '{}', use asc or desc",
+                other
+            ));
+        }
+    };
+    Ok(SortField { key, descending })
+}
+
+fn compare(a: &Entry, b: &Entry, key: SortKey) -> Ordering {
+    match key {
+        SortKey::Time => a.started_date_time.cmp(&b.started_date_time),
+        SortKey::Duration => a.time.total_cmp(&b.time),
+        SortKey::Size => a.response.body_bytes().cmp(&b.response.body_bytes()),
+        SortKey::Url => a.request.url.cmp(&b.request.url),
+        SortKey::Status => a.response.status.cmp(&b.response.status),
+    }
+}
+
+/// Sort entries by each field in turn, keeping the HAR's order between entries that tie on
+/// all of them. Returns the request numbers the entries had before sorting.
+pub fn sort_entries(har: &mut Har, fields: &[SortField]) -> Vec<usize> {
+    let entries = std::mem::take(&mut har.log.entries);
+    let mut numbered: Vec<(usize, Entry)> = entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| (i + 1, entry))
+        .collect();
+    // a stable sort, so ties stay in capture order
+    numbered.sort_by(|(_, a), (_, b)| {
+        fields
+            .iter()
+            .map(|field| {
+                let ordering = compare(a, b, field.key);
+                if field.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                }
+            })
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+
+    let (numbers, entries) = numbered.into_iter().unzip();
+    har.log.entries = entries;
+    numbers
+}
+
+/// Print one line per entry in its sorted order, numbered as in the unsorted HAR.
+pub fn print_listing(har: &Har, numbers: &[usize]) {
+    if har.log.entries.is_empty() {
+        println!("{}", "No requests found.".yellow());
+        return;
+    }
+
+    for (num, entry) in numbers.iter().zip(&har.log.entries) {
+        println!(
+            "#{:<5} {} {} {:>8.0} ms {:>10} {} {}",
+            num,
+            format_timestamp(&entry.started_date_time).dimmed(),
+            entry.response.status,
+            entry.time,
+            format::bytes(entry.response.body_bytes()),
+            entry.request.method.bold(),
+            entry.request.url
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use anyhow::{Context, Result};
+use chrono::Utc;
+use colored::Colorize;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs,
+    path::Path,
+};
+
+use crate::{
+    domain,
+    har::{Browser, Creator, Entry, Har, Page},
+};
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum SplitBy {
+    /// One file per page, with entries outside any page together.
+    Page,
+    /// One file per requested host.
+    Domain,
+    /// One file per hour, by when the request started (UTC).
+    Hour,
+}
+
+/// A HAR borrowing its log metadata and a subset of its pages and entries from another.
+#[derive(Serialize)]
+struct PartHar<'a> {
+    log: PartLog<'a>,
+    #[serde(flatten)]
+    extra: &'a Map<String, Value>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PartLog<'a> {
+    version: &'a str,
+    creator: &'a Creator<'a>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    browser: Option<&'a Browser<'a>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pages: Vec<&'a Page<'a>>,
+    entries: Vec<&'a Entry<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    comment: Option<&'a str>,
+    #[serde(flatten)]
+    extra: &'a Map<String, Value>,
+}
+
+fn part_key(entry: &Entry, by: SplitBy) -> String {
+    match by {
+        SplitBy::Page => entry.pageref.as_deref().unwrap_or("no-page").to_string(),
+        SplitBy::Domain => entry
+            .request
+            .host()
+            .map(|host| domain::to_ascii(&host))
+            .unwrap_or_else(|| "no-host".to_string()),
+        SplitBy::Hour => entry
+            .started_date_time
+            .with_timezone(&Utc)
+            .format("%Y-%m-%dT%H")
+            .to_string(),
+    }
+}
+
+/// Group the entries into parts, keeping their order within each.
+pub fn split_entries<'a>(har: &'a Har, by: SplitBy) -> BTreeMap<String, Vec<&'a Entry<'a>>> {
+    let mut parts: BTreeMap<String, Vec<&Entry>> = BTreeMap::new();
+    for entry in &har.log.entries {
+        parts.entry(part_key(entry, by)).or_default().push(entry);
+    }
+    parts
+}
+
+/// The file name for a part, with the characters that aren't safe in one replaced and a number
+/// added where that makes it the same as an earlier part's, ignoring case for filesystems that do.
+fn file_name(prefix: &str, key: &str, used: &mut HashSet<String>) -> String {
+    let base = format!("{}-{}", prefix, key).replace(['/', '\\', ':'], "_");
+    let mut name = format!("{}.har", base);
+    let mut n = 1;
+    while !used.insert(name.to_lowercase()) {
+        n += 1;
+        name = format!("{}-{}.har", base, n);
+    }
+    name
+}
+
+/// Write each part to `<dir>/<prefix>-<key>.har`, returning the files written and how many
+/// entries each has.
+pub fn split(har: &Har, by: SplitBy, dir: &Path, prefix: &str) -> Result<Vec<(String, usize)>> {
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {:?}", dir))?;
+
+    let pages = har.log.pages.as_deref().unwrap_or_default();
+    let mut written = Vec::new();
+    let mut used = HashSet::new();
+    for (key, entries) in split_entries(har, by) {
+        // only the pages the part's entries belong to
+        let pages = pages
+            .iter()
+            .filter(|page| {
+                entries
+                    .iter()
+                    .any(|entry| entry.pageref.as_deref() == Some(&*page.id))
+            })
+            .collect();
+
+        let part = PartHar {
+            log: PartLog {
+                version: &har.log.version,
+                creator: &har.log.creator,
+                browser: har.log.browser.as_ref(),
+                pages,
+                entries,
+                comment: har.log.comment.as_deref(),
+                extra: &har.log.extra,
+            },
+            extra: &har.extra,
+        };
+
+        let name = file_name(prefix, &key, &mut used);
+        let path = dir.join(&name);
+        let json = serde_json::to_string_pretty(&part)?;
+        fs::write(&path, json).with_context(|| format!("Failed to write {:?}", path))?;
+        written.push((name, part.log.entries.len()));
+    }
+
+    Ok(written)
+}
+
+pub fn print_split(written: &[(String, usize)], dir: &Path) {
+    if written.is_empty() {
+        println!("{}", "No entries to split.".green());
+        return;
+    }
+
+    for (name, count) in written {
+        println!("{} ({} entries)", name, count);
+    }
+    println!();
+
+    println!(
+        "{}: {} files to {:?}",
+        "Saved".green().bold(),
+        written.len(),
+        dir
+    );
+}
@@ -0,0 +1,304 @@
+use colored::Colorize;
+use std::collections::BTreeMap;
+
+use crate::{
+    format,
+    har::{Entry, Har},
+};
+
+// streams open at least this long are reported as long-lived
+const LONG_LIVED_MS: f64 = 30_000.0;
+
+// the type of an SSE event without an `event:` field
+const DEFAULT_EVENT_TYPE: &str = "message";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFormat {
+    /// `text/event-stream`
+    EventStream,
+    /// Newline-delimited JSON, one event per line.
+    JsonLines,
+}
+
+impl StreamFormat {
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        match mime {
+            "text/event-stream" => Some(Self::EventStream),
+            "application/x-ndjson"
+            | "application/ndjson"
+            | "application/jsonl"
+            | "application/x-jsonlines"
+            | "application/stream+json"
+            | "application/json-seq" => Some(Self::JsonLines),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Event {
+    pub event_type: String,
+    pub data: String,
+    pub id: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct EventTypeStats {
+    pub count: usize,
+    pub bytes: u64,
+    pub largest: u64,
+}
+
+#[derive(Debug)]
+pub struct Stream<'a> {
+    pub request_num: usize,
+    pub entry: &'a Entry<'a>,
+    pub format: StreamFormat,
+    pub events: Vec<Event>,
+    // `:` lines, which servers send as keep-alives
+    pub comments: usize,
+    // the reconnection delay the server asked for, in ms
+    pub retry: Option<u64>,
+    // whether the body was captured at all
+    pub captured: bool,
+}
+
+impl Stream<'_> {
+    /// How long the stream was open, if the exporter recorded it.
+    pub fn duration_ms(&self) -> Option<f64> {
+        Some(self.entry.time).filter(|ms| *ms >= 0.0)
+    }
+
+    /// How long the server took to start the stream, then how long it streamed for.
+    pub fn wait_and_receive_ms(&self) -> Option<(f64, f64)> {
+        let timings = self.entry.timings.as_ref()?;
+        Some((timings.wait, timings.receive))
+            .filter(|(wait, receive)| *wait >= 0.0 && *receive >= 0.0)
+    }
+
+    pub fn by_type(&self) -> BTreeMap<&str, EventTypeStats> {
+        let mut types: BTreeMap<&str, EventTypeStats> = BTreeMap::new();
+        for event in &self.events {
+            let stats = types.entry(&event.event_type).or_default();
+            let bytes = event.data.len() as u64;
+            stats.count += 1;
+            stats.bytes += bytes;
+            stats.largest = stats.largest.max(bytes);
+        }
+        types
+    }
+}
+
+/// Parse an event stream as the HTML spec does: `field: value` lines build up an event, which a
+/// blank line dispatches if it has any data.
+pub fn parse_event_stream(body: &str) -> (Vec<Event>, usize, Option<u64>) {
+    let mut events = Vec::new();
+    let mut comments = 0;
+    let mut retry = None;
+    let mut event_type = String::new();
+    let mut data: Vec<&str> = Vec::new();
+    let mut last_id: Option<String> = None;
+
+    let body = body.strip_prefix('\u{feff}').unwrap_or(body);
+    let normalised = body.replace("\r\n", "\n").replace('\r', "\n");
+    for line in normalised.split('\n') {
+        if line.is_empty() {
+            if !data.is_empty() {
+                events.push(Event {
+                    event_type: if event_type.is_empty() {
+                        DEFAULT_EVENT_TYPE.to_string()
+                    } else {
+                        event_type.clone()
+                    },
+                    data: data.join("\n"),
+                    id: last_id.clone(),
+                });
+            }
+            event_type.clear();
+            data.clear();
+            continue;
+        }
+        if line.starts_with(':') {
+            comments += 1;
+            continue;
+        }
+        let (field, value) = line.split_once(':').unwrap_or((line, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "event" => event_type = value.to_string(),
+            "data" => data.push(value),
+            "id" if !value.contains('\0') => last_id = Some(value.to_string()),
+            "retry" => retry = value.parse().ok().or(retry),
+            _ => {}
+        }
+    }
+    // an event without the blank line after it was still being sent when the capture ended,
+    // and browsers drop it, so it isn't counted
+    (events, comments, retry)
+}
+
+/// Split a newline-delimited JSON stream into an event per line, typed by its `type` or `event`
+/// field where it has one.
+fn parse_json_lines(body: &str) -> Vec<Event> {
+    body.lines()
+        // json-seq starts each record with a record separator
+        .map(|line| line.trim().trim_start_matches('\u{1e}'))
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let event_type = serde_json::from_str::<serde_json::Value>(line)
+                .ok()
+                .and_then(|value| {
+                    ["type", "event"]
+                        .iter()
+                        .find_map(|key| value.get(key)?.as_str().map(String::from))
+                })
+                .unwrap_or_else(|| "line".to_string());
+            Event {
+                event_type,
+                data: line.to_string(),
+                id: None,
+            }
+        })
+        .collect()
+}
+
+/// Find the streamed responses and parse their captured bodies into events.
+pub fn find_streams<'a>(har: &'a Har) -> Vec<Stream<'a>> {
+    har.log
+        .entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, entry)| {
+            let format = StreamFormat::from_mime(&entry.response.mime_type()?)?;
+            let body = entry
+                .response
+                .content
+                .as_ref()
+                .and_then(|c| c.decoded_text())
+                .unwrap_or_default();
+            let (events, comments, retry) = match format {
+                StreamFormat::EventStream => parse_event_stream(&body),
+                StreamFormat::JsonLines => (parse_json_lines(&body), 0, None),
+            };
+            Some(Stream {
+                request_num: i + 1,
+                entry,
+                format,
+                events,
+                comments,
+                retry,
+                captured: !body.is_empty(),
+            })
+        })
+        .collect()
+}
+
+fn seconds(ms: f64) -> String {
+    format!("{:.1} s", ms / 1000.0)
+}
+
+pub fn streams(har: &Har) {
+    let streams = find_streams(har);
+    if streams.is_empty() {
+        println!(
+            "{}",
+            "No event streams or streamed JSON responses found.".yellow()
+        );
+        return;
+    }
+
+    let mut total_events = 0;
+    let mut total_bytes = 0;
+    let mut long_lived = 0;
+    let mut uncaptured = 0;
+    for stream in &streams {
+        let format = match stream.format {
+            StreamFormat::EventStream => "SSE",
+            StreamFormat::JsonLines => "JSON lines",
+        };
+        println!(
+            "#{} {} ({}):",
+            stream.request_num,
+            stream.entry.request.url.bold().blue(),
+            format
+        );
+
+        if let Some(duration) = stream.duration_ms() {
+            let open = match stream.wait_and_receive_ms() {
+                Some((wait, receive)) => format!(
+                    "open {} ({} to first byte, {} streaming)",
+                    seconds(duration),
+                    seconds(wait),
+                    seconds(receive)
+                ),
+                None => format!("open {}", seconds(duration)),
+            };
+            if duration >= LONG_LIVED_MS {
+                long_lived += 1;
+                println!("    {}", open.yellow());
+            } else {
+                println!("    {}", open);
+            }
+        }
+
+        if !stream.captured {
+            uncaptured += 1;
+            println!(
+                "    {}",
+                "no body captured, as when the stream was still open at export".dimmed()
+            );
+            println!();
+            continue;
+        }
+
+        let bytes: u64 = stream.events.iter().map(|e| e.data.len() as u64).sum();
+        total_events += stream.events.len();
+        total_bytes += bytes;
+        let rate = stream
+            .duration_ms()
+            .filter(|ms| *ms > 0.0)
+            .map(|ms| {
+                format!(
+                    ", {:.1} per minute",
+                    stream.events.len() as f64 / ms * 60_000.0
+                )
+            })
+            .unwrap_or_default();
+        println!(
+            "    {} events, {} of data{}",
+            stream.events.len(),
+            format::bytes(bytes),
+            rate
+        );
+        for (event_type, stats) in stream.by_type() {
+            println!(
+                "        {}: {} ({}, largest {})",
+                event_type,
+                stats.count,
+                format::bytes(stats.bytes),
+                format::bytes(stats.largest)
+            );
+        }
+        if stream.comments > 0 {
+            println!("    {} keep-alive comments", stream.comments);
+        }
+        if let Some(retry) = stream.retry {
+            println!("    reconnects after {} ms", retry);
+        }
+        if let Some(id) = stream.events.last().and_then(|e| e.id.as_deref()) {
+            println!("    last event ID {}", id);
+        }
+        println!();
+    }
+
+    println!(
+        "{}: {} streams with {} events totalling {}, {} open over {} s, {} without a captured body",
+        "Summary".bold(),
+        streams.len(),
+        total_events,
+        format::bytes(total_bytes),
+        long_lived,
+        LONG_LIVED_MS / 1000.0,
+        uncaptured
+    );
+}
@@ -0,0 +1,143 @@
+use chrono::{DateTime, FixedOffset};
+use colored::Colorize;
+use std::time::Duration;
+
+use crate::{format, har::Har};
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+// width of the longest histogram bar
+const BAR_WIDTH: usize = 40;
+
+// runs of at least this many empty buckets are collapsed into one idle line
+const MIN_IDLE_BUCKETS: usize = 3;
+
+#[derive(Debug, Default, Clone)]
+pub struct Bucket {
+    pub requests: usize,
+    pub bytes: u64,
+}
+
+/// Bucket the entries by start time, returning the time of the first bucket and the buckets.
+pub fn bucket_entries(
+    har: &Har,
+    interval: Duration,
+) -> Option<(DateTime<FixedOffset>, Vec<Bucket>)> {
+    let mut times: Vec<(DateTime<FixedOffset>, u64)> = har
+        .log
+        .entries
+        .iter()
+        .map(|entry| (*entry.started_date_time, entry.response.body_bytes()))
+        .collect();
+    times.sort_by_key(|(t, _)| *t);
+
+    let start = times.first()?.0;
+    let interval_ms = (interval.as_millis() as i64).max(1);
+
+    let mut buckets: Vec<Bucket> = Vec::new();
+    for (time, bytes) in times {
+        let index = ((time - start).num_milliseconds() / interval_ms) as usize;
+        if index >= buckets.len() {
+            buckets.resize(index + 1, Bucket::default());
+        }
+        buckets[index].requests += 1;
+        buckets[index].bytes += bytes;
+    }
+
+    Some((start, buckets))
+}
+
+fn sparkline(values: &[u64]) -> String {
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    values
+        .iter()
+        .map(|v| {
+            if *v == 0 {
+                ' '
+            } else {
+                SPARKS[(v * (SPARKS.len() as u64 - 1) / max) as usize]
+            }
+        })
+        .collect()
+}
+
+fn bar(value: usize, max: usize) -> String {
+    let eighths = value * BAR_WIDTH * 8 / max.max(1);
+    let mut bar = "█".repeat(eighths / 8);
+    let partial = eighths % 8;
+    if partial > 0 {
+        bar.push(['▏', '▎', '▍', '▌', '▋', '▊', '▉'][partial - 1]);
+    }
+    bar
+}
+
+pub fn timeline(har: &Har, interval: Duration) {
+    let Some((start, buckets)) = bucket_entries(har, interval) else {
+        println!("{}", "No timed requests found.".green());
+        return;
+    };
+
+    let counts: Vec<usize> = buckets.iter().map(|b| b.requests).collect();
+    let max_requests = counts.iter().copied().max().unwrap_or(0);
+    let interval_secs = interval.as_secs_f64();
+
+    let requests: Vec<u64> = buckets.iter().map(|b| b.requests as u64).collect();
+    let bytes: Vec<u64> = buckets.iter().map(|b| b.bytes).collect();
+    println!("{} ({}s buckets):", "Timeline".bold().blue(), interval_secs);
+    println!("    Requests {}", sparkline(&requests).cyan());
+    println!("    Bytes    {}", sparkline(&bytes).yellow());
+    println!();
+
+    let mut idle_buckets = 0;
+    let mut idle_run = 0;
+    for (i, bucket) in buckets.iter().enumerate() {
+        if bucket.requests == 0 {
+            idle_run += 1;
+            idle_buckets += 1;
+            continue;
+        }
+
+        if idle_run > 0 {
+            if idle_run >= MIN_IDLE_BUCKETS {
+                println!(
+                    "    {}",
+                    format!("... idle for {:.1}s ...", idle_run as f64 * interval_secs).dimmed()
+                );
+            } else {
+                for _ in 0..idle_run {
+                    println!("    {}", "-".dimmed());
+                }
+            }
+            idle_run = 0;
+        }
+
+        let offset = interval.mul_f64(i as f64);
+        let time = start + chrono::Duration::from_std(offset).unwrap_or_default();
+        println!(
+            "    {} {:<width$} {:>4} reqs {:>10}",
+            time.format("%H:%M:%S%.3f"),
+            bar(bucket.requests, max_requests).green(),
+            bucket.requests,
+            format::bytes(bucket.bytes),
+            width = BAR_WIDTH
+        );
+    }
+    println!();
+
+    let total: usize = counts.iter().sum();
+    let peak = buckets
+        .iter()
+        .enumerate()
+        .max_by_key(|(_, b)| b.requests)
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+    println!(
+        "{}: {} requests over {:.1}s, peak {} requests at +{:.1}s, idle {:.1}s",
+        "Summary".bold(),
+        total,
+        buckets.len() as f64 * interval_secs,
+        max_requests,
+        peak as f64 * interval_secs,
+        idle_buckets as f64 * interval_secs
+    );
+}
@@ -0,0 +1,308 @@
+use anyhow::{Context, Result, anyhow};
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use rustls::{
+    ClientConfig, DigitallySignedStruct, Error as TlsError, RootCertStore, SignatureScheme,
+    SupportedProtocolVersion,
+    client::{
+        WebPkiServerVerifier,
+        danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    },
+    crypto::{CryptoProvider, aws_lc_rs},
+    pki_types::{CertificateDer, ServerName, UnixTime},
+    version::{TLS12, TLS13},
+};
+use std::{
+    collections::BTreeSet,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{net::TcpStream, time::timeout};
+use tokio_rustls::TlsConnector;
+use url::Url;
+use x509_parser::{
+    extensions::GeneralName,
+    prelude::{FromDer, X509Certificate},
+    public_key::PublicKey,
+};
+
+use crate::{check, har::Har, offline};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Wraps the WebPKI verifier, recording verification failures instead of aborting the
+/// handshake so that the certificates of misconfigured hosts can still be inspected.
+#[derive(Debug)]
+struct RecordingVerifier {
+    inner: Arc<WebPkiServerVerifier>,
+    error: Mutex<Option<TlsError>>,
+}
+
+impl ServerCertVerifier for RecordingVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        intermediates: &[CertificateDer<'_>],
+        server_name: &ServerName<'_>,
+        ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> Result<ServerCertVerified, TlsError> {
+        if let Err(e) = self.inner.verify_server_cert(
+            end_entity,
+            intermediates,
+            server_name,
+            ocsp_response,
+            now,
+        ) {
+            *self.error.lock().unwrap() = Some(e);
+        }
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TlsError> {
+        self.inner.verify_tls12_signature(message, cert, dss)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, TlsError> {
+        self.inner.verify_tls13_signature(message, cert, dss)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.inner.supported_verify_schemes()
+    }
+}
+
+#[derive(Debug)]
+pub struct HandshakeInfo {
+    pub version: String,
+    pub cipher: String,
+    pub chain: Vec<CertificateDer<'static>>,
+    pub verify_error: Option<TlsError>,
+}
+
+struct Auditor {
+    provider: Arc<CryptoProvider>,
+    roots: Arc<RootCertStore>,
+}
+
+impl Auditor {
+    fn new() -> Result<Self> {
+        let mut roots = RootCertStore::empty();
+        for cert in rustls_native_certs::load_native_certs().certs {
+            // individual unparseable system certs aren't worth failing over
+            let _ = roots.add(cert);
+        }
+
+        Ok(Self {
+            provider: Arc::new(aws_lc_rs::default_provider()),
+            roots: Arc::new(roots),
+        })
+    }
+
+    async fn handshake(
+        &self,
+        host: &str,
+        port: u16,
+        versions: &[&'static SupportedProtocolVersion],
+    ) -> Result<HandshakeInfo> {
+        let inner =
+            WebPkiServerVerifier::builder_with_provider(self.roots.clone(), self.provider.clone())
+                .build()?;
+        let verifier = Arc::new(RecordingVerifier {
+            inner,
+            error: Mutex::new(None),
+        });
+
+        let config = ClientConfig::builder_with_provider(self.provider.clone())
+            .with_protocol_versions(versions)?
+            .dangerous()
+            .with_custom_certificate_verifier(verifier.clone())
+            .with_no_client_auth();
+
+        let server_name = ServerName::try_from(host.to_string())?;
+        let connector = TlsConnector::from(Arc::new(config));
+
+        let stream = timeout(CONNECT_TIMEOUT, async {
+            let tcp = TcpStream::connect((host, port)).await?;
+            connector.connect(server_name, tcp).await
+        })
+        .await
+        .map_err(|_| anyhow!("Connection timed out"))??;
+
+        let (_, conn) = stream.get_ref();
+        let info = HandshakeInfo {
+            version: conn
+                .protocol_version()
+                .map(|v| format!("{:?}", v))
+                .unwrap_or_default(),
+            cipher: conn
+                .negotiated_cipher_suite()
+                .map(|c| format!("{:?}", c.suite()))
+                .unwrap_or_default(),
+            chain: conn
+                .peer_certificates()
+                .map(|certs| certs.iter().map(|c| c.clone().into_owned()).collect())
+                .unwrap_or_default(),
+            verify_error: verifier.error.lock().unwrap().take(),
+        };
+
+        Ok(info)
+    }
+}
+
+fn https_hosts(har: &Har<'_>) -> BTreeSet<(String, u16)> {
+    har.log
+        .entries
+        .iter()
+        .filter_map(|entry| Url::parse(&entry.request.url).ok())
+        .filter(|url| url.scheme() == "https")
+        .filter_map(|url| Some((url.host_str()?.to_string(), url.port_or_known_default()?)))
+        .collect()
+}
+
+fn name_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.to_lowercase();
+    let host = host.to_lowercase();
+
+    match pattern.strip_prefix("*.") {
+        Some(suffix) => host
+            .split_once('.')
+            .is_some_and(|(label, rest)| !label.is_empty() && rest == suffix),
+        None => pattern == host,
+    }
+}
+
+fn key_description(cert: &X509Certificate) -> (String, bool) {
+    match cert.public_key().parsed() {
+        Ok(PublicKey::RSA(rsa)) => {
+            let bits = rsa.key_size();
+            (format!("RSA {} bits", bits), bits < 2048)
+        }
+        Ok(PublicKey::EC(ec)) => (format!("EC {} bits", ec.key_size()), false),
+        Ok(key) => (format!("{} bits", key.key_size()), false),
+        Err(_) => (cert.public_key().algorithm.algorithm.to_id_string(), false),
+    }
+}
+
+fn print_certificate(host: &str, info: &HandshakeInfo, warn_days: i64) -> Result<Vec<String>> {
+    let mut issues = Vec::new();
+
+    let leaf = info
+        .chain
+        .first()
+        .ok_or_else(|| anyhow!("Server sent no certificates"))?;
+    let (_, cert) = X509Certificate::from_der(leaf).context("Failed to parse leaf certificate")?;
+
+    let not_after = DateTime::<Utc>::from_timestamp(cert.validity().not_after.timestamp(), 0)
+        .unwrap_or_default();
+    let days_left = (not_after - Utc::now()).num_days();
+
+    let sans: Vec<String> = cert
+        .subject_alternative_name()
+        .ok()
+        .flatten()
+        .map(|ext| {
+            ext.value
+                .general_names
+                .iter()
+                .filter_map(|name| match name {
+                    GeneralName::DNSName(dns) => Some(dns.to_string()),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let (key, weak_key) = key_description(&cert);
+
+    println!("    Subject: {}", cert.subject().to_string().cyan());
+    println!("    Issuer: {}", cert.issuer().to_string().cyan());
+    println!("    Expires: {} ({} days)", not_after, days_left);
+    println!("    Key: {}", key);
+    println!("    SANs: {}", sans.join(", "));
+    println!("    Chain length: {}", info.chain.len());
+    println!("    Negotiated: {} {}", info.version, info.cipher);
+
+    if days_left < 0 {
+        issues.push("certificate has expired".to_string());
+    } else if days_left < warn_days {
+        issues.push(format!("certificate expires in {} days", days_left));
+    }
+
+    if !sans.iter().any(|san| name_matches(san, host)) {
+        issues.push("hostname not covered by subject alternative names".to_string());
+    }
+
+    if weak_key {
+        issues.push("weak key size".to_string());
+    }
+
+    if let Some(e) = &info.verify_error {
+        issues.push(format!("chain verification failed: {}", e));
+    }
+
+    Ok(issues)
+}
+
+pub async fn tls_audit(har: &Har<'_>, warn_days: i64) -> Result<()> {
+    if offline::is_offline() {
+        offline::notice("TLS handshakes are skipped.");
+        return Ok(());
+    }
+
+    let auditor = Auditor::new()?;
+
+    for (host, port) in https_hosts(har) {
+        println!("{}:", format!("{}:{}", host, port).bold().blue());
+
+        let info = match auditor.handshake(&host, port, &[&TLS13, &TLS12]).await {
+            Ok(info) => info,
+            Err(e) => {
+                println!("{}: {:#}", "TLS handshake failed".red(), e);
+                check::report(1);
+                println!();
+                continue;
+            }
+        };
+
+        let mut issues = match print_certificate(&host, &info, warn_days) {
+            Ok(issues) => issues,
+            Err(e) => vec![format!("{:#}", e)],
+        };
+
+        // probe which protocol versions are accepted
+        let mut supported = Vec::new();
+        for (name, version) in [("TLSv1.3", &TLS13), ("TLSv1.2", &TLS12)] {
+            if auditor.handshake(&host, port, &[version]).await.is_ok() {
+                supported.push(name);
+            }
+        }
+        println!("    Supported versions: {}", supported.join(", "));
+
+        if !supported.contains(&"TLSv1.3") {
+            issues.push("TLSv1.3 not supported".to_string());
+        }
+
+        if issues.is_empty() {
+            println!("{}", "No issues found.".green());
+        }
+        check::report(issues.len());
+        for issue in issues {
+            println!("{}: {}", "Issue".yellow(), issue);
+        }
+
+        println!();
+    }
+
+    Ok(())
+}
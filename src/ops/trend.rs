@@ -0,0 +1,371 @@
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, FixedOffset};
+use colored::Colorize;
+use indicatif::ParallelProgressIterator;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::{collections::BTreeMap, fs, path::Path};
+use url::Url;
+
+use crate::{
+    check, format,
+    har::{Har, format_timestamp},
+    progress,
+};
+
+/// How each run is read and narrowed down, the same as the HAR given on the command line.
+pub struct Loader<'a> {
+    pub parse: fn(&mut [u8]) -> Result<Har<'_>>,
+    pub filter: &'a (dyn Fn(&mut Har) + Sync),
+}
+
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum TrendFormat {
+    /// Endpoint trends and regressions for reading.
+    Text,
+    /// One row per endpoint and run, for dashboards.
+    Csv,
+    /// Runs, endpoints and their samples as one document.
+    Json,
+}
+
+// changes smaller than these are noise however large they are relatively
+const MIN_TIME_CHANGE_MS: f64 = 50.0;
+const MIN_SIZE_CHANGE_BYTES: u64 = 1024;
+
+// how many runs of each series to show in text output
+const MAX_SERIES: usize = 10;
+
+#[derive(Debug, Serialize)]
+pub struct Run {
+    pub name: String,
+    #[serde(serialize_with = "serialize_started")]
+    pub started: Option<DateTime<FixedOffset>>,
+}
+
+fn serialize_started<S: serde::Serializer>(
+    started: &Option<DateTime<FixedOffset>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match started {
+        Some(started) => serializer.serialize_str(&format_timestamp(started)),
+        None => serializer.serialize_none(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Sample {
+    pub requests: usize,
+    // `None` where no request to the endpoint had a timing
+    pub median_ms: Option<f64>,
+    pub median_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Regression {
+    pub metric: &'static str,
+    pub baseline: f64,
+    pub latest: f64,
+    pub change_percent: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EndpointTrend {
+    pub endpoint: String,
+    // one per run, `None` where the run didn't request it
+    pub samples: Vec<Option<Sample>>,
+    pub regressions: Vec<Regression>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Trends {
+    pub runs: Vec<Run>,
+    pub endpoints: Vec<EndpointTrend>,
+}
+
+/// Whether a path segment is an ID rather than part of the route, i.e. a number, UUID or hash.
+fn is_id(segment: &str) -> bool {
+    let hex = segment
+        .chars()
+        .filter(|c| *c != '-')
+        .all(|c| c.is_ascii_hexdigit());
+    !segment.is_empty()
+        && (segment.chars().all(|c| c.is_ascii_digit())
+            || (hex && segment.len() >= 8 && segment.chars().any(|c| c.is_ascii_digit())))
+}
+
+/// The method, host and path of a URL with IDs replaced, so the same endpoint lines up across
+/// runs that requested different items from it.
+pub fn endpoint_pattern(method: &str, url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    let host = url.host_str()?;
+    let path: Vec<&str> = url
+        .path()
+        .split('/')
+        .map(|segment| if is_id(segment) { "{id}" } else { segment })
+        .collect();
+    Some(format!("{} {}{}", method, host, path.join("/")))
+}
+
+fn median(values: &mut [f64]) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    }
+}
+
+/// Load one run, summarising each endpoint's timing and size.
+fn load_run(path: &Path, loader: &Loader) -> Result<(Run, BTreeMap<String, Sample>)> {
+    let mut contents =
+        fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut har = (loader.parse)(&mut contents)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    (loader.filter)(&mut har);
+
+    // endpoint -> (times, sizes)
+    let mut endpoints: BTreeMap<String, (Vec<f64>, Vec<f64>)> = BTreeMap::new();
+    for entry in &har.log.entries {
+        let Some(endpoint) = endpoint_pattern(&entry.request.method, &entry.request.url) else {
+            continue;
+        };
+        let (times, sizes) = endpoints.entry(endpoint).or_default();
+        // -1 is an unknown time
+        if entry.time >= 0.0 {
+            times.push(entry.time);
+        }
+        sizes.push(entry.response.body_bytes() as f64);
+    }
+
+    let samples = endpoints
+        .into_iter()
+        .map(|(endpoint, (mut times, mut sizes))| {
+            let sample = Sample {
+                requests: sizes.len(),
+                median_ms: (!times.is_empty()).then(|| median(&mut times)),
+                median_bytes: median(&mut sizes) as u64,
+            };
+            (endpoint, sample)
+        })
+        .collect();
+
+    let run = Run {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        started: har.log.entries.iter().map(|e| *e.started_date_time).min(),
+    };
+    Ok((run, samples))
+}
+
+/// Compare an endpoint's latest sample against the median of the runs before it.
+fn find_regressions(samples: &[Option<Sample>], threshold: f64) -> Vec<Regression> {
+    let Some(Some(latest)) = samples.last() else {
+        return Vec::new();
+    };
+    let earlier: Vec<&Sample> = samples[..samples.len() - 1].iter().flatten().collect();
+    if earlier.is_empty() {
+        return Vec::new();
+    }
+
+    let mut regressions = Vec::new();
+    let mut compare = |metric, mut values: Vec<f64>, latest: Option<f64>, min_change: f64| {
+        let Some(latest) = latest.filter(|_| !values.is_empty()) else {
+            return;
+        };
+        let baseline = median(&mut values);
+        if latest - baseline >= min_change && latest > baseline * (1.0 + threshold / 100.0) {
+            regressions.push(Regression {
+                metric,
+                baseline,
+                latest,
+                change_percent: if baseline > 0.0 {
+                    (latest - baseline) / baseline * 100.0
+                } else {
+                    f64::INFINITY
+                },
+            });
+        }
+    };
+    compare(
+        "time",
+        earlier.iter().filter_map(|s| s.median_ms).collect(),
+        latest.median_ms,
+        MIN_TIME_CHANGE_MS,
+    );
+    compare(
+        "size",
+        earlier.iter().map(|s| s.median_bytes as f64).collect(),
+        Some(latest.median_bytes as f64),
+        MIN_SIZE_CHANGE_BYTES as f64,
+    );
+    regressions
+}
+
+/// Load every HAR in a directory as a series of runs, ordered by when each capture started, and
+/// line up their endpoints. Regressions compare the latest run to the median of the earlier ones.
+pub fn find_trends(dir: &Path, threshold: f64, loader: &Loader) -> Result<Trends> {
+    let mut paths: Vec<_> = fs::read_dir(dir)
+        .with_context(|| format!("Failed to read directory {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("har"))
+        })
+        .collect();
+    paths.sort();
+    if paths.is_empty() {
+        bail!("No HAR files found in {}", dir.display());
+    }
+
+    let pb = progress::items(paths.len() as u64, "Loading runs");
+    let mut runs = paths
+        .par_iter()
+        .progress_with(pb.clone())
+        .map(|path| load_run(path, loader))
+        .collect::<Result<Vec<_>>>()?;
+    pb.finish_and_clear();
+    // captures without entries have no start, and keep their file name order at the front
+    runs.sort_by_key(|(run, _)| run.started);
+
+    let mut endpoints: BTreeMap<String, Vec<Option<Sample>>> = BTreeMap::new();
+    for (i, (_, samples)) in runs.iter().enumerate() {
+        for (endpoint, sample) in samples {
+            endpoints
+                .entry(endpoint.clone())
+                .or_insert_with(|| vec![None; runs.len()])[i] = Some(sample.clone());
+        }
+    }
+
+    Ok(Trends {
+        endpoints: endpoints
+            .into_iter()
+            .map(|(endpoint, samples)| EndpointTrend {
+                regressions: find_regressions(&samples, threshold),
+                endpoint,
+                samples,
+            })
+            .collect(),
+        runs: runs.into_iter().map(|(run, _)| run).collect(),
+    })
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn print_csv(trends: &Trends) {
+    println!("endpoint,run,started,requests,median_ms,median_bytes");
+    for endpoint in &trends.endpoints {
+        for (run, sample) in trends.runs.iter().zip(&endpoint.samples) {
+            let Some(sample) = sample else {
+                continue;
+            };
+            println!(
+                "{},{},{},{},{},{}",
+                csv_field(&endpoint.endpoint),
+                csv_field(&run.name),
+                run.started
+                    .as_ref()
+                    .map(format_timestamp)
+                    .unwrap_or_default(),
+                sample.requests,
+                sample
+                    .median_ms
+                    .map(|ms| format!("{:.1}", ms))
+                    .unwrap_or_default(),
+                sample.median_bytes
+            );
+        }
+    }
+}
+
+fn series(samples: &[Option<Sample>], value: impl Fn(&Sample) -> String) -> String {
+    let skip = samples.len().saturating_sub(MAX_SERIES);
+    let values: Vec<String> = samples[skip..]
+        .iter()
+        .map(|sample| sample.as_ref().map_or("-".to_string(), &value))
+        .collect();
+    let more = if skip > 0 { "... " } else { "" };
+    format!("{}{}", more, values.join(" "))
+}
+
+fn print_text(trends: &Trends) {
+    let regressed: Vec<&EndpointTrend> = trends
+        .endpoints
+        .iter()
+        .filter(|e| !e.regressions.is_empty())
+        .collect();
+
+    if regressed.is_empty() {
+        println!("{}", "No regressions found.".green());
+        println!();
+    }
+    for endpoint in &regressed {
+        println!("{}:", endpoint.endpoint.bold().blue());
+        for regression in &endpoint.regressions {
+            let (baseline, latest) = match regression.metric {
+                "size" => (
+                    format::bytes(regression.baseline as u64),
+                    format::bytes(regression.latest as u64),
+                ),
+                _ => (
+                    format!("{:.0} ms", regression.baseline),
+                    format!("{:.0} ms", regression.latest),
+                ),
+            };
+            println!(
+                "    {}: {} -> {} ({})",
+                regression.metric,
+                baseline,
+                latest.red(),
+                format!("+{:.0}%", regression.change_percent).red()
+            );
+        }
+        println!(
+            "    Times: {}",
+            series(&endpoint.samples, |s| s
+                .median_ms
+                .map_or("-".to_string(), |ms| format!("{:.0}", ms)))
+        );
+        println!(
+            "    Sizes: {}",
+            series(&endpoint.samples, |s| format::bytes(s.median_bytes))
+        );
+        println!();
+    }
+
+    let latest = trends.runs.last().map_or("", |run| run.name.as_str());
+    println!(
+        "{}: {} runs, {} endpoints, {} regressed in {}",
+        "Summary".bold(),
+        trends.runs.len(),
+        trends.endpoints.len(),
+        regressed.len(),
+        latest
+    );
+}
+
+pub fn trend(dir: &Path, format: TrendFormat, threshold: f64, loader: &Loader) -> Result<()> {
+    let trends = find_trends(dir, threshold, loader)?;
+    match format {
+        TrendFormat::Text => print_text(&trends),
+        TrendFormat::Csv => print_csv(&trends),
+        TrendFormat::Json => println!("{}", serde_json::to_string_pretty(&trends)?),
+    }
+
+    let regressions = trends
+        .endpoints
+        .iter()
+        .filter(|e| !e.regressions.is_empty())
+        .count();
+    check::report(regressions);
+    Ok(())
+}
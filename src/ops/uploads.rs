@@ -0,0 +1,199 @@
+use colored::Colorize;
+use std::collections::BTreeMap;
+use url::Url;
+
+use crate::{
+    check, domain, format,
+    har::{Entry, Har},
+    mime, sizes,
+};
+
+use super::{auth_flows, multipart};
+
+// methods whose body is what's being sent somewhere
+const UPLOAD_METHODS: [&str; 3] = ["POST", "PUT", "PATCH"];
+
+#[derive(Debug)]
+pub struct Upload<'a> {
+    pub request_num: usize,
+    pub entry: &'a Entry<'a>,
+    pub bytes: u64,
+    // the declared type of the body as a whole
+    pub content_type: Option<String>,
+    // the type of each file sent, from its magic bytes where they survive, else as declared
+    pub files: Vec<String>,
+    pub plain_http: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Destination<'a> {
+    pub third_party: bool,
+    pub uploads: Vec<Upload<'a>>,
+}
+
+impl Destination<'_> {
+    pub fn bytes(&self) -> u64 {
+        self.uploads.iter().map(|u| u.bytes).sum()
+    }
+}
+
+/// The type of an uploaded file, preferring its magic bytes over what the client declared.
+fn file_type(body: &str, declared: Option<&str>) -> String {
+    mime::sniff(body.as_bytes())
+        .map(String::from)
+        .or_else(|| declared.map(mime::essence))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn check_entry<'a>(i: usize, entry: &'a Entry<'a>) -> Option<Upload<'a>> {
+    let request = &entry.request;
+    let url = Url::parse(&request.url).ok()?;
+    if !url.scheme().starts_with("http") {
+        return None;
+    }
+    let bytes = sizes::entry_sizes(entry).request_body;
+    let method = request.method.to_uppercase();
+    let post_data = request.post_data.as_ref();
+    // a bodyless POST (a logout, a ping) sent nothing, whatever its method suggests
+    if bytes == 0 && (post_data.is_none() || !UPLOAD_METHODS.contains(&method.as_str())) {
+        return None;
+    }
+
+    let content_type = post_data
+        .map(|p| mime::essence(&p.mime_type))
+        .filter(|t| !t.is_empty())
+        .or_else(|| request.header("content-type").map(mime::essence));
+    let files = match post_data.and_then(multipart::parts) {
+        Some(parts) => parts
+            .iter()
+            .filter(|part| part.is_file())
+            .map(|part| {
+                let file_type = file_type(part.body, part.content_type.as_deref());
+                match part.filename.as_deref().filter(|f| !f.is_empty()) {
+                    Some(filename) => format!("{} ({})", file_type, filename),
+                    None => file_type,
+                }
+            })
+            .collect(),
+        // a raw body is a file when its magic bytes say it's one
+        None => post_data
+            .and_then(|p| mime::sniff(p.text.as_bytes()))
+            .filter(|sniffed| *sniffed != "text/html")
+            .map(|sniffed| vec![sniffed.to_string()])
+            .unwrap_or_default(),
+    };
+
+    Some(Upload {
+        request_num: i + 1,
+        entry,
+        bytes,
+        content_type,
+        files,
+        plain_http: url.scheme() == "http" && !auth_flows::is_local(&url),
+    })
+}
+
+/// Find the requests that sent a body, grouped by the registrable domain they went to.
+pub fn find_uploads<'a>(har: &'a Har) -> BTreeMap<String, Destination<'a>> {
+    let tld_extractor = domain::build_tld_extractor();
+    let first_party = domain::first_party(har, &tld_extractor);
+    let mut destinations: BTreeMap<String, Destination> = BTreeMap::new();
+    for (i, entry) in har.log.entries.iter().enumerate() {
+        let Some(upload) = check_entry(i, entry) else {
+            continue;
+        };
+        let Some(host) = entry.request.host() else {
+            continue;
+        };
+        let recipient = domain::registrable_domain(&host, &tld_extractor).unwrap_or(host);
+        let destination = destinations
+            .entry(recipient.clone())
+            .or_insert_with(|| Destination {
+                third_party: !first_party
+                    .as_ref()
+                    .is_some_and(|fp| fp.contains(&recipient)),
+                ..Default::default()
+            });
+        destination.uploads.push(upload);
+    }
+    destinations
+}
+
+pub fn uploads(har: &Har) {
+    let destinations = find_uploads(har);
+    if destinations.is_empty() {
+        println!("{}", "No requests sent a body.".green());
+        return;
+    }
+
+    let mut destinations: Vec<(String, Destination)> = destinations.into_iter().collect();
+    destinations.sort_by_key(|(_, d)| std::cmp::Reverse(d.bytes()));
+
+    let mut uploads = 0;
+    let mut total_bytes = 0;
+    let mut files = 0;
+    let mut third_party = 0;
+    let mut plain_http = 0;
+    let mut flagged = 0;
+    for (recipient, destination) in &destinations {
+        let bytes = destination.bytes();
+        let party = if destination.third_party {
+            "third party".yellow()
+        } else {
+            "first party".normal()
+        };
+        println!(
+            "{} ({}, {} in {} requests):",
+            domain::display_host(recipient).bold().blue(),
+            party,
+            format::bytes(bytes),
+            destination.uploads.len()
+        );
+        for upload in &destination.uploads {
+            println!(
+                "    #{} {} {} {} ({})",
+                upload.request_num,
+                upload.entry.request.method,
+                upload.entry.request.url,
+                format::bytes(upload.bytes),
+                upload.content_type.as_deref().unwrap_or("no type")
+            );
+            for file in &upload.files {
+                println!("        file: {}", file);
+            }
+            if upload.plain_http {
+                println!("        {}", "sent over plain HTTP".red());
+            }
+        }
+        println!();
+
+        uploads += destination.uploads.len();
+        total_bytes += bytes;
+        files += destination
+            .uploads
+            .iter()
+            .map(|u| u.files.len())
+            .sum::<usize>();
+        plain_http += destination.uploads.iter().filter(|u| u.plain_http).count();
+        if destination.third_party {
+            third_party += destination.uploads.len();
+        }
+        flagged += destination
+            .uploads
+            .iter()
+            .filter(|u| destination.third_party || u.plain_http)
+            .count();
+    }
+
+    check::report(flagged);
+    println!(
+        "{}: {} uploads to {} domains totalling {}, {} files, {} to third parties, {} over plain HTTP",
+        "Summary".bold(),
+        uploads,
+        destinations.len(),
+        format::bytes(total_bytes),
+        files,
+        third_party,
+        plain_http
+    );
+}
@@ -0,0 +1,197 @@
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use reqwest::Client;
+use serde_json::Value;
+use std::{collections::BTreeSet, time::Duration};
+
+use crate::{domain, har::Har, offline, progress};
+
+use super::list_domains;
+
+// redirects to the registry's RDAP server for the TLD
+const RDAP_URL: &str = "https://rdap.org/domain/";
+
+// covers the redirect too, so a registry that doesn't answer fails its domain, not the run
+const RDAP_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Default)]
+pub struct Registration {
+    pub registrar: Option<String>,
+    pub created: Option<DateTime<Utc>>,
+    pub registrant_country: Option<String>,
+}
+
+/// Find the first entity with a role, searching entities nested inside others too.
+fn entity_with_role<'a>(value: &'a Value, role: &str) -> Option<&'a Value> {
+    for entity in value.get("entities")?.as_array()? {
+        let has_role = entity
+            .get("roles")
+            .and_then(Value::as_array)
+            .is_some_and(|roles| roles.iter().any(|r| r.as_str() == Some(role)));
+        if has_role {
+            return Some(entity);
+        }
+        if let Some(found) = entity_with_role(entity, role) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+/// Get the properties named `name` from an entity's jCard.
+fn vcard_properties<'a>(entity: &'a Value, name: &'a str) -> impl Iterator<Item = &'a Vec<Value>> {
+    entity
+        .pointer("/vcardArray/1")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_array)
+        .filter(move |property| property.first().and_then(Value::as_str) == Some(name))
+}
+
+fn registrar(rdap: &Value) -> Option<String> {
+    let entity = entity_with_role(rdap, "registrar")?;
+    vcard_properties(entity, "fn")
+        .find_map(|property| property.get(3)?.as_str())
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+}
+
+fn registrant_country(rdap: &Value) -> Option<String> {
+    let entity = entity_with_role(rdap, "registrant")?;
+    vcard_properties(entity, "adr").find_map(|property| {
+        // the country is either a `cc` parameter or the last part of the structured address
+        let cc = property.get(1)?.get("cc").and_then(Value::as_str);
+        let name = property.get(3)?.as_array()?.get(6).and_then(Value::as_str);
+        cc.or(name)
+            .filter(|country| !country.is_empty())
+            .map(String::from)
+    })
+}
+
+fn created(rdap: &Value) -> Option<DateTime<Utc>> {
+    rdap.get("events")?
+        .as_array()?
+        .iter()
+        .find(|event| event.get("eventAction").and_then(Value::as_str) == Some("registration"))
+        .and_then(|event| event.get("eventDate")?.as_str())
+        .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+        .map(|date| date.with_timezone(&Utc))
+}
+
+/// Build the client for [`lookup`], which gives up on a registry after [`RDAP_TIMEOUT`].
+pub fn client() -> Result<Client> {
+    Client::builder()
+        .timeout(RDAP_TIMEOUT)
+        .build()
+        .context("Failed to build HTTP client")
+}
+
+/// Look up a domain's registration data over RDAP.
+pub async fn lookup(client: &Client, domain: &str) -> Result<Registration> {
+    let url = format!("{}{}", RDAP_URL, domain);
+    let text = client
+        .get(&url)
+        .header("accept", "application/rdap+json")
+        .send()
+        .await
+        .with_context(|| format!("Failed to send request to {}", url))?
+        .error_for_status()?
+        .text()
+        .await
+        .with_context(|| format!("Failed to read RDAP response for {}", domain))?;
+
+    let rdap: Value = serde_json::from_str(&text)
+        .with_context(|| format!("Failed to parse RDAP response for {}", domain))?;
+    Ok(Registration {
+        registrar: registrar(&rdap),
+        created: created(&rdap),
+        registrant_country: registrant_country(&rdap),
+    })
+}
+
+/// Look up the registration of the registrable domain of each host in the HAR, flagging those
+/// registered within the last `recent_days` days.
+pub async fn print_whois(har: &Har<'_>, recent_days: i64) -> Result<()> {
+    offline::ensure_online("look up registration data")?;
+
+    let tld_extractor = domain::build_tld_extractor();
+    let domains: BTreeSet<String> = list_domains::list_domains(har)
+        .iter()
+        .filter(|host| domain::ip_literal(host).is_none())
+        .filter_map(|host| domain::registrable_domain(host, &tld_extractor))
+        .collect();
+
+    if domains.is_empty() {
+        println!("{}", "No domains found.".green());
+        return Ok(());
+    }
+
+    let client = client()?;
+    let pb = progress::items(domains.len() as u64, "Looking up registrations");
+    let mut results = Vec::new();
+    for domain in domains {
+        let registration = lookup(&client, &domain).await;
+        results.push((domain, registration));
+        pb.inc(1);
+    }
+    pb.finish_and_clear();
+
+    let now = Utc::now();
+    let mut recent = 0;
+    let mut failed = 0;
+    for (domain, registration) in &results {
+        println!("{}:", domain::display_host(domain).bold().blue());
+        let registration = match registration {
+            Ok(registration) => registration,
+            Err(e) => {
+                println!("    {}", format!("RDAP lookup failed: {:#}", e).red());
+                println!();
+                failed += 1;
+                continue;
+            }
+        };
+
+        let unknown = || "unknown".dimmed().to_string();
+        println!(
+            "    Registrar: {}",
+            registration.registrar.clone().unwrap_or_else(unknown)
+        );
+        match registration.created {
+            Some(created) => {
+                let age = (now - created).num_days();
+                let date = created.format("%Y-%m-%d").to_string();
+                if age < recent_days {
+                    recent += 1;
+                    println!(
+                        "    Created: {} {}",
+                        date,
+                        format!("(registered {} days ago)", age).yellow().bold()
+                    );
+                } else {
+                    println!("    Created: {} ({} days ago)", date, age);
+                }
+            }
+            None => println!("    Created: {}", unknown()),
+        }
+        println!(
+            "    Registrant country: {}",
+            registration
+                .registrant_country
+                .clone()
+                .unwrap_or_else(unknown)
+        );
+        println!();
+    }
+
+    println!(
+        "{}: {} domains, {} registered in the last {} days, {} lookups failed",
+        "Summary".bold(),
+        results.len(),
+        recent,
+        recent_days,
+        failed
+    );
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use indicatif::{ProgressBar, ProgressStyle};
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+// files smaller than this are read and parsed too quickly for a bar to be worth drawing
+pub const MIN_INPUT_BYTES: u64 = 16 * 1024 * 1024;
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Hide every progress bar, for `--quiet`.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+fn styled(len: u64, template: &str) -> ProgressBar {
+    if QUIET.load(Ordering::Relaxed) {
+        return ProgressBar::hidden();
+    }
+
+    let style = ProgressStyle::default_bar()
+        .template(template)
+        .unwrap()
+        .progress_chars("##-");
+    let pb = ProgressBar::new(len).with_style(style);
+    pb.enable_steady_tick(Duration::from_millis(100));
+    pb
+}
+
+/// A bar for a transfer of `len` bytes.
+pub fn bytes(len: u64) -> ProgressBar {
+    styled(
+        len,
+        "{spinner:.green} [{elapsed_precise}] {msg:25!} [{bar:20.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})",
+    )
+}
+
+/// A bar for `len` items of work, such as domains to look up.
+pub fn items(len: u64, message: &'static str) -> ProgressBar {
+    styled(
+        len,
+        "{spinner:.green} [{elapsed_precise}] {msg:25!} [{bar:20.cyan/blue}] {pos}/{len} ({eta})",
+    )
+    .with_message(message)
+}
+
+/// A spinner for work with no measurable progress.
+pub fn spinner(message: &'static str) -> ProgressBar {
+    styled(0, "{spinner:.green} [{elapsed_precise}] {msg}").with_message(message)
+}
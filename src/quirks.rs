@@ -0,0 +1,249 @@
+use colored::Colorize;
+use std::{borrow::Cow, collections::BTreeMap, fmt};
+use tracing::{info, warn};
+
+use crate::har::{Expiry, Har, Log};
+
+/// The tool that exported a HAR, as its `creator` or `browser` says.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Tool {
+    Firefox,
+    /// Chrome and the other Chromium browsers, whose DevTools all call themselves WebInspector.
+    Chrome,
+    Safari,
+    Charles,
+    Fiddler,
+    Other(String),
+}
+
+impl fmt::Display for Tool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Tool::Firefox => "Firefox",
+            Tool::Chrome => "Chrome",
+            Tool::Safari => "Safari",
+            Tool::Charles => "Charles",
+            Tool::Fiddler => "Fiddler",
+            Tool::Other(name) if name.is_empty() => "an unknown tool",
+            Tool::Other(name) => name,
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Tool {
+    /// Whether it records traffic as a proxy, rather than from inside the browser.
+    pub fn is_proxy(&self) -> bool {
+        matches!(self, Tool::Charles | Tool::Fiddler)
+    }
+}
+
+/// Identify the exporting tool, going by `creator` and then `browser`.
+pub fn detect(log: &Log) -> Tool {
+    let creator = log.creator.name.to_lowercase();
+    let browser = log
+        .browser
+        .as_ref()
+        .map(|b| b.name.to_lowercase())
+        .unwrap_or_default();
+    // Safari's inspector is "WebKit Web Inspector", Chromium's is "WebInspector"
+    if creator.contains("webkit") || browser.contains("safari") {
+        Tool::Safari
+    } else if creator.contains("firefox") || browser.contains("firefox") {
+        Tool::Firefox
+    } else if creator.contains("webinspector")
+        || ["chrome", "chromium", "edge", "opera", "brave"]
+            .iter()
+            .any(|name| browser.contains(name))
+    {
+        Tool::Chrome
+    } else if creator.contains("charles") {
+        Tool::Charles
+    } else if creator.contains("fiddler") {
+        Tool::Fiddler
+    } else {
+        Tool::Other(log.creator.name.to_string())
+    }
+}
+
+/// Something in a HAR that strays from the spec. The parser and `sizes` read each the same way
+/// whichever tool exported the HAR, so the tool only decides whether it's worth a warning.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Quirk {
+    /// `content` left empty or out.
+    EmptyContent,
+    /// `timings` left empty or out.
+    MissingTimings,
+    /// `headersSize` or `content.size` left out.
+    MissingSizes,
+    /// A body size of 0 for responses served from the cache.
+    CachedBodySize,
+    /// Brackets around IPv6 server addresses, the one quirk [`apply`] normalises.
+    BracketedIp,
+    /// HTTP/2 pseudo-headers such as `:authority` among the request headers.
+    PseudoHeaders,
+    /// Sizes of `-1` without Chrome's `_transferSize`.
+    UnknownSizes,
+    /// Versions, timings and sizes are the proxy's connection to the server, not the browser's.
+    ProxyConnection,
+    /// A `startedDateTime` that isn't ISO 8601 and had to be read leniently.
+    RepairedTimestamp,
+    /// A cookie or cache `expires` date that couldn't be read at all.
+    UnreadableExpiry,
+}
+
+impl fmt::Display for Quirk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self {
+            Quirk::EmptyContent => "empty or missing response content",
+            Quirk::MissingTimings => "empty or missing timings",
+            Quirk::MissingSizes => "missing headersSize or content.size",
+            Quirk::CachedBodySize => "body size 0 for cached responses",
+            Quirk::BracketedIp => "brackets around IPv6 server addresses, removed",
+            Quirk::PseudoHeaders => "HTTP/2 pseudo-headers among the request headers",
+            Quirk::UnknownSizes => "sizes of -1 without _transferSize",
+            Quirk::ProxyConnection => {
+                "timings, sizes and HTTP versions of the proxy's connection, not the browser's"
+            }
+            Quirk::RepairedTimestamp => "startedDateTime not in ISO 8601, rewritten",
+            Quirk::UnreadableExpiry => "unreadable cookie or cache expiry dates, kept as written",
+        };
+        write!(f, "{}", description)
+    }
+}
+
+impl Quirk {
+    /// Whether the tool is known to write HARs this way, so it isn't worth a warning.
+    fn expected_from(self, tool: &Tool) -> bool {
+        match self {
+            Quirk::EmptyContent
+            | Quirk::MissingTimings
+            | Quirk::MissingSizes
+            | Quirk::CachedBodySize => *tool == Tool::Firefox,
+            Quirk::BracketedIp | Quirk::PseudoHeaders => *tool == Tool::Chrome,
+            // the spec allows -1 for unknown sizes, but Chrome always knows them
+            Quirk::UnknownSizes => *tool != Tool::Chrome,
+            Quirk::ProxyConnection => tool.is_proxy(),
+            // no tool is known to, so they're worth a warning wherever they turn up
+            Quirk::RepairedTimestamp | Quirk::UnreadableExpiry => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Quirks {
+    pub tool: Tool,
+    pub version: String,
+    // how many entries each quirk turned up in
+    pub found: BTreeMap<Quirk, usize>,
+}
+
+/// Detect the exporting tool and the quirks of its HAR, removing the brackets around IPv6
+/// server addresses in place.
+pub fn apply(har: &mut Har) -> Quirks {
+    let tool = detect(&har.log);
+    let mut found: BTreeMap<Quirk, usize> = BTreeMap::new();
+    let mut note = |quirk: Quirk| *found.entry(quirk).or_default() += 1;
+
+    for entry in &mut har.log.entries {
+        let response = &entry.response;
+        match &response.content {
+            None => note(Quirk::EmptyContent),
+            Some(content) => {
+                if response.headers_size.is_none() || content.size.is_none() {
+                    note(Quirk::MissingSizes);
+                }
+                let content_bytes = content.size.unwrap_or(0);
+                if response.status == 200
+                    && response.body_size == 0
+                    && response.transfer_size.is_none()
+                    && content_bytes > 0
+                    && entry.cache.before_request.is_some()
+                {
+                    note(Quirk::CachedBodySize);
+                }
+            }
+        }
+        if entry.timings.is_none() {
+            note(Quirk::MissingTimings);
+        }
+        if response.body_size < 0 && response.transfer_size.is_none() {
+            note(Quirk::UnknownSizes);
+        }
+        if entry
+            .request
+            .headers
+            .iter()
+            .any(|h| h.name.starts_with(':'))
+        {
+            note(Quirk::PseudoHeaders);
+        }
+        if let Some(ip) = &mut entry.server_ip_address {
+            if let Some(unbracketed) = ip.strip_prefix('[').and_then(|ip| ip.strip_suffix(']')) {
+                *ip = Cow::Owned(unbracketed.to_string());
+                note(Quirk::BracketedIp);
+            }
+        }
+        if tool.is_proxy() {
+            note(Quirk::ProxyConnection);
+        }
+        if entry.started_date_time.is_repaired() {
+            note(Quirk::RepairedTimestamp);
+        }
+        let mut expiries = entry
+            .request
+            .cookies
+            .iter()
+            .chain(&entry.response.cookies)
+            .filter_map(|cookie| cookie.expires.as_ref())
+            .chain(
+                [&entry.cache.before_request, &entry.cache.after_request]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|cache| cache.expires.as_ref()),
+            );
+        if expiries.any(Expiry::is_unreadable) {
+            note(Quirk::UnreadableExpiry);
+        }
+    }
+
+    Quirks {
+        tool,
+        version: har.log.creator.version.to_string(),
+        found,
+    }
+}
+
+/// Warn about quirks the exporting tool doesn't account for, and the proxy caveat, which
+/// changes what every timing means. Quirks the tool is known for are only logged.
+pub fn report(quirks: &Quirks) {
+    for (quirk, count) in &quirks.found {
+        if *quirk == Quirk::ProxyConnection || !quirk.expected_from(&quirks.tool) {
+            warn!("{} entries with {}", count, quirk);
+        } else {
+            info!(tool = %quirks.tool, "{} entries with {}", count, quirk);
+        }
+    }
+}
+
+/// Describe the detected tool and each quirk, for `--explain-quirks`.
+pub fn explain(quirks: &Quirks) {
+    println!(
+        "{}: exported by {} {}",
+        "Quirks".bold().blue(),
+        quirks.tool,
+        quirks.version
+    );
+    if quirks.found.is_empty() {
+        println!("    {}", "none found".green());
+    }
+    for (quirk, count) in &quirks.found {
+        let expected = if quirk.expected_from(&quirks.tool) {
+            format!("expected from {}", quirks.tool).dimmed()
+        } else {
+            "unexpected".yellow()
+        };
+        println!("    {} entries with {} ({})", count, quirk, expected);
+    }
+    println!();
+}
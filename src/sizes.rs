@@ -0,0 +1,103 @@
+use std::ops::AddAssign;
+
+use crate::har::{Entry, Header};
+
+// the blank line that ends a HTTP/1 header block
+const HEADER_END_BYTES: u64 = 2;
+
+/// The bytes an entry sent and received, split into headers and bodies.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Sizes {
+    pub request_headers: u64,
+    pub request_body: u64,
+    pub response_headers: u64,
+    pub response_body: u64,
+    // how many of the sizes were unknown (`-1` or missing) and estimated instead
+    pub estimated: usize,
+}
+
+impl Sizes {
+    pub fn request(&self) -> u64 {
+        self.request_headers + self.request_body
+    }
+
+    pub fn response(&self) -> u64 {
+        self.response_headers + self.response_body
+    }
+
+    pub fn headers(&self) -> u64 {
+        self.request_headers + self.response_headers
+    }
+
+    pub fn total(&self) -> u64 {
+        self.request() + self.response()
+    }
+}
+
+impl AddAssign for Sizes {
+    fn add_assign(&mut self, other: Sizes) {
+        self.request_headers += other.request_headers;
+        self.request_body += other.request_body;
+        self.response_headers += other.response_headers;
+        self.response_body += other.response_body;
+        self.estimated += other.estimated;
+    }
+}
+
+/// A size from the HAR, `None` for the `-1` exporters write when they don't know it.
+fn known(size: Option<i64>) -> Option<u64> {
+    size.filter(|size| *size >= 0).map(|size| size as u64)
+}
+
+/// Size of a header block as HTTP/1 sends it, i.e. `name: value\r\n` per header.
+fn header_block_bytes(start_line: usize, headers: &[Header]) -> u64 {
+    let fields: usize = headers
+        .iter()
+        .map(|h| h.name.len() + 2 + h.value.len() + 2)
+        .sum();
+    (start_line + 2 + fields) as u64 + HEADER_END_BYTES
+}
+
+/// Account for the bytes of an entry, estimating the sizes the HAR leaves unknown: headers from
+/// the recorded header list, the request body from its post data and the response body from
+/// Chrome's `_transferSize` or the content size.
+pub fn entry_sizes(entry: &Entry) -> Sizes {
+    let request = &entry.request;
+    let response = &entry.response;
+    let mut sizes = Sizes::default();
+
+    sizes.request_headers = known(request.headers_size).unwrap_or_else(|| {
+        sizes.estimated += 1;
+        let start_line = request.method.len() + request.url.len() + request.http_version.len() + 2;
+        header_block_bytes(start_line, &request.headers)
+    });
+    sizes.request_body = known(Some(request.body_size)).unwrap_or_else(|| {
+        sizes.estimated += 1;
+        request
+            .post_data
+            .as_ref()
+            .map_or(0, |post_data| post_data.text.len() as u64)
+    });
+
+    // a failed request received nothing
+    if response.status == 0 {
+        return sizes;
+    }
+    let known_headers = known(response.headers_size);
+    let known_body = known(Some(response.body_size));
+    let transfer = known(response.transfer_size);
+    sizes.response_headers = known_headers
+        .or_else(|| Some(transfer?.saturating_sub(known_body?)))
+        .unwrap_or_else(|| {
+            sizes.estimated += 1;
+            let start_line = response.http_version.len() + response.status_text.len() + 5;
+            header_block_bytes(start_line, &response.headers)
+        });
+    sizes.response_body = known_body
+        .or_else(|| Some(transfer?.saturating_sub(sizes.response_headers)))
+        .unwrap_or_else(|| {
+            sizes.estimated += 1;
+            known(response.content.as_ref().and_then(|c| c.size)).unwrap_or(0)
+        });
+    sizes
+}
@@ -0,0 +1,44 @@
+use anyhow::{Context, Result};
+use handlebars::{Handlebars, handlebars_helper, no_escape};
+use serde::Serialize;
+use std::{fs, path::Path};
+
+use crate::{
+    format,
+    har::{Entry, Har},
+};
+
+#[derive(Serialize)]
+struct TemplateContext<'a, T: Serialize> {
+    command: &'a str,
+    results: &'a T,
+    entries: &'a [Entry<'a>],
+}
+
+handlebars_helper!(bytes: |n: u64| format::bytes(n));
+
+/// Render the results of `command` through the handlebars template at `path`.
+///
+/// The template gets `command`, `results` and the filtered HAR `entries`.
+pub fn render<T: Serialize>(path: &Path, command: &str, results: &T, har: &Har) -> Result<()> {
+    let template =
+        fs::read_to_string(path).with_context(|| format!("Failed to read template: {:?}", path))?;
+
+    let mut registry = Handlebars::new();
+    // output is plain text, not HTML
+    registry.register_escape_fn(no_escape);
+    registry.register_helper("bytes", Box::new(bytes));
+
+    let context = TemplateContext {
+        command,
+        results,
+        entries: &har.log.entries,
+    };
+
+    let rendered = registry
+        .render_template(&template, &context)
+        .with_context(|| format!("Failed to render template: {:?}", path))?;
+    print!("{}", rendered);
+
+    Ok(())
+}